@@ -0,0 +1,30 @@
+#[cfg(feature = "ssr")]
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    use actix_files::Files;
+    use actix_web::HttpServer;
+    use fixture_actix::App;
+    use leptos::*;
+    use leptos_actix::{generate_route_list, LeptosRoutes};
+
+    let conf = get_configuration(None).await.unwrap();
+    let addr = conf.leptos_options.site_addr;
+    let routes = generate_route_list(|cx| view! { cx, <App/> });
+
+    HttpServer::new(move || {
+        let leptos_options = &conf.leptos_options;
+        actix_web::App::new()
+            .leptos_routes(
+                leptos_options.to_owned(),
+                routes.to_owned(),
+                |cx| view! { cx, <App/> },
+            )
+            .service(Files::new("/", &leptos_options.site_root))
+    })
+    .bind(&addr)?
+    .run()
+    .await
+}
+
+#[cfg(not(feature = "ssr"))]
+fn main() {}
@@ -0,0 +1,12 @@
+use leptos::*;
+
+#[component]
+pub fn App(cx: Scope) -> impl IntoView {
+    view! { cx, <h1>"fixture"</h1> }
+}
+
+#[cfg(feature = "hydrate")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+pub fn hydrate() {
+    leptos::mount_to_body(|cx| view! { cx, <App/> });
+}
@@ -0,0 +1,27 @@
+#[cfg(feature = "ssr")]
+#[tokio::main]
+async fn main() {
+    use axum::Router;
+    use fixture_axum::App;
+    use leptos::*;
+    use leptos_axum::{generate_route_list, LeptosRoutes};
+    use tower_http::services::ServeDir;
+
+    let conf = get_configuration(None).await.unwrap();
+    let leptos_options = conf.leptos_options;
+    let addr = leptos_options.site_addr;
+    let routes = generate_route_list(|cx| view! { cx, <App/> }).await;
+
+    let app = Router::new()
+        .leptos_routes(&leptos_options, routes, |cx| view! { cx, <App/> })
+        .fallback_service(ServeDir::new(&leptos_options.site_root))
+        .with_state(leptos_options);
+
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[cfg(not(feature = "ssr"))]
+fn main() {}
@@ -0,0 +1,12 @@
+use leptos::*;
+
+#[component]
+pub fn App(cx: Scope) -> impl IntoView {
+    view! { cx, <h1>"fixture"</h1> }
+}
+
+#[cfg(feature = "csr")]
+#[wasm_bindgen::prelude::wasm_bindgen(start)]
+pub fn main() {
+    leptos::mount_to_body(|cx| view! { cx, <App/> });
+}
@@ -0,0 +1,17 @@
+#[cfg(feature = "serve")]
+#[tokio::main]
+async fn main() {
+    use tower_http::services::ServeDir;
+
+    let site_root = std::env::var("LEPTOS_SITE_ROOT").unwrap();
+    let addr = std::env::var("LEPTOS_SITE_ADDR").unwrap().parse().unwrap();
+    let app = axum::Router::new().fallback_service(ServeDir::new(site_root));
+
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[cfg(not(feature = "serve"))]
+fn main() {}
@@ -0,0 +1,307 @@
+use std::{cmp::Reverse, collections::BTreeMap, fmt::Write};
+
+use crate::{
+    config::{Config, Opts, Project},
+    ext::{
+        anyhow::{bail, Context, Result},
+        fs, LeptosError,
+    },
+    logger::GRAY,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Args;
+
+use super::diff::human_size;
+
+#[derive(Clone, Debug, Args, PartialEq)]
+pub struct AnalyzeCommand {
+    /// The number of crates listed in the table, the biggest first.
+    #[arg(long, default_value_t = 20)]
+    pub top: usize,
+
+    /// The dir where the HTML treemap of each project is written. Defaults to the
+    /// `leptos-analyze` dir of the cargo target dir.
+    #[arg(long)]
+    pub output: Option<Utf8PathBuf>,
+
+    #[command(flatten)]
+    pub opts: Opts,
+}
+
+/// The functions without a crate path, e.g. the exports of wasm-bindgen and the allocator shims.
+const NO_CRATE: &str = "[no crate]";
+/// The data segments: string literals, statics and vtables.
+const DATA: &str = "[data]";
+
+#[derive(Debug, PartialEq)]
+struct Item {
+    /// the demangled function name, without the symbol hash
+    name: String,
+    size: u64,
+}
+
+#[derive(Debug, PartialEq)]
+struct CrateSize {
+    name: String,
+    size: u64,
+    /// the biggest first
+    items: Vec<Item>,
+}
+
+pub async fn analyze_all(conf: &Config, cmd: &AnalyzeCommand) -> Result<()> {
+    // relative to the dir cargo-leptos was run in, and not to the workspace root
+    let output = match &cmd.output {
+        Some(dir) => conf.cwd.join(dir),
+        None => conf.target_dir.join("leptos-analyze"),
+    };
+    for proj in &conf.projects {
+        if !super::build::build_proj(proj).await.dot()? {
+            return Err(LeptosError::Compile {
+                project: proj.name.clone(),
+            }
+            .into());
+        }
+        analyze(proj, cmd.top, &output).await?;
+    }
+    Ok(())
+}
+
+/// The cargo output is analyzed, as wasm-bindgen and wasm-opt remove the function names.
+async fn analyze(proj: &Project, top: usize, output: &Utf8Path) -> Result<()> {
+    let wasm = &proj.lib.wasm_file.source;
+    let data = fs::read(wasm).await?;
+    let module = walrus::Module::from_buffer(&data).context(format!("Reading {wasm}"))?;
+
+    let mut named = 0;
+    let mut items = Vec::new();
+    for func in module.funcs.iter() {
+        let walrus::FunctionKind::Local(local) = &func.kind else {
+            continue;
+        };
+        let size = local
+            .original_range
+            .as_ref()
+            .map_or(0, |range| (range.end - range.start) as u64);
+        let name = match &func.name {
+            Some(name) => {
+                named += 1;
+                format!("{:#}", rustc_demangle::demangle(name))
+            }
+            None => format!("function[{}]", func.id().index()),
+        };
+        items.push(Item { name, size });
+    }
+    if named == 0 {
+        bail!(
+            "The wasm {wasm} has no function names, set `strip = false` in the {} profile of the lib",
+            proj.lib.profile
+        );
+    }
+    let data_size = module
+        .data
+        .iter()
+        .map(|segment| segment.value.len() as u64)
+        .sum::<u64>();
+    let crates = by_crate(items, data_size);
+    let total = data.len() as u64;
+
+    log::info!(
+        "Analyze {} {} {}",
+        proj.name,
+        GRAY.paint(wasm.as_str()),
+        human_size(total)
+    );
+    for krate in crates.iter().take(top) {
+        log::info!(
+            "Analyze {:<32} {:>10} {:>5.1}%",
+            krate.name,
+            human_size(krate.size),
+            krate.size as f64 / total as f64 * 100.0
+        );
+    }
+    if crates.len() > top {
+        log::info!("Analyze and {} smaller crates", crates.len() - top);
+    }
+
+    fs::create_dir_all(output).await?;
+    let html_file = output.join(format!("{}.html", proj.name));
+    fs::write(&html_file, treemap_html(&proj.name, total, &crates)).await?;
+    log::info!(
+        "Analyze treemap written to {}",
+        GRAY.paint(html_file.as_str())
+    );
+    Ok(())
+}
+
+/// The sizes grouped by crate, the biggest first.
+fn by_crate(items: Vec<Item>, data_size: u64) -> Vec<CrateSize> {
+    let mut crates: BTreeMap<String, Vec<Item>> = BTreeMap::new();
+    for item in items {
+        crates
+            .entry(crate_of(&item.name).to_string())
+            .or_default()
+            .push(item);
+    }
+    let mut crates = crates
+        .into_iter()
+        .map(|(name, mut items)| {
+            items.sort_by_key(|item| Reverse(item.size));
+            CrateSize {
+                name,
+                size: items.iter().map(|item| item.size).sum(),
+                items,
+            }
+        })
+        .collect::<Vec<_>>();
+    if data_size > 0 {
+        crates.push(CrateSize {
+            name: DATA.to_string(),
+            size: data_size,
+            items: Vec::new(),
+        });
+    }
+    crates.sort_by_key(|krate| Reverse(krate.size));
+    crates
+}
+
+/// The crate of the first path of the demangled name. The trait impls, e.g.
+/// `<alloc::vec::Vec<T> as core::fmt::Debug>::fmt`, are attributed to the crate of the type.
+fn crate_of(name: &str) -> &str {
+    let mut name = name;
+    loop {
+        let trimmed = name
+            .trim_start_matches(['<', '&', '*', '(', '['])
+            .trim_start_matches("mut ")
+            .trim_start_matches("const ")
+            .trim_start_matches("dyn ");
+        if trimmed == name {
+            break;
+        }
+        name = trimmed;
+    }
+    match name.split_once("::") {
+        Some((krate, _))
+            if !krate.is_empty() && krate.chars().all(|c| c.is_alphanumeric() || c == '_') =>
+        {
+            krate
+        }
+        _ => NO_CRATE,
+    }
+}
+
+/// A self-contained treemap: the crates are laid out in rows by size, each one split into its
+/// biggest functions.
+fn treemap_html(project: &str, total: u64, crates: &[CrateSize]) -> String {
+    const ITEMS_PER_CRATE: usize = 30;
+    let mut cells = String::new();
+    for (i, krate) in crates.iter().filter(|krate| krate.size > 0).enumerate() {
+        let hue = i * 47 % 360;
+        let mut items = String::new();
+        let shown = krate.items.iter().take(ITEMS_PER_CRATE);
+        let shown_size = shown.clone().map(|item| item.size).sum::<u64>();
+        for item in shown.filter(|item| item.size > 0) {
+            write!(
+                items,
+                r#"<div class="item" style="flex-grow:{}" title="{} ({})"></div>"#,
+                item.size,
+                escape(&item.name),
+                human_size(item.size)
+            )
+            .unwrap();
+        }
+        if krate.size > shown_size {
+            write!(
+                items,
+                r#"<div class="item rest" style="flex-grow:{}" title="other functions ({})"></div>"#,
+                krate.size - shown_size,
+                human_size(krate.size - shown_size)
+            )
+            .unwrap();
+        }
+        write!(
+            cells,
+            r#"<div class="crate" style="flex-grow:{size};background:hsl({hue},55%,70%)" title="{name} ({human})"><div class="label">{name} {human}</div><div class="items">{items}</div></div>"#,
+            size = krate.size,
+            name = escape(&krate.name),
+            human = human_size(krate.size),
+        )
+        .unwrap();
+    }
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>cargo-leptos analyze: {project}</title>
+<style>
+body {{ font: 14px sans-serif; margin: 2em; }}
+.map {{ display: flex; flex-wrap: wrap; height: 80vh; }}
+.crate {{ display: flex; flex-direction: column; flex-basis: 120px; min-width: 0; border: 1px solid #fff; box-sizing: border-box; }}
+.label {{ padding: 2px 4px; font-size: 12px; white-space: nowrap; overflow: hidden; text-overflow: ellipsis; }}
+.items {{ display: flex; flex-wrap: wrap; flex-grow: 1; }}
+.item {{ flex-basis: 24px; border: 1px solid rgba(255,255,255,.5); box-sizing: border-box; }}
+.item:hover {{ background: rgba(0,0,0,.15); }}
+.rest {{ background: rgba(0,0,0,.08); }}
+</style>
+</head>
+<body>
+<h1>cargo-leptos analyze: {project}</h1>
+<p>The wasm of the cargo build, {total}, by crate. Hover a function for its name and size, before wasm-opt.</p>
+<div class="map">{cells}</div>
+</body>
+</html>
+"#,
+        project = escape(project),
+        total = human_size(total),
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crates() {
+        assert_eq!(crate_of("leptos::view::render"), "leptos");
+        assert_eq!(
+            crate_of("<alloc::vec::Vec<T> as core::fmt::Debug>::fmt"),
+            "alloc"
+        );
+        assert_eq!(
+            crate_of("<&mut serde_json::ser::Serializer<W>>::new"),
+            "serde_json"
+        );
+        assert_eq!(crate_of("<T as core::any::Any>::type_id"), NO_CRATE);
+        assert_eq!(crate_of("__wbindgen_malloc"), NO_CRATE);
+
+        let item = |name: &str, size| Item {
+            name: name.to_string(),
+            size,
+        };
+        let crates = by_crate(
+            vec![
+                item("app::main", 10),
+                item("leptos::mount", 40),
+                item("leptos::view", 30),
+                item("__wbindgen_free", 5),
+            ],
+            50,
+        );
+        let sizes = crates
+            .iter()
+            .map(|krate| (krate.name.as_str(), krate.size))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sizes,
+            vec![("leptos", 70), (DATA, 50), ("app", 10), (NO_CRATE, 5)]
+        );
+        assert_eq!(crates[0].items[0].name, "leptos::mount");
+    }
+}
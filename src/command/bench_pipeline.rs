@@ -0,0 +1,225 @@
+use std::{sync::Arc, time::SystemTime};
+
+use crate::{
+    command::build::build_proj,
+    config::{Config, Opts, Project},
+    ext::{
+        anyhow::{bail, Result},
+        fs,
+    },
+    logger::GRAY,
+    signal::{CycleTimes, Interrupt, Timings},
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Args;
+use serde::Serialize;
+
+#[derive(Clone, Debug, Args, PartialEq)]
+pub struct BenchPipelineCommand {
+    /// The number of measured builds of each scenario, after a warm-up build.
+    #[arg(long, default_value_t = 5)]
+    pub runs: usize,
+
+    /// The file touched before each build of the single file change scenario. Defaults to the `src/lib.rs` of the lib package.
+    #[arg(long)]
+    pub touch: Option<Utf8PathBuf>,
+
+    /// Where the JSON results are written. Defaults to `bench-pipeline.json` in the cargo target dir.
+    #[arg(long)]
+    pub output: Option<Utf8PathBuf>,
+
+    #[command(flatten)]
+    pub opts: Opts,
+}
+
+/// The results of a `bench-pipeline`, comparable between cargo-leptos versions.
+#[derive(Debug, Serialize)]
+struct BenchReport {
+    cargo_leptos: &'static str,
+    /// unix timestamp (seconds) of when the report was written
+    ran_at: u64,
+    runs: usize,
+    projects: Vec<ProjectBench>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProjectBench {
+    project: String,
+    release: bool,
+    scenarios: Vec<ScenarioBench>,
+}
+
+#[derive(Debug, Serialize)]
+struct ScenarioBench {
+    /// `no-op` or `change`
+    scenario: &'static str,
+    /// the file touched before each build of the `change` scenario
+    touched: Option<String>,
+    /// the wall time of the builds first, then the pipeline stages that ran
+    stages: Vec<StageStats>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct StageStats {
+    stage: &'static str,
+    /// the number of builds in which the stage ran
+    runs: usize,
+    p50_secs: f64,
+    p95_secs: f64,
+    max_secs: f64,
+}
+
+pub async fn bench_pipeline(conf: &Config, cmd: &BenchPipelineCommand) -> Result<()> {
+    if cmd.runs == 0 {
+        bail!("Bench needs at least one run");
+    }
+    // relative to the dir cargo-leptos was run in, and not to the workspace root
+    let output = match &cmd.output {
+        Some(file) => conf.cwd.join(file),
+        None => conf.target_dir.join("bench-pipeline.json"),
+    };
+    let mut projects = Vec::new();
+    for proj in &conf.projects {
+        let touched = match &cmd.touch {
+            Some(file) => conf.cwd.join(file),
+            None => proj.lib.abs_dir.join("src").join("lib.rs"),
+        };
+        if !touched.is_file() {
+            bail!("Bench can't touch the missing {touched}, choose another file with --touch");
+        }
+
+        log::info!("Bench {} warm-up build", proj.name);
+        bench_build(proj).await?;
+
+        let noop = bench_scenario(proj, cmd.runs, None).await?;
+        let change = bench_scenario(proj, cmd.runs, Some(&touched)).await?;
+        projects.push(ProjectBench {
+            project: proj.name.clone(),
+            release: proj.release,
+            scenarios: vec![
+                ScenarioBench {
+                    scenario: "no-op",
+                    touched: None,
+                    stages: noop,
+                },
+                ScenarioBench {
+                    scenario: "change",
+                    touched: Some(touched.to_string()),
+                    stages: change,
+                },
+            ],
+        });
+    }
+
+    let report = BenchReport {
+        cargo_leptos: env!("CARGO_PKG_VERSION"),
+        ran_at: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        runs: cmd.runs,
+        projects,
+    };
+    if let Some(dir) = output.parent().filter(|dir| !dir.as_str().is_empty()) {
+        fs::create_dir_all(dir).await?;
+    }
+    fs::write(&output, serde_json::to_string_pretty(&report)?).await?;
+    log::info!("Bench results written to {}", GRAY.paint(output.as_str()));
+    Ok(())
+}
+
+async fn bench_scenario(
+    proj: &Arc<Project>,
+    runs: usize,
+    touch: Option<&Utf8Path>,
+) -> Result<Vec<StageStats>> {
+    let scenario = if touch.is_some() { "change" } else { "no-op" };
+    let mut cycles = Vec::new();
+    for run in 1..=runs {
+        if let Some(file) = touch {
+            touch_file(file).await?;
+        }
+        log::info!("Bench {} {scenario} build {run}/{runs}", proj.name);
+        cycles.push(bench_build(proj).await?);
+    }
+    let stages = stage_stats(&cycles);
+    for stats in &stages {
+        log::info!(
+            "Bench {} {scenario} {} p50 {:.2}s p95 {:.2}s",
+            proj.name,
+            stats.stage,
+            stats.p50_secs,
+            stats.p95_secs
+        );
+    }
+    Ok(stages)
+}
+
+async fn bench_build(proj: &Arc<Project>) -> Result<CycleTimes> {
+    let recorder = Timings::start_cycle();
+    let started = Timings::start();
+    if !build_proj(proj).await? {
+        if Interrupt::is_shutdown_requested().await {
+            bail!("Bench interrupted");
+        }
+        bail!("Bench build of {} failed", proj.name);
+    }
+    Ok(recorder.finish(started))
+}
+
+/// Rewrites the file as is, which updates its modification time like an edit would.
+async fn touch_file(file: &Utf8Path) -> Result<()> {
+    let content = fs::read(file).await?;
+    fs::write(file, content).await
+}
+
+/// The percentiles of the total and of each stage, for the stages that ran in any cycle.
+fn stage_stats(cycles: &[CycleTimes]) -> Vec<StageStats> {
+    let mut samples: Vec<(&'static str, Vec<f64>)> = vec![(
+        "total",
+        cycles.iter().map(|c| c.total().as_secs_f64()).collect(),
+    )];
+    for cycle in cycles {
+        for (stage, duration) in cycle.phases() {
+            match samples.iter_mut().find(|(name, _)| *name == stage) {
+                Some((_, secs)) => secs.push(duration.as_secs_f64()),
+                None => samples.push((stage, vec![duration.as_secs_f64()])),
+            }
+        }
+    }
+    samples
+        .into_iter()
+        .map(|(stage, mut secs)| {
+            secs.sort_by(f64::total_cmp);
+            StageStats {
+                stage,
+                runs: secs.len(),
+                p50_secs: percentile(&secs, 50),
+                p95_secs: percentile(&secs, 95),
+                max_secs: secs.last().copied().unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+/// The nearest-rank percentile of the sorted samples.
+fn percentile(sorted: &[f64], pct: usize) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((pct * sorted.len() + 99) / 100).max(1);
+    sorted[rank - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles() {
+        let secs = (1..=20).map(f64::from).collect::<Vec<_>>();
+        assert_eq!(percentile(&secs, 50), 10.0);
+        assert_eq!(percentile(&secs, 95), 19.0);
+        assert_eq!(percentile(&[3.0], 95), 3.0);
+        assert_eq!(percentile(&[], 50), 0.0);
+    }
+}
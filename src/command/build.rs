@@ -2,52 +2,144 @@ use std::sync::Arc;
 
 use crate::{
     compile,
-    compile::ChangeSet,
-    config::{Config, Project},
+    compile::{ChangeSet, Pipeline, StageKeys},
+    config::{Config, Opts, Project},
     ext::{
-        anyhow::{anyhow, Context, Result},
-        fs,
+        anyhow::{Context, Result},
+        fs, LeptosError,
     },
+    service::hooks::{self, Hook},
+    signal::{BuildResult, Interrupt, Status, Timings},
 };
+use clap::Args;
+
+#[derive(Clone, Debug, Default, Args, PartialEq)]
+pub struct BuildCommand {
+    /// Run `cargo fmt --check` and clippy of the lib and bin (with their features, the warnings denied) before the build, and fail at the first gate that doesn't pass.
+    #[arg(long)]
+    pub strict: bool,
+
+    #[command(flatten)]
+    pub opts: Opts,
+}
+
+pub async fn build_all(conf: &Config, cmd: &BuildCommand) -> Result<()> {
+    if conf.cli.split_deps {
+        return super::build_deps_all(conf).await;
+    }
+
+    // the gates of all the projects, before any build
+    if cmd.strict {
+        for proj in &conf.projects {
+            super::strict::strict_gates(proj)
+                .await
+                .categorize(LeptosError::Compile {
+                    project: proj.name.clone(),
+                })?;
+        }
+    }
 
-pub async fn build_all(conf: &Config) -> Result<()> {
     let mut first_failed_project = None;
 
     for proj in &conf.projects {
-        if !build_proj(proj).await? && first_failed_project.is_none() {
+        let changes = compile::reusable_changes(proj).await.dot()?;
+        if !build_proj_with(proj, changes).await? && first_failed_project.is_none() {
             first_failed_project = Some(proj);
         }
     }
 
     if let Some(proj) = first_failed_project {
-        Err(anyhow!("Failed to build {}", proj.name))
+        Err(LeptosError::Compile {
+            project: proj.name.clone(),
+        }
+        .into())
     } else {
         Ok(())
     }
 }
 
+/// Logs the commands the build would run, without running anything.
+pub async fn dry_run_all(conf: &Config) -> Result<()> {
+    for proj in &conf.projects {
+        compile::dry_run(proj).await?;
+    }
+    Ok(())
+}
+
 /// Build the project. Returns true if the build was successful
 pub async fn build_proj(proj: &Arc<Project>) -> Result<bool> {
-    if proj.site.root_dir.exists() {
+    build_proj_with(proj, ChangeSet::all_changes()).await
+}
+
+/// Build the given changes of the project. Returns true if the build was successful
+pub async fn build_proj_with(proj: &Arc<Project>, changes: ChangeSet) -> Result<bool> {
+    Status::start_build();
+    let res = build_proj_steps(proj, &changes).await;
+    let result = match res {
+        Ok(true) => BuildResult::Success,
+        _ if Interrupt::is_shutdown_requested().await => BuildResult::Interrupted,
+        _ => BuildResult::Failed,
+    };
+    Status::finish_build(result, None);
+    res
+}
+
+async fn build_proj_steps(proj: &Arc<Project>, changes: &ChangeSet) -> Result<bool> {
+    // the site of a build without the front keeps the previous front
+    if changes.need_front_build() && proj.site.root_dir.exists() {
         fs::rm_dir_content(&proj.site.root_dir).await.dot()?;
     }
-    let changes = ChangeSet::all_changes();
-
-    if !compile::front(proj, &changes).await.await??.is_success() {
+    if !hooks::run(proj, Hook::PreBuild).await? {
         return Ok(false);
     }
-    if !compile::assets(proj, &changes, true)
-        .await
-        .await??
-        .is_success()
-    {
+    let keys = match proj.reuse_artifacts {
+        true => Some(StageKeys::of(proj).dot()?),
+        false => None,
+    };
+    compile::clear_seen_diagnostics();
+    let recorder = Timings::start_cycle();
+    let started = Timings::start();
+
+    // the front (lib, wasm-bindgen, wasm-opt), assets, style and server are
+    // independent of each other, so they are built concurrently
+    let outcomes = Pipeline::build(proj).run(proj, changes).await?;
+
+    if !outcomes.iter().all(|outcome| outcome.is_success()) {
         return Ok(false);
     }
-    if !compile::style(proj, &changes).await.await??.is_success() {
-        return Ok(false);
+    for locale_proj in &proj.locales {
+        if !build_locale(locale_proj, changes).await? {
+            return Ok(false);
+        }
     }
-    if !compile::server(proj, &changes).await.await??.is_success() {
-        return Ok(false);
+    let manifest = compile::write_manifest(proj).await.dot()?;
+    if let Some(keys) = keys {
+        compile::record_artifacts(proj, changes, keys, &manifest)
+            .await
+            .dot()?;
+    }
+    for proj in std::iter::once(proj).chain(&proj.locales) {
+        compile::write_preload_hints(proj).await.dot()?;
+    }
+    compile::write_sitemap(proj).await.dot()?;
+    let cycle = recorder.finish(started);
+    if proj.timings_dir.is_some() {
+        compile::write_timings(proj, &cycle).await.dot()?;
+    }
+    let passed = hooks::run(proj, Hook::PostBuild).await?;
+    if passed {
+        compile::check_precompressed(proj);
+        compile::check_wasm_profile(proj).await.dot()?;
     }
-    Ok(true)
+    Ok(passed)
+}
+
+/// Builds the front, assets and style of a locale variant into `<site-root>/<locale>`.
+/// The server is shared by all the locales.
+async fn build_locale(proj: &Arc<Project>, changes: &ChangeSet) -> Result<bool> {
+    let locale = proj.locale.as_deref().unwrap_or_default();
+    log::info!("Locale {locale} building");
+
+    let outcomes = Pipeline::build(proj).run(proj, changes).await?;
+    Ok(outcomes.iter().all(|outcome| outcome.is_success()))
 }
@@ -0,0 +1,168 @@
+use crate::{
+    compile::{ensure_target, toolchain_args, WASM_TARGET},
+    config::{CheckMatrixConfig, CheckTarget, Config, Opts, Project},
+    ext::{
+        anyhow::{bail, Context, Result},
+        sync::CommandResult,
+        tool::Tool,
+    },
+    logger::GRAY,
+    signal::Interrupt,
+};
+use clap::Args;
+use tokio::process::Command;
+
+#[derive(Clone, Debug, Args, PartialEq)]
+pub struct CheckAllCommand {
+    /// Run `cargo clippy` instead of `cargo check` in every cell.
+    #[arg(long)]
+    pub clippy: bool,
+
+    #[command(flatten)]
+    pub opts: Opts,
+}
+
+/// A feature set checked for a target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cell {
+    features: String,
+    target: CheckTarget,
+}
+
+impl std::fmt::Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.features, self.target)
+    }
+}
+
+pub async fn check_all(conf: &Config, cmd: &CheckAllCommand) -> Result<()> {
+    let mut failed = Vec::new();
+    let mut count = 0;
+    for proj in &conf.projects {
+        let mut results = Vec::new();
+        for cell in cells(&proj.check_matrix) {
+            let passed = check_cell(proj, &cell, cmd.clippy).await?;
+            // a partial matrix isn't a pass
+            if Interrupt::is_shutdown_requested().await {
+                bail!("Check all of {} interrupted at {cell}", proj.name);
+            }
+            results.push((cell, passed));
+        }
+        for (cell, passed) in &results {
+            if *passed {
+                log::info!("Check {} {cell} passed", proj.name);
+            } else {
+                log::warn!("Check {} {cell} failed", proj.name);
+                failed.push(format!("{} {cell}", proj.name));
+            }
+        }
+        count += results.len();
+    }
+    if !failed.is_empty() {
+        bail!(
+            "{} of the {count} checks failed: {}",
+            failed.len(),
+            failed.join(", ")
+        );
+    }
+    log::info!("Check all {count} checks passed");
+    Ok(())
+}
+
+fn cells(matrix: &CheckMatrixConfig) -> Vec<Cell> {
+    let mut cells = Vec::new();
+    for features in &matrix.features {
+        for target in &matrix.targets {
+            let cell = Cell {
+                features: features.replace(' ', ""),
+                target: *target,
+            };
+            if !matrix.skip.contains(&cell.to_string()) {
+                cells.push(cell);
+            }
+        }
+    }
+    cells
+}
+
+async fn check_cell(proj: &Project, cell: &Cell, clippy: bool) -> Result<bool> {
+    let toolchain = match cell.target {
+        CheckTarget::Native => proj.bin.toolchain.as_deref(),
+        CheckTarget::Wasm32 => proj.lib.toolchain.as_deref(),
+    };
+    if cell.target == CheckTarget::Wasm32 {
+        ensure_target(WASM_TARGET, toolchain, proj.toolchain_install).await?;
+    }
+
+    let args = check_args(proj, cell, clippy);
+    log::info!("Check {} {cell} {}", proj.name, GRAY.paint(args.join(" ")));
+    let mut command = Command::new("cargo");
+    command
+        .args(toolchain_args(toolchain))
+        .args(&args)
+        .envs(proj.to_cargo_envs());
+    let result = Tool::new("Cargo", command)
+        .with_timeouts(&proj.tool_timeouts)
+        .spawn()?
+        .wait(Interrupt::subscribe_any())
+        .await
+        .dot()?;
+    Ok(matches!(result, CommandResult::Success(_)))
+}
+
+/// The cargo arguments of a cell, checking the lib package with only the features of the cell.
+/// The native cells share the target dir of the server build, and the wasm ones the one of the front.
+fn check_args(proj: &Project, cell: &Cell, clippy: bool) -> Vec<String> {
+    let mut args = vec![
+        if clippy { "clippy" } else { "check" }.to_string(),
+        format!("--package={}", proj.lib.name),
+        "--lib".to_string(),
+        "--no-default-features".to_string(),
+        "--message-format=short".to_string(),
+    ];
+    if !cell.features.is_empty() {
+        args.push(format!("--features={}", cell.features));
+    }
+    match cell.target {
+        CheckTarget::Native => args.push(format!("--target-dir={}", proj.bin.target_dir)),
+        CheckTarget::Wasm32 => {
+            args.push(format!("--target={WASM_TARGET}"));
+            args.push(format!("--target-dir={}", proj.lib.target_dir));
+        }
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrix_cells() {
+        let names = cells(&CheckMatrixConfig::default())
+            .iter()
+            .map(Cell::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "ssr@native",
+                "hydrate@native",
+                "hydrate@wasm32",
+                "csr@native",
+                "csr@wasm32"
+            ]
+        );
+
+        let matrix = CheckMatrixConfig {
+            features: vec!["ssr, tracing".to_string(), String::new()],
+            targets: vec![CheckTarget::Native],
+            skip: vec![],
+        };
+        let names = cells(&matrix)
+            .iter()
+            .map(Cell::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["ssr,tracing@native", "@native"]);
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::{
+    compile::{front_cargo_command, server_cargo_command, toolchain_args},
+    config::{Config, Opts, Project},
+    ext::{
+        anyhow::{anyhow, Context, Result},
+        fs,
+        sync::CommandResult,
+        tool::Tool,
+        LeptosError, MetadataExt, PathExt,
+    },
+    logger::{GRAY, RESULT},
+    signal::Interrupt,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use cargo_metadata::Metadata;
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+/// Layer friendly builds for Docker: `prepare` a recipe from the full sources
+/// and `cook` it in a separate layer to build and cache only the dependencies.
+#[derive(Clone, Debug, Args, PartialEq)]
+pub struct ChefCommand {
+    #[command(subcommand)]
+    pub action: ChefAction,
+}
+
+#[derive(Clone, Debug, Subcommand, PartialEq)]
+pub enum ChefAction {
+    /// Write a recipe with the workspace manifests and the target skeleton.
+    Prepare {
+        /// Where to write the recipe.
+        #[arg(long, default_value = "recipe.json")]
+        recipe_path: Utf8PathBuf,
+    },
+    /// Recreate the workspace skeleton from a recipe and build only the dependencies.
+    Cook {
+        /// The recipe written by `chef prepare`.
+        #[arg(long, default_value = "recipe.json")]
+        recipe_path: Utf8PathBuf,
+
+        #[command(flatten)]
+        opts: Box<Opts>,
+    },
+}
+
+impl ChefCommand {
+    pub fn opts(&self) -> Option<Opts> {
+        match &self.action {
+            ChefAction::Prepare { .. } => None,
+            ChefAction::Cook { opts, .. } => Some(opts.as_ref().clone()),
+        }
+    }
+
+    /// Runs the part of the command that doesn't need the leptos config.
+    /// Returns true if the command is done.
+    pub async fn run_without_config(&self, manifest_path: &Utf8Path) -> Result<bool> {
+        match &self.action {
+            ChefAction::Prepare { recipe_path } => {
+                let recipe = Recipe::prepare(manifest_path).dot()?;
+                let json = serde_json::to_string_pretty(&recipe).dot()?;
+                fs::write(recipe_path, json).await?;
+                log::info!(
+                    "Chef recipe written to {}",
+                    GRAY.paint(recipe_path.as_str())
+                );
+                Ok(true)
+            }
+            ChefAction::Cook { recipe_path, .. } => {
+                let json = fs::read_to_string(recipe_path).await?;
+                let recipe: Recipe = serde_json::from_str(&json)
+                    .context(format!("Could not parse the recipe {recipe_path}"))?;
+                recipe
+                    .write_skeleton(&recipe.workspace_root(manifest_path))
+                    .await?;
+                Ok(false)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Recipe {
+    /// the manifest `prepare` ran with, relative to the workspace root
+    #[serde(default = "root_manifest")]
+    manifest: Utf8PathBuf,
+    /// manifests and lock file, relative to the workspace root
+    files: Vec<RecipeFile>,
+    /// target source files that get a dummy content
+    skeleton: Vec<SkeletonFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RecipeFile {
+    path: Utf8PathBuf,
+    contents: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SkeletonFile {
+    path: Utf8PathBuf,
+    /// true for targets that need a `fn main`
+    main: bool,
+}
+
+impl Recipe {
+    fn prepare(manifest_path: &Utf8Path) -> Result<Self> {
+        let metadata = Metadata::load_cleaned(manifest_path)?;
+        let root = &metadata.workspace_root;
+
+        let mut manifests = vec![root.join("Cargo.toml"), root.join("Cargo.lock")];
+        let mut skeleton = Vec::new();
+
+        for package in metadata.workspace_packages() {
+            manifests.push(package.manifest_path.clone());
+            for target in &package.targets {
+                let main = target.kind.iter().any(|k| {
+                    matches!(
+                        k.as_str(),
+                        "bin" | "custom-build" | "example" | "test" | "bench"
+                    )
+                });
+                skeleton.push(SkeletonFile {
+                    path: target.src_path.unbase(root)?,
+                    main,
+                });
+            }
+        }
+
+        let manifest = manifest_path
+            .canonicalize_utf8()
+            .context(format!("Could not resolve {manifest_path}"))?
+            .unbase(root)?;
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+        for path in manifests {
+            if !path.exists() || !seen.insert(path.clone()) {
+                continue;
+            }
+            files.push(RecipeFile {
+                contents: std::fs::read_to_string(&path)
+                    .context(format!("Could not read {path}"))?,
+                path: path.unbase(root)?,
+            });
+        }
+        Ok(Self {
+            manifest,
+            files,
+            skeleton,
+        })
+    }
+
+    /// The workspace root the recipe was prepared from, as `cook` sees it: the dir the member
+    /// manifest of `prepare` is in when cooking with the same one, else the dir of the root
+    /// manifest.
+    fn workspace_root(&self, manifest_path: &Utf8Path) -> Utf8PathBuf {
+        let manifest_dir = manifest_path.parent().unwrap_or(Utf8Path::new(""));
+        let member_dir = self.manifest.parent().unwrap_or(Utf8Path::new(""));
+        let depth = member_dir.components().count();
+        if depth > 0 && manifest_dir.ends_with(member_dir) {
+            if let Some(root) = manifest_dir.ancestors().nth(depth) {
+                return root.to_path_buf();
+            }
+        }
+        manifest_dir.to_path_buf()
+    }
+
+    /// Writes the manifests and dummy sources. Existing files are never overwritten.
+    async fn write_skeleton(&self, root: &Utf8Path) -> Result<()> {
+        let files = self.files.iter().map(|f| (&f.path, f.contents.as_str()));
+        let skeleton = self
+            .skeleton
+            .iter()
+            .map(|f| (&f.path, if f.main { "fn main() {}\n" } else { "" }));
+
+        for (path, contents) in files.chain(skeleton) {
+            let path = root.join(path);
+            if path.exists() {
+                log::debug!("Chef keeping existing {}", GRAY.paint(path.as_str()));
+                continue;
+            }
+            if let Some(dir) = path.parent().filter(|d| !d.as_str().is_empty()) {
+                fs::create_dir_all(dir).await?;
+            }
+            fs::write(&path, contents).await?;
+            log::trace!("Chef wrote {}", GRAY.paint(path.as_str()));
+        }
+        Ok(())
+    }
+}
+
+fn root_manifest() -> Utf8PathBuf {
+    "Cargo.toml".into()
+}
+
+/// Builds only the dependencies of the front and server, for caching them
+/// in a separate (Docker) layer. The workspace crates' own artifacts are removed
+/// afterwards so that they are always rebuilt from the real sources.
+pub async fn build_deps_all(conf: &Config) -> Result<()> {
+    for proj in &conf.projects {
+        if !build_deps_proj(conf, proj).await? {
+            return Err(anyhow!("The dependencies could not be built")).categorize(
+                LeptosError::Compile {
+                    project: proj.name.clone(),
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn build_deps_proj(conf: &Config, proj: &Arc<Project>) -> Result<bool> {
+    let (envs, line, command) = front_cargo_command("build", true, proj).await;
+    if !wait_cargo(proj, command).await? {
+        return Ok(false);
+    }
+    log::debug!("Cargo envs: {}", GRAY.paint(envs));
+    log::info!(target: RESULT, "Cargo finished {}", GRAY.paint(line));
+
+    let (envs, line, command) = server_cargo_command("build", proj);
+    if !wait_cargo(proj, command).await? {
+        return Ok(false);
+    }
+    log::debug!("Cargo envs: {}", GRAY.paint(envs));
+    log::info!(target: RESULT, "Cargo finished {}", GRAY.paint(line));
+
+    clean_workspace_packages(conf, proj).await
+}
+
+async fn wait_cargo(proj: &Project, command: Command) -> Result<bool> {
+    let process = Tool::new("Cargo", command)
+        .with_timeouts(&proj.tool_timeouts)
+        .spawn()?;
+    Ok(matches!(
+        process.wait(Interrupt::subscribe_any()).await?,
+        CommandResult::Success(_)
+    ))
+}
+
+/// The packages of the workspace of the manifest `cook` wrote the skeleton with.
+async fn clean_workspace_packages(conf: &Config, proj: &Project) -> Result<bool> {
+    let metadata = Metadata::load_cleaned(&conf.manifest_path)?;
+    let packages = metadata
+        .workspace_packages()
+        .iter()
+        .map(|p| format!("--package={}", p.name))
+        .collect::<Vec<_>>();
+
+    let mut front_args = vec![
+        format!("--target-dir={}", proj.lib.target_dir),
+        "--target=wasm32-unknown-unknown".to_string(),
+    ];
+    proj.lib.profile.add_to_args(&mut front_args);
+
+    let mut server_args = vec![format!("--target-dir={}", proj.bin.target_dir)];
+    if let Some(triple) = &proj.bin.target_triple {
+        server_args.push(format!("--target={triple}"));
+    }
+    proj.bin.profile.add_to_args(&mut server_args);
+
+    let front_toolchain = toolchain_args(proj.lib.toolchain.as_deref());
+    let server_toolchain = toolchain_args(proj.bin.toolchain.as_deref());
+    for (toolchain, args) in [
+        (front_toolchain, front_args),
+        (server_toolchain, server_args),
+    ] {
+        let mut command = Command::new("cargo");
+        command
+            .args(&toolchain)
+            .arg("clean")
+            .args(&packages)
+            .args(&args);
+        let line = format!(
+            "cargo {}clean {} {}",
+            toolchain
+                .iter()
+                .map(|t| format!("{t} "))
+                .collect::<String>(),
+            packages.join(" "),
+            args.join(" ")
+        );
+        if !wait_cargo(proj, command).await? {
+            log::warn!(
+                "Chef could not remove workspace artifacts {}",
+                GRAY.paint(line)
+            );
+            return Ok(false);
+        }
+        log::debug!("Chef removed workspace artifacts {}", GRAY.paint(line));
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(manifest: &str) -> Recipe {
+        Recipe {
+            manifest: manifest.into(),
+            files: Vec::new(),
+            skeleton: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn cook_uses_the_workspace_root_of_prepare() {
+        let member = recipe("crates/app/Cargo.toml");
+        assert_eq!(
+            member.workspace_root(Utf8Path::new("/ws/crates/app/Cargo.toml")),
+            "/ws"
+        );
+        // cooked from the root manifest
+        assert_eq!(
+            member.workspace_root(Utf8Path::new("/ws/Cargo.toml")),
+            "/ws"
+        );
+        // a dir merely ending with the name of the member dir
+        let member = recipe("app/Cargo.toml");
+        assert_eq!(
+            member.workspace_root(Utf8Path::new("/ws/myapp/Cargo.toml")),
+            "/ws/myapp"
+        );
+
+        let root = recipe("Cargo.toml");
+        assert_eq!(root.workspace_root(Utf8Path::new("/ws/Cargo.toml")), "/ws");
+    }
+}
@@ -0,0 +1,19 @@
+use std::io;
+
+use crate::{config::Cli, ext::anyhow::Result};
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+/// The name of the binary, which cargo runs for `cargo leptos`
+const BIN_NAME: &str = "cargo-leptos";
+
+/// Prints the completions script for the shell, generated from the clap definitions.
+pub fn completions(shell: Shell) {
+    clap_complete::generate(shell, &mut Cli::command(), BIN_NAME, &mut io::stdout());
+}
+
+/// Prints the man page, generated from the clap definitions.
+pub fn man() -> Result<()> {
+    clap_mangen::Man::new(Cli::command()).render(&mut io::stdout())?;
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use std::{
+    env,
+    fs::File,
+    process::{self, Stdio},
+    sync::Arc,
+};
+
+use crate::{
+    config::{Config, Opts, Project},
+    ext::{
+        anyhow::{anyhow, Context, Result},
+        fs,
+    },
+    logger::GRAY,
+    service::{
+        control::{self, DaemonInfo},
+        profile,
+    },
+    signal::Interrupt,
+};
+use clap::{Args, Subcommand};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+#[derive(Clone, Debug, Args, PartialEq)]
+pub struct DaemonCommand {
+    /// Run in this terminal instead of detaching, the logs are then printed and not written to the log file.
+    #[arg(long)]
+    pub foreground: bool,
+
+    #[command(flatten)]
+    pub opts: Opts,
+}
+
+#[derive(Clone, Debug, Args, PartialEq)]
+pub struct CtlCommand {
+    #[command(subcommand)]
+    pub action: CtlAction,
+
+    #[command(flatten)]
+    pub opts: Opts,
+}
+
+#[derive(Clone, Debug, Subcommand, PartialEq)]
+pub enum CtlAction {
+    /// Print the build state of the daemon as json.
+    Status,
+    /// Rebuild everything, as if all the sources changed.
+    Rebuild,
+    /// Stop the daemon and its server.
+    Stop,
+    /// Print the last lines of the daemon's log file.
+    Logs {
+        /// The number of lines to print.
+        #[arg(short = 'n', long, default_value = "50")]
+        lines: usize,
+    },
+    /// Record a flamegraph of the running server, written to `target/leptos-profiles`. Needs `cargo install flamegraph`.
+    Profile {
+        /// The number of seconds to record.
+        #[arg(short, long, default_value_t = profile::DEFAULT_SECONDS)]
+        seconds: u64,
+    },
+}
+
+/// Watches the project in the background, controlled with `cargo leptos ctl`.
+pub async fn daemon(config: &Config, cmd: &DaemonCommand) -> Result<()> {
+    let proj = &config.current_project()?;
+    // checked before detaching too, to report it in the terminal
+    if let Some(info) = running(proj).await {
+        return Err(anyhow!(
+            "A daemon is already running for {} (pid {})",
+            proj.name,
+            info.pid
+        ));
+    }
+    if !cmd.foreground {
+        return detach(proj).await;
+    }
+    let control = control::spawn(proj).await?;
+    let res = super::watch(config).await;
+    // lets the control socket remove its info file before exiting
+    Interrupt::request_shutdown().await;
+    _ = control.await;
+    res
+}
+
+/// Starts the daemon again as a background process, with its output in the log file.
+async fn detach(proj: &Project) -> Result<()> {
+    fs::create_dir_all(&proj.cache_dir).await?;
+    let log_file = control::log_file(proj);
+    let log = File::create(&log_file).context(format!("Could not create {log_file}"))?;
+
+    let exe = env::current_exe().context("Could not find the running cargo-leptos")?;
+    let mut cmd = process::Command::new(exe);
+    cmd.args(env::args().skip(1))
+        .arg("--foreground")
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    // so that the daemon isn't stopped with the terminal
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        cmd.creation_flags(DETACHED_PROCESS);
+    }
+    let child = cmd.spawn().context("Could not start the daemon")?;
+    log::info!(
+        "Daemon started for {} (pid {}), logs in {}",
+        proj.name,
+        child.id(),
+        GRAY.paint(log_file.as_str())
+    );
+    Ok(())
+}
+
+/// The info of the daemon of the project, if one is listening.
+async fn running(proj: &Project) -> Option<DaemonInfo> {
+    let content = fs::read_to_string(control::info_file(proj)).await.ok()?;
+    let info: DaemonInfo = serde_json::from_str(&content).ok()?;
+    TcpStream::connect(info.addr).await.ok()?;
+    Some(info)
+}
+
+/// Sends the action to the daemon of the project and prints the response.
+pub async fn ctl(proj: &Arc<Project>, cmd: &CtlCommand) -> Result<()> {
+    let info = running(proj).await.ok_or_else(|| {
+        anyhow!(
+            "No daemon is running for {}, start one with `cargo leptos daemon`",
+            proj.name
+        )
+    })?;
+    let request = match &cmd.action {
+        CtlAction::Status => "status".to_string(),
+        CtlAction::Rebuild => "rebuild".to_string(),
+        CtlAction::Stop => "stop".to_string(),
+        CtlAction::Logs { lines } => format!("logs {lines}"),
+        CtlAction::Profile { seconds } => format!("profile {seconds}"),
+    };
+    let mut stream = TcpStream::connect(info.addr).await?;
+    stream
+        .write_all(format!("{} {request}\n", info.token).as_bytes())
+        .await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    print!("{response}");
+    Ok(())
+}
@@ -0,0 +1,325 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::{
+    compile::dir_files,
+    ext::anyhow::{bail, Context, Result},
+    logger::GRAY,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Args;
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Args, PartialEq, Eq)]
+pub struct DiffCommand {
+    /// The build manifest (json) or the site dir of the base build, e.g. of the main branch.
+    pub base: Utf8PathBuf,
+
+    /// The build manifest (json) or the site dir of the build to compare, e.g. of the pull request.
+    pub head: Utf8PathBuf,
+
+    /// Write the markdown summary to the file instead of stdout.
+    #[arg(long)]
+    pub output: Option<Utf8PathBuf>,
+}
+
+impl DiffCommand {
+    pub async fn run(&self) -> Result<()> {
+        let base = Build::read(&self.base)?;
+        let head = Build::read(&self.head)?;
+        let report = report(&base, &head);
+        match &self.output {
+            Some(file) => {
+                std::fs::write(file, report).context(format!("Could not write {file}"))?;
+                log::info!("Diff written {}", GRAY.paint(file.as_str()));
+            }
+            None => print!("{report}"),
+        }
+        Ok(())
+    }
+}
+
+/// The site files of a build, by site path.
+#[derive(Debug, Default)]
+struct Build {
+    files: BTreeMap<String, BuildFile>,
+}
+
+#[derive(Debug, Default)]
+struct BuildFile {
+    /// one of: wasm, js, css, snippet, server or asset
+    kind: String,
+    size: u64,
+    hash: String,
+    /// the exported symbols, for the wasm file
+    exports: Option<BTreeSet<String>>,
+}
+
+/// The fields of the build manifest needed for the diff.
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestFile {
+    kind: String,
+    path: Utf8PathBuf,
+    site: Option<String>,
+    size: u64,
+    hash: String,
+}
+
+impl Build {
+    fn read(path: &Utf8Path) -> Result<Self> {
+        if path.is_dir() {
+            Ok(Self::from_site_dir(path))
+        } else if path.is_file() {
+            Self::from_manifest(path)
+        } else {
+            bail!("Diff expected a build manifest or a site dir, found nothing at {path}")
+        }
+    }
+
+    fn from_manifest(file: &Utf8Path) -> Result<Self> {
+        let json = std::fs::read_to_string(file).context(format!("Could not read {file}"))?;
+        let manifest: Manifest =
+            serde_json::from_str(&json).context(format!("Could not parse the manifest {file}"))?;
+
+        let mut files = BTreeMap::new();
+        for f in manifest.files {
+            let exports = if f.kind == "wasm" {
+                wasm_path(file, &f.path).and_then(|path| wasm_exports(&path))
+            } else {
+                None
+            };
+            let key = f.site.unwrap_or_else(|| f.path.to_string());
+            let file = BuildFile {
+                kind: f.kind,
+                size: f.size,
+                hash: f.hash,
+                exports,
+            };
+            files.insert(key, file);
+        }
+        Ok(Self { files })
+    }
+
+    fn from_site_dir(dir: &Utf8Path) -> Self {
+        let mut files = BTreeMap::new();
+        for path in dir_files(dir) {
+            let Ok(data) = std::fs::read(&path) else {
+                continue;
+            };
+            let site = path.strip_prefix(dir).unwrap_or(&path);
+            let kind = match site.extension() {
+                _ if site.components().any(|c| c.as_str() == "snippets") => "snippet",
+                Some("wasm") => "wasm",
+                Some("js") => "js",
+                Some("css") => "css",
+                _ => "asset",
+            };
+            let file = BuildFile {
+                kind: kind.to_string(),
+                size: data.len() as u64,
+                hash: format!("{:016x}", seahash::hash(&data)),
+                exports: (kind == "wasm").then(|| wasm_exports(&path)).flatten(),
+            };
+            // always use `/` as separator for the site path
+            let site = site.as_str().replace('\\', "/");
+            files.insert(site, file);
+        }
+        Self { files }
+    }
+
+    fn of_kind<'a>(&'a self, kind: &'a str) -> impl Iterator<Item = (&'a String, &'a BuildFile)> {
+        self.files.iter().filter(move |(_, file)| file.kind == kind)
+    }
+}
+
+/// The manifest paths are relative to the working dir of the build, which isn't
+/// necessarily the current one, so the dirs of the manifest are tried too.
+fn wasm_path(manifest: &Utf8Path, path: &Utf8Path) -> Option<Utf8PathBuf> {
+    if path.exists() {
+        return Some(path.to_path_buf());
+    }
+    let found = manifest
+        .ancestors()
+        .skip(1)
+        .map(|dir| dir.join(path))
+        .find(|path| path.exists());
+    if found.is_none() {
+        log::warn!("Diff could not find the wasm {path}, skipping its exports");
+    }
+    found
+}
+
+fn wasm_exports(path: &Utf8Path) -> Option<BTreeSet<String>> {
+    let module = std::fs::read(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|data| walrus::Module::from_buffer(&data));
+    match module {
+        Ok(module) => Some(module.exports.iter().map(|e| e.name.clone()).collect()),
+        Err(e) => {
+            log::warn!("Diff could not read the wasm exports of {path}: {e}");
+            None
+        }
+    }
+}
+
+/// The markdown summary of the differences, for posting on pull requests.
+fn report(base: &Build, head: &Build) -> String {
+    let mut md = String::from("## Leptos build diff\n\n");
+
+    md.push_str("| Artifact | Base | Head | Change |\n|---|---:|---:|---:|\n");
+    for kind in ["wasm", "wasm-split", "js", "css", "snippet", "server"] {
+        let base_size = total_size(base, kind);
+        let head_size = total_size(head, kind);
+        if base_size.is_none() && head_size.is_none() {
+            continue;
+        }
+        let (base_size, head_size) = (base_size.unwrap_or(0), head_size.unwrap_or(0));
+        writeln!(
+            md,
+            "| {kind} | {} | {} | {} |",
+            human_size(base_size),
+            human_size(head_size),
+            size_change(base_size, head_size)
+        )
+        .unwrap();
+    }
+
+    let base_exports = exports(base);
+    let head_exports = exports(head);
+    if let (Some(base_exports), Some(head_exports)) = (base_exports, head_exports) {
+        let added = head_exports.difference(&base_exports).collect::<Vec<_>>();
+        let removed = base_exports.difference(&head_exports).collect::<Vec<_>>();
+        md.push_str("\n### Wasm exports\n\n");
+        if added.is_empty() && removed.is_empty() {
+            md.push_str("No changes.\n");
+        }
+        for (title, symbols) in [("Added", added), ("Removed", removed)] {
+            if !symbols.is_empty() {
+                let symbols = symbols.iter().map(|s| format!("`{s}`")).collect::<Vec<_>>();
+                writeln!(md, "- {title}: {}", symbols.join(", ")).unwrap();
+            }
+        }
+    }
+
+    let mut assets = Vec::new();
+    let sites = base
+        .of_kind("asset")
+        .chain(head.of_kind("asset"))
+        .map(|(site, _)| site)
+        .collect::<BTreeSet<_>>();
+    for site in sites {
+        let change = match (base.files.get(site), head.files.get(site)) {
+            (None, Some(file)) => format!("added ({})", human_size(file.size)),
+            (Some(file), None) => format!("removed ({})", human_size(file.size)),
+            (Some(old), Some(new)) if old.hash != new.hash => {
+                format!("changed ({})", size_change(old.size, new.size))
+            }
+            _ => continue,
+        };
+        assets.push(format!("| `{site}` | {change} |"));
+    }
+    md.push_str("\n### Assets\n\n");
+    if assets.is_empty() {
+        md.push_str("No changes.\n");
+    } else {
+        md.push_str("| Asset | Change |\n|---|---|\n");
+        for line in assets {
+            md.push_str(&line);
+            md.push('\n');
+        }
+    }
+    md
+}
+
+fn total_size(build: &Build, kind: &str) -> Option<u64> {
+    let mut files = build.of_kind(kind).peekable();
+    files.peek()?;
+    Some(files.map(|(_, file)| file.size).sum())
+}
+
+/// The exports of the wasm files, if they could all be read.
+fn exports(build: &Build) -> Option<BTreeSet<String>> {
+    let mut exports = BTreeSet::new();
+    for (_, file) in build.of_kind("wasm") {
+        exports.extend(file.exports.clone()?);
+    }
+    Some(exports)
+}
+
+fn size_change(base: u64, head: u64) -> String {
+    let delta = head as i64 - base as i64;
+    let sign = if delta < 0 { "-" } else { "+" };
+    let size = human_size(delta.unsigned_abs());
+    if base == 0 {
+        format!("{sign}{size}")
+    } else {
+        format!(
+            "{sign}{size} ({sign}{:.1}%)",
+            delta.abs() as f64 * 100.0 / base as f64
+        )
+    }
+}
+
+pub(super) fn human_size(size: u64) -> String {
+    match size {
+        s if s < 1000 => format!("{s} B"),
+        s if s < 1_000_000 => format!("{:.1} kB", s as f64 / 1000.0),
+        s => format!("{:.2} MB", s as f64 / 1_000_000.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(files: &[(&str, &str, u64, &str)]) -> Build {
+        let files = files
+            .iter()
+            .map(|(site, kind, size, hash)| {
+                let file = BuildFile {
+                    kind: kind.to_string(),
+                    size: *size,
+                    hash: hash.to_string(),
+                    exports: None,
+                };
+                (site.to_string(), file)
+            })
+            .collect();
+        Build { files }
+    }
+
+    #[test]
+    fn markdown_report() {
+        let mut base = build(&[
+            ("pkg/app.wasm", "wasm", 2_000_000, "a"),
+            ("pkg/app.css", "css", 1000, "b"),
+            ("favicon.ico", "asset", 500, "c"),
+            ("old.png", "asset", 100, "d"),
+        ]);
+        let mut head = build(&[
+            ("pkg/app.wasm", "wasm", 2_100_000, "e"),
+            ("pkg/app.css", "css", 1000, "b"),
+            ("favicon.ico", "asset", 600, "f"),
+            ("new.png", "asset", 200, "g"),
+        ]);
+        let exports = |names: &[&str]| Some(names.iter().map(|n| n.to_string()).collect());
+        base.files.get_mut("pkg/app.wasm").unwrap().exports = exports(&["hydrate", "old"]);
+        head.files.get_mut("pkg/app.wasm").unwrap().exports = exports(&["hydrate", "new"]);
+
+        let report = report(&base, &head);
+        assert!(
+            report.contains("| wasm | 2.00 MB | 2.10 MB | +100.0 kB (+5.0%) |"),
+            "{report}"
+        );
+        assert!(report.contains("| css | 1.0 kB | 1.0 kB | +0 B (+0.0%) |"));
+        assert!(report.contains("- Added: `new`\n- Removed: `old`\n"));
+        assert!(report.contains("| `favicon.ico` | changed (+100 B (+20.0%)) |"));
+        assert!(report.contains("| `new.png` | added (200 B) |"));
+        assert!(report.contains("| `old.png` | removed (100 B) |"));
+    }
+}
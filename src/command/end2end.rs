@@ -6,7 +6,7 @@ use tokio::process::Command;
 
 use crate::config::{Config, Project};
 use crate::ext::anyhow::{anyhow, Context, Result};
-use crate::service::serve;
+use crate::service::{serve, tls};
 use crate::signal::Interrupt;
 
 pub async fn end2end_all(conf: &Config) -> Result<()> {
@@ -22,6 +22,7 @@ pub async fn end2end_proj(proj: &Arc<Project>) -> Result<()> {
             return Ok(());
         }
 
+        tls::ensure_cert(proj).await?;
         let server = serve::spawn(proj).await;
         try_run(&e2e.cmd, &e2e.dir)
             .await
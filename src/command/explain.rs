@@ -0,0 +1,25 @@
+use crate::ext::{
+    anyhow::{bail, Result},
+    ErrorCode,
+};
+
+/// Prints the remediation text of the error code, or lists the codes when none is given.
+pub fn explain(code: Option<&str>) -> Result<()> {
+    let Some(code) = code else {
+        for code in ErrorCode::ALL {
+            println!("{}  {}", code.code(), code.summary());
+        }
+        return Ok(());
+    };
+    let Some(code) = ErrorCode::parse(code) else {
+        let codes = ErrorCode::ALL.map(|c| c.code()).join(", ");
+        bail!("Unknown error code {code}, the codes are: {codes}");
+    };
+    println!(
+        "{}: {}\n\n{}",
+        code.code(),
+        code.summary(),
+        code.explanation()
+    );
+    Ok(())
+}
@@ -1,13 +1,42 @@
+mod analyze;
+mod bench_pipeline;
 mod build;
+mod check_all;
+mod chef;
+mod completions;
+mod daemon;
+mod diff;
 mod end2end;
+mod explain;
 mod new;
+mod pack;
+mod prerender;
+mod prune_assets;
 mod serve;
+mod show_config;
+mod strict;
 mod test;
+mod tools;
+mod update_self;
 pub mod watch;
 
-pub use build::build_all;
+pub use analyze::{analyze_all, AnalyzeCommand};
+pub use bench_pipeline::{bench_pipeline, BenchPipelineCommand};
+pub use build::{build_all, dry_run_all, BuildCommand};
+pub use check_all::{check_all, CheckAllCommand};
+pub use chef::{build_deps_all, ChefCommand};
+pub use completions::{completions, man};
+pub use daemon::{ctl, daemon, CtlCommand, DaemonCommand};
+pub use diff::DiffCommand;
 pub use end2end::end2end_all;
+pub use explain::explain;
 pub use new::NewCommand;
+pub use pack::{pack_all, PackCommand};
+pub use prerender::{prerender_all, PrerenderCommand};
+pub use prune_assets::{prune_assets_all, PruneAssetsCommand};
 pub use serve::serve;
+pub use show_config::show_config;
 pub use test::test_all;
+pub use tools::{tools, ToolsCommand};
+pub use update_self::UpdateSelfCommand;
 pub use watch::watch;
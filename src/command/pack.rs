@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::sync::Arc;
+
+use crate::{
+    compile::{self, dir_files},
+    config::{target_dir_name, Config, Opts, Project},
+    ext::{
+        anyhow::{anyhow, Context, Result},
+        fs, LeptosError,
+    },
+    logger::GRAY,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{Args, ValueEnum};
+use flate2::{write::GzEncoder, Compression};
+use tar::HeaderMode;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PackFormat {
+    /// A single tar.gz archive
+    Tar,
+    /// A directory with a generated minimal Dockerfile, ready for `docker build`
+    Dir,
+}
+
+#[derive(Clone, Debug, Args, PartialEq)]
+pub struct PackCommand {
+    /// The format of the deployment package.
+    #[arg(long, value_enum, default_value = "tar")]
+    pub format: PackFormat,
+
+    /// Where to write the package. Defaults to the `pack` dir of the cargo target dir.
+    #[arg(long)]
+    pub out_dir: Option<Utf8PathBuf>,
+
+    #[command(flatten)]
+    pub opts: Opts,
+}
+
+pub async fn pack_all(conf: &Config, cmd: &PackCommand) -> Result<()> {
+    // relative to the dir cargo-leptos was run in, and not to the workspace root
+    let out_dir = match &cmd.out_dir {
+        Some(dir) => conf.cwd.join(dir),
+        None => conf.target_dir.join("pack"),
+    };
+    for proj in &conf.projects {
+        if !super::build::build_proj(proj).await.dot()? {
+            return Err(LeptosError::Compile {
+                project: proj.name.clone(),
+            }
+            .into());
+        }
+        pack_proj(proj, cmd, &out_dir).await?;
+    }
+    Ok(())
+}
+
+async fn pack_proj(proj: &Arc<Project>, cmd: &PackCommand, out_dir: &Utf8Path) -> Result<()> {
+    let name = package_name(proj).await?;
+    let dir = out_dir.join(&name);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).await?;
+    }
+    fs::create_dir_all(&dir).await?;
+
+    let exe_name = proj
+        .bin
+        .exe_file
+        .file_name()
+        .ok_or_else(|| anyhow!("No server binary name in {}", proj.bin.exe_file))?;
+    fs::copy(&proj.bin.exe_file, dir.join(exe_name)).await?;
+    fs::copy_dir_all(&proj.site.root_dir, dir.join("site")).await?;
+    fs::write(dir.join(".env.example"), env_template(proj)).await?;
+
+    match cmd.format {
+        PackFormat::Dir => {
+            fs::write(dir.join("Dockerfile"), dockerfile(proj, exe_name)).await?;
+            log::info!("Pack created {}", GRAY.paint(dir.as_str()));
+        }
+        PackFormat::Tar => {
+            let file = out_dir.join(format!("{name}.tar.gz"));
+            write_tar(&dir, &name, &file, proj.source_date_epoch)
+                .context(format!("Could not write {file}"))?;
+            fs::remove_dir_all(&dir).await?;
+            log::info!("Pack created {}", GRAY.paint(file.as_str()));
+        }
+    }
+    Ok(())
+}
+
+/// `<name>-<version>-<target triple>`, using the host triple of the server toolchain if no
+/// triple is set, and the name of the spec file of a custom target
+async fn package_name(proj: &Project) -> Result<String> {
+    let triple = match &proj.bin.target_triple {
+        Some(triple) => target_dir_name(triple),
+        None => compile::host_triple(proj.bin.toolchain.as_deref()).await?,
+    };
+    Ok(format!("{}-{}-{triple}", proj.name, proj.bin.version))
+}
+
+/// With a source date epoch the files are archived sorted, with its mtime and without owners,
+/// for a byte-identical archive (the gzip header has no mtime).
+fn write_tar(dir: &Utf8Path, name: &str, file: &Utf8Path, epoch: Option<u64>) -> Result<()> {
+    let enc = GzEncoder::new(File::create(file)?, Compression::default());
+    let mut tar = tar::Builder::new(enc);
+    match epoch {
+        None => tar.append_dir_all(name, dir)?,
+        Some(epoch) => {
+            for path in dir_files(dir) {
+                let rel = path.strip_prefix(dir)?;
+                let mut header = tar::Header::new_gnu();
+                header.set_metadata_in_mode(&path.metadata()?, HeaderMode::Deterministic);
+                header.set_mtime(epoch);
+                let data = std::fs::read(&path)?;
+                tar.append_data(&mut header, Utf8Path::new(name).join(rel), data.as_slice())?;
+            }
+        }
+    }
+    tar.into_inner()?.finish()?;
+    Ok(())
+}
+
+fn env_template(proj: &Project) -> String {
+    let mut addr = proj.site.addr;
+    addr.set_ip([0, 0, 0, 0].into());
+    [
+        format!("LEPTOS_OUTPUT_NAME={}", proj.lib.output_name),
+        "LEPTOS_SITE_ROOT=site".to_string(),
+        format!("LEPTOS_SITE_PKG_DIR={}", proj.site.pkg_dir),
+        format!("LEPTOS_SITE_ADDR={addr}"),
+        format!("LEPTOS_RELOAD_PORT={}", proj.site.reload.port()),
+        String::new(),
+    ]
+    .join("\n")
+}
+
+fn dockerfile(proj: &Project, exe_name: &str) -> String {
+    let port = proj.site.addr.port();
+    format!(
+        r#"FROM debian:bookworm-slim
+WORKDIR /app
+COPY {exe_name} /app/
+COPY site /app/site
+ENV LEPTOS_OUTPUT_NAME="{}"
+ENV LEPTOS_SITE_ROOT="site"
+ENV LEPTOS_SITE_PKG_DIR="{}"
+ENV LEPTOS_SITE_ADDR="0.0.0.0:{port}"
+EXPOSE {port}
+CMD ["/app/{exe_name}"]
+"#,
+        proj.lib.output_name, proj.site.pkg_dir
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn reproducible_tar() {
+        let dir = TempDir::new().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        let site = root.join("app");
+        std::fs::create_dir_all(site.join("site/pkg")).unwrap();
+        std::fs::write(site.join("site/pkg/app.js"), "let a = 1;").unwrap();
+        std::fs::write(site.join("app"), "binary").unwrap();
+
+        let (first, second) = (root.join("first.tar.gz"), root.join("second.tar.gz"));
+        write_tar(&site, "app", &first, Some(1_700_000_000)).unwrap();
+        // rewritten, with a newer mtime
+        std::fs::write(site.join("app"), "binary").unwrap();
+        write_tar(&site, "app", &second, Some(1_700_000_000)).unwrap();
+        assert_eq!(
+            std::fs::read(first).unwrap(),
+            std::fs::read(second).unwrap()
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+};
+
+use crate::{
+    compile::RouteManifest,
+    config::{Config, Opts, Project},
+    ext::{
+        anyhow::{bail, Context, Result},
+        fs,
+        sync::wait_for_socket,
+        LeptosError,
+    },
+    logger::GRAY,
+    service::{serve, tls},
+};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use clap::Args;
+use reqwest::{header::CONTENT_TYPE, Client};
+use tokio::sync::Notify;
+
+#[derive(Clone, Debug, Args, PartialEq)]
+pub struct PrerenderCommand {
+    /// A file listing the routes to render, one per line (`#` starts a comment), relative to the workspace root. Added to the `prerender-routes` of the config.
+    #[arg(long, value_name = "FILE")]
+    pub routes: Option<Utf8PathBuf>,
+
+    /// Also render the pages listed in the `/sitemap.xml` of the site.
+    #[arg(long)]
+    pub sitemap: bool,
+
+    /// Follow the links of the rendered pages to the other pages of the site.
+    #[arg(long)]
+    pub crawl: bool,
+
+    #[command(flatten)]
+    pub opts: Opts,
+}
+
+/// The number of pages a crawl renders at most, in case of links generated without end.
+const MAX_PAGES: usize = 10_000;
+
+pub async fn prerender_all(conf: &Config, cmd: &PrerenderCommand) -> Result<()> {
+    let mut routes = Vec::new();
+    if let Some(file) = &cmd.routes {
+        routes = parse_routes(&fs::read_to_string(file).await?);
+    }
+    for proj in &conf.projects {
+        if !super::build::build_proj(proj).await.dot()? {
+            return Err(LeptosError::Compile {
+                project: proj.name.clone(),
+            }
+            .into());
+        }
+        let mut proj_routes = proj.prerender_routes.clone();
+        proj_routes.extend(routes.iter().cloned());
+        prerender(proj, proj_routes, cmd).await?;
+    }
+    Ok(())
+}
+
+/// Runs the server of the built site and writes the pages it renders into the site dir.
+async fn prerender(
+    proj: &Arc<Project>,
+    mut routes: Vec<String>,
+    cmd: &PrerenderCommand,
+) -> Result<()> {
+    if routes.is_empty() && !cmd.sitemap {
+        // the pages of the route manifest, when the sitemap step wrote one
+        routes = match RouteManifest::read(proj).await {
+            Some(manifest) => manifest.static_paths(),
+            None => vec!["/".to_string()],
+        };
+    }
+    tls::ensure_cert(proj).await?;
+    // stopped without a shutdown, which the next project must not see
+    let stop = Arc::new(Notify::new());
+    let server = serve::spawn_stoppable(proj, stop.clone()).await;
+    let rendered = if wait_for_socket("Prerender", proj.site.addr).await {
+        render_routes(proj, routes, cmd).await
+    } else {
+        Err(LeptosError::Server.into())
+    };
+    stop.notify_one();
+    server.await.dot()??;
+
+    let (pages, failed) = rendered?;
+    log::info!(
+        "Prerender wrote {pages} pages to {}",
+        GRAY.paint(proj.site.root_dir.as_str())
+    );
+    if failed > 0 {
+        bail!(
+            "Prerender could not render {failed} routes of {}",
+            proj.name
+        );
+    }
+    Ok(())
+}
+
+/// The number of pages written and of routes that failed.
+async fn render_routes(
+    proj: &Project,
+    routes: Vec<String>,
+    cmd: &PrerenderCommand,
+) -> Result<(usize, usize)> {
+    // the routes are relative to the base-href
+    let app_url = format!(
+        "{}{}",
+        proj.site_url(),
+        proj.base_href.trim_end_matches('/')
+    );
+    // the local https certificate may not be trusted
+    let client = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+
+    let mut queue = routes.into_iter().collect::<VecDeque<_>>();
+    if cmd.sitemap {
+        let url = format!("{app_url}/sitemap.xml");
+        let sitemap = client.get(&url).send().await?.error_for_status();
+        match sitemap {
+            Ok(sitemap) => queue.extend(
+                sitemap_routes(&sitemap.text().await?)
+                    .iter()
+                    .filter_map(|path| app_route(&proj.base_href, path)),
+            ),
+            Err(e) => log::warn!("Prerender found no sitemap at {}: {e}", GRAY.paint(&url)),
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let (mut pages, mut failed) = (0, 0);
+    while let Some(route) = queue.pop_front() {
+        let Some(route) = normalize_route(&route) else {
+            log::warn!("Prerender skipped {route}, which isn't a path of the site dir");
+            continue;
+        };
+        if !seen.insert(route.clone()) {
+            continue;
+        }
+        if pages == MAX_PAGES {
+            log::warn!("Prerender stopped after {MAX_PAGES} pages");
+            break;
+        }
+        let resp = match client.get(format!("{app_url}{route}")).send().await {
+            Ok(resp) if resp.status().is_success() => resp,
+            Ok(resp) => {
+                log::error!("Prerender {route} returned {}", resp.status());
+                failed += 1;
+                continue;
+            }
+            Err(e) => {
+                log::error!("Prerender {route} failed: {e}");
+                failed += 1;
+                continue;
+            }
+        };
+        let is_html = resp
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/html"));
+        let body = resp.bytes().await?;
+        let file = proj.site.root_dir.join(route_file(&route, is_html));
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        fs::write(&file, &body).await?;
+        log::debug!("Prerender {route} written to {}", GRAY.paint(file.as_str()));
+        pages += 1;
+
+        if cmd.crawl && is_html {
+            queue.extend(
+                page_links(&String::from_utf8_lossy(&body))
+                    .iter()
+                    .filter_map(|path| app_route(&proj.base_href, path)),
+            );
+        }
+    }
+    Ok((pages, failed))
+}
+
+fn parse_routes(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The path of the route, with a leading and without a trailing `/`, and its `.` and `..`
+/// segments resolved as a browser does. `None` for a segment that isn't a plain file name,
+/// e.g. `C:` or `a\b` on windows, which would be written outside of the site dir.
+fn normalize_route(route: &str) -> Option<String> {
+    let mut segments = Vec::new();
+    for segment in route.trim().split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => _ = segments.pop(),
+            segment => {
+                let mut components = Utf8Path::new(segment).components();
+                let plain = matches!(
+                    (components.next(), components.next()),
+                    (Some(Utf8Component::Normal(_)), None)
+                );
+                if !plain || segment.contains('\\') {
+                    return None;
+                }
+                segments.push(segment);
+            }
+        }
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+/// The route of a path of the site, relative to the base-href: `/app/about` is `/about` with
+/// `base-href = "/app"`. `None` for the paths outside of the app.
+fn app_route(base_href: &str, path: &str) -> Option<String> {
+    let rest = path.strip_prefix(base_href.trim_end_matches('/'))?;
+    (rest.is_empty() || rest.starts_with('/')).then(|| format!("/{}", rest.trim_start_matches('/')))
+}
+
+/// The file of the page in the site dir, as the static hosts serve them: `/about` is written
+/// to `about/index.html`. A route with an extension (e.g. `/feed.xml`) is written as is.
+fn route_file(route: &str, is_html: bool) -> Utf8PathBuf {
+    let path = Utf8Path::new(route.trim_start_matches('/'));
+    match path.extension() {
+        Some(_) => path.to_path_buf(),
+        None if is_html || path.as_str().is_empty() => path.join("index.html"),
+        None => path.to_path_buf(),
+    }
+}
+
+/// The paths of the `<loc>` urls of the sitemap.
+fn sitemap_routes(xml: &str) -> Vec<String> {
+    xml.split("<loc>")
+        .skip(1)
+        .filter_map(|part| part.split_once("</loc>"))
+        .map(|(loc, _)| url_path(loc.trim()))
+        .collect()
+}
+
+/// The path of an absolute url, e.g. `https://example.com/about` is `/about`.
+fn url_path(url: &str) -> String {
+    match url.split_once("://") {
+        Some((_, rest)) => match rest.find('/') {
+            Some(i) => rest[i..].to_string(),
+            None => "/".to_string(),
+        },
+        None => url.to_string(),
+    }
+}
+
+/// The links of the page to the other pages of the site: the `href`s of root-relative paths,
+/// without their query and fragment, and not to the files of the site (e.g. `/pkg/app.css`).
+fn page_links(html: &str) -> Vec<String> {
+    let mut links = Vec::new();
+    for quote in ['"', '\''] {
+        let attr = format!("href={quote}");
+        for part in html.split(attr.as_str()).skip(1) {
+            let Some((href, _)) = part.split_once(quote) else {
+                continue;
+            };
+            let path = href.split(['?', '#']).next().unwrap_or_default();
+            if path.starts_with('/')
+                && !path.starts_with("//")
+                && Utf8Path::new(path).extension().is_none()
+            {
+                links.push(path.to_string());
+            }
+        }
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes() {
+        assert_eq!(
+            parse_routes("/\n# the blog\n/blog/first-post  # pinned\n\n/about/\n"),
+            vec!["/", "/blog/first-post", "/about/"]
+        );
+        assert_eq!(normalize_route("/about/").unwrap(), "/about");
+        assert_eq!(normalize_route("about").unwrap(), "/about");
+        assert_eq!(normalize_route("/").unwrap(), "/");
+        assert_eq!(normalize_route("/blog/./../about").unwrap(), "/about");
+        assert_eq!(normalize_route("/../../etc/passwd").unwrap(), "/etc/passwd");
+        assert_eq!(normalize_route("/a\\..\\..\\x"), None);
+        #[cfg(target_os = "windows")]
+        assert_eq!(normalize_route("/C:/x"), None);
+
+        assert_eq!(app_route("/", "/about").unwrap(), "/about");
+        assert_eq!(app_route("/app/", "/app/about").unwrap(), "/about");
+        assert_eq!(app_route("/app/", "/app").unwrap(), "/");
+        assert_eq!(app_route("/app/", "/application"), None);
+        assert_eq!(app_route("/app/", "/about"), None);
+
+        assert_eq!(route_file("/", true), "index.html");
+        assert_eq!(
+            route_file("/blog/first-post", true),
+            "blog/first-post/index.html"
+        );
+        assert_eq!(route_file("/feed.xml", false), "feed.xml");
+        assert_eq!(route_file("/api/data", false), "api/data");
+    }
+
+    #[test]
+    fn discovered_routes() {
+        let sitemap = r#"<?xml version="1.0"?>
+<urlset><url><loc>https://example.com/</loc></url><url><loc> https://example.com/about </loc></url></urlset>"#;
+        assert_eq!(sitemap_routes(sitemap), vec!["/", "/about"]);
+
+        let html = r#"<link rel="stylesheet" href="/pkg/app.css"><a href="/blog?page=2">Blog</a>
+<a href='/about#team'>About</a><a href="https://leptos.dev">Leptos</a><a href="//cdn.example.com/x">x</a>"#;
+        assert_eq!(page_links(html), vec!["/blog", "/about"]);
+    }
+}
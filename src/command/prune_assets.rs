@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+
+use crate::{
+    compile::dir_files,
+    config::{Config, Opts, Project},
+    ext::{
+        anyhow::{bail, Context, Result},
+        fs, LeptosError,
+    },
+    logger::GRAY,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Args;
+
+#[derive(Clone, Debug, Args, PartialEq)]
+pub struct PruneAssetsCommand {
+    /// Fail when some assets are not referenced, e.g. in CI.
+    #[arg(long, conflicts_with = "fix")]
+    pub check: bool,
+
+    /// Delete the assets that are not referenced from the assets dir.
+    #[arg(long)]
+    pub fix: bool,
+
+    #[command(flatten)]
+    pub opts: Opts,
+}
+
+/// Served by convention, without any reference in the site.
+const CONVENTIONAL: &[&str] = &["favicon.ico", "robots.txt", "sitemap.xml", "humans.txt"];
+
+/// The extensions of the assets that can reference other assets.
+const TEXT_EXTENSIONS: &[&str] = &[
+    "html",
+    "htm",
+    "css",
+    "js",
+    "mjs",
+    "json",
+    "webmanifest",
+    "svg",
+    "xml",
+    "txt",
+];
+
+pub async fn prune_assets_all(conf: &Config, cmd: &PruneAssetsCommand) -> Result<()> {
+    let mut unused_count = 0;
+    for proj in &conf.projects {
+        let Some(assets) = &proj.assets else {
+            log::info!("Assets {} has no assets-dir, nothing to prune", proj.name);
+            continue;
+        };
+        // the references are looked up in the build output
+        if !super::build::build_proj(proj).await.dot()? {
+            return Err(LeptosError::Compile {
+                project: proj.name.clone(),
+            }
+            .into());
+        }
+        let unused = unused_assets(proj, &assets.dir).await?;
+        for asset in &unused {
+            let file = assets.dir.join(asset);
+            if cmd.fix {
+                fs::remove_file(&file).await?;
+                log::info!("Assets removed unused {}", GRAY.paint(file.as_str()));
+            } else {
+                log::warn!("Assets unused {}", GRAY.paint(file.as_str()));
+            }
+        }
+        unused_count += unused.len();
+    }
+    if unused_count == 0 {
+        log::info!("Assets all referenced");
+    } else if cmd.check {
+        bail!("Found {unused_count} unused assets, delete them with `cargo leptos prune-assets --fix`");
+    }
+    Ok(())
+}
+
+/// The assets (relative to the assets dir) that none of the built files reference:
+/// the wasm, js and css of the site, the server binary and the text assets.
+async fn unused_assets(proj: &Project, assets_dir: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+    let assets = dir_files(assets_dir)
+        .into_iter()
+        .map(|file| file.strip_prefix(assets_dir).unwrap_or(&file).to_path_buf())
+        .collect::<Vec<_>>();
+    let asset_set = assets.iter().collect::<HashSet<_>>();
+
+    // the site files that are not copies of the assets, and the text assets
+    let mut built = dir_files(&proj.site.root_dir)
+        .into_iter()
+        .filter(|file| {
+            let site = file.strip_prefix(&proj.site.root_dir).unwrap_or(file);
+            !asset_set.contains(&site.to_path_buf())
+        })
+        .map(|file| (None, file))
+        .collect::<Vec<_>>();
+    if proj.bin.exe_file.exists() {
+        built.push((None, proj.bin.exe_file.clone()));
+    }
+    for asset in &assets {
+        if matches!(asset.extension(), Some(ext) if TEXT_EXTENSIONS.contains(&ext)) {
+            built.push((Some(asset), assets_dir.join(asset)));
+        }
+    }
+
+    let mut tables = Vec::new();
+    for (asset, file) in built {
+        let data = fs::read(&file).await?;
+        log::trace!("Assets scanning {}", GRAY.paint(file.as_str()));
+        tables.push((asset, string_table(&data)));
+    }
+
+    let unused = assets
+        .iter()
+        .filter(|asset| {
+            let path = asset.as_str().replace('\\', "/");
+            !CONVENTIONAL.contains(&path.as_str())
+                && !path.starts_with(".well-known/")
+                && !tables
+                    .iter()
+                    .any(|(source, table)| source.as_ref() != Some(asset) && table.contains(&path))
+        })
+        .cloned()
+        .collect();
+    Ok(unused)
+}
+
+/// The runs of printable characters that could hold a file name (they contain a `.`),
+/// one per line. Binaries keep their string literals in such runs.
+fn string_table(data: &[u8]) -> String {
+    let mut table = String::new();
+    for run in data.split(|b| !(b.is_ascii_graphic() || *b == b' ')) {
+        if run.len() >= 4 && run.contains(&b'.') {
+            // only ascii, so it's valid utf-8
+            table.push_str(std::str::from_utf8(run).unwrap_or_default());
+            table.push('\n');
+        }
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::string_table;
+
+    #[test]
+    fn string_table_keeps_file_names() {
+        let data =
+            b"\0\x01<link rel=\"icon\" href=\"/img/logo.png\">\xff\xfe\0abc\0no dot here\0a.b";
+        let table = string_table(data);
+        assert_eq!(table, "<link rel=\"icon\" href=\"/img/logo.png\">\n");
+        assert!(table.contains("img/logo.png"));
+    }
+}
@@ -2,13 +2,17 @@ use std::sync::Arc;
 
 use crate::config::Project;
 use crate::ext::anyhow::{Context, Result};
-use crate::service::serve;
+use crate::service::{open, proxy, serve, tls};
 
 pub async fn serve(proj: &Arc<Project>) -> Result<()> {
+    proj.site.ensure_addrs_free(false)?;
     if !super::build::build_proj(proj).await.dot()? {
         return Ok(());
     }
+    tls::ensure_cert(proj).await?;
     let server = serve::spawn(proj).await;
+    let _proxy = proxy::spawn(proj).await;
+    open::spawn(proj);
     server.await??;
     Ok(())
 }
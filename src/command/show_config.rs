@@ -0,0 +1,28 @@
+use crate::{config::Config, ext::anyhow::Result};
+
+/// Prints the leptos config of the projects as the builds read it, with the table of the
+/// `--env` overlaid and the keys it overrode marked.
+pub async fn show_config(conf: &Config) -> Result<()> {
+    let env = conf.cli.env.clone();
+    for proj in &conf.projects {
+        let keys = {
+            let proj = proj.clone();
+            let env = env.clone();
+            // cargo metadata blocks
+            tokio::task::spawn_blocking(move || proj.effective_config(env.as_deref())).await??
+        };
+        match &env {
+            Some(env) => println!("# {} with env.{env}", proj.name),
+            None => println!("# {}", proj.name),
+        }
+        for key in keys {
+            match key.env {
+                Some(env) => println!("{} = {}  # {env}", key.key, key.value),
+                None => println!("{} = {}", key.key, key.value),
+            }
+        }
+        println!();
+    }
+    println!("# The .env file, the LEPTOS_* env vars and the command line take precedence.");
+    Ok(())
+}
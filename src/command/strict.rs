@@ -0,0 +1,121 @@
+use crate::{
+    compile::{
+        add_json_diagnostics, cargo_log, front_cargo_command, server_cargo_command, wait_cargo,
+    },
+    config::Project,
+    ext::{
+        anyhow::{bail, Context, Result},
+        sync::CommandResult,
+        tool::Tool,
+    },
+    logger::{GRAY, RESULT},
+    signal::Interrupt,
+};
+use tokio::process::Command;
+
+/// The gates of `build --strict`, run before the build: `cargo fmt --check` of the lib and bin
+/// packages, then clippy of the lib (with the lib features, for wasm) and of the bin (with the
+/// bin features), with the warnings denied. Stops at the first failed gate.
+pub async fn strict_gates(proj: &Project) -> Result<()> {
+    let unformatted = fmt_check(proj).await?;
+    if !unformatted.is_empty() {
+        for file in &unformatted {
+            log::warn!("Strict fmt {}", GRAY.paint(file));
+        }
+        bail!(
+            "Strict {} files of {} are not formatted, run cargo fmt",
+            unformatted.len(),
+            proj.name
+        );
+    }
+    for (build, (_, line, mut command)) in [
+        (
+            "clippy-lib",
+            front_cargo_command("clippy", true, proj).await,
+        ),
+        ("clippy-bin", server_cargo_command("clippy", proj)),
+    ] {
+        add_json_diagnostics(&mut command);
+        // after the tool args of a clippy alias of the cargo config
+        let (_, tool_args) = proj.cargo_config.expand_alias("clippy");
+        command.arg("--").args(tool_args).arg("-Dwarnings");
+        let process = Tool::new("Cargo", command)
+            .with_timeouts(&proj.tool_timeouts)
+            .log_output_to(cargo_log(proj, build))
+            .spawn()?;
+        match wait_cargo(proj, build, process).await.dot()? {
+            CommandResult::Success(_) => {
+                log::info!(target: RESULT, "Strict {build} passed {}", GRAY.paint(line))
+            }
+            CommandResult::Failure(diags) => bail!(
+                "Strict {build} of {} failed with {} diagnostics",
+                proj.name,
+                diags.len()
+            ),
+            CommandResult::Interrupted => bail!("Strict {build} interrupted"),
+        }
+    }
+    Ok(())
+}
+
+/// The files of the lib and bin packages that `cargo fmt` would change.
+async fn fmt_check(proj: &Project) -> Result<Vec<String>> {
+    let mut command = Command::new("cargo");
+    command.args(fmt_args(proj));
+    log::debug!("Strict cargo {}", GRAY.paint(fmt_args(proj).join(" ")));
+    match Tool::new("Rustfmt", command)
+        .with_timeouts(&proj.tool_timeouts)
+        .output(Interrupt::subscribe_any())
+        .await?
+    {
+        CommandResult::Success(_) => Ok(Vec::new()),
+        CommandResult::Failure(output) => Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .collect()),
+        CommandResult::Interrupted => bail!("Strict fmt interrupted"),
+    }
+}
+
+/// The lib and bin packages are the same one in a single package project.
+fn fmt_args(proj: &Project) -> Vec<String> {
+    let mut args = vec!["fmt".to_string(), format!("--package={}", proj.lib.name)];
+    if proj.bin.name != proj.lib.name {
+        args.push(format!("--package={}", proj.bin.name));
+    }
+    // only lists the files with changes
+    args.extend(["--check", "--", "--files-with-diff"].map(String::from));
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, Opts};
+
+    #[test]
+    fn fmt_packages() {
+        let conf = Config::test_load(
+            Opts::default(),
+            "examples",
+            "examples/workspace/Cargo.toml",
+            false,
+        );
+        assert_eq!(
+            fmt_args(&conf.projects[0]).join(" "),
+            "fmt --package=front-package --package=server-package --check -- --files-with-diff"
+        );
+
+        let conf = Config::test_load(
+            Opts::default(),
+            "examples",
+            "examples/project/Cargo.toml",
+            false,
+        );
+        assert_eq!(
+            fmt_args(&conf.projects[0]).join(" "),
+            "fmt --package=example --check -- --files-with-diff"
+        );
+    }
+}
@@ -1,7 +1,9 @@
-use crate::compile::{front_cargo_process, server_cargo_process};
+use crate::compile::{front_cargo_command, server_cargo_command};
 use crate::config::{Config, Project};
 use crate::ext::anyhow::{Context, Result, anyhow};
-use crate::logger::GRAY;
+use crate::ext::{sync::CommandResult, tool::Tool};
+use crate::logger::{GRAY, RESULT};
+use crate::signal::Interrupt;
 
 pub async fn test_all(conf: &Config) -> Result<()> {
     let mut first_failed_project = None;
@@ -20,17 +22,26 @@ pub async fn test_all(conf: &Config) -> Result<()> {
 }
 
 pub async fn test_proj(proj: &Project) -> Result<bool> {
-    let (envs, line, mut proc) = server_cargo_process("test", proj).dot()?;
+    let (envs, line, command) = server_cargo_command("test", proj);
+    let proc = Tool::new("Cargo", command)
+        .with_timeouts(&proj.tool_timeouts)
+        .spawn()
+        .dot()?;
 
-    let server_exit_status = proc.wait().await.dot()?;
+    let server_result = proc.wait(Interrupt::subscribe_any()).await.dot()?;
     log::debug!("Cargo envs: {}", GRAY.paint(envs));
-    log::info!("Cargo server tests finished {}", GRAY.paint(line));
+    log::info!(target: RESULT, "Cargo server tests finished {}", GRAY.paint(line));
 
-    let (envs, line, mut proc) = front_cargo_process("test", false, proj).dot()?;
+    let (envs, line, command) = front_cargo_command("test", false, proj).await;
+    let proc = Tool::new("Cargo", command)
+        .with_timeouts(&proj.tool_timeouts)
+        .spawn()
+        .dot()?;
 
-    let front_exit_status = proc.wait().await.dot()?;
+    let front_result = proc.wait(Interrupt::subscribe_any()).await.dot()?;
     log::debug!("Cargo envs: {}", GRAY.paint(envs));
-    log::info!("Cargo front tests finished {}", GRAY.paint(line));
+    log::info!(target: RESULT, "Cargo front tests finished {}", GRAY.paint(line));
 
-    Ok(server_exit_status.success() && front_exit_status.success())
+    Ok(matches!(server_result, CommandResult::Success(_))
+        && matches!(front_result, CommandResult::Success(_)))
 }
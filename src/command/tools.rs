@@ -0,0 +1,54 @@
+use crate::{
+    config::{Config, Opts},
+    ext::{anyhow::Result, Exe, ToolsLock},
+};
+use clap::{Args, Subcommand};
+
+#[derive(Clone, Debug, Args, PartialEq)]
+pub struct ToolsCommand {
+    #[command(subcommand)]
+    pub action: ToolsAction,
+
+    #[command(flatten)]
+    pub opts: Opts,
+}
+
+#[derive(Clone, Debug, Subcommand, PartialEq, Eq)]
+pub enum ToolsAction {
+    /// Lock the latest releases of sass, tailwind, wasm-opt, wasm-split and esbuild, and the wasm-bindgen of the Cargo.lock.
+    Update,
+}
+
+pub async fn tools(conf: &Config, cmd: &ToolsCommand) -> Result<()> {
+    match cmd.action {
+        ToolsAction::Update => update(conf).await,
+    }
+}
+
+async fn update(conf: &Config) -> Result<()> {
+    let mut versions = Vec::new();
+    for exe in [
+        Exe::Sass,
+        Exe::Tailwind,
+        Exe::WasmOpt,
+        Exe::WasmSplit,
+        Exe::Esbuild,
+    ] {
+        match exe.latest_version().await {
+            Some(version) => versions.push((exe.name(), version)),
+            None => log::warn!(
+                "Tools could not find the latest {} version, keeping the locked one",
+                exe.name()
+            ),
+        }
+    }
+    if let Some(version) = &conf.wasm_bindgen_version {
+        versions.push(("wasm-bindgen", version.clone()));
+    }
+    let versions = versions
+        .iter()
+        .map(|(name, version)| (*name, version.as_str()))
+        .collect::<Vec<_>>();
+    ToolsLock::set(&versions);
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use std::{
+    env,
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+use crate::{
+    ext::{
+        anyhow::{anyhow, bail, Context, Result},
+        exe::{download_self, is_newer_version, latest_self_release},
+    },
+    logger::GRAY,
+};
+use clap::{Args, ValueEnum};
+use tokio::process::Command;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Channel {
+    /// The latest release
+    Stable,
+    /// The latest release, including the pre-releases
+    Nightly,
+}
+
+#[derive(Clone, Debug, Args, PartialEq, Eq)]
+pub struct UpdateSelfCommand {
+    /// The release channel to update from.
+    #[arg(long, value_enum, default_value = "stable")]
+    pub channel: Channel,
+
+    /// Don't ask for confirmation before replacing the installed cargo-leptos.
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+impl UpdateSelfCommand {
+    pub async fn run(&self) -> Result<()> {
+        let current = env!("CARGO_PKG_VERSION");
+        let nightly = self.channel == Channel::Nightly;
+        let latest = latest_self_release(nightly)
+            .await
+            .ok_or_else(|| anyhow!("Could not find the latest cargo-leptos release"))?;
+
+        if !is_newer_version(&latest, current) {
+            log::info!("Update cargo-leptos {current} is up to date (latest {latest})");
+            return Ok(());
+        }
+        if !self.yes && !confirm(&format!("Update cargo-leptos {current} to {latest}?"))? {
+            log::info!("Update cancelled");
+            return Ok(());
+        }
+
+        if cfg!(feature = "no_downloads") {
+            return cargo_install(&latest).await;
+        }
+        match download_self(&latest).await {
+            Ok(new_exe) => replace_current_exe(&new_exe)?,
+            Err(e) => {
+                log::warn!("Update could not download the prebuilt binary: {e:#}");
+                return cargo_install(&latest).await;
+            }
+        }
+        log::info!("Update cargo-leptos updated to {latest}");
+        Ok(())
+    }
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    print!("{question} [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// The running binary can't be overwritten on all platforms, but it can be renamed,
+/// so it is moved aside before the new one is copied in its place.
+fn replace_current_exe(new_exe: &Path) -> Result<()> {
+    let current = env::current_exe().context("Could not find the running cargo-leptos")?;
+    let old = current.with_extension("old");
+    if old.exists() {
+        std::fs::remove_file(&old).context(format!("Could not remove {old:?}"))?;
+    }
+    std::fs::rename(&current, &old).context(format!("Could not move aside {current:?}"))?;
+
+    if let Err(e) = std::fs::copy(new_exe, &current) {
+        std::fs::rename(&old, &current).ok();
+        bail!("Could not copy {new_exe:?} to {current:?}: {e}");
+    }
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::prelude::PermissionsExt;
+        std::fs::set_permissions(&current, std::fs::Permissions::from_mode(0o755))?;
+    }
+    log::debug!("Update replaced {}", GRAY.paint(current.to_string_lossy()));
+    Ok(())
+}
+
+async fn cargo_install(version: &str) -> Result<()> {
+    let version = version.trim_start_matches('v');
+    log::info!("Update running cargo install cargo-leptos --version {version}");
+    let status = Command::new("cargo")
+        .args([
+            "install",
+            "cargo-leptos",
+            "--locked",
+            "--force",
+            "--version",
+            version,
+        ])
+        .status()
+        .await
+        .context("Could not run cargo install")?;
+    if !status.success() {
+        bail!("cargo install cargo-leptos --version {version} failed");
+    }
+    Ok(())
+}
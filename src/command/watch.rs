@@ -1,25 +1,68 @@
 use std::sync::Arc;
 
 use crate::{
-    compile::{self},
-    config::Project,
-    ext::anyhow::Context,
+    compile::{self, Change, ChangeSet, Pipeline, StageKeys},
+    config::{Config, ConfigChanges, Project},
+    ext::{anyhow::Context, sync::is_interrupted, LeptosError, MetadataExt},
     service,
-    signal::{Interrupt, Outcome, Product, ProductSet, ReloadSignal, ServerRestart},
+    signal::{
+        BuildResult, Interrupt, Outcome, Product, ProductSet, ReloadSignal, ServerRestart, Status,
+        Timings, TimingsHistory,
+    },
 };
 use anyhow::Result;
+use cargo_metadata::Metadata;
 use leptos_hot_reload::ViewMacros;
-use tokio::try_join;
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    task::JoinHandle,
+};
+
+use super::build::{build_proj, build_proj_with};
+
+/// What ended the watch of a project.
+pub enum WatchEnd {
+    Shutdown,
+    /// the Cargo.toml changed the config of the running services, which are started again with
+    /// the reloaded project
+    Restart(Arc<Project>),
+}
 
-use super::build::build_proj;
+/// Watches the current project, starting again with the reloaded config when a change of the
+/// Cargo.toml can't be applied to the running watch.
+pub async fn watch(config: &Config) -> Result<()> {
+    let mut proj = config.current_project()?;
+    let mut first_start = true;
+    loop {
+        match watch_proj(config, &proj, first_start).await? {
+            WatchEnd::Shutdown => break,
+            WatchEnd::Restart(reloaded) => {
+                log::info!("Watch restarting with the reloaded config");
+                first_start = false;
+                // the restart builds everything
+                Interrupt::clear_source_changes().await;
+                proj = reloaded;
+            }
+        }
+    }
+    service::watchdog::check(proj.watchdog_strict).await
+}
 
-pub async fn watch(proj: &Arc<Project>) -> Result<()> {
+/// The browser is only opened on the first start, the restarts reload the page it shows.
+async fn watch_proj(config: &Config, proj: &Arc<Project>, first_start: bool) -> Result<WatchEnd> {
+    proj.site.ensure_addrs_free(true)?;
     // even if the build fails, we continue
-    build_proj(proj).await?;
+    if proj.lazy_front {
+        // the previous front is served, or a placeholder, until the front is built
+        compile::write_placeholder(proj).await?;
+        build_proj_with(proj, ChangeSet::all_but_front()).await?;
+    } else {
+        build_proj(proj).await?;
+    }
 
     // but if ctrl-c is pressed, we stop
     if Interrupt::is_shutdown_requested().await {
-        return Ok(());
+        return Ok(WatchEnd::Shutdown);
     }
 
     let view_macros = if proj.hot_reload {
@@ -31,71 +74,207 @@ pub async fn watch(proj: &Arc<Project>) -> Result<()> {
         None
     };
 
-    let _watch = service::notify::spawn(proj).await?;
+    service::tls::ensure_cert(proj).await?;
+    let mut services = Services {
+        notify: service::notify::spawn(proj)
+            .await
+            .categorize(LeptosError::Watch)?,
+        others: Vec::new(),
+    };
     if let Some(view_macros) = view_macros {
-        let _patch = service::patch::spawn(proj, &view_macros).await?;
+        services
+            .others
+            .push(service::patch::spawn(proj, &view_macros).await?);
     }
 
-    service::serve::spawn(proj).await;
-    service::reload::spawn(proj).await;
+    let serve = service::serve::spawn(proj).await;
+    services.others.push(tokio::spawn(async move {
+        if let Ok(Err(e)) = serve.await {
+            log::error!("Serve {e:#}");
+        }
+    }));
+    if first_start {
+        service::open::spawn(proj);
+    }
+    services.others.push(service::reload::spawn(proj).await);
+    services.others.extend(service::proxy::spawn(proj).await);
+    services.others.extend(service::sidecar::spawn(proj).await);
+    services.others.push(service::watchdog::spawn());
+    services.others.extend(service::console::spawn(proj));
 
-    let res = run_loop(proj).await;
+    // subscribed before the front build is triggered, so that it isn't missed
+    let int = Interrupt::subscribe_any();
+    if proj.lazy_front {
+        log::info!("Watch building the front in the background");
+        tokio::task::spawn_blocking(|| Interrupt::send(&[Change::LibSource])).await?;
+    }
+    let res = run_loop(config, proj, &mut services, int).await;
     if res.is_err() {
         Interrupt::request_shutdown().await;
     }
+    if let Ok(WatchEnd::Restart(_)) = &res {
+        services.stop().await;
+    }
     res
 }
 
-pub async fn run_loop(proj: &Arc<Project>) -> Result<()> {
-    let mut int = Interrupt::subscribe_any();
+/// The tasks of the services of the watch, stopped for a restart. The file watcher is also
+/// started again when the config of the builds changes.
+struct Services {
+    notify: JoinHandle<()>,
+    others: Vec<JoinHandle<()>>,
+}
+
+impl Services {
+    async fn stop(self) {
+        self.notify.abort();
+        Interrupt::stop_services();
+        for task in self.others {
+            _ = task.await;
+        }
+    }
+}
+
+async fn run_loop(
+    config: &Config,
+    proj: &Arc<Project>,
+    services: &mut Services,
+    mut int: broadcast::Receiver<()>,
+) -> Result<WatchEnd> {
+    // the products of the builds discarded as outdated, still to be reloaded
+    let mut pending = Vec::new();
+    // the timings of the cycles, for the averages of --stats
+    let mut history = TimingsHistory::default();
+    let mut proj = proj.clone();
+    let mut metadata = read_metadata(&proj).await;
+    let mut pipeline = Pipeline::watch(&proj);
+    let mut style_pipeline = Pipeline::watch_style(&proj);
     loop {
         log::debug!("Watch waiting for changes");
-        int.recv().await.dot()?;
+        match int.recv().await {
+            Ok(()) | Err(RecvError::Lagged(_)) => {}
+            Err(e) => return Err(e).categorize(LeptosError::Watch),
+        }
+        if let Some(adaptive) = &proj.adaptive {
+            service::adaptive::wait_while_busy(adaptive, Interrupt::take_rebuild_requested()).await;
+        }
+        // the interrupts sent while the last build ran are coalesced into this
+        // build, as their changes are all in the change set
+        while is_interrupted(&mut int) {}
 
         if Interrupt::is_shutdown_requested().await {
             log::debug!("Shutting down");
-            return Ok(());
+            return Ok(WatchEnd::Shutdown);
         }
 
+        let generation = Interrupt::generation();
         let changes = Interrupt::get_source_changes().await;
+        if changes.need_config_reload() {
+            match reload_config(config, &proj, &mut metadata).await {
+                Some(Reloaded::Restart(reloaded)) => return Ok(WatchEnd::Restart(reloaded)),
+                Some(Reloaded::Live(reloaded)) => {
+                    proj = reloaded;
+                    pipeline = Pipeline::watch(&proj);
+                    style_pipeline = Pipeline::watch_style(&proj);
+                    services.notify.abort();
+                    services.notify = service::notify::spawn(&proj)
+                        .await
+                        .categorize(LeptosError::Watch)?;
+                    service::reload::update_css_link(&proj).await;
+                }
+                None => {}
+            }
+            compile::reload_style_vars(&proj).await;
+        }
+        let proj = &proj;
+        // taken before the build, as the saves while it runs aren't built
+        let keys = match proj.reuse_artifacts {
+            true => StageKeys::of(proj).ok(),
+            false => None,
+        };
+        Status::start_build();
+        let recorder = Timings::start_cycle();
+        compile::clear_seen_diagnostics();
+        let started = Timings::start();
 
         // spawn separate style-update process
         tokio::spawn({
             let changes = changes.to_owned();
             let proj = Arc::clone(proj);
+            let style_pipeline = style_pipeline.clone();
             async move {
-                let style = compile::style(&proj, &changes).await;
-                if let Ok(Ok(Outcome::Success(Product::Style(_)))) = style.await {
+                let Ok(outcomes) = style_pipeline.run(&proj, &changes).await else {
+                    return;
+                };
+                let updated = outcomes
+                    .iter()
+                    .any(|outcome| matches!(outcome, Outcome::Success(Product::Style(_))));
+                if Interrupt::generation() != generation {
+                    log::debug!("Watch discarded the outdated style");
+                } else if updated {
                     ReloadSignal::send_style();
                     log::info!("Watch updated style");
                     Interrupt::clear_source_changes().await;
+                    if let Err(e) = compile::write_manifest(&proj).await {
+                        log::warn!("Manifest could not be written: {e}");
+                    }
                 }
             }
         });
 
-        let server_hdl = compile::server(proj, &changes).await;
-        let front_hdl = compile::front(proj, &changes).await;
-        let assets_hdl = compile::assets(proj, &changes, false).await;
-
-        let (serve, front, assets) = try_join!(server_hdl, front_hdl, assets_hdl)?;
-
-        let outcomes = vec![serve?, front?, assets?];
+        let outcomes = pipeline.run(proj, &changes).await?;
 
         let failed = outcomes.iter().any(|outcome| *outcome == Outcome::Failed);
         let interrupted = outcomes.iter().any(|outcome| *outcome == Outcome::Stopped);
+        // changes that arrived after the compiles finished don't interrupt them,
+        // but the output is outdated all the same
+        let outdated = Interrupt::generation() != generation;
 
-        if failed {
+        if interrupted || outdated {
+            log::info!("Build interrupted. Restarting.");
+            Status::finish_build(BuildResult::Interrupted, None);
+            pending.extend(outcomes.into_iter().filter(|outcome| outcome.is_success()));
+        } else if failed {
             log::warn!("Build failed");
+            Status::finish_build(BuildResult::Failed, None);
             Interrupt::clear_source_changes().await;
-        } else if interrupted {
-            log::info!("Build interrupted. Restarting.");
         } else {
-            let set = ProductSet::from(outcomes);
+            ReloadSignal::send_clear_build_error();
+            pending.extend(outcomes);
+            let set = ProductSet::from(std::mem::take(&mut pending));
+
+            let cycle = recorder.finish(started);
+            Status::finish_build(BuildResult::Success, Some(&cycle));
+            log::info!("Watch cycle {cycle}");
+            history.push(cycle);
+            if proj.stats {
+                log::info!(
+                    "Watch average of last {} cycles {}",
+                    history.count(),
+                    history.averages()
+                );
+            }
 
             if set.is_empty() {
                 log::trace!("Build step done with no changes");
             } else {
                 log::trace!("Build step done with changes: {set}");
+                match compile::write_manifest(proj).await {
+                    // for a build to reuse the artifacts of the cycle
+                    Ok(manifest) => {
+                        if let Some(keys) = keys {
+                            let recorded =
+                                compile::record_artifacts(proj, &changes, keys, &manifest).await;
+                            if let Err(e) = recorded {
+                                log::warn!("Reuse could not record the artifacts: {e:#}");
+                            }
+                        }
+                    }
+                    Err(e) => log::warn!("Manifest could not be written: {e}"),
+                }
+                if let Err(e) = compile::write_preload_hints(proj).await {
+                    log::warn!("Front preload hints could not be written: {e}");
+                }
             }
 
             if set.only_style() {
@@ -113,3 +292,82 @@ pub async fn run_loop(proj: &Arc<Project>) -> Result<()> {
         }
     }
 }
+
+/// How the watch applies a reloaded config.
+enum Reloaded {
+    /// to the next builds
+    Live(Arc<Project>),
+    /// by starting again
+    Restart(Arc<Project>),
+}
+
+/// Loads the project again after a change of the Cargo.toml, when its leptos config changed.
+/// A config that can't be loaded is reported, and the current one kept until it is fixed.
+async fn reload_config(
+    config: &Config,
+    proj: &Arc<Project>,
+    metadata: &mut Option<serde_json::Value>,
+) -> Option<Reloaded> {
+    let config = config.clone();
+    let proj = proj.clone();
+    let old = metadata.clone();
+    // cargo metadata blocks, and is run once for both the leptos config and the project
+    let res = tokio::task::spawn_blocking(move || load_changed(&config, &proj, old.as_ref()))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|res| res);
+    match res {
+        Ok((new, reloaded)) => {
+            *metadata = Some(new);
+            reloaded
+        }
+        Err(e) => {
+            log::warn!("Watch could not reload the config, the current one is kept: {e:#}");
+            None
+        }
+    }
+}
+
+/// The leptos metadata of the project as now written, and the project reloaded with it when
+/// it differs from the old one.
+fn load_changed(
+    config: &Config,
+    proj: &Project,
+    old: Option<&serde_json::Value>,
+) -> Result<(serde_json::Value, Option<Reloaded>)> {
+    let cargo_metadata = Metadata::load_cleaned(&config.manifest_path)?;
+    let new = proj.leptos_metadata_in(&cargo_metadata)?;
+    let changes = match old {
+        Some(old) => ConfigChanges::between(old, &new),
+        None => ConfigChanges::default(),
+    };
+    if changes.is_empty() {
+        return Ok((new, None));
+    }
+    let reloaded = config.reload_project(&proj.name, cargo_metadata)?;
+    if changes.restart.is_empty() {
+        log::info!("Watch reloaded {} from the config", changes.live.join(", "));
+        Ok((new, Some(Reloaded::Live(reloaded))))
+    } else {
+        log::info!(
+            "Watch config changes of {} need a restart",
+            changes.restart.join(", ")
+        );
+        Ok((new, Some(Reloaded::Restart(reloaded))))
+    }
+}
+
+async fn read_metadata(proj: &Arc<Project>) -> Option<serde_json::Value> {
+    let proj = proj.clone();
+    match tokio::task::spawn_blocking(move || proj.read_metadata()).await {
+        Ok(Ok(metadata)) => Some(metadata),
+        Ok(Err(e)) => {
+            log::warn!("Watch could not read the config: {e:#}");
+            None
+        }
+        Err(e) => {
+            log::warn!("Watch could not read the config: {e}");
+            None
+        }
+    }
+}
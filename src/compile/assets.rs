@@ -5,8 +5,12 @@ use crate::config::Project;
 use crate::ext::anyhow::{Context, Result};
 use crate::service::notify::Watched;
 use crate::service::site::SourcedSiteFile;
-use crate::signal::{Outcome, Product};
-use crate::{ext::PathExt, fs, logger::GRAY};
+use crate::signal::{Outcome, Phase, Product, Timings};
+use crate::{
+    ext::{utf8_path, PathExt},
+    fs,
+    logger::GRAY,
+};
 use camino::{Utf8Path, Utf8PathBuf};
 use tokio::task::JoinHandle;
 
@@ -20,13 +24,18 @@ pub async fn assets(
     let proj = proj.clone();
     tokio::spawn(async move {
         let Some(assets) = &proj.assets else {
-             return Ok(Outcome::Success(Product::None));
+            if first_sync {
+                root_files(&proj).await?;
+            }
+            return Ok(Outcome::Success(Product::None));
         };
         let dest_root = &proj.site.root_dir;
 
+        let started = Timings::start();
         let change = if first_sync {
             log::trace!("Assets starting full resync");
             resync(&assets.dir, dest_root).await?;
+            root_files(&proj).await?;
             true
         } else {
             let mut changed = false;
@@ -38,6 +47,9 @@ pub async fn assets(
             }
             changed
         };
+        if first_sync || changes.asset_iter().next().is_some() {
+            Timings::record(Phase::Assets, started);
+        }
         if change {
             log::debug!("Assets finished (with changes)");
             Ok(Outcome::Success(Product::Assets))
@@ -100,13 +112,82 @@ async fn update_asset(
             };
             proj.site.updated(&file).await?
         }
+        // the watcher lost track of the changes, and the site may have changed meanwhile
         Watched::Rescan => {
             resync(src_root, dest_root).await?;
+            root_files(proj).await?;
             true
         }
     })
 }
 
+/// A stub `robots.txt`, allowing everything.
+const ROBOTS_STUB: &str = "User-agent: *\nAllow: /\n";
+
+/// A stub `favicon.ico`: a single transparent pixel, as the icon dir with one 1x1 32 bit image,
+/// its bitmap header (twice as high for the and mask), the pixel and the and mask row.
+const FAVICON_STUB: [u8; 70] = [
+    0, 0, 1, 0, 1, 0, 1, 1, 0, 0, 1, 0, 32, 0, 48, 0, 0, 0, 22, 0, 0, 0, 40, 0, 0, 0, 1, 0, 0, 0,
+    2, 0, 0, 0, 1, 0, 32, 0, 0, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// Browsers and crawlers request the favicon and robots.txt from the site root, so the ones of
+/// the conventional `assets` dir are copied there, unless the assets dir already provides them.
+async fn root_files(proj: &Project) -> Result<()> {
+    let assets_dir = proj.assets.as_ref().map(|assets| assets.dir.as_path());
+    copy_root_files(
+        &proj.root_files_dir,
+        assets_dir,
+        &proj.site.root_dir,
+        proj.root_file_stubs,
+    )
+    .await
+}
+
+async fn copy_root_files(
+    dir: &Utf8Path,
+    assets_dir: Option<&Utf8Path>,
+    dest_root: &Utf8Path,
+    stubs: bool,
+) -> Result<()> {
+    fs::create_dir_all(dest_root).await?;
+    if dir.is_dir() && assets_dir != Some(dir) {
+        for entry in dir.read_dir_utf8()?.flatten() {
+            let name = entry.file_name();
+            let conventional = name == "robots.txt" || entry.path().file_stem() == Some("favicon");
+            let provided = assets_dir.is_some_and(|assets| assets.join(name).exists());
+            if conventional && !provided && entry.path().is_file() {
+                let to = dest_root.join(name);
+                log::debug!(
+                    "Assets copy root file {} -> {}",
+                    GRAY.paint(entry.path().as_str()),
+                    GRAY.paint(to.as_str())
+                );
+                fs::copy(entry.path(), &to).await?;
+            }
+        }
+    }
+    if !stubs {
+        return Ok(());
+    }
+    let robots = dest_root.join("robots.txt");
+    if !robots.exists() {
+        log::debug!("Assets writing a stub {}", GRAY.paint(robots.as_str()));
+        fs::write(&robots, ROBOTS_STUB).await?;
+    }
+    let has_favicon = dest_root
+        .read_dir_utf8()?
+        .flatten()
+        .any(|entry| entry.path().file_stem() == Some("favicon"));
+    if !has_favicon {
+        let favicon = dest_root.join("favicon.ico");
+        log::debug!("Assets writing a stub {}", GRAY.paint(favicon.as_str()));
+        fs::write(&favicon, FAVICON_STUB).await?;
+    }
+    Ok(())
+}
+
 pub fn reserved(src: &Utf8Path) -> Vec<Utf8PathBuf> {
     vec![src.join("index.html"), src.join("pkg")]
 }
@@ -124,6 +205,8 @@ pub fn reserved(src: &Utf8Path) -> Vec<Utf8PathBuf> {
 // }
 
 async fn resync(src: &Utf8Path, dest: &Utf8Path) -> Result<()> {
+    // the front build may not have created the site dir yet
+    fs::create_dir_all(dest).await?;
     clean_dest(dest)
         .await
         .context(format!("Cleaning {dest:?}"))?;
@@ -136,21 +219,15 @@ async fn resync(src: &Utf8Path, dest: &Utf8Path) -> Result<()> {
 async fn clean_dest(dest: &Utf8Path) -> Result<()> {
     let mut entries = fs::read_dir(dest).await?;
     while let Some(entry) = entries.next_entry().await? {
-        let path = entry.path();
+        let path = utf8_path(entry.path())?;
 
         if entry.file_type().await?.is_dir() {
             if entry.file_name() != "pkg" {
-                log::debug!(
-                    "Assets removing folder {}",
-                    GRAY.paint(path.to_string_lossy())
-                );
+                log::debug!("Assets removing folder {}", GRAY.paint(path.as_str()));
                 fs::remove_dir_all(path).await?;
             }
         } else if entry.file_name() != "index.html" {
-            log::debug!(
-                "Assets removing file {}",
-                GRAY.paint(path.to_string_lossy())
-            );
+            log::debug!("Assets removing file {}", GRAY.paint(path.as_str()));
             fs::remove_file(path).await?;
         }
     }
@@ -185,3 +262,42 @@ async fn mirror(src_root: &Utf8Path, dest_root: &Utf8Path, reserved: &[Utf8PathB
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_dir::TempDir;
+
+    #[tokio::test]
+    async fn root_files_are_copied() {
+        let tmp = TempDir::new().unwrap();
+        let root = Utf8Path::from_path(tmp.path()).unwrap();
+        let (dir, assets, site) = (root.join("assets"), root.join("public"), root.join("site"));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::create_dir_all(&assets).unwrap();
+        std::fs::write(dir.join("favicon.svg"), "<svg/>").unwrap();
+        std::fs::write(dir.join("robots.txt"), "conventional").unwrap();
+        std::fs::write(dir.join("logo.png"), "").unwrap();
+        std::fs::write(assets.join("robots.txt"), "from the assets").unwrap();
+
+        copy_root_files(&dir, Some(&assets), &site, false)
+            .await
+            .unwrap();
+        assert!(site.join("favicon.svg").exists());
+        assert!(!site.join("logo.png").exists());
+        // provided by the assets dir, and mirrored from it
+        assert!(!site.join("robots.txt").exists());
+
+        // the stubs, when there are none
+        let site = root.join("empty");
+        copy_root_files(&root.join("none"), None, &site, true)
+            .await
+            .unwrap();
+        let robots = std::fs::read_to_string(site.join("robots.txt")).unwrap();
+        assert_eq!(robots, ROBOTS_STUB);
+        assert_eq!(
+            std::fs::read(site.join("favicon.ico")).unwrap(),
+            FAVICON_STUB
+        );
+    }
+}
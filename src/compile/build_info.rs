@@ -0,0 +1,87 @@
+use std::{sync::OnceLock, time::SystemTime};
+
+use serde::Serialize;
+
+use super::manifest::unix_secs;
+use crate::{
+    config::{Profile, Project},
+    ext::GitState,
+};
+
+/// The provenance of a build with `build-info`, given to the lib and bin builds as env vars
+/// (read with `option_env!`) and recorded in the build manifest.
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    /// the commit of the working dir, if it is a git repository
+    pub git_sha: Option<String>,
+    /// whether the tracked files had uncommitted changes
+    pub git_dirty: bool,
+    /// unix timestamp (seconds), the `SOURCE_DATE_EPOCH` when set for reproducible builds
+    pub timestamp: u64,
+    pub cargo_leptos: &'static str,
+}
+
+static INFO: OnceLock<BuildInfo> = OnceLock::new();
+
+/// Reads the git state on a blocking thread, before the builds read the [build_info].
+pub async fn load_build_info(proj: &Project) {
+    if proj.build_info && INFO.get().is_none() {
+        let git = GitState::load(&proj.working_dir).await;
+        _ = INFO.set(new_build_info(proj, git));
+    }
+}
+
+/// Computed once, so that the timestamp of the watch rebuilds doesn't change and force cargo to
+/// recompile the crates reading it. Read from git here by the commands that didn't
+/// [load_build_info], e.g. `test`.
+pub fn build_info(proj: &Project) -> &'static BuildInfo {
+    INFO.get_or_init(|| new_build_info(proj, GitState::of(&proj.working_dir)))
+}
+
+fn new_build_info(proj: &Project, git: Option<GitState>) -> BuildInfo {
+    BuildInfo {
+        git_dirty: git.as_ref().is_some_and(|git| git.dirty),
+        git_sha: git.map(|git| git.sha),
+        timestamp: proj
+            .source_date_epoch
+            .unwrap_or_else(|| timestamp(std::env::var("SOURCE_DATE_EPOCH").ok().as_deref())),
+        cargo_leptos: env!("CARGO_PKG_VERSION"),
+    }
+}
+
+/// The env vars of the cargo builds, with the profile of the built package.
+pub fn cargo_envs(proj: &Project, profile: &Profile) -> Vec<(&'static str, String)> {
+    if !proj.build_info {
+        return Vec::new();
+    }
+    let info = build_info(proj);
+    let mut envs = vec![
+        ("LEPTOS_BUILD_TIMESTAMP", info.timestamp.to_string()),
+        ("LEPTOS_BUILD_CARGO_LEPTOS", info.cargo_leptos.to_string()),
+        ("LEPTOS_BUILD_PROFILE", profile.to_string()),
+        ("LEPTOS_BUILD_GIT_DIRTY", info.git_dirty.to_string()),
+    ];
+    if let Some(sha) = &info.git_sha {
+        envs.push(("LEPTOS_BUILD_GIT_SHA", sha.clone()));
+    }
+    envs
+}
+
+fn timestamp(source_date_epoch: Option<&str>) -> u64 {
+    if let Some(secs) = source_date_epoch.and_then(|secs| secs.trim().parse().ok()) {
+        return secs;
+    }
+    unix_secs(SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_date_epoch() {
+        assert_eq!(timestamp(Some("1700000000")), 1_700_000_000);
+        assert!(timestamp(Some("not a number")) > 1_700_000_000);
+        assert!(timestamp(None) > 1_700_000_000);
+    }
+}
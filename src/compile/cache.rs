@@ -0,0 +1,87 @@
+use std::hash::Hasher;
+
+use crate::ext::{
+    anyhow::{Context, Result},
+    fs,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use seahash::SeaHasher;
+
+/// Content-hash keyed cache for the output of build steps, persisted in
+/// `target/.leptos-cache` so that unchanged steps are skipped across rebuilds.
+#[derive(Debug, Clone)]
+pub struct BuildCache {
+    dir: Utf8PathBuf,
+}
+
+impl BuildCache {
+    pub fn new(dir: &Utf8Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// Hash of the content of the given files and dirs (recursively), and the extra string.
+    /// Paths that don't exist are hashed by name only.
+    pub fn key<P: AsRef<Utf8Path>>(paths: &[P], extra: &str) -> Result<u64> {
+        let mut files = Vec::new();
+        for path in paths {
+            collect_files(path.as_ref(), &mut files)?;
+        }
+        files.sort();
+        files.dedup();
+
+        let mut hasher = SeaHasher::new();
+        hasher.write(extra.as_bytes());
+        for file in files {
+            hasher.write(file.as_str().as_bytes());
+            if file.is_file() {
+                let data = std::fs::read(&file).context(format!("Could not read {file}"))?;
+                hasher.write(&data);
+            }
+        }
+        Ok(hasher.finish())
+    }
+
+    /// Returns the cached output of the step if it was stored with the same key.
+    pub async fn get(&self, step: &str, key: u64) -> Option<String> {
+        let stored = fs::read_to_string(self.key_file(step)).await.ok()?;
+        if stored.trim() != key.to_string() {
+            log::trace!("Cache miss for {step}");
+            return None;
+        }
+        let output = fs::read_to_string(self.output_file(step)).await.ok()?;
+        log::debug!("Cache hit for {step}");
+        Some(output)
+    }
+
+    /// Returns true if the step was last stored with the same key.
+    pub async fn is_fresh(&self, step: &str, key: u64) -> bool {
+        self.get(step, key).await.is_some()
+    }
+
+    pub async fn put(&self, step: &str, key: u64, output: &str) -> Result<()> {
+        fs::create_dir_all(&self.dir).await?;
+        fs::write(self.output_file(step), output).await?;
+        fs::write(self.key_file(step), key.to_string()).await
+    }
+
+    fn key_file(&self, step: &str) -> Utf8PathBuf {
+        self.dir.join(format!("{step}.key"))
+    }
+
+    fn output_file(&self, step: &str) -> Utf8PathBuf {
+        self.dir.join(format!("{step}.out"))
+    }
+}
+
+fn collect_files(path: &Utf8Path, files: &mut Vec<Utf8PathBuf>) -> Result<()> {
+    if path.is_dir() {
+        for entry in path.read_dir_utf8()? {
+            collect_files(entry?.path(), files)?;
+        }
+    } else {
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
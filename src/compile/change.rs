@@ -30,6 +30,32 @@ impl ChangeSet {
         ])
     }
 
+    /// All the changes but those of the front (lib sources and Cargo.toml),
+    /// for serving before the front is built
+    pub fn all_but_front() -> Self {
+        Self(vec![
+            Change::BinSource,
+            Change::Style,
+            Change::Asset(Watched::Rescan),
+        ])
+    }
+
+    /// All the changes but the lib or bin sources (and the Cargo.toml) of the reused front
+    /// or server, for a build reusing their artifacts
+    pub fn all_but_reused(front: bool, server: bool) -> Self {
+        if !front && !server {
+            return Self::all_changes();
+        }
+        let mut changes = Self(vec![Change::Style, Change::Asset(Watched::Rescan)]);
+        if !front {
+            changes.add(Change::LibSource);
+        }
+        if !server {
+            changes.add(Change::BinSource);
+        }
+        changes
+    }
+
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
@@ -53,8 +79,16 @@ impl ChangeSet {
         })
     }
 
+    /// A change of a Cargo.toml, after which the watch loads the whole project config again,
+    /// and applies it to the next builds or starts again with it.
+    pub fn need_config_reload(&self) -> bool {
+        self.0.contains(&Change::Conf)
+    }
+
     pub fn need_style_build(&self, css_files: bool, css_in_source: bool) -> bool {
-        (css_files && self.0.contains(&Change::Style))
+        // the style vars are in the Cargo.toml
+        self.0.contains(&Change::Conf)
+            || (css_files && self.0.contains(&Change::Style))
             || (css_in_source && self.0.contains(&Change::LibSource))
     }
 
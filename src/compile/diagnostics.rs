@@ -0,0 +1,339 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    hash::{Hash, Hasher},
+    process::Stdio,
+    sync::Mutex,
+};
+
+use crate::{
+    config::Project,
+    ext::{
+        anyhow::Result,
+        fs,
+        sync::CommandResult,
+        tool::{write_log, RunningTool},
+        ErrorCode,
+    },
+    logger::{self, eprint_above_status, uncolored, GRAY, RESULT},
+    signal::Interrupt,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use cargo_metadata::{diagnostic::DiagnosticLevel, Message};
+use seahash::SeaHasher;
+use serde::Serialize;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+};
+
+/// The number of errors and warnings printed in full (all are with -v)
+const MAX_ERRORS: usize = 5;
+const MAX_WARNINGS: usize = 10;
+
+lazy_static::lazy_static! {
+    /// the diagnostics printed in this build cycle, so that the ones shared
+    /// by the lib and bin builds are only printed once
+    static ref SEEN: Mutex<HashSet<u64>> = Mutex::new(HashSet::new());
+    /// the diagnostics of the last finished build, by build (lib or bin)
+    static ref LAST: Mutex<BTreeMap<String, Vec<Diagnostic>>> = Mutex::new(BTreeMap::new());
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub level: &'static str,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// the lint or error code, e.g. `unused_variables` or `E0425`
+    pub code: Option<String>,
+    /// as rustc renders it, with ansi colors
+    pub rendered: String,
+}
+
+/// A diagnostic of `--log-format json`, with the build it is of.
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    build: &'a str,
+    #[serde(flatten)]
+    diagnostic: &'a Diagnostic,
+}
+
+impl Diagnostic {
+    fn from_cargo(diag: cargo_metadata::diagnostic::Diagnostic) -> Option<Self> {
+        let level = match diag.level {
+            DiagnosticLevel::Ice | DiagnosticLevel::Error => "error",
+            DiagnosticLevel::Warning => "warning",
+            _ => return None,
+        };
+        let span = diag.spans.iter().find(|s| s.is_primary);
+        Some(Self {
+            level,
+            file: span.map(|s| s.file_name.clone()),
+            line: span.map(|s| s.line_start),
+            column: span.map(|s| s.column_start),
+            code: diag.code.map(|code| code.code),
+            rendered: diag.rendered.unwrap_or_else(|| diag.message.clone()),
+            message: diag.message,
+        })
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.level == "error"
+    }
+
+    /// A `suppress-warnings` entry is a lint name or the start of it, e.g. `unused` for
+    /// `unused_variables` and `clippy` for all the clippy lints.
+    fn is_suppressed(&self, suppressed: &[String]) -> bool {
+        let Some(code) = self.code.as_deref().filter(|_| !self.is_error()) else {
+            return false;
+        };
+        suppressed.iter().any(|lint| {
+            code.strip_prefix(lint.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['_', ':']))
+        })
+    }
+
+    fn location(&self) -> String {
+        match (&self.file, self.line, self.column) {
+            (Some(file), Some(line), Some(col)) => format!("{file}:{line}:{col}"),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Forgets the diagnostics printed so far, call when starting a new build cycle.
+pub fn clear_seen_diagnostics() {
+    SEEN.lock().unwrap().clear();
+}
+
+/// The diagnostics of the last finished lib and bin builds.
+pub fn last_diagnostics() -> BTreeMap<String, Vec<Diagnostic>> {
+    LAST.lock().unwrap().clone()
+}
+
+/// Makes cargo write its diagnostics as json to stdout, for [wait_cargo]. Its stderr is piped
+/// to the log, so the colors of its progress are asked for when the log has them.
+pub fn add_json_diagnostics(command: &mut Command) {
+    command
+        .arg("--message-format=json-diagnostic-rendered-ansi")
+        .stdout(Stdio::piped());
+    if logger::colored() {
+        command.arg("--color=always");
+    }
+}
+
+/// The file the whole output of the cargo build is written to, for the process spawned with
+/// it as [crate::ext::tool::Tool::log_output_to].
+pub fn cargo_log(proj: &Project, build: &str) -> Utf8PathBuf {
+    proj.cache_dir.join(format!("cargo-{build}.log"))
+}
+
+/// Waits for the cargo build and prints a condensed summary of its diagnostics.
+/// The full diagnostics are written to `diagnostics-<build>.json` in the cache dir.
+pub async fn wait_cargo(
+    proj: &Project,
+    build: &str,
+    mut process: RunningTool,
+) -> Result<CommandResult<Vec<Diagnostic>>> {
+    let output_log = process.output_log();
+    let reader = process.take_stdout().map(|stdout| {
+        tokio::spawn(async move {
+            let mut diags = Vec::new();
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                match serde_json::from_str::<Message>(&line) {
+                    Ok(Message::CompilerMessage(msg)) => {
+                        if let (Some(log), Some(rendered)) = (&output_log, &msg.message.rendered) {
+                            write_log(log, rendered);
+                        }
+                        diags.extend(Diagnostic::from_cargo(msg.message))
+                    }
+                    Ok(_) => {}
+                    // e.g. output of build scripts
+                    Err(_) => {
+                        if let Some(log) = &output_log {
+                            write_log(log, &line);
+                        }
+                        log::info!("Cargo {line}")
+                    }
+                }
+            }
+            diags
+        })
+    });
+    let result = process.wait(Interrupt::subscribe_any()).await?;
+    let diags = match reader {
+        Some(reader) => reader.await?,
+        None => Vec::new(),
+    };
+
+    if !matches!(result, CommandResult::Interrupted) {
+        report(
+            build,
+            &diags,
+            &proj.suppress_warnings,
+            &cargo_log(proj, build),
+        );
+        if diags
+            .iter()
+            .any(|d| d.rendered.contains("target may not be installed"))
+        {
+            log::error!(
+                "Cargo {} {}",
+                ErrorCode::MissingWasmTarget.summary(),
+                ErrorCode::MissingWasmTarget.hint()
+            );
+        }
+        let file = proj.cache_dir.join(format!("diagnostics-{build}.json"));
+        fs::create_dir_all(&proj.cache_dir).await?;
+        fs::write(&file, serde_json::to_string_pretty(&diags)?).await?;
+        LAST.lock()
+            .unwrap()
+            .insert(build.to_string(), diags.clone());
+    }
+    Ok(match result {
+        CommandResult::Success(()) => CommandResult::Success(diags),
+        CommandResult::Failure(()) => CommandResult::Failure(diags),
+        CommandResult::Interrupted => CommandResult::Interrupted,
+    })
+}
+
+/// The rendered errors, for the browser error overlay.
+pub fn error_output(diags: &[Diagnostic], line: &str) -> String {
+    let errors = diags
+        .iter()
+        .filter(|d| d.is_error())
+        .map(|d| d.rendered.as_str())
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        format!("{line} failed")
+    } else {
+        errors.join("\n")
+    }
+}
+
+fn report(build: &str, diags: &[Diagnostic], suppressed: &[String], output_log: &Utf8Path) {
+    let json = logger::json();
+    if json {
+        // all of them, suppressed or printed before, for the consumer to filter
+        for diagnostic in diags {
+            logger::eprint_json(&JsonDiagnostic {
+                kind: "diagnostic",
+                build,
+                diagnostic,
+            });
+        }
+    }
+    let verbose = log::log_enabled!(log::Level::Debug);
+    let unseen = {
+        let mut seen = SEEN.lock().unwrap();
+        diags
+            .iter()
+            .filter(|d| {
+                let mut hasher = SeaHasher::new();
+                d.rendered.hash(&mut hasher);
+                seen.insert(hasher.finish())
+            })
+            .collect::<Vec<_>>()
+    };
+    let (errors, mut warnings): (Vec<_>, Vec<_>) = unseen.into_iter().partition(|d| d.is_error());
+    // all of them are in the diagnostics file, and with -v
+    let shown = warnings.len();
+    if !verbose {
+        warnings.retain(|d| !d.is_suppressed(suppressed));
+    }
+    let suppressed = shown - warnings.len();
+
+    // printed as json above
+    if !json {
+        for (i, warning) in warnings.iter().enumerate() {
+            if verbose {
+                eprint_above_status(&uncolored(&warning.rendered));
+            } else if i < MAX_WARNINGS {
+                log::warn!(
+                    "Cargo {build} {} {}",
+                    warning.message,
+                    GRAY.paint(warning.location())
+                );
+            }
+        }
+        for (i, error) in errors.iter().enumerate() {
+            if verbose || i < MAX_ERRORS {
+                eprint_above_status(&uncolored(&error.rendered));
+            }
+        }
+    }
+
+    let hidden_errors = errors.len().saturating_sub(MAX_ERRORS);
+    let hidden_warnings = warnings.len().saturating_sub(MAX_WARNINGS);
+    if !errors.is_empty() || !warnings.is_empty() {
+        let suppressed = match suppressed {
+            0 => String::new(),
+            n => format!(" ({n} suppressed)"),
+        };
+        log::info!(
+            target: RESULT,
+            "Cargo {build} {} errors, {} warnings{suppressed}",
+            errors.len(),
+            warnings.len()
+        );
+    }
+    if !json && !verbose && hidden_errors + hidden_warnings > 0 {
+        log::info!(
+            "Cargo {build} {hidden_errors} errors and {hidden_warnings} warnings not shown, use -v to see all or the output in {}",
+            GRAY.paint(output_log.as_str())
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suppressed() {
+        let diag = |level, code: &str| Diagnostic {
+            level,
+            message: String::new(),
+            file: None,
+            line: None,
+            column: None,
+            code: Some(code.to_string()),
+            rendered: String::new(),
+        };
+        let suppressed = ["unused".to_string(), "clippy".to_string()];
+        assert!(diag("warning", "unused_variables").is_suppressed(&suppressed));
+        assert!(diag("warning", "unused").is_suppressed(&suppressed));
+        assert!(diag("warning", "clippy::needless_return").is_suppressed(&suppressed));
+        assert!(!diag("warning", "unusual_byte_groupings").is_suppressed(&suppressed));
+        assert!(!diag("warning", "dead_code").is_suppressed(&suppressed));
+        assert!(!diag("error", "unused_must_use").is_suppressed(&suppressed));
+    }
+
+    #[test]
+    fn json_diagnostic() {
+        let diagnostic = Diagnostic {
+            level: "error",
+            message: "cannot find value `x`".to_string(),
+            file: Some("src/lib.rs".to_string()),
+            line: Some(3),
+            column: Some(5),
+            code: Some("E0425".to_string()),
+            rendered: String::new(),
+        };
+        let json = serde_json::to_value(JsonDiagnostic {
+            kind: "diagnostic",
+            build: "bin",
+            diagnostic: &diagnostic,
+        })
+        .unwrap();
+        assert_eq!(json["type"], "diagnostic");
+        assert_eq!(json["build"], "bin");
+        assert_eq!(json["level"], "error");
+        assert_eq!(json["code"], "E0425");
+        assert_eq!(json["line"], 3);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{
+    config::Project,
+    ext::{anyhow::Result, Exe},
+    logger::GRAY,
+};
+use tokio::process::Command;
+
+use super::{
+    front::{build_cargo_front_cmd, detect_nightly, wasm_opt_args},
+    sass::{load_paths, sass_args},
+    server::build_cargo_server_cmd,
+    tailwind::{node_args, tailwind_args},
+    targets::WasmFeatures,
+    wasm_split::{self, wasm_split_args},
+};
+
+/// Logs the commands a build of the project would run, without running them.
+pub async fn dry_run(proj: &Project) -> Result<()> {
+    log::info!(
+        "Dry-run {} site-root {} site-pkg-dir {} site-addr {} reload-port {}",
+        proj.name,
+        GRAY.paint(proj.site.root_dir.as_str()),
+        GRAY.paint(proj.site.pkg_dir.as_str()),
+        proj.site.addr,
+        proj.site.reload.port()
+    );
+    hook("pre-build", &proj.hooks.pre_build);
+
+    front(proj).await?;
+    for variant in proj.locales.iter().chain(&proj.entrypoints) {
+        front(variant).await?;
+    }
+
+    let (envs, line) = build_cargo_server_cmd("build", proj, &mut Command::new("cargo"));
+    if let Some(cargo) = &proj.bin.cargo_command {
+        log::info!("Dry-run server cargo command {}", GRAY.paint(cargo));
+    }
+    log::info!("Dry-run server {}", GRAY.paint(line));
+    log::debug!("Dry-run server envs {}", GRAY.paint(envs));
+
+    if let Some(style_file) = &proj.style.file {
+        if matches!(style_file.source.extension(), Some("sass") | Some("scss")) {
+            log::info!(
+                "Dry-run style {} {}",
+                GRAY.paint(Exe::Sass.locate(proj.sass_bin.as_deref()).await?),
+                GRAY.paint(
+                    sass_args(style_file, proj.style_source_map(), &load_paths(proj)).join(" ")
+                )
+            );
+        }
+    }
+    if let Some(tw_conf) = &proj.style.tailwind {
+        if let Some(node) = &tw_conf.node {
+            log::info!(
+                "Dry-run style {} {}",
+                GRAY.paint(node.package_manager.program()),
+                GRAY.paint(node_args(node, tw_conf)?.join(" "))
+            );
+        } else {
+            log::info!(
+                "Dry-run style {} {}",
+                GRAY.paint(Exe::Tailwind.locate(proj.tailwind_bin.as_deref()).await?),
+                GRAY.paint(tailwind_args(tw_conf).join(" "))
+            );
+        }
+    }
+    if let Some(assets) = &proj.assets {
+        log::info!(
+            "Dry-run assets copy {} to {}",
+            GRAY.paint(assets.dir.as_str()),
+            GRAY.paint(proj.site.root_dir.as_str())
+        );
+    }
+
+    hook("post-build", &proj.hooks.post_build);
+    hook("pre-serve", &proj.hooks.pre_serve);
+    match &proj.server_runner {
+        Some(runner) => log::info!(
+            "Dry-run serve {} {}",
+            GRAY.paint(runner),
+            GRAY.paint(proj.bin.exe_file.as_str())
+        ),
+        None => log::info!("Dry-run serve {}", GRAY.paint(proj.bin.exe_file.as_str())),
+    }
+    Ok(())
+}
+
+async fn front(proj: &Project) -> Result<()> {
+    detect_nightly(proj).await;
+    let (envs, line) = build_cargo_front_cmd("build", true, proj, &mut Command::new("cargo"));
+    log::info!("Dry-run front {}", GRAY.paint(line));
+    log::debug!("Dry-run front envs {}", GRAY.paint(envs));
+
+    let wasm_file = &proj.lib.wasm_file;
+    log::info!(
+        "Dry-run front wasm-bindgen (built in) {} to {}",
+        GRAY.paint(wasm_file.source.as_str()),
+        GRAY.paint(proj.site.root_relative_pkg_dir().as_str())
+    );
+    if wasm_split::is_split(proj) {
+        log::info!(
+            "Dry-run front {} {}",
+            GRAY.paint(Exe::WasmSplit.locate(None).await?),
+            GRAY.paint(
+                wasm_split_args(
+                    &wasm_file.dest,
+                    &proj.lib.split_file.dest,
+                    &proj.lib.wasm_split,
+                    proj.debug_symbols
+                )
+                .join(" ")
+            )
+        );
+    }
+    if proj.release && !proj.lib.wasm_debug {
+        log::info!(
+            "Dry-run front {} {}",
+            GRAY.paint(Exe::WasmOpt.locate(proj.wasm_opt_bin.as_deref()).await?),
+            GRAY.paint(
+                wasm_opt_args(
+                    proj,
+                    &wasm_file.dest,
+                    WasmFeatures::of_project(proj)?.unwrap_or_default()
+                )
+                .join(" ")
+            )
+        );
+    }
+    if proj.release && proj.lib.js_minify {
+        log::info!(
+            "Dry-run front {} --minify {}",
+            GRAY.paint(Exe::Esbuild.locate(None).await?),
+            GRAY.paint(proj.lib.js_file.dest.as_str())
+        );
+    }
+    Ok(())
+}
+
+fn hook(name: &str, cmd: &Option<String>) {
+    if let Some(cmd) = cmd {
+        log::info!("Dry-run hook {name} {}", GRAY.paint(cmd));
+    }
+}
@@ -1,21 +1,32 @@
 use std::collections::HashMap;
-use std::sync::Arc;
-
-use super::ChangeSet;
+use std::sync::{Arc, OnceLock};
+
+use super::{
+    build_info,
+    diagnostics::{add_json_diagnostics, cargo_log, error_output, wait_cargo},
+    streaming,
+    targets::{self, WasmFeatures},
+    toolchain::{self, WASM_TARGET},
+    wasm_split, ChangeSet,
+};
 use crate::config::Project;
 use crate::ext::fs;
-use crate::ext::sync::{wait_interruptible, CommandResult};
-use crate::service::site::SiteFile;
-use crate::signal::{Interrupt, Outcome, Product};
+use crate::ext::sync::{is_interrupted, CommandResult};
+use crate::ext::tool::Tool;
+use crate::service::{
+    self,
+    site::{SiteFile, SourcedSiteFile},
+};
+use crate::signal::{Interrupt, Outcome, Phase, Product, ReloadSignal, Timings};
 use crate::{
     ext::{
-        anyhow::{Context, Result},
+        anyhow::{bail, Context, Result},
         exe::Exe,
     },
-    logger::GRAY,
+    logger::{GRAY, RESULT},
 };
+use base64::{engine::general_purpose::STANDARD, Engine};
 use camino::{Utf8Path, Utf8PathBuf};
-use tokio::process::Child;
 use tokio::{process::Command, sync::broadcast, task::JoinHandle};
 use wasm_bindgen_cli_support::Bindgen;
 
@@ -32,29 +43,126 @@ pub async fn front(
         }
 
         fs::create_dir_all(&proj.site.root_relative_pkg_dir()).await?;
-
-        let (envs, line, process) = front_cargo_process("build", true, &proj)?;
-
-        match wait_interruptible("Cargo", process, Interrupt::subscribe_any()).await? {
+        build_info::load_build_info(&proj).await;
+        toolchain::ensure_target(
+            WASM_TARGET,
+            proj.lib.toolchain.as_deref(),
+            proj.toolchain_install,
+        )
+        .await?;
+
+        detect_nightly(&proj).await;
+        let permit = proj.cargo_jobs.acquire().await?;
+        let mut command = Command::new("cargo");
+        let (envs, line) = build_cargo_front_cmd("build", true, &proj, &mut command);
+        add_json_diagnostics(&mut command);
+        let process = Tool::new("Cargo", command)
+            .with_timeouts(&proj.tool_timeouts)
+            .log_output_to(cargo_log(&proj, "lib"))
+            .spawn()?;
+
+        let started = Timings::start();
+        let result = wait_cargo(&proj, "lib", process).await?;
+        drop(permit);
+        match result {
             CommandResult::Interrupted => return Ok(Outcome::Stopped),
-            CommandResult::Failure(_) => return Ok(Outcome::Failed),
+            CommandResult::Failure(diags) => {
+                if proj.watch {
+                    ReloadSignal::send_build_error(&error_output(&diags, &line));
+                }
+                return Ok(Outcome::Failed);
+            }
             _ => {}
         }
+        Timings::record(Phase::CargoLib, started);
         log::debug!("Cargo envs: {}", GRAY.paint(envs));
-        log::info!("Cargo finished {}", GRAY.paint(line));
+        log::info!(target: RESULT, "Cargo finished {}", GRAY.paint(line));
 
         bindgen(&proj).await.dot()
     })
 }
 
-pub fn front_cargo_process(
+const THREADS_RUSTFLAGS: &str = "-C target-feature=+atomics,+bulk-memory,+mutable-globals";
+
+/// Whether the active toolchain is nightly, checked once.
+static NIGHTLY: OnceLock<bool> = OnceLock::new();
+
+/// Checks once if the active toolchain is nightly, off the async threads, for the wasm-threads
+/// builds of the cargo commands that follow.
+pub async fn detect_nightly(proj: &Project) {
+    if !proj.lib.wasm_threads || proj.lib.toolchain.is_some() || NIGHTLY.get().is_some() {
+        return;
+    }
+    let nightly = tokio::task::spawn_blocking(rustc_is_nightly)
+        .await
+        .unwrap_or(false);
+    set_nightly(nightly);
+}
+
+fn rustc_is_nightly() -> bool {
+    std::process::Command::new("rustc")
+        .arg("--version")
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).contains("nightly"))
+        .unwrap_or(false)
+}
+
+/// The warning is only logged by the first check.
+fn set_nightly(nightly: bool) -> bool {
+    *NIGHTLY.get_or_init(|| {
+        if !nightly {
+            log::warn!("Front wasm-threads needs a nightly toolchain to rebuild std with atomics");
+        }
+        nightly
+    })
+}
+
+/// Checks if the toolchain is nightly, which `-Zbuild-std` needs. The active one is the one
+/// found by [detect_nightly], or checked here when it didn't run.
+fn is_nightly(toolchain: Option<&str>) -> bool {
+    if let Some(toolchain) = toolchain {
+        return toolchain.starts_with("nightly");
+    }
+    match NIGHTLY.get() {
+        Some(nightly) => *nightly,
+        None => set_nightly(rustc_is_nightly()),
+    }
+}
+
+/// The script served by `--lazy-front` until the first front build, with the
+/// `init` and `hydrate` of the wasm-bindgen glue doing nothing.
+const PLACEHOLDER_JS: &str = "\
+// written by cargo-leptos --lazy-front, the page reloads once the front is built
+export default async function init() {
+  console.log(\"cargo-leptos: the front is building, the page reloads once it is ready\");
+}
+export function hydrate() {}
+";
+
+/// Writes a placeholder js glue when there's no front from a previous build, so
+/// that the site can be served while the front is built.
+pub async fn write_placeholder(proj: &Project) -> Result<()> {
+    let file = &proj.lib.js_file;
+    if !file.dest.exists() {
+        log::info!("Front placeholder {}", GRAY.paint(file.dest.as_str()));
+        proj.site
+            .updated_with(file, PLACEHOLDER_JS.as_bytes())
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn front_cargo_command(
     cmd: &str,
     wasm: bool,
     proj: &Project,
-) -> Result<(String, String, Child)> {
+) -> (String, String, Command) {
+    if wasm {
+        detect_nightly(proj).await;
+    }
     let mut command = Command::new("cargo");
     let (envs, line) = build_cargo_front_cmd(cmd, wasm, proj, &mut command);
-    Ok((envs, line, command.spawn()?))
+    (envs, line, command)
 }
 
 pub fn build_cargo_front_cmd(
@@ -63,14 +171,22 @@ pub fn build_cargo_front_cmd(
     proj: &Project,
     command: &mut Command,
 ) -> (String, String) {
-    let mut args = vec![
-        cmd.to_string(),
+    let mut args = toolchain::toolchain_args(proj.lib.toolchain.as_deref());
+    // the tool args of an alias are left to the caller, to stay after its own
+    args.extend(proj.cargo_config.expand_alias(cmd).0);
+    args.extend([
         format!("--package={}", proj.lib.name.as_str()),
         "--lib".to_string(),
-        "--target-dir=target/front".to_string(),
-    ];
+        format!("--target-dir={}", proj.lib.target_dir),
+    ]);
     if wasm {
-        args.push("--target=wasm32-unknown-unknown".to_string());
+        args.push(format!("--target={WASM_TARGET}"));
+    }
+
+    let threads = wasm && proj.lib.wasm_threads;
+    if threads && is_nightly(proj.lib.toolchain.as_deref()) {
+        // the prebuilt std is compiled without atomics
+        args.push("-Zbuild-std=panic_abort,std".to_string());
     }
 
     if !proj.lib.default_features {
@@ -82,8 +198,30 @@ pub fn build_cargo_front_cmd(
     }
 
     proj.lib.profile.add_to_args(&mut args);
+    if let Some(jobs) = service::adaptive::cargo_jobs() {
+        args.push(format!("--jobs={jobs}"));
+    }
+    if proj.timings_dir.is_some() && cmd == "build" {
+        args.push("--timings".to_string());
+    }
+    if proj.debug_symbols {
+        args.push(format!(
+            "--config=profile.{}.debug=true",
+            proj.lib.profile.cargo_name()
+        ));
+    }
 
-    let envs = proj.to_envs();
+    let mut envs = proj.to_cargo_envs();
+    envs.extend(build_info::cargo_envs(proj, &proj.lib.profile));
+    if threads {
+        // RUSTFLAGS replaces the rustflags of the cargo config, so they are kept in it
+        let flags = std::env::var("RUSTFLAGS")
+            .unwrap_or_else(|_| proj.cargo_config.rustflags(WASM_TARGET).join(" "));
+        envs.push((
+            "RUSTFLAGS",
+            format!("{flags} {THREADS_RUSTFLAGS}").trim().to_string(),
+        ));
+    }
 
     let envs_str = envs
         .iter()
@@ -98,29 +236,82 @@ pub fn build_cargo_front_cmd(
 
 async fn bindgen(proj: &Project) -> Result<Outcome<Product>> {
     let wasm_file = &proj.lib.wasm_file;
-    let interrupt = Interrupt::subscribe_any();
+    let mut interrupt = Interrupt::subscribe_any();
 
     log::info!("Front compiling WASM");
 
+    // wasm-bindgen applies its threads transform by itself when the module uses shared memory
     // see:
     // https://github.com/rustwasm/wasm-bindgen/blob/main/crates/cli-support/src/lib.rs#L95
     // https://github.com/rustwasm/wasm-bindgen/blob/main/crates/cli/src/bin/wasm-bindgen.rs#L13
-    let mut bindgen = Bindgen::new()
+    let started = Timings::start();
+    let features = WasmFeatures::of_project(proj)?;
+    let mut bindgen = Bindgen::new();
+    bindgen
         .input_path(&wasm_file.source)
         .web(true)
         .dot()?
-        .generate_output()
-        .dot()?;
+        // the DWARF debug info, for the stack traces of --wasm-debug
+        .keep_debug(proj.debug_symbols || proj.lib.wasm_debug)
+        .demangle(true)
+        .remove_name_section(false);
+    // the features all the browsers of the browser-targets support
+    if let Some(features) = features {
+        log::debug!("Front wasm-bindgen features for the browser-targets: {features:?}");
+        bindgen
+            .reference_types(features.reference_types)
+            .weak_refs(features.weak_refs);
+    }
+    let mut bindgen = bindgen.generate_output().dot()?;
+    // wasm-bindgen runs in-process and can't be killed, but its output is
+    // discarded when newer changes arrived meanwhile
+    if is_interrupted(&mut interrupt) {
+        log::debug!("Front discarded the outdated wasm-bindgen output");
+        return Ok(Outcome::Stopped);
+    }
 
     bindgen.wasm_mut().emit_wasm_file(&wasm_file.dest).dot()?;
     log::trace!("Front wrote wasm to {:?}", wasm_file.dest.as_str());
-    if proj.release {
-        match optimize(&wasm_file.dest, interrupt).await.dot()? {
+    Timings::record(Phase::WasmBindgen, started);
+    let split = wasm_split::is_split(proj);
+    if split {
+        match wasm_split::split(proj, Interrupt::subscribe_any())
+            .await
+            .dot()?
+        {
             CommandResult::Interrupted => return Ok(Outcome::Stopped),
             CommandResult::Failure(_) => return Ok(Outcome::Failed),
             _ => {}
         }
     }
+    if proj.release && !proj.lib.wasm_debug {
+        let started = Timings::start();
+        let features = features.unwrap_or_default();
+        match optimize(&wasm_file.dest, proj, features, interrupt)
+            .await
+            .dot()?
+        {
+            CommandResult::Interrupted => return Ok(Outcome::Stopped),
+            CommandResult::Failure(_) => return Ok(Outcome::Failed),
+            _ => {}
+        }
+        if split {
+            let split_file = &proj.lib.split_file.dest;
+            match optimize(split_file, proj, features, Interrupt::subscribe_any())
+                .await
+                .dot()?
+            {
+                CommandResult::Interrupted => return Ok(Outcome::Stopped),
+                CommandResult::Failure(_) => return Ok(Outcome::Failed),
+                _ => {}
+            }
+        }
+        Timings::record(Phase::WasmOpt, started);
+    }
+
+    if proj.debug_symbols {
+        copy_source_map(wasm_file).await?;
+    }
 
     let mut js_changed = false;
 
@@ -133,11 +324,15 @@ async fn bindgen(proj: &Project) -> Result<Outcome<Product>> {
         .did_file_change(&proj.lib.wasm_file.as_site_file())
         .await
         .dot()?;
-    js_changed |= proj
-        .site
-        .updated_with(&proj.lib.js_file, bindgen.js().as_bytes())
-        .await
-        .dot()?;
+    let js = if split {
+        wasm_split::with_loader(bindgen.js(), &proj.lib.split_file.dest)?
+    } else {
+        bindgen.js().to_string()
+    };
+    js_changed |= write_js(proj, &proj.lib.js_file, &js, false).await.dot()?;
+    if js_changed {
+        streaming::check_glue(bindgen.js());
+    }
     log::debug!("Front js changed: {js_changed}");
     log::debug!("Front wasm changed: {wasm_changed}");
 
@@ -150,16 +345,61 @@ async fn bindgen(proj: &Project) -> Result<Outcome<Product>> {
 
 async fn optimize(
     file: &Utf8Path,
+    proj: &Project,
+    features: WasmFeatures,
     interrupt: broadcast::Receiver<()>,
 ) -> Result<CommandResult<()>> {
-    let wasm_opt = Exe::WasmOpt.get().await.dot()?;
+    let wasm_opt = Exe::WasmOpt
+        .get_or_local(proj.wasm_opt_bin.as_deref())
+        .await
+        .dot()?;
 
-    let args = [file.as_str(), "-Os", "-o", file.as_str()];
-    let process = Command::new(wasm_opt)
-        .args(args)
-        .spawn()
-        .context("Could not spawn command")?;
-    wait_interruptible("wasm-opt", process, interrupt).await
+    let mut command = Command::new(wasm_opt);
+    command.args(wasm_opt_args(proj, file, features));
+    Ok(
+        match Tool::new("wasm-opt", command)
+            .with_timeouts(&proj.tool_timeouts)
+            .output(interrupt)
+            .await?
+        {
+            CommandResult::Success(_) => CommandResult::Success(()),
+            CommandResult::Failure(_) => CommandResult::Failure(()),
+            CommandResult::Interrupted => CommandResult::Interrupted,
+        },
+    )
+}
+
+/// Copies the `.wasm.map` written next to the cargo output (e.g. by a linker wrapper), if any.
+async fn copy_source_map(wasm_file: &SourcedSiteFile) -> Result<()> {
+    let source = wasm_file.source.with_extension("wasm.map");
+    if source.exists() {
+        let dest = wasm_file.dest.with_extension("wasm.map");
+        fs::copy(&source, &dest).await?;
+        log::debug!("Front copied source map to {}", GRAY.paint(dest.as_str()));
+    }
+    Ok(())
+}
+
+pub fn wasm_opt_args<'a>(
+    proj: &Project,
+    file: &'a Utf8Path,
+    features: WasmFeatures,
+) -> Vec<&'a str> {
+    let mut args = vec![file.as_str(), "-Os", "-o", file.as_str()];
+    if proj.debug_symbols {
+        args.push("-g");
+    }
+    if proj.lib.wasm_threads {
+        args.extend([
+            "--enable-threads",
+            "--enable-bulk-memory",
+            "--enable-mutable-globals",
+        ]);
+    }
+    if features.reference_types {
+        args.push("--enable-reference-types");
+    }
+    args
 }
 
 async fn write_snippets(proj: &Project, snippets: &HashMap<String, Vec<String>>) -> Result<bool> {
@@ -179,7 +419,12 @@ async fn write_snippets(proj: &Project, snippets: &HashMap<String, Vec<String>>)
                 site: site_path,
             };
 
-            js_changed |= proj.site.updated_with(&site_file, js.as_bytes()).await?;
+            if proj.lib.bundle_snippets {
+                let js = bundle(proj, js, &proj.lib.abs_dir).await?;
+                js_changed |= write_js(proj, &site_file, &js, true).await?;
+            } else {
+                js_changed |= write_js(proj, &site_file, js, false).await?;
+            }
         }
     }
     Ok(js_changed)
@@ -199,7 +444,136 @@ async fn write_modules(proj: &Project, modules: &HashMap<String, String>) -> Res
             site: site_path,
         };
 
-        js_changed |= proj.site.updated_with(&site_file, js.as_bytes()).await?;
+        if proj.lib.bundle_snippets {
+            // the module path is relative to the lib package, its imports to the module
+            let source = proj.lib.abs_dir.join(path.trim_start_matches('/'));
+            let dir = source.parent().unwrap_or(&proj.lib.abs_dir);
+            let js = bundle(proj, js, dir).await?;
+            js_changed |= write_js(proj, &site_file, &js, true).await?;
+        } else {
+            js_changed |= write_js(proj, &site_file, js, false).await?;
+        }
     }
     Ok(js_changed)
 }
+
+/// Writes the js to the site, minified in release builds with `js-minify` unless it
+/// already is (bundled snippets are minified by esbuild in release builds).
+async fn write_js(proj: &Project, file: &SiteFile, js: &str, minified: bool) -> Result<bool> {
+    if !proj.release || !proj.lib.js_minify || minified {
+        return proj.site.updated_with(file, js.as_bytes()).await;
+    }
+    let name = file.site.file_name().unwrap_or_default();
+    let target = targets::browsers(proj)?.and_then(|b| targets::esbuild_target(&b));
+    let (js, map) = minify(proj, js, name, target).await?;
+    if let Some(map) = map {
+        let map_file = SiteFile {
+            dest: file.dest.with_extension("js.map"),
+            site: file.site.with_extension("js.map"),
+        };
+        proj.site.updated_with(&map_file, map.as_bytes()).await?;
+    }
+    proj.site.updated_with(file, js.as_bytes()).await
+}
+
+/// Minifies the js with esbuild. The source map is inlined by esbuild (as it reads from stdin),
+/// so it is taken out and referenced by the `<name>.map` file instead. The syntax is lowered
+/// for the browser-targets, when set.
+async fn minify(
+    proj: &Project,
+    js: &str,
+    name: &str,
+    target: Option<String>,
+) -> Result<(String, Option<String>)> {
+    let esbuild = Exe::Esbuild.get().await.dot()?;
+    let mut args = vec![
+        "--minify".to_string(),
+        "--format=esm".to_string(),
+        "--log-level=warning".to_string(),
+    ];
+    if let Some(target) = target {
+        args.push(format!("--target={target}"));
+    }
+    if proj.lib.js_source_map {
+        args.push("--sourcemap=inline".to_string());
+        args.push(format!("--sourcefile={name}"));
+    }
+    let mut command = Command::new(esbuild);
+    command.args(args);
+    let CommandResult::Success(output) = Tool::new("esbuild", command)
+        .with_timeouts(&proj.tool_timeouts)
+        .output_with_input(js.as_bytes())
+        .await?
+    else {
+        bail!("Front esbuild failed to minify {name}");
+    };
+    let minified = String::from_utf8(output.stdout)?;
+    log::trace!(
+        "Front minified {name} from {} to {} bytes",
+        js.len(),
+        minified.len()
+    );
+    if !source_map {
+        return Ok((minified, None));
+    }
+    split_inline_source_map(&minified, name)
+}
+
+const INLINE_SOURCE_MAP: &str = "//# sourceMappingURL=data:application/json;base64,";
+
+fn split_inline_source_map(js: &str, name: &str) -> Result<(String, Option<String>)> {
+    let Some((code, map)) = js.rsplit_once(INLINE_SOURCE_MAP) else {
+        return Ok((js.to_string(), None));
+    };
+    let map = STANDARD
+        .decode(map.trim())
+        .context("Could not decode the esbuild source map")?;
+    let code = format!("{code}//# sourceMappingURL={name}.map\n");
+    Ok((code, Some(String::from_utf8(map)?)))
+}
+
+/// Bundles the imports of a snippet into it with esbuild, so that the npm
+/// packages it uses are served with the site.
+async fn bundle(proj: &Project, js: &str, resolve_dir: &Utf8Path) -> Result<String> {
+    let esbuild = Exe::Esbuild.get().await.dot()?;
+    let mut args = vec![
+        "--bundle".to_string(),
+        "--format=esm".to_string(),
+        "--log-level=warning".to_string(),
+    ];
+    if proj.release {
+        args.push("--minify".to_string());
+    }
+    if let Some(target) = targets::browsers(proj)?.and_then(|b| targets::esbuild_target(&b)) {
+        args.push(format!("--target={target}"));
+    }
+    // from stdin, esbuild resolves the imports from the current dir
+    let mut command = Command::new(esbuild);
+    command.args(args).current_dir(resolve_dir);
+    let CommandResult::Success(output) = Tool::new("esbuild", command)
+        .with_timeouts(&proj.tool_timeouts)
+        .output_with_input(js.as_bytes())
+        .await?
+    else {
+        bail!("Front esbuild failed to bundle a snippet");
+    };
+    log::trace!("Front bundled a snippet with esbuild");
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_source_map() {
+        let map = STANDARD.encode(r#"{"version":3}"#);
+        let js = format!("let a=1;\n{INLINE_SOURCE_MAP}{map}\n");
+        let (code, map) = split_inline_source_map(&js, "app.js").unwrap();
+        assert_eq!(code, "let a=1;\n//# sourceMappingURL=app.js.map\n");
+        assert_eq!(map.as_deref(), Some(r#"{"version":3}"#));
+
+        let (code, map) = split_inline_source_map("let a=1;", "app.js").unwrap();
+        assert_eq!((code.as_str(), map), ("let a=1;", None));
+    }
+}
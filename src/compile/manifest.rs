@@ -0,0 +1,235 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{
+    build_info::{build_info, load_build_info, BuildInfo},
+    wasm_split,
+};
+use crate::{
+    config::Project,
+    ext::{
+        anyhow::{Context, Result},
+        fs, PathExt,
+    },
+    logger::GRAY,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Serialize;
+use sha2::{Digest, Sha384};
+
+/// Machine readable description of the build output, written after every build
+/// so that deployment tooling and server integrations don't have to guess paths.
+#[derive(Debug, Serialize)]
+pub struct BuildManifest {
+    /// the cargo-leptos version that produced the build
+    pub cargo_leptos: &'static str,
+    pub project: String,
+    pub release: bool,
+    pub site_root: String,
+    pub site_pkg_dir: String,
+    /// the public url prefix of the site, see [ArtifactFile::url]
+    pub base_href: String,
+    /// unix timestamp (seconds) of when the manifest was written, the source date epoch with
+    /// `--reproducible`
+    pub built_at: u64,
+    pub lib: PackageInfo,
+    pub bin: PackageInfo,
+    /// the provenance given to the builds, with `build-info`
+    pub build_info: Option<BuildInfo>,
+    pub files: Vec<ArtifactFile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageInfo {
+    pub name: String,
+    pub profile: String,
+    pub features: Vec<String>,
+    pub default_features: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArtifactFile {
+    /// one of: wasm, wasm-split, js, css, snippet, server
+    pub kind: &'static str,
+    /// path relative to the working dir
+    pub path: String,
+    /// path relative to the site root (not set for the server binary)
+    pub site: Option<String>,
+    /// the public url path, the base-href followed by the site path
+    pub url: Option<String>,
+    pub size: u64,
+    /// seahash of the file content as hex
+    pub hash: String,
+    /// the subresource integrity (sha384) of the site files, for the `integrity`
+    /// attribute of the script and link tags
+    pub integrity: Option<String>,
+    /// unix timestamp (seconds) of the last modification, the source date epoch with
+    /// `--reproducible`
+    pub modified: u64,
+}
+
+impl BuildManifest {
+    pub async fn new(proj: &Project) -> Result<Self> {
+        let lib = &proj.lib;
+        let bin = &proj.bin;
+
+        let mut files = Vec::new();
+        let site_files = [
+            ("wasm", &lib.wasm_file.dest, &lib.wasm_file.site),
+            ("js", &lib.js_file.dest, &lib.js_file.site),
+            (
+                "css",
+                &proj.style.site_file.dest,
+                &proj.style.site_file.site,
+            ),
+        ];
+        // the entrypoints have their own wasm and js, with their own hashes
+        let entry_files = proj.entrypoints.iter().flat_map(|entry| {
+            [
+                ("wasm", &entry.lib.wasm_file.dest, &entry.lib.wasm_file.site),
+                ("js", &entry.lib.js_file.dest, &entry.lib.js_file.site),
+            ]
+        });
+        for (kind, dest, site) in site_files.into_iter().chain(entry_files) {
+            if let Some(file) = ArtifactFile::read(proj, kind, dest, Some(site)).await? {
+                files.push(file);
+            }
+        }
+        if wasm_split::is_split(proj) {
+            let split = &lib.split_file;
+            if let Some(file) =
+                ArtifactFile::read(proj, "wasm-split", &split.dest, Some(&split.site)).await?
+            {
+                files.push(file);
+            }
+        }
+        // the js snippets of wasm-bindgen, imported by the js file
+        let pkg_dir = proj.site.root_relative_pkg_dir();
+        for dest in dir_files(&pkg_dir.join("snippets")) {
+            let site = proj
+                .site
+                .pkg_dir
+                .join(dest.strip_prefix(&pkg_dir).unwrap_or(&dest));
+            if let Some(file) = ArtifactFile::read(proj, "snippet", &dest, Some(&site)).await? {
+                files.push(file);
+            }
+        }
+        if let Some(file) = ArtifactFile::read(proj, "server", &bin.exe_file, None).await? {
+            files.push(file);
+        }
+        for file in &mut files {
+            file.url = file
+                .site
+                .as_ref()
+                .map(|site| format!("{}{site}", proj.base_href));
+        }
+
+        Ok(Self {
+            cargo_leptos: env!("CARGO_PKG_VERSION"),
+            project: proj.name.clone(),
+            release: proj.release,
+            site_root: proj.site.root_dir.to_string(),
+            site_pkg_dir: proj.site.pkg_dir.to_string(),
+            base_href: proj.base_href.clone(),
+            built_at: proj
+                .source_date_epoch
+                .unwrap_or_else(|| unix_secs(SystemTime::now())),
+            lib: PackageInfo {
+                name: lib.name.clone(),
+                profile: lib.profile.to_string(),
+                features: lib.features.clone(),
+                default_features: lib.default_features,
+            },
+            bin: PackageInfo {
+                name: bin.name.clone(),
+                profile: bin.profile.to_string(),
+                features: bin.features.clone(),
+                default_features: bin.default_features,
+            },
+            build_info: if proj.build_info {
+                load_build_info(proj).await;
+                Some(build_info(proj).clone())
+            } else {
+                None
+            },
+            files,
+        })
+    }
+}
+
+impl ArtifactFile {
+    async fn read(
+        proj: &Project,
+        kind: &'static str,
+        path: &Utf8Path,
+        site: Option<&Utf8Path>,
+    ) -> Result<Option<Self>> {
+        if !path.exists() {
+            log::trace!(
+                "Manifest skipping missing {kind} file {}",
+                GRAY.paint(path.as_str())
+            );
+            return Ok(None);
+        }
+        let data = fs::read(path).await?;
+        let modified = path.metadata().and_then(|m| m.modified()).dot()?;
+
+        Ok(Some(Self {
+            kind,
+            path: path.to_string(),
+            // always use `/` as separator for the site path
+            site: site.map(|s| s.to_url_path()),
+            url: None,
+            integrity: site.map(|_| integrity(&data)),
+            size: data.len() as u64,
+            hash: format!("{:016x}", seahash::hash(&data)),
+            modified: proj.source_date_epoch.unwrap_or(unix_secs(modified)),
+        }))
+    }
+}
+
+/// The subresource integrity value of the data, as `sha384-<base64 digest>`
+fn integrity(data: &[u8]) -> String {
+    format!("sha384-{}", STANDARD.encode(Sha384::digest(data)))
+}
+
+/// The files in the dir and its subdirs, sorted.
+pub(crate) fn dir_files(dir: &Utf8Path) -> Vec<Utf8PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = dir.read_dir_utf8() else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path().to_path_buf();
+        if path.is_dir() {
+            files.extend(dir_files(&path));
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+pub fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Writes the build manifest to the configured `build-manifest-file`.
+pub async fn write_manifest(proj: &Project) -> Result<BuildManifest> {
+    let manifest = BuildManifest::new(proj).await.dot()?;
+    let json = serde_json::to_string_pretty(&manifest).dot()?;
+
+    let file = &proj.build_manifest_file;
+    if let Some(dir) = file.parent() {
+        if !dir.as_str().is_empty() {
+            fs::create_dir_all(dir).await?;
+        }
+    }
+    fs::write(file, json).await?;
+    log::debug!("Manifest written {}", GRAY.paint(file.as_str()));
+    super::pwa::write_pwa(proj, &manifest).await.dot()?;
+    Ok(manifest)
+}
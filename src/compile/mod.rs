@@ -2,15 +2,47 @@
 mod tests;
 
 mod assets;
+mod build_info;
+mod cache;
 mod change;
+mod diagnostics;
+mod dry_run;
 mod front;
+mod manifest;
+mod musl;
+mod preload;
+mod profile_lint;
+mod pwa;
+mod reuse;
 mod sass;
 mod server;
+mod sitemap;
+mod step;
+mod streaming;
 mod style;
 mod tailwind;
+mod targets;
+mod timings;
+mod toolchain;
+mod wasm_split;
 
 pub use assets::assets;
 pub use change::{Change, ChangeSet};
-pub use front::{front, front_cargo_process};
-pub use server::{server, server_cargo_process};
-pub use style::style;
+pub use diagnostics::{
+    add_json_diagnostics, cargo_log, clear_seen_diagnostics, last_diagnostics, wait_cargo,
+    Diagnostic,
+};
+pub use dry_run::dry_run;
+pub use front::{front, front_cargo_command, write_placeholder};
+pub(crate) use manifest::dir_files;
+pub use manifest::{unix_secs, write_manifest, BuildManifest};
+pub use preload::write_preload_hints;
+pub use profile_lint::check_wasm_profile;
+pub use reuse::{record_artifacts, reusable_changes, StageKeys};
+pub use server::{server, server_cargo_command};
+pub use sitemap::{write_sitemap, RouteManifest, ENV_VAR_PRINT_ROUTES};
+pub use step::{Pipeline, Step, STEP_NAMES};
+pub use streaming::check_precompressed;
+pub use style::{reload_style_vars, style};
+pub use timings::{write_timings, TimingsReport};
+pub use toolchain::{ensure_target, host_triple, toolchain_args, WASM_TARGET};
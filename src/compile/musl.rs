@@ -0,0 +1,114 @@
+use std::env;
+
+use camino::Utf8Path;
+
+use crate::{
+    ext::{anyhow::Result, fs, is_linux_musl_env, os_arch},
+    logger::GRAY,
+};
+
+pub fn is_musl(triple: &str) -> bool {
+    triple.ends_with("-linux-musl")
+}
+
+/// The env for building the musl target on a host that isn't musl. The same architecture
+/// links with the default linker, and uses `musl-gcc` for the C dependencies when installed.
+/// Other architectures and operating systems link with `rust-lld`.
+/// Variables already set in the environment are kept.
+pub fn linker_envs(triple: &str) -> Vec<(String, String)> {
+    if is_linux_musl_env() {
+        return Vec::new();
+    }
+    let native = match os_arch() {
+        Ok((os, arch)) => os == "linux" && triple.starts_with(arch),
+        Err(_) => false,
+    };
+    let mut envs = Vec::new();
+    if native {
+        if which::which("musl-gcc").is_ok() {
+            envs.push((
+                format!("CC_{}", triple.replace('-', "_")),
+                "musl-gcc".to_string(),
+            ));
+        }
+    } else {
+        let name = format!(
+            "CARGO_TARGET_{}_LINKER",
+            triple.replace('-', "_").to_uppercase()
+        );
+        envs.push((name, "rust-lld".to_string()));
+    }
+    envs.retain(|(name, _)| env::var_os(name).is_none());
+    envs
+}
+
+/// Warns if the built server binary is dynamically linked.
+pub async fn check_static(exe: &Utf8Path) -> Result<()> {
+    let data = fs::read(exe).await?;
+    match is_static_elf(&data) {
+        Some(true) => log::debug!("Cargo server binary is statically linked"),
+        Some(false) => log::warn!(
+            "Cargo server binary {} is dynamically linked, check the linker of the musl target",
+            GRAY.paint(exe.as_str())
+        ),
+        None => log::debug!("Cargo server binary {} is not a 64-bit ELF", exe),
+    }
+    Ok(())
+}
+
+/// A 64-bit little endian ELF is statically linked when it has no interpreter
+/// (the dynamic loader) program header. `None` when it isn't such an ELF.
+fn is_static_elf(data: &[u8]) -> Option<bool> {
+    const PT_INTERP: u32 = 3;
+
+    if data.get(..6)? != b"\x7fELF\x02\x01" {
+        return None;
+    }
+    let read = |at: usize, len: usize| -> Option<u64> {
+        let bytes = data.get(at..at + len)?;
+        Some(bytes.iter().rev().fold(0, |acc, b| acc << 8 | *b as u64))
+    };
+    let phoff = read(0x20, 8)? as usize;
+    let phentsize = read(0x36, 2)? as usize;
+    let phnum = read(0x38, 2)? as usize;
+    for i in 0..phnum {
+        if read(phoff + i * phentsize, 4)? as u32 == PT_INTERP {
+            return Some(false);
+        }
+    }
+    Some(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elf(program_headers: &[u32]) -> Vec<u8> {
+        let mut data = vec![0u8; 0x40 + program_headers.len() * 0x38];
+        data[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        data[0x20..0x28].copy_from_slice(&0x40u64.to_le_bytes());
+        data[0x36..0x38].copy_from_slice(&0x38u16.to_le_bytes());
+        data[0x38..0x3a].copy_from_slice(&(program_headers.len() as u16).to_le_bytes());
+        for (i, kind) in program_headers.iter().enumerate() {
+            let at = 0x40 + i * 0x38;
+            data[at..at + 4].copy_from_slice(&kind.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn static_elf() {
+        // PT_LOAD, PT_DYNAMIC (static-pie) and PT_NOTE
+        assert_eq!(is_static_elf(&elf(&[1, 2, 4])), Some(true));
+        // with PT_INTERP
+        assert_eq!(is_static_elf(&elf(&[6, 3, 1, 2])), Some(false));
+        assert_eq!(is_static_elf(b"MZ not an elf"), None);
+    }
+
+    #[test]
+    fn musl_triples() {
+        assert!(is_musl("x86_64-unknown-linux-musl"));
+        assert!(is_musl("aarch64-unknown-linux-musl"));
+        assert!(!is_musl("x86_64-unknown-linux-gnu"));
+    }
+}
@@ -0,0 +1,95 @@
+use camino::Utf8Path;
+
+use super::dir_files;
+use crate::{
+    config::Project,
+    ext::{anyhow::Result, fs, PathExt},
+    logger::GRAY,
+};
+
+/// Delimit the tags written by cargo-leptos, so that they are replaced when the file names change.
+const START: &str = "<!-- cargo-leptos preload -->";
+const END: &str = "<!-- /cargo-leptos preload -->";
+
+/// The tags that start fetching the wasm and js glue before the js glue is executed.
+/// The wasm is fetched with `crossorigin` like the js glue fetches it, so that the preload
+/// is used.
+fn preload_tags(proj: &Project) -> String {
+    let url = |site: &Utf8Path| format!("{}{}", proj.base_href, site.to_url_path());
+    format!(
+        r#"<link rel="modulepreload" href="{}"><link rel="preload" href="{}" as="fetch" type="application/wasm" crossorigin>"#,
+        url(&proj.lib.js_file.site),
+        url(&proj.lib.wasm_file.site)
+    )
+}
+
+/// Adds the preload tags to the head of the static html files of the site, e.g. those of the
+/// assets. The pages rendered by the server get them from the leptos `HydrationScripts`.
+pub async fn write_preload_hints(proj: &Project) -> Result<()> {
+    if !proj.preload_hints || !proj.lib.wasm_file.dest.exists() {
+        return Ok(());
+    }
+    let tags = preload_tags(proj);
+    let locale_roots = proj
+        .locales
+        .iter()
+        .map(|locale| &locale.site.root_dir)
+        .collect::<Vec<_>>();
+    for file in dir_files(&proj.site.root_dir) {
+        if file.extension() != Some("html")
+            || locale_roots.iter().any(|root| file.starts_with(root))
+        {
+            continue;
+        }
+        let html = fs::read_to_string(&file).await?;
+        if let Some(html) = with_preload(&html, &tags) {
+            fs::write(&file, html).await?;
+            log::debug!("Front preload hints added to {}", GRAY.paint(file.as_str()));
+        }
+    }
+    Ok(())
+}
+
+/// The html with the tags at the end of the head, none if it has no head or already has them.
+fn with_preload(html: &str, tags: &str) -> Option<String> {
+    let mut html = html.to_string();
+    let block = format!("{START}{tags}{END}");
+    if html.contains(&block) {
+        return None;
+    }
+    if let (Some(start), Some(end)) = (html.find(START), html.find(END)) {
+        if start < end {
+            html.replace_range(start..end + END.len(), "");
+        }
+    }
+    // the lowercase html has the same byte offsets
+    let head_end = html.to_ascii_lowercase().find("</head>")?;
+    html.insert_str(head_end, &block);
+    Some(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preload() {
+        let html = "<html><HEAD><title>App</title></HEAD><body></body></html>";
+        let tags = r#"<link rel="modulepreload" href="/pkg/app.1a2b.js">"#;
+        let with_tags = with_preload(html, tags).unwrap();
+        assert_eq!(
+            with_tags,
+            format!("<html><HEAD><title>App</title>{START}{tags}{END}</HEAD><body></body></html>")
+        );
+        assert_eq!(with_preload(&with_tags, tags), None);
+
+        let new_tags = r#"<link rel="modulepreload" href="/pkg/app.3c4d.js">"#;
+        assert_eq!(
+            with_preload(&with_tags, new_tags).unwrap(),
+            format!(
+                "<html><HEAD><title>App</title>{START}{new_tags}{END}</HEAD><body></body></html>"
+            )
+        );
+        assert_eq!(with_preload("<p>fragment</p>", tags), None);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{
+    config::Project,
+    ext::{anyhow::Result, fs},
+    logger::GRAY,
+};
+
+/// The settings of the release profile of the lib that keep the wasm small, with their value in
+/// the Cargo.toml. `panic = "abort"` drops the unwinding machinery, the others inline and
+/// optimize for size across the crates.
+const RECOMMENDED: [(&str, &str); 4] = [
+    ("opt-level", "'z'"),
+    ("lto", "true"),
+    ("codegen-units", "1"),
+    ("panic", "\"abort\""),
+];
+
+/// The profile written by `--fix-profile` when the lib uses the plain release profile, which
+/// the server build shares.
+const WASM_PROFILE: &str = "wasm-release";
+
+/// Checks the release profile of the lib for what makes the wasm bigger than needed, and writes
+/// the missing settings with `--fix-profile`.
+pub async fn check_wasm_profile(proj: &Project) -> Result<()> {
+    if !proj.release {
+        return Ok(());
+    }
+    let cargo_toml = proj.working_dir.join("Cargo.toml");
+    let manifest = fs::read_to_string(&cargo_toml).await?;
+    let profile = proj.lib.profile.cargo_name();
+
+    let missing = missing_settings(&manifest, profile);
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let settings = missing
+        .iter()
+        .map(|(key, value)| format!("{key} = {value}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    log::warn!("Front the {profile} profile of the lib is missing {settings} for a small wasm");
+
+    let fixed_profile = if profile == "release" {
+        WASM_PROFILE
+    } else {
+        profile
+    };
+    if !proj.fix_profile {
+        log::warn!(
+            "Front run with --fix-profile to write them to the [profile.{fixed_profile}] of {}",
+            GRAY.paint(cargo_toml.as_str())
+        );
+        return Ok(());
+    }
+    let fixed = with_profile(&manifest, fixed_profile);
+    fs::write(&cargo_toml, fixed).await?;
+    log::info!(
+        "Front wrote the [profile.{fixed_profile}] of {}",
+        GRAY.paint(cargo_toml.as_str())
+    );
+    if fixed_profile != profile {
+        log::warn!(
+            "Front set {} in the leptos metadata to build the lib with it",
+            GRAY.paint(format!("lib-profile-release = \"{fixed_profile}\""))
+        );
+    }
+    Ok(())
+}
+
+/// The recommended settings that the profile (or the profiles it inherits) doesn't have.
+fn missing_settings(manifest: &str, profile: &str) -> Vec<(&'static str, &'static str)> {
+    RECOMMENDED
+        .into_iter()
+        .filter(|(key, value)| {
+            let current = profile_value(manifest, profile, key);
+            // opt-level 's' and the `lto = "fat"` alias are as good
+            !(current.as_deref() == Some(unquote(value))
+                || matches!(
+                    (*key, current.as_deref()),
+                    ("opt-level", Some("s")) | ("lto", Some("fat"))
+                ))
+        })
+        .collect()
+}
+
+/// The value of the key in the `[profile.<name>]` section, or in the one it inherits.
+fn profile_value(manifest: &str, profile: &str, key: &str) -> Option<String> {
+    let mut profile = profile.to_string();
+    // bounded, in case of an inherits cycle, which cargo rejects anyway
+    for _ in 0..8 {
+        let lines = section(manifest, &profile).unwrap_or_default();
+        if let Some(value) = lines.iter().find_map(|line| line_value(line, key)) {
+            return Some(value);
+        }
+        profile = lines.iter().find_map(|line| line_value(line, "inherits"))?;
+    }
+    None
+}
+
+/// The lines of the `[profile.<name>]` section.
+fn section<'a>(manifest: &'a str, profile: &str) -> Option<Vec<&'a str>> {
+    let header = format!("[profile.{profile}]");
+    let mut lines = manifest.lines().skip_while(|line| line.trim() != header);
+    lines.next()?;
+    Some(
+        lines
+            .take_while(|line| !line.trim_start().starts_with('['))
+            .collect(),
+    )
+}
+
+fn line_value(line: &str, key: &str) -> Option<String> {
+    let (line_key, value) = line.split_once('=')?;
+    if line_key.trim() != key {
+        return None;
+    }
+    let value = value.split(" #").next().unwrap_or_default().trim();
+    Some(unquote(value).to_string())
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim_matches(|c| c == '"' || c == '\'')
+}
+
+/// The manifest with the recommended settings in the profile section: the missing ones are
+/// added at the start of an existing section, the others are left as they are.
+fn with_profile(manifest: &str, profile: &str) -> String {
+    let header = format!("[profile.{profile}]");
+    let missing = missing_settings(manifest, profile);
+    let settings = missing
+        .iter()
+        .map(|(key, value)| format!("{key} = {value}\n"))
+        .collect::<String>();
+    if section(manifest, profile).is_some() {
+        let mut fixed = String::new();
+        for line in manifest.split_inclusive('\n') {
+            fixed.push_str(line);
+            if line.trim() == header {
+                if !line.ends_with('\n') {
+                    fixed.push('\n');
+                }
+                // a key set in the section (e.g. `panic = "unwind"`) is replaced
+                fixed.push_str(&settings);
+            }
+        }
+        let keys = missing.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+        return remove_overridden(&fixed, profile, &keys);
+    }
+    let mut fixed = manifest.to_string();
+    if !fixed.ends_with('\n') {
+        fixed.push('\n');
+    }
+    format!("{fixed}\n{header}\ninherits = \"release\"\n{settings}")
+}
+
+/// Removes the former values of the keys written at the start of the section.
+fn remove_overridden(manifest: &str, profile: &str, keys: &[&str]) -> String {
+    let header = format!("[profile.{profile}]");
+    let mut fixed = String::new();
+    let mut in_section = false;
+    let mut seen = Vec::new();
+    for line in manifest.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_section = trimmed == header;
+        } else if in_section {
+            if let Some(key) = keys.iter().find(|key| line_value(line, key).is_some()) {
+                if seen.contains(key) {
+                    continue;
+                }
+                seen.push(*key);
+            }
+        }
+        fixed.push_str(line);
+    }
+    fixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "app"
+
+[profile.release]
+panic = "unwind"
+opt-level = 3
+
+[profile.wasm-release]
+inherits = "release"
+opt-level = 'z' # smallest
+lto = true
+
+[dependencies]
+leptos = "0.5"
+"#;
+
+    #[test]
+    fn settings() {
+        assert_eq!(
+            profile_value(MANIFEST, "wasm-release", "opt-level").as_deref(),
+            Some("z")
+        );
+        assert_eq!(
+            profile_value(MANIFEST, "wasm-release", "panic").as_deref(),
+            Some("unwind")
+        );
+        assert_eq!(
+            profile_value(MANIFEST, "wasm-release", "codegen-units"),
+            None
+        );
+        assert_eq!(
+            missing_settings(MANIFEST, "wasm-release"),
+            vec![("codegen-units", "1"), ("panic", "\"abort\"")]
+        );
+        assert_eq!(missing_settings(MANIFEST, "release").len(), 4);
+    }
+
+    #[test]
+    fn fix() {
+        let fixed = with_profile(MANIFEST, "wasm-release");
+        assert!(fixed.contains(
+            "[profile.wasm-release]\ncodegen-units = 1\npanic = \"abort\"\ninherits = \"release\"\nopt-level = 'z' # smallest\nlto = true\n"
+        ));
+        assert!(missing_settings(&fixed, "wasm-release").is_empty());
+
+        let fixed = with_profile("[package]\nname = \"app\"", WASM_PROFILE);
+        assert!(fixed.ends_with("\n\n[profile.wasm-release]\ninherits = \"release\"\nopt-level = 'z'\nlto = true\ncodegen-units = 1\npanic = \"abort\"\n"));
+        assert!(missing_settings(&fixed, WASM_PROFILE).is_empty());
+
+        let fixed = with_profile("[profile.release]\npanic = \"unwind\"\n", "release");
+        assert_eq!(
+            fixed,
+            "[profile.release]\nopt-level = 'z'\nlto = true\ncodegen-units = 1\npanic = \"abort\"\n"
+        );
+    }
+}
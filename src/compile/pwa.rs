@@ -0,0 +1,82 @@
+use crate::{
+    config::Project,
+    ext::{
+        anyhow::{bail, Context, Result},
+        fs, PathExt,
+    },
+    logger::GRAY,
+};
+use camino::Utf8Path;
+use serde::Serialize;
+
+use super::{manifest::dir_files, BuildManifest};
+
+/// Replaced by the precache manifest, the same placeholder as workbox uses.
+const PLACEHOLDER: &str = "self.__WB_MANIFEST";
+const DEFAULT_SW: &str = include_str!("sw.js");
+
+#[derive(Debug, Serialize)]
+struct PrecacheEntry {
+    url: String,
+    revision: String,
+}
+
+/// Copies the web app manifest and icons to the site root and writes the
+/// service worker, with the precache manifest of the build output, to `<site-root>/sw.js`.
+pub async fn write_pwa(proj: &Project, manifest: &BuildManifest) -> Result<()> {
+    let Some(pwa) = &proj.pwa else {
+        return Ok(());
+    };
+    let root = &proj.site.root_dir;
+
+    let mut entries = manifest
+        .files
+        .iter()
+        .filter_map(|file| {
+            Some(PrecacheEntry {
+                url: file.url.clone()?,
+                revision: file.hash.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(file) = &pwa.manifest_file {
+        let dest = root.join(file.file_name().unwrap_or("manifest.webmanifest"));
+        fs::copy(file, &dest).await?;
+        entries.push(entry(proj, root, &dest).await?);
+    }
+    if let Some(dir) = &pwa.icons_dir {
+        let dest = root.join(dir.file_name().unwrap_or("icons"));
+        fs::copy_dir_all(dir, &dest).await?;
+        for file in dir_files(&dest) {
+            entries.push(entry(proj, root, &file).await?);
+        }
+    }
+
+    let sw = match &pwa.sw_file {
+        Some(file) => fs::read_to_string(file).await?,
+        None => DEFAULT_SW.to_string(),
+    };
+    if !sw.contains(PLACEHOLDER) {
+        bail!("The service worker must contain {PLACEHOLDER}, which is replaced by the precache manifest");
+    }
+    let sw = sw.replace(PLACEHOLDER, &serde_json::to_string(&entries).dot()?);
+    let sw_file = root.join("sw.js");
+    fs::write(&sw_file, sw).await?;
+    log::debug!(
+        "Pwa service worker written with {} precached files {}",
+        entries.len(),
+        GRAY.paint(sw_file.as_str())
+    );
+    Ok(())
+}
+
+async fn entry(proj: &Project, root: &Utf8Path, file: &Utf8Path) -> Result<PrecacheEntry> {
+    let data = fs::read(file).await?;
+    let site = file.strip_prefix(root).unwrap_or(file);
+    Ok(PrecacheEntry {
+        // always use `/` as separator for the url
+        url: format!("{}{}", proj.base_href, site.to_url_path()),
+        revision: format!("{:016x}", seahash::hash(&data)),
+    })
+}
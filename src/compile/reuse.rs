@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+
+use camino::Utf8PathBuf;
+
+use super::{cache::BuildCache, BuildManifest, ChangeSet};
+use crate::{
+    config::Project,
+    ext::anyhow::{Context, Result},
+};
+
+/// The names of the records in the build cache.
+const FRONT: &str = "reuse-front";
+const SERVER: &str = "reuse-server";
+
+/// The manifest kinds of the front and server artifacts.
+const FRONT_KINDS: &[&str] = &["wasm", "wasm-split", "js", "snippet"];
+const SERVER_KINDS: &[&str] = &["server"];
+
+/// The keys of the inputs of the front and server, taken before a build so that the changes
+/// saved while it runs aren't recorded as built.
+#[derive(Debug, Clone, Copy)]
+pub struct StageKeys {
+    front: u64,
+    server: u64,
+}
+
+impl StageKeys {
+    pub fn of(proj: &Project) -> Result<Self> {
+        Ok(Self {
+            front: front_key(proj)?,
+            server: server_key(proj)?,
+        })
+    }
+}
+
+/// Records the hashes of the artifacts of the front and server, when built by the changes,
+/// with the keys of their inputs. The stages that didn't run keep their previous record.
+pub async fn record_artifacts(
+    proj: &Project,
+    changes: &ChangeSet,
+    keys: StageKeys,
+    manifest: &BuildManifest,
+) -> Result<()> {
+    let cache = BuildCache::new(&proj.cache_dir);
+    let stages = [
+        (FRONT, changes.need_front_build(), keys.front, FRONT_KINDS),
+        (
+            SERVER,
+            changes.need_server_build(),
+            keys.server,
+            SERVER_KINDS,
+        ),
+    ];
+    for (step, built, key, kinds) in stages {
+        if !built {
+            continue;
+        }
+        let hashes = manifest
+            .files
+            .iter()
+            .filter(|file| kinds.contains(&file.kind))
+            .map(|file| (file.path.clone(), file.hash.clone()))
+            .collect::<BTreeMap<_, _>>();
+        cache
+            .put(step, key, &serde_json::to_string(&hashes)?)
+            .await?;
+    }
+    log::debug!("Reuse recorded the artifacts of {}", proj.name);
+    Ok(())
+}
+
+/// The changes of a full build, without the front and server stages whose inputs are the
+/// recorded ones and whose artifacts are unchanged since.
+pub async fn reusable_changes(proj: &Project) -> Result<ChangeSet> {
+    // the locale variants have their own fronts, which aren't recorded
+    if !proj.reuse_artifacts || !proj.locales.is_empty() {
+        return Ok(ChangeSet::all_changes());
+    }
+    let keys = StageKeys::of(proj)?;
+    let front = is_reusable(proj, FRONT, keys.front).await;
+    let server = is_reusable(proj, SERVER, keys.server).await;
+    for (name, reused) in [("front", front), ("server", server)] {
+        if reused {
+            log::info!("Reuse the {name} artifacts of the last build, use --force to rebuild");
+        }
+    }
+    Ok(ChangeSet::all_but_reused(front, server))
+}
+
+async fn is_reusable(proj: &Project, step: &str, key: u64) -> bool {
+    let Some(recorded) = BuildCache::new(&proj.cache_dir).get(step, key).await else {
+        return false;
+    };
+    let Ok(hashes) = serde_json::from_str::<BTreeMap<String, String>>(&recorded) else {
+        return false;
+    };
+    !hashes.is_empty()
+        && hashes.iter().all(|(path, hash)| {
+            let unchanged = content_hash(path).is_some_and(|content| &content == hash);
+            if !unchanged {
+                log::debug!("Reuse found {path} changed since the last build");
+            }
+            unchanged
+        })
+}
+
+/// As the hash of the manifest.
+fn content_hash(path: &str) -> Option<String> {
+    let data = std::fs::read(path).ok()?;
+    Some(format!("{:016x}", seahash::hash(&data)))
+}
+
+/// The sources of the lib (with its path dependencies), the manifests and lock file, and the
+/// settings of the lib build.
+fn front_key(proj: &Project) -> Result<u64> {
+    let lib = &proj.lib;
+    let mut sources = manifests(proj);
+    sources.push(lib.abs_dir.join("Cargo.toml"));
+    sources.extend(lib.src_paths.iter().cloned());
+    let settings = format!(
+        "front {} {:?} {} {} {} {} {:?} {:?}",
+        proj.release,
+        lib.features,
+        lib.default_features,
+        lib.profile,
+        proj.debug_symbols,
+        lib.wasm_debug,
+        lib.browser_targets,
+        cargo_envs(proj),
+    );
+    BuildCache::key(&sources, &settings).context("Reuse could not hash the front sources")
+}
+
+fn server_key(proj: &Project) -> Result<u64> {
+    let bin = &proj.bin;
+    let mut sources = manifests(proj);
+    sources.push(bin.abs_dir.join("Cargo.toml"));
+    sources.extend(bin.src_paths.iter().cloned());
+    let settings = format!(
+        "server {} {:?} {} {} {:?} {:?}",
+        proj.release,
+        bin.features,
+        bin.default_features,
+        bin.profile,
+        bin.target_triple,
+        cargo_envs(proj),
+    );
+    BuildCache::key(&sources, &settings).context("Reuse could not hash the server sources")
+}
+
+/// Without `LEPTOS_WATCH`, which the server reads at runtime, so that a build reuses the
+/// artifacts of a watch.
+fn cargo_envs(proj: &Project) -> Vec<(&'static str, String)> {
+    let mut envs = proj.to_cargo_envs();
+    envs.retain(|(name, _)| *name != "LEPTOS_WATCH");
+    envs
+}
+
+fn manifests(proj: &Project) -> Vec<Utf8PathBuf> {
+    vec![
+        proj.working_dir.join("Cargo.toml"),
+        proj.working_dir.join("Cargo.lock"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reused_changes() {
+        let changes = ChangeSet::all_but_reused(true, false);
+        assert!(!changes.need_front_build());
+        assert!(changes.need_server_build());
+        assert!(changes.need_style_build(true, true));
+        assert_eq!(changes.asset_iter().count(), 1);
+
+        let changes = ChangeSet::all_but_reused(true, true);
+        assert!(!changes.need_front_build() && !changes.need_server_build());
+
+        let changes = ChangeSet::all_but_reused(false, false);
+        assert!(changes.need_front_build() && changes.need_config_reload());
+    }
+}
@@ -1,36 +1,207 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use crate::{
+    config::Project,
     ext::{
         anyhow::{Context, Result},
-        sync::{wait_piped_interruptible, CommandResult, OutputExt},
+        fs,
+        sync::{CommandResult, OutputExt},
+        tool::Tool,
     },
-    logger::GRAY,
+    logger::{GRAY, RESULT},
     signal::{Interrupt, Outcome},
 };
+use camino::{Utf8Path, Utf8PathBuf};
 use tokio::process::Command;
 
 use crate::{ext::Exe, service::site::SourcedSiteFile};
 
-pub async fn compile_sass(style_file: &SourcedSiteFile, optimise: bool) -> Result<Outcome<String>> {
-    let mut args = vec![style_file.source.as_str()];
-    optimise.then(|| args.push("--no-source-map"));
+pub async fn compile_sass(
+    style_file: &SourcedSiteFile,
+    source_map: bool,
+    load_paths: &[Utf8PathBuf],
+    proj: &Project,
+) -> Result<Outcome<String>> {
+    let args = sass_args(style_file, source_map, load_paths);
 
-    let exe = Exe::Sass.get().await.dot()?;
+    let exe = Exe::Sass
+        .get_or_local(proj.sass_bin.as_deref())
+        .await
+        .dot()?;
 
     let mut cmd = Command::new(exe);
     cmd.args(&args);
 
-    log::trace!(
-        "Style running {}",
-        GRAY.paint(format!("sass {}", args.join(" ")))
-    );
-
-    match wait_piped_interruptible("Tailwind", cmd, Interrupt::subscribe_any()).await? {
+    match Tool::new("Sass", cmd)
+        .with_timeouts(&proj.tool_timeouts)
+        .output(Interrupt::subscribe_any())
+        .await?
+    {
         CommandResult::Success(output) => Ok(Outcome::Success(output.stdout())),
         CommandResult::Interrupted => Ok(Outcome::Stopped),
-        CommandResult::Failure(output) => {
-            log::warn!("Tailwind failed with:");
-            println!("{}", output.stderr());
+        CommandResult::Failure(_) => {
+            log::warn!(target: RESULT, "Sass failed {}", GRAY.paint(style_file.source.as_str()));
             Ok(Outcome::Failed)
         }
     }
 }
+
+/// The load paths are the ones of [load_paths]. The source map is embedded in the output,
+/// with the sources, to be carried over to the source map of the processed css.
+pub fn sass_args(
+    style_file: &SourcedSiteFile,
+    source_map: bool,
+    load_paths: &[Utf8PathBuf],
+) -> Vec<String> {
+    let mut args = vec![style_file.source.to_string()];
+    args.extend(load_paths.iter().map(|path| format!("--load-path={path}")));
+    if source_map {
+        args.extend(["--embed-source-map", "--embed-sources"].map(String::from));
+    } else {
+        args.push("--no-source-map".to_string());
+    }
+    args
+}
+
+/// The dir of the generated `_leptos-vars.scss`.
+pub fn vars_dir(proj: &Project) -> Utf8PathBuf {
+    proj.cache_dir.join("style")
+}
+
+/// The dir of the style vars, for `@use "leptos-vars"`, and the `style-load-paths`.
+pub fn load_paths(proj: &Project) -> Vec<Utf8PathBuf> {
+    let mut paths = vec![vars_dir(proj)];
+    paths.extend(proj.style.load_paths.iter().cloned());
+    paths
+}
+
+/// The files loaded by the `@use`, `@forward` and `@import` rules of the style file and of
+/// the files it loads, resolved like sass does: relative to the loading file, then to the
+/// load paths. The built-in modules, urls and plain css imports aren't files of the build.
+pub fn sass_deps(entry: &Utf8Path, load_paths: &[Utf8PathBuf]) -> BTreeSet<Utf8PathBuf> {
+    let mut deps = BTreeSet::new();
+    let mut pending = vec![entry.to_path_buf()];
+    while let Some(file) = pending.pop() {
+        let Ok(content) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let dir = file.parent().unwrap_or(Utf8Path::new("."));
+        for url in load_urls(&content) {
+            let found = std::iter::once(dir)
+                .chain(load_paths.iter().map(Utf8PathBuf::as_path))
+                .find_map(|base| resolve(&base.join(&url)));
+            if let Some(found) = found {
+                if deps.insert(found.clone()) {
+                    pending.push(found);
+                }
+            }
+        }
+    }
+    deps
+}
+
+/// The urls of the load rules, e.g. `components/button` of `@use "components/button" as b;`.
+fn load_urls(content: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let Some(rest) = ["@use", "@forward", "@import"]
+            .iter()
+            .find_map(|rule| line.strip_prefix(rule))
+        else {
+            continue;
+        };
+        // @import takes a list of urls
+        for part in rest.split(',') {
+            let part = part.trim();
+            let Some(quote) = part.chars().next().filter(|c| matches!(c, '"' | '\'')) else {
+                continue;
+            };
+            let Some(url) = part[1..].split(quote).next() else {
+                continue;
+            };
+            let is_file =
+                !url.starts_with("sass:") && !url.contains("://") && !url.ends_with(".css");
+            if is_file {
+                urls.push(url.to_string());
+            }
+        }
+    }
+    urls
+}
+
+/// The file of a url: with a sass or css extension, as a `_` partial, or the index of a dir.
+fn resolve(path: &Utf8Path) -> Option<Utf8PathBuf> {
+    // canonical, as the paths of the file events
+    if path.extension().is_some() && path.is_file() {
+        return path.canonicalize_utf8().ok();
+    }
+    let name = path.file_name()?;
+    let dir = path.parent()?;
+    let mut candidates = Vec::new();
+    for ext in ["scss", "sass", "css"] {
+        candidates.push(dir.join(format!("{name}.{ext}")));
+        candidates.push(dir.join(format!("_{name}.{ext}")));
+    }
+    for ext in ["scss", "sass", "css"] {
+        candidates.push(path.join(format!("_index.{ext}")));
+        candidates.push(path.join(format!("index.{ext}")));
+    }
+    let file = candidates.into_iter().find(|file| file.is_file())?;
+    file.canonicalize_utf8().ok()
+}
+
+/// Writes the style vars as sass variables, when they changed.
+pub async fn write_vars(proj: &Project, vars: &BTreeMap<String, String>) -> Result<()> {
+    let mut content = String::from("// generated by cargo-leptos from the style-vars config\n");
+    for (name, value) in vars {
+        content.push_str(&format!("${}: {value};\n", name.trim_start_matches('-')));
+    }
+    let dir = vars_dir(proj);
+    let file = dir.join("_leptos-vars.scss");
+    if fs::read_to_string(&file).await.ok().as_deref() != Some(content.as_str()) {
+        fs::create_dir_all(&dir).await?;
+        fs::write(&file, content).await?;
+        log::debug!("Style vars written {}", GRAY.paint(file.as_str()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn deps() {
+        let dir = TempDir::new().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        let write = |path: &str, content: &str| {
+            let file = root.join(path);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, content).unwrap();
+        };
+        write(
+            "style/main.scss",
+            "@use \"sass:math\";\n@use 'theme' as t;\n@import \"reset.css\", \"buttons\";\n",
+        );
+        write("style/_buttons.scss", "@forward \"../shared/tokens\";\n");
+        write("shared/tokens/_index.scss", "$gap: 4px;\n");
+        write("lib/theme.sass", "$primary: blue\n");
+
+        let deps = sass_deps(&root.join("style/main.scss"), &[root.join("lib")]);
+        let root = root.canonicalize_utf8().unwrap();
+        let names = deps
+            .iter()
+            .map(|dep| dep.strip_prefix(&root).unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "lib/theme.sass",
+                "shared/tokens/_index.scss",
+                "style/_buttons.scss"
+            ]
+        );
+    }
+}
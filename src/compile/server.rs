@@ -1,17 +1,19 @@
 use std::sync::Arc;
 
-use super::ChangeSet;
+use super::{
+    build_info,
+    diagnostics::{add_json_diagnostics, cargo_log, error_output, wait_cargo},
+    musl, toolchain, ChangeSet,
+};
 use crate::{
     config::Project,
     ext::anyhow::{Context, Result},
-    ext::sync::{wait_interruptible, CommandResult},
-    logger::GRAY,
-    signal::{Interrupt, Outcome, Product},
-};
-use tokio::{
-    process::{Child, Command},
-    task::JoinHandle,
+    ext::{sync::CommandResult, tool::Tool},
+    logger::{GRAY, RESULT},
+    service,
+    signal::{Outcome, Phase, Product, ReloadSignal, Timings},
 };
+use tokio::{process::Command, task::JoinHandle};
 
 pub async fn server(
     proj: &Arc<Project>,
@@ -24,13 +26,43 @@ pub async fn server(
         if !changes.need_server_build() {
             return Ok(Outcome::Success(Product::None));
         }
+        build_info::load_build_info(&proj).await;
+
+        let musl_triple = proj
+            .bin
+            .target_triple
+            .as_deref()
+            .filter(|t| musl::is_musl(t));
+        if let Some(triple) = musl_triple {
+            toolchain::ensure_target(
+                triple,
+                proj.bin.toolchain.as_deref(),
+                proj.toolchain_install,
+            )
+            .await?;
+        }
 
-        let (envs, line, process) = server_cargo_process("build", &proj)?;
+        let permit = proj.cargo_jobs.acquire().await?;
+        let mut command = Command::new(proj.bin.cargo_command.as_deref().unwrap_or("cargo"));
+        let (envs, line) = build_cargo_server_cmd("build", &proj, &mut command);
+        add_musl_envs(&proj, &mut command);
+        add_json_diagnostics(&mut command);
+        let process = Tool::new("Cargo", command)
+            .with_timeouts(&proj.tool_timeouts)
+            .log_output_to(cargo_log(&proj, "bin"))
+            .spawn()?;
 
-        match wait_interruptible("Cargo", process, Interrupt::subscribe_any()).await? {
+        let started = Timings::start();
+        let result = wait_cargo(&proj, "bin", process).await?;
+        drop(permit);
+        match result {
             CommandResult::Success(_) => {
+                Timings::record(Phase::CargoServer, started);
                 log::debug!("Cargo envs: {}", GRAY.paint(envs));
-                log::info!("Cargo finished {}", GRAY.paint(line));
+                log::info!(target: RESULT, "Cargo finished {}", GRAY.paint(line));
+                if musl_triple.is_some() {
+                    musl::check_static(&proj.bin.exe_file).await?;
+                }
 
                 let changed = proj
                     .site
@@ -46,15 +78,37 @@ pub async fn server(
                 }
             }
             CommandResult::Interrupted => Ok(Outcome::Stopped),
-            CommandResult::Failure(_) => Ok(Outcome::Failed),
+            CommandResult::Failure(diags) => {
+                if proj.watch {
+                    ReloadSignal::send_build_error(&error_output(&diags, &line));
+                }
+                Ok(Outcome::Failed)
+            }
         }
     })
 }
 
-pub fn server_cargo_process(cmd: &str, proj: &Project) -> Result<(String, String, Child)> {
+pub fn server_cargo_command(cmd: &str, proj: &Project) -> (String, String, Command) {
     let mut command = Command::new(proj.bin.cargo_command.as_deref().unwrap_or("cargo"));
     let (envs, line) = build_cargo_server_cmd(cmd, proj, &mut command);
-    Ok((envs, line, command.spawn()?))
+    add_musl_envs(proj, &mut command);
+    (envs, line, command)
+}
+
+/// The linker env for building a musl server on a host that isn't musl.
+fn add_musl_envs(proj: &Project, command: &mut Command) {
+    let Some(triple) = proj
+        .bin
+        .target_triple
+        .as_deref()
+        .filter(|t| musl::is_musl(t))
+    else {
+        return;
+    };
+    for (name, val) in musl::linker_envs(triple) {
+        log::debug!("Cargo musl env {name}={val}");
+        command.env(name, val);
+    }
 }
 
 pub fn build_cargo_server_cmd(
@@ -62,17 +116,14 @@ pub fn build_cargo_server_cmd(
     proj: &Project,
     command: &mut Command,
 ) -> (String, String) {
-    let mut args = vec![
-        cmd.to_string(),
-        format!("--package={}", proj.bin.name.as_str()),
-    ];
+    let mut args = toolchain::toolchain_args(proj.bin.toolchain.as_deref());
+    // the tool args of an alias are left to the caller, to stay after its own
+    args.extend(proj.cargo_config.expand_alias(cmd).0);
+    args.push(format!("--package={}", proj.bin.name.as_str()));
     if cmd != "test" {
         args.push(format!("--bin={}", proj.bin.target))
     }
-    args.push(format!(
-        "--target-dir={}",
-        proj.bin.target_dir.as_deref().unwrap_or("target/server")
-    ));
+    args.push(format!("--target-dir={}", proj.bin.target_dir));
     if let Some(triple) = &proj.bin.target_triple {
         args.push(format!("--target={triple}"));
     }
@@ -86,8 +137,15 @@ pub fn build_cargo_server_cmd(
     }
 
     proj.bin.profile.add_to_args(&mut args);
+    if let Some(jobs) = service::adaptive::cargo_jobs() {
+        args.push(format!("--jobs={jobs}"));
+    }
+    if proj.timings_dir.is_some() && cmd == "build" {
+        args.push("--timings".to_string());
+    }
 
-    let envs = proj.to_envs();
+    let mut envs = proj.to_cargo_envs();
+    envs.extend(build_info::cargo_envs(proj, &proj.bin.profile));
 
     let envs_str = envs
         .iter()
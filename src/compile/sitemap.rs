@@ -0,0 +1,220 @@
+use std::{net::SocketAddr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tokio::{
+    net::TcpStream,
+    process::Command,
+    time::{sleep, timeout},
+};
+
+use crate::{
+    config::{Project, SitemapConfig},
+    ext::{
+        anyhow::{bail, Result},
+        fs,
+        sync::{CommandResult, OutputExt},
+        tool::Tool,
+    },
+    logger::GRAY,
+    signal::Interrupt,
+};
+
+/// Set for the server binary run after the build, which then prints the paths of its routes
+/// (one per line) to stdout and exits instead of serving.
+pub const ENV_VAR_PRINT_ROUTES: &str = "LEPTOS_PRINT_ROUTES";
+
+/// The route manifest, written next to the sitemap in the site root.
+const ROUTES_FILE: &str = "routes.json";
+
+/// The time the server has to print its routes.
+const PRINT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteManifest {
+    pub routes: Vec<Route>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Route {
+    pub path: String,
+    /// the path has parameters (`:id`) or a wildcard (`*any`), so it isn't in the sitemap
+    pub dynamic: bool,
+}
+
+impl RouteManifest {
+    /// The manifest of the last build with a sitemap step, if any.
+    pub async fn read(proj: &Project) -> Option<Self> {
+        let text = fs::read_to_string(proj.site.root_dir.join(ROUTES_FILE))
+            .await
+            .ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    /// The routes that are pages of their own, e.g. for `prerender`.
+    pub fn static_paths(&self) -> Vec<String> {
+        self.routes
+            .iter()
+            .filter(|route| !route.dynamic)
+            .map(|route| route.path.clone())
+            .collect()
+    }
+}
+
+/// Writes the `sitemap.xml` and the route manifest of the routes the server prints when run
+/// with `LEPTOS_PRINT_ROUTES` set.
+pub async fn write_sitemap(proj: &Project) -> Result<()> {
+    let Some(sitemap) = &proj.sitemap else {
+        return Ok(());
+    };
+    let Some(routes) = print_routes(proj).await? else {
+        return Ok(());
+    };
+    let root = &proj.site.root_dir;
+    let manifest = RouteManifest { routes };
+    fs::write(
+        root.join(ROUTES_FILE),
+        serde_json::to_string_pretty(&manifest)?,
+    )
+    .await?;
+    let file = root.join("sitemap.xml");
+    fs::write(&file, sitemap_xml(sitemap, &manifest.routes)).await?;
+    log::debug!(
+        "Sitemap {} routes written to {}",
+        manifest.routes.len(),
+        GRAY.paint(file.as_str())
+    );
+    Ok(())
+}
+
+/// The routes of the server, none when interrupted. A server that doesn't support
+/// `LEPTOS_PRINT_ROUTES` starts serving (or hangs) instead, which is reported as a warning
+/// without failing the build.
+async fn print_routes(proj: &Project) -> Result<Option<Vec<Route>>> {
+    let addr = proj.site.addr;
+    // a server already listening (e.g. of another project) can't be told apart
+    let detect_serving = TcpStream::connect(addr).await.is_err();
+    let mut command = Command::new(&proj.bin.exe_file);
+    command.envs(proj.to_envs()).env(ENV_VAR_PRINT_ROUTES, "1");
+    // the server is killed when the output is dropped
+    let output = Tool::new("Server", command)
+        .with_timeouts(&proj.tool_timeouts)
+        .output(Interrupt::subscribe_any());
+    let result = tokio::select! {
+        res = timeout(PRINT_TIMEOUT, output) => res,
+        _ = serving(addr), if detect_serving => {
+            warn_unsupported("started serving");
+            return Ok(None);
+        }
+    };
+    let Ok(result) = result else {
+        warn_unsupported(&format!("didn't print its routes within {PRINT_TIMEOUT:?}"));
+        return Ok(None);
+    };
+    match result? {
+        CommandResult::Success(output) => Ok(Some(parse_routes(&output.stdout()))),
+        CommandResult::Failure(_) => {
+            bail!("The server failed to print its routes with {ENV_VAR_PRINT_ROUTES} set")
+        }
+        CommandResult::Interrupted => Ok(None),
+    }
+}
+
+/// Resolves once the addr accepts connections.
+async fn serving(addr: SocketAddr) {
+    while TcpStream::connect(addr).await.is_err() {
+        sleep(Duration::from_millis(200)).await;
+    }
+}
+
+fn warn_unsupported(what: &str) {
+    log::warn!(
+        "Sitemap not written, the server {what}: its main must print the routes and exit when {ENV_VAR_PRINT_ROUTES} is set"
+    );
+}
+
+/// The lines starting with `/`, so that the other output of the server is left out.
+fn parse_routes(stdout: &str) -> Vec<Route> {
+    let mut routes: Vec<Route> = Vec::new();
+    for path in stdout.lines().map(str::trim).filter(|l| l.starts_with('/')) {
+        if routes.iter().any(|route| route.path == path) {
+            continue;
+        }
+        let dynamic = path
+            .split('/')
+            .any(|segment| segment.starts_with(':') || segment.starts_with('*'));
+        routes.push(Route {
+            path: path.to_string(),
+            dynamic,
+        });
+    }
+    routes
+}
+
+fn sitemap_xml(sitemap: &SitemapConfig, routes: &[Route]) -> String {
+    let base_url = sitemap.base_url.trim_end_matches('/');
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for route in routes
+        .iter()
+        .filter(|route| !route.dynamic && !sitemap.excludes(&route.path))
+    {
+        xml.push_str(&format!(
+            "  <url><loc>{}</loc></url>\n",
+            escape(&format!("{base_url}{}", route.path))
+        ));
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sitemap() {
+        let routes = parse_routes(
+            "listening on 127.0.0.1:3000\n/\n/about\n/blog/:slug\n/admin\n/admin/users\n/*any\n/about\n",
+        );
+        assert_eq!(
+            routes.iter().map(|r| r.path.as_str()).collect::<Vec<_>>(),
+            vec![
+                "/",
+                "/about",
+                "/blog/:slug",
+                "/admin",
+                "/admin/users",
+                "/*any"
+            ]
+        );
+        assert!(routes[2].dynamic && routes[5].dynamic && !routes[1].dynamic);
+
+        let config = SitemapConfig {
+            base_url: "https://example.com/".to_string(),
+            exclude: vec!["/admin*".to_string()],
+        };
+        assert_eq!(
+            sitemap_xml(&config, &routes),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>https://example.com/</loc></url>
+  <url><loc>https://example.com/about</loc></url>
+</urlset>
+"#
+        );
+        let manifest = RouteManifest { routes };
+        assert_eq!(
+            manifest.static_paths(),
+            vec!["/", "/about", "/admin", "/admin/users"]
+        );
+    }
+}
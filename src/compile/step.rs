@@ -0,0 +1,168 @@
+use std::sync::Arc;
+
+use tokio::task::JoinHandle;
+
+use super::ChangeSet;
+use crate::{
+    config::Project,
+    ext::anyhow::Result,
+    signal::{Outcome, Product},
+};
+
+/// A stage of the build, left out of the builds by the `skip-build-steps` config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// the lib build with cargo, then wasm-bindgen and wasm-opt
+    Front,
+    Style,
+    /// the assets sync, with a full resync when `first_sync`
+    Assets {
+        first_sync: bool,
+    },
+    /// the bin build with cargo
+    Server,
+    /// the fronts of the extra wasm entrypoints, one after the other
+    Entrypoints,
+}
+
+/// The names of the steps, for validating the `skip-build-steps` config.
+pub const STEP_NAMES: [&str; 5] = ["front", "style", "assets", "server", "entrypoints"];
+
+impl Step {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Front => "front",
+            Self::Style => "style",
+            Self::Assets { .. } => "assets",
+            Self::Server => "server",
+            Self::Entrypoints => "entrypoints",
+        }
+    }
+
+    /// Spawns the step, which rebuilds only what the changes affect.
+    async fn spawn(
+        &self,
+        proj: &Arc<Project>,
+        changes: &ChangeSet,
+    ) -> JoinHandle<Result<Outcome<Product>>> {
+        match self {
+            Self::Front => super::front(proj, changes).await,
+            Self::Style => super::style(proj, changes).await,
+            Self::Assets { first_sync } => super::assets(proj, changes, *first_sync).await,
+            Self::Server => super::server(proj, changes).await,
+            Self::Entrypoints => {
+                let proj = proj.clone();
+                let changes = changes.clone();
+                tokio::spawn(async move {
+                    let mut product = Product::None;
+                    for entry in &proj.entrypoints {
+                        match super::front(entry, &changes).await.await?? {
+                            Outcome::Success(Product::None) => {}
+                            Outcome::Success(built) => product = built,
+                            outcome => return Ok(outcome),
+                        }
+                    }
+                    Ok(Outcome::Success(product))
+                })
+            }
+        }
+    }
+}
+
+/// The steps of a build that aren't skipped, which are independent of each other.
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    steps: Vec<Step>,
+}
+
+impl Pipeline {
+    /// The steps of a full build: the server is left out of the locale variants, which share
+    /// the one of the main project.
+    pub fn build(proj: &Project) -> Self {
+        let mut steps = vec![Step::Front, Step::Assets { first_sync: true }, Step::Style];
+        if proj.locale.is_none() {
+            steps.push(Step::Server);
+        }
+        if !proj.entrypoints.is_empty() {
+            steps.push(Step::Entrypoints);
+        }
+        Self::new(steps, &proj.skip_build_steps)
+    }
+
+    /// The steps of a watch rebuild, without the style, see [Pipeline::watch_style].
+    pub fn watch(proj: &Project) -> Self {
+        let mut steps = vec![
+            Step::Server,
+            Step::Front,
+            Step::Assets { first_sync: false },
+        ];
+        if !proj.entrypoints.is_empty() {
+            steps.push(Step::Entrypoints);
+        }
+        Self::new(steps, &proj.skip_build_steps)
+    }
+
+    /// The style of a watch rebuild, which is updated on its own.
+    pub fn watch_style(proj: &Project) -> Self {
+        Self::new(vec![Step::Style], &proj.skip_build_steps)
+    }
+
+    pub fn new(steps: Vec<Step>, skip: &[String]) -> Self {
+        let steps = steps
+            .into_iter()
+            .filter(|step| {
+                let skipped = skip.iter().any(|name| name == step.name());
+                if skipped {
+                    log::debug!("Build skips the {} step", step.name());
+                }
+                !skipped
+            })
+            .collect();
+        Self { steps }
+    }
+
+    /// Runs the steps concurrently, returning their outcomes in order.
+    pub async fn run(
+        &self,
+        proj: &Arc<Project>,
+        changes: &ChangeSet,
+    ) -> Result<Vec<Outcome<Product>>> {
+        let mut handles = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            handles.push(step.spawn(proj, changes).await);
+        }
+        let mut outcomes = Vec::with_capacity(handles.len());
+        for handle in handles {
+            outcomes.push(handle.await??);
+        }
+        Ok(outcomes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skipped_steps() {
+        let steps = vec![Step::Front, Step::Style, Step::Assets { first_sync: true }];
+        let pipeline = Pipeline::new(steps, &["style".to_string(), "assets".to_string()]);
+        assert_eq!(pipeline.steps, vec![Step::Front]);
+
+        assert!(Pipeline::new(vec![Step::Style], &["style".to_string()])
+            .steps
+            .is_empty());
+    }
+
+    #[test]
+    fn step_names() {
+        let steps = [
+            Step::Front,
+            Step::Style,
+            Step::Assets { first_sync: false },
+            Step::Server,
+            Step::Entrypoints,
+        ];
+        assert_eq!(steps.map(|step| step.name()), STEP_NAMES);
+    }
+}
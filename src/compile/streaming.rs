@@ -0,0 +1,128 @@
+use std::io::Read;
+
+use camino::Utf8Path;
+use flate2::read::GzDecoder;
+
+use crate::{config::Project, logger::GRAY};
+
+/// The extensions of the precompressed variants of the wasm, e.g. `app.wasm.br`.
+const PRECOMPRESSED: [&str; 3] = ["br", "gz", "zst"];
+
+/// The first bytes of every wasm module.
+const WASM_MAGIC: [u8; 4] = *b"\0asm";
+
+/// The browsers compile the wasm while it downloads with `WebAssembly.instantiateStreaming`,
+/// which the js glue only uses when the wasm is served as `application/wasm`.
+pub fn check_glue(js: &str) {
+    for issue in glue_issues(js) {
+        log::warn!("Front {issue}, the wasm is only compiled once downloaded");
+    }
+}
+
+fn glue_issues(js: &str) -> Vec<&'static str> {
+    let mut issues = Vec::new();
+    if !js.contains("WebAssembly.instantiateStreaming") {
+        issues.push("the js glue doesn't use WebAssembly.instantiateStreaming");
+    } else if !js.contains("application/wasm") {
+        issues.push("the js glue doesn't check that the wasm is served as application/wasm");
+    }
+    issues
+}
+
+/// Checks the precompressed variants of the wasm written by the post-build hooks: they must be
+/// up to date and named `<name>.wasm.<encoding>`, so that the servers serving them keep the
+/// `application/wasm` content type of the `.wasm` extension.
+pub fn check_precompressed(proj: &Project) {
+    for issue in precompressed_issues(&proj.lib.wasm_file.dest) {
+        log::warn!("Front {issue}");
+    }
+}
+
+fn precompressed_issues(wasm: &Utf8Path) -> Vec<String> {
+    let mut issues = Vec::new();
+    let (Some(dir), Some(stem)) = (wasm.parent(), wasm.file_stem()) else {
+        return issues;
+    };
+    let Ok(entries) = dir.read_dir_utf8() else {
+        return issues;
+    };
+    let modified = |path: &Utf8Path| path.metadata().and_then(|meta| meta.modified()).ok();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(ext) = path.extension().filter(|ext| PRECOMPRESSED.contains(ext)) else {
+            continue;
+        };
+        if path.file_stem() == Some(stem) {
+            // e.g. app.br, served as application/octet-stream
+            issues.push(format!(
+                "the precompressed {} loses the .wasm extension, name it {}",
+                GRAY.paint(path.as_str()),
+                GRAY.paint(format!("{wasm}.{ext}"))
+            ));
+            continue;
+        }
+        if path.file_stem() != wasm.file_name() {
+            continue;
+        }
+        if modified(path) < modified(wasm) {
+            issues.push(format!(
+                "the precompressed {} is older than the wasm",
+                GRAY.paint(path.as_str())
+            ));
+        } else if ext == "gz" && !is_gzipped_wasm(path) {
+            issues.push(format!(
+                "the precompressed {} is not a gzipped wasm module",
+                GRAY.paint(path.as_str())
+            ));
+        }
+    }
+    issues
+}
+
+fn is_gzipped_wasm(path: &Utf8Path) -> bool {
+    let Ok(file) = std::fs::File::open(path) else {
+        return false;
+    };
+    let mut magic = [0; 4];
+    GzDecoder::new(file).read_exact(&mut magic).is_ok() && magic == WASM_MAGIC
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn glue() {
+        let js = "if (typeof WebAssembly.instantiateStreaming === 'function') { \
+            if (module.headers.get('Content-Type') != 'application/wasm') {} }";
+        assert!(glue_issues(js).is_empty());
+        assert_eq!(
+            glue_issues("WebAssembly.instantiateStreaming(module)").len(),
+            1
+        );
+        assert_eq!(glue_issues("WebAssembly.instantiate(bytes)").len(), 1);
+    }
+
+    #[test]
+    fn precompressed() {
+        let tmp = TempDir::new().unwrap();
+        let dir = Utf8Path::from_path(tmp.path()).unwrap();
+        let wasm = dir.join("app.wasm");
+        std::fs::write(&wasm, b"\0asm\x01\0\0\0").unwrap();
+        assert!(precompressed_issues(&wasm).is_empty());
+
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(b"\0asm\x01\0\0\0").unwrap();
+        std::fs::write(dir.join("app.wasm.gz"), gz.finish().unwrap()).unwrap();
+        assert!(precompressed_issues(&wasm).is_empty());
+
+        std::fs::write(dir.join("app.wasm.gz"), b"not gzipped").unwrap();
+        std::fs::write(dir.join("app.br"), b"").unwrap();
+        std::fs::write(dir.join("other.wasm.br"), b"").unwrap();
+        let issues = precompressed_issues(&wasm);
+        assert_eq!(issues.len(), 2);
+    }
+}
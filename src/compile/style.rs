@@ -1,6 +1,6 @@
-use super::ChangeSet;
+use super::{cache::BuildCache, ChangeSet};
 use crate::{
-    compile::{sass::compile_sass, tailwind::compile_tailwind},
+    compile::{sass, tailwind::compile_tailwind},
     config::Project,
     ext::{
         anyhow::{anyhow, bail, Context, Result},
@@ -8,16 +8,70 @@ use crate::{
     },
     fs,
     logger::GRAY,
-    signal::{Outcome, Product},
+    service::{notify, site::SiteFile},
+    signal::{Outcome, Phase, Product, Timings},
 };
 use lightningcss::{
     stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleSheet},
     targets::Browsers,
     targets::Targets,
 };
-use std::sync::Arc;
+use parcel_sourcemap::SourceMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
 use tokio::task::JoinHandle;
 
+lazy_static::lazy_static! {
+  /// the style vars re-read after a Cargo.toml change in watch, by project name
+  static ref RELOADED_VARS: Mutex<HashMap<String, BTreeMap<String, String>>> = Mutex::new(HashMap::new());
+}
+
+/// The style vars of the project, the reloaded ones if the Cargo.toml changed.
+fn style_vars(proj: &Project) -> BTreeMap<String, String> {
+    RELOADED_VARS
+        .lock()
+        .unwrap()
+        .get(&proj.name)
+        .cloned()
+        .unwrap_or_else(|| proj.style.vars.clone())
+}
+
+/// Re-reads the style vars after a Cargo.toml change, as the project config isn't reloaded by watch.
+pub async fn reload_style_vars(proj: &Arc<Project>) {
+    let reader = proj.clone();
+    let vars = tokio::task::spawn_blocking(move || reader.read_style_vars())
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|vars| vars);
+    match vars {
+        Ok(vars) => {
+            if vars != style_vars(proj) {
+                log::info!("Style vars reloaded from the Cargo.toml");
+            }
+            RELOADED_VARS
+                .lock()
+                .unwrap()
+                .insert(proj.name.clone(), vars);
+        }
+        Err(e) => log::warn!("Style could not reload the vars: {e}"),
+    }
+}
+
+/// The vars as css custom properties on `:root`, appended to the style so that they win
+/// over the defaults the style sheet may define.
+fn root_vars(vars: &BTreeMap<String, String>) -> String {
+    if vars.is_empty() {
+        return String::new();
+    }
+    let props = vars
+        .iter()
+        .map(|(name, value)| format!("  --{}: {value};\n", name.trim_start_matches('-')))
+        .collect::<String>();
+    format!("\n:root {{\n{props}}}\n")
+}
+
 pub async fn style(
     proj: &Arc<Project>,
     changes: &ChangeSet,
@@ -47,9 +101,38 @@ fn build_sass(proj: &Arc<Project>) -> JoinHandle<Result<Outcome<String>>> {
             .await
             .dot()?;
         match style_file.source.extension() {
-            Some("sass") | Some("scss") => compile_sass(style_file, proj.release)
-                .await
-                .context(format!("compile sass/scss: {}", &style_file)),
+            Some("sass") | Some("scss") => {
+                let vars = style_vars(&proj);
+                sass::write_vars(&proj, &vars).await?;
+                let cache = BuildCache::new(&proj.cache_dir);
+                let load_paths = sass::load_paths(&proj);
+                // the files loaded from outside the dir of the style file too
+                let deps = sass::sass_deps(&style_file.source, &load_paths);
+                if proj.watch {
+                    notify::watch_style_deps(&deps);
+                }
+                let mut sources = vec![
+                    style_file.source.clone(),
+                    style_file.source.clone().without_last(),
+                ];
+                sources.extend(deps);
+                let source_map = proj.style_source_map();
+                let key = BuildCache::key(
+                    &sources,
+                    &format!("sass {} {source_map} {vars:?}", proj.release),
+                )?;
+                if let Some(css) = cache.get("sass", key).await {
+                    log::debug!("Style sass unchanged, using cached output");
+                    return Ok(Outcome::Success(css));
+                }
+                let outcome = sass::compile_sass(style_file, source_map, &load_paths, &proj)
+                    .await
+                    .context(format!("compile sass/scss: {}", &style_file))?;
+                if let Outcome::Success(css) = &outcome {
+                    cache.put("sass", key, css).await?;
+                }
+                Ok(outcome)
+            }
             Some("css") => Ok(Outcome::Success(
                 fs::read_to_string(&style_file.source).await.dot()?,
             )),
@@ -66,11 +149,28 @@ fn build_tailwind(proj: &Arc<Project>) -> JoinHandle<Result<Outcome<String>>> {
             return Ok(Outcome::Success("".to_string()));
         };
         log::trace!("Tailwind config: {:?}", &tw_conf);
-        compile_tailwind(&proj, tw_conf).await
+
+        // tailwind scans the rust sources for class names
+        let cache = BuildCache::new(&proj.cache_dir);
+        let mut sources = vec![tw_conf.input_file.clone(), tw_conf.config_file.clone()];
+        sources.extend(tw_conf.input_dir.clone());
+        sources.extend(proj.lib.src_paths.iter().cloned());
+        sources.extend(proj.bin.src_paths.iter().cloned());
+        let key = BuildCache::key(&sources, "tailwind")?;
+        if let Some(css) = cache.get("tailwind", key).await {
+            log::debug!("Style tailwind unchanged, using cached output");
+            return Ok(Outcome::Success(css));
+        }
+        let outcome = compile_tailwind(&proj, tw_conf).await?;
+        if let Outcome::Success(css) = &outcome {
+            cache.put("tailwind", key, css).await?;
+        }
+        Ok(outcome)
     })
 }
 
 async fn build(proj: &Arc<Project>) -> Result<Outcome<Product>> {
+    let started = Timings::start();
     let css_handle = build_sass(proj);
     let tw_handle = build_tailwind(proj);
     let css = css_handle.await??;
@@ -80,40 +180,46 @@ async fn build(proj: &Arc<Project>) -> Result<Outcome<Product>> {
     let css = match (css, tw) {
         (Stopped, _) | (_, Stopped) => return Ok(Stopped),
         (Failed, _) | (_, Failed) => return Ok(Failed),
-        (Success(css), Success(tw)) => format!("{css}\n{tw}"),
+        (Success(css), Success(tw)) => format!("{css}\n{tw}{}", root_vars(&style_vars(proj))),
     };
-    Ok(Success(process_css(proj, css).await?))
+    let product = process_css(proj, css).await?;
+    Timings::record(Phase::Style, started);
+    Ok(Success(product))
 }
 
-fn browser_lists(query: &str) -> Result<Option<Browsers>> {
+pub(super) fn browser_lists(query: &str) -> Result<Option<Browsers>> {
     Browsers::from_browserslist([query]).context(format!("Error in browserlist query: {query}"))
 }
 
 async fn process_css(proj: &Project, css: String) -> Result<Product> {
     let browsers = browser_lists(&proj.style.browserquery).context("leptos.style.browserquery")?;
-
-    let mut stylesheet =
-        StyleSheet::parse(&css, ParserOptions::default()).map_err(|e| anyhow!("{e}"))?;
-
-    if proj.release {
-        stylesheet.minify(MinifyOptions::default())?;
-    }
-
-    let options = PrinterOptions::<'_> {
-        targets: Targets::from(browsers),
-        minify: proj.release,
-        ..Default::default()
+    let site_file = &proj.style.site_file;
+    let map_file = SiteFile {
+        dest: format!("{}.map", site_file.dest).into(),
+        site: format!("{}.map", site_file.site).into(),
     };
 
-    let style_output = stylesheet.to_css(options)?;
-
-    let bytes = style_output.code.as_bytes();
+    let (mut code, map) = print_css(
+        &css,
+        site_file.site.as_str(),
+        Targets::from(browsers),
+        proj.release,
+        proj.style_source_map(),
+    )?;
+    if let Some(map) = map {
+        proj.site.updated_with(&map_file, map.as_bytes()).await?;
+        let name = map_file.site.file_name().unwrap_or_default();
+        code.push_str(&format!("\n/*# sourceMappingURL={name} */\n"));
+    } else if map_file.dest.exists() {
+        // left by a dev build
+        fs::remove_file(&map_file.dest).await?;
+    }
 
-    let prod = match proj.site.updated_with(&proj.style.site_file, bytes).await? {
+    let prod = match proj.site.updated_with(site_file, code.as_bytes()).await? {
         true => {
             log::trace!(
                 "Style finished with changes {}",
-                GRAY.paint(&proj.style.site_file.to_string())
+                GRAY.paint(site_file.to_string())
             );
             Product::Style("".to_string()) //TODO
         }
@@ -124,3 +230,86 @@ async fn process_css(proj: &Project, css: String) -> Result<Product> {
     };
     Ok(prod)
 }
+
+/// The processed css, and its source map as json when `source_map` is set. The source map of the
+/// sass output, embedded in the css, is carried over so that the map points to the sass sources.
+fn print_css(
+    css: &str,
+    filename: &str,
+    targets: Targets,
+    minify: bool,
+    source_map: bool,
+) -> Result<(String, Option<String>)> {
+    let options = ParserOptions {
+        filename: filename.to_string(),
+        ..Default::default()
+    };
+    let mut stylesheet = StyleSheet::parse(css, options).map_err(|e| anyhow!("{e}"))?;
+
+    if minify {
+        stylesheet.minify(MinifyOptions::default())?;
+    }
+
+    let mut map = source_map.then(|| {
+        let mut map = SourceMap::new("/");
+        map.add_source(filename);
+        let _ = map.set_source_content(0, css);
+        map
+    });
+    let options = PrinterOptions::<'_> {
+        targets,
+        minify,
+        source_map: map.as_mut(),
+        ..Default::default()
+    };
+    let code = stylesheet.to_css(options)?.code;
+
+    let Some(mut map) = map else {
+        return Ok((code, None));
+    };
+    if let Some(mut sass_map) = stylesheet.source_map(0) {
+        if let Err(e) = map.extends(&mut sass_map) {
+            log::debug!("Style could not apply the sass source map: {e:?}");
+        }
+    }
+    let json = map.to_json(None).map_err(|e| anyhow!("{e:?}"))?;
+    Ok((code, Some(json)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{print_css, root_vars};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use lightningcss::targets::Targets;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn vars_as_custom_properties() {
+        assert_eq!(root_vars(&BTreeMap::new()), "");
+        let vars = BTreeMap::from([
+            ("primary".to_string(), "#3366ff".to_string()),
+            ("--radius".to_string(), "4px".to_string()),
+        ]);
+        assert_eq!(
+            root_vars(&vars),
+            "\n:root {\n  --radius: 4px;\n  --primary: #3366ff;\n}\n"
+        );
+    }
+
+    #[test]
+    fn source_map_points_to_the_sass_sources() {
+        let sass_map = r#"{"version":3,"sources":["main.scss"],"names":[],"mappings":"AAAA;EACE","sourcesContent":["a {\n  color: red;\n}"]}"#;
+        let css = format!(
+            "a {{\n  color: red;\n}}\n/*# sourceMappingURL=data:application/json;base64,{} */",
+            STANDARD.encode(sass_map)
+        );
+
+        let (code, map) = print_css(&css, "pkg/app.css", Targets::default(), false, true).unwrap();
+        assert!(code.contains("color: red"), "{code}");
+        let map = map.unwrap();
+        assert!(map.contains("main.scss"), "{map}");
+
+        let (_, map) = print_css(&css, "pkg/app.css", Targets::default(), true, false).unwrap();
+        assert_eq!(map, None);
+    }
+}
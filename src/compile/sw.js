@@ -0,0 +1,40 @@
+// Service worker generated by cargo-leptos. It precaches the build output
+// and serves it cache-first. Other requests go to the network.
+const PRECACHE = self.__WB_MANIFEST;
+const CACHE = "leptos-" + hash(PRECACHE.map((entry) => entry.url + entry.revision).join());
+const URLS = new Set(PRECACHE.map((entry) => new URL(entry.url, self.location).href));
+
+self.addEventListener("install", (event) => {
+  event.waitUntil(
+    caches
+      .open(CACHE)
+      .then((cache) => cache.addAll([...URLS]))
+      .then(() => self.skipWaiting())
+  );
+});
+
+self.addEventListener("activate", (event) => {
+  event.waitUntil(
+    caches
+      .keys()
+      .then((keys) => Promise.all(keys.filter((key) => key !== CACHE).map((key) => caches.delete(key))))
+      .then(() => self.clients.claim())
+  );
+});
+
+self.addEventListener("fetch", (event) => {
+  if (event.request.method !== "GET" || !URLS.has(event.request.url)) {
+    return;
+  }
+  event.respondWith(
+    caches.match(event.request).then((cached) => cached || fetch(event.request))
+  );
+});
+
+function hash(text) {
+  let hash = 7;
+  for (let i = 0; i < text.length; i++) {
+    hash = (hash * 31 + text.charCodeAt(i)) >>> 0;
+  }
+  return hash.toString(16);
+}
@@ -1,86 +1,252 @@
 use anyhow::Result;
+use camino::{Utf8Path, Utf8PathBuf};
 use tokio::process::Command;
 
 use crate::{
-    config::{Project, TailwindConfig},
+    config::{NodeTailwind, Project, TailwindConfig},
     ext::{
-        anyhow::Context,
+        anyhow::{bail, Context},
         fs,
-        sync::{wait_piped_interruptible, CommandResult, OutputExt},
-        Exe,
+        sync::{CommandResult, OutputExt},
+        tool::{self, Tool},
+        utf8_path, Exe,
     },
-    logger::GRAY,
+    logger::{GRAY, RESULT},
     signal::{Interrupt, Outcome},
 };
 
-pub async fn compile_tailwind(
-    _proj: &Project,
-    tw_conf: &TailwindConfig,
-) -> Result<Outcome<String>> {
+pub async fn compile_tailwind(proj: &Project, tw_conf: &TailwindConfig) -> Result<Outcome<String>> {
     if !tw_conf.config_file.exists() {
-        create_default_tailwind_config(tw_conf).await?;
+        create_default_tailwind_config(proj, tw_conf).await?;
     }
 
-    let (line, process) = tailwind_process("tailwind", tw_conf).await?;
+    let (line, process) =
+        tailwind_process("tailwind", tw_conf, proj.tailwind_bin.as_deref()).await?;
 
-    match wait_piped_interruptible("Tailwind", process, Interrupt::subscribe_any()).await? {
+    match Tool::new("Tailwind", process)
+        .with_timeouts(&proj.tool_timeouts)
+        .stderr_logged_by_caller()
+        .output(Interrupt::subscribe_any())
+        .await?
+    {
         CommandResult::Success(output) => {
-            let done = output
-                .stderr()
-                .lines()
-                .last()
-                .map(|l| l.contains("Done"))
-                .unwrap_or(false);
+            // postcss doesn't report the end of the build
+            let postcss = tw_conf
+                .node
+                .as_ref()
+                .is_some_and(|node| node.postcss_config.is_some());
+            let done = postcss
+                || output
+                    .stderr()
+                    .lines()
+                    .last()
+                    .map(|l| l.contains("Done"))
+                    .unwrap_or(false);
 
             if done {
-                log::info!("Tailwind finished {}", GRAY.paint(line));
+                tool::log_lines("Tailwind", &output.stderr(), false);
+                log::info!(target: RESULT, "Tailwind finished {}", GRAY.paint(line));
                 Ok(Outcome::Success(output.stdout()))
             } else {
-                log::warn!("Tailwind failed {}", GRAY.paint(line));
-                println!("{}\n{}", output.stdout(), output.stderr());
+                tool::log_lines("Tailwind", &output.stdout(), true);
+                tool::log_lines("Tailwind", &output.stderr(), true);
+                log::warn!(target: RESULT, "Tailwind failed {}", GRAY.paint(line));
                 Ok(Outcome::Failed)
             }
         }
         CommandResult::Interrupted => Ok(Outcome::Stopped),
         CommandResult::Failure(output) => {
-            log::warn!("Tailwind failed");
-            if output.has_stdout() {
-                println!("{}", output.stdout());
-            }
-            println!("{}", output.stderr());
+            tool::log_lines("Tailwind", &output.stdout(), true);
+            tool::log_lines("Tailwind", &output.stderr(), true);
+            log::warn!(target: RESULT, "Tailwind failed {}", GRAY.paint(line));
             Ok(Outcome::Failed)
         }
     }
 }
 
-async fn create_default_tailwind_config(tw_conf: &TailwindConfig) -> Result<()> {
-    let contents = r#"/** @type {import('tailwindcss').Config} */
-    module.exports = {
-      content: {
-        relative: true,
-        files: ["*.html", "./src/**/*.rs"],
-      },
-      theme: {
-        extend: {},
-      },
-      plugins: [],
+/// Writes a default config scanning the html files next to it and the rust sources of the
+/// project, including its path dependencies.
+async fn create_default_tailwind_config(proj: &Project, tw_conf: &TailwindConfig) -> Result<()> {
+    let config_dir = tw_conf.config_file.parent().unwrap_or(Utf8Path::new(""));
+    let src_paths = proj.lib.src_paths.iter().chain(&proj.bin.src_paths);
+    let contents = default_config(config_dir, src_paths);
+    fs::write(&tw_conf.config_file, contents).await?;
+    log::info!(
+        "Tailwind created the default config {}",
+        GRAY.paint(tw_conf.config_file.as_str())
+    );
+    Ok(())
+}
+
+fn default_config<'a>(
+    config_dir: &Utf8Path,
+    src_paths: impl IntoIterator<Item = &'a Utf8PathBuf>,
+) -> String {
+    let mut files = vec!["\"*.html\"".to_string()];
+    for path in src_paths {
+        let file = format!("\"{}/**/*.rs\"", relative_path(config_dir, path));
+        if !files.contains(&file) {
+            files.push(file);
+        }
     }
-    "#;
-    fs::write(&tw_conf.config_file, contents).await
+    format!(
+        r#"/** @type {{import('tailwindcss').Config}} */
+module.exports = {{
+  content: {{
+    relative: true,
+    files: [{}],
+  }},
+  theme: {{
+    extend: {{}},
+  }},
+  plugins: [],
+}}
+"#,
+        files.join(", ")
+    )
 }
 
-pub async fn tailwind_process(cmd: &str, tw_conf: &TailwindConfig) -> Result<(String, Command)> {
-    let tailwind = Exe::Tailwind.get().await.dot()?;
+/// The path from the dir, both being relative to the working dir, e.g. `../app/src`.
+fn relative_path(dir: &Utf8Path, path: &Utf8Path) -> String {
+    if path.is_absolute() {
+        return path.to_string();
+    }
+    let dir = dir.components().collect::<Vec<_>>();
+    let path = path.components().collect::<Vec<_>>();
+    let common = dir.iter().zip(&path).take_while(|(a, b)| a == b).count();
+    let mut rel = vec![".."; dir.len() - common];
+    if rel.is_empty() {
+        rel.push(".");
+    }
+    rel.extend(path[common..].iter().map(|c| c.as_str()));
+    rel.join("/")
+}
 
-    let args: Vec<&str> = vec![
-        "--input",
-        tw_conf.input_file.as_str(),
-        "--config",
-        tw_conf.config_file.as_str(),
-    ];
+pub async fn tailwind_process(
+    cmd: &str,
+    tw_conf: &TailwindConfig,
+    tailwind_bin: Option<&Utf8Path>,
+) -> Result<(String, Command)> {
+    if let Some(node) = &tw_conf.node {
+        return node_process(node, tw_conf);
+    }
+    let tailwind = Exe::Tailwind.get_or_local(tailwind_bin).await.dot()?;
+
+    let args = tailwind_args(tw_conf);
     let line = format!("{} {}", cmd, args.join(" "));
     let mut command = Command::new(tailwind);
     command.args(args);
 
     Ok((line, command))
 }
+
+/// Runs the tailwindcss or postcss of the node_modules with the package manager, in the node dir.
+fn node_process(node: &NodeTailwind, tw_conf: &TailwindConfig) -> Result<(String, Command)> {
+    let pm = node.package_manager;
+    let Ok(program) = which::which(pm.program()) else {
+        bail!(
+            "Tailwind style-node-package-manager {pm} needs {} in the PATH",
+            pm.program()
+        );
+    };
+    let dir = utf8_path(std::env::current_dir()?)?.join(&node.dir);
+    if !has_node_bin(&dir, node.bin()) {
+        bail!(
+            "Tailwind found no {} in the node_modules of {}, install the node packages with `{}`",
+            node.bin(),
+            node.dir,
+            pm.install_command()
+        );
+    }
+
+    let args = node_args(node, tw_conf)?;
+    let line = format!("{} {}", pm.program(), args.join(" "));
+    let mut command = Command::new(program);
+    command.args(args).current_dir(dir);
+    Ok((line, command))
+}
+
+/// The package manager arguments, with absolute paths as the tools run in the node dir.
+pub fn node_args(node: &NodeTailwind, tw_conf: &TailwindConfig) -> Result<Vec<String>> {
+    let cwd = utf8_path(std::env::current_dir()?)?;
+    let input = cwd.join(&tw_conf.input_file);
+    let mut args = node
+        .package_manager
+        .exec_args()
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect::<Vec<_>>();
+    args.push(node.bin().to_string());
+    match &node.postcss_config {
+        // the tailwind config is read by its postcss plugin
+        Some(postcss_config) => args.extend([
+            input.to_string(),
+            "--config".to_string(),
+            cwd.join(postcss_config.parent().unwrap_or(&node.dir))
+                .to_string(),
+        ]),
+        None => args.extend([
+            "--input".to_string(),
+            input.to_string(),
+            "--config".to_string(),
+            cwd.join(&tw_conf.config_file).to_string(),
+        ]),
+    }
+    Ok(args)
+}
+
+/// Whether the binary is installed in the node_modules of the dir or of a parent dir (e.g. the
+/// root of a workspace). Yarn's plug'n'play installs have no node_modules.
+fn has_node_bin(dir: &Utf8Path, bin: &str) -> bool {
+    dir.ancestors().any(|dir| {
+        let bins = dir.join("node_modules").join(".bin");
+        dir.join(".pnp.cjs").exists()
+            || bins.join(bin).exists()
+            || bins.join(format!("{bin}.cmd")).exists()
+    })
+}
+
+pub fn tailwind_args(tw_conf: &TailwindConfig) -> Vec<&str> {
+    vec![
+        "--input",
+        tw_conf.input_file.as_str(),
+        "--config",
+        tw_conf.config_file.as_str(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_modules_bin() {
+        let tmp = temp_dir::TempDir::new().unwrap();
+        let root = Utf8Path::from_path(tmp.path()).unwrap();
+        let app = root.join("app");
+        std::fs::create_dir_all(root.join("node_modules/.bin")).unwrap();
+        std::fs::create_dir_all(&app).unwrap();
+        assert!(!has_node_bin(&app, "tailwindcss"));
+
+        // hoisted to the root of the workspace
+        std::fs::write(root.join("node_modules/.bin/tailwindcss"), "").unwrap();
+        assert!(has_node_bin(&app, "tailwindcss"));
+        assert!(!has_node_bin(&app, "postcss"));
+    }
+
+    #[test]
+    fn default_config_scans_the_sources() {
+        let src = ["app/src", "server/src", "app/src"].map(Utf8PathBuf::from);
+        let config = default_config(Utf8Path::new("server"), &src);
+        assert!(
+            config.contains(r#"files: ["*.html", "../app/src/**/*.rs", "./src/**/*.rs"],"#),
+            "{config}"
+        );
+
+        let config = default_config(Utf8Path::new(""), &[Utf8PathBuf::from("src")]);
+        assert!(
+            config.contains(r#"files: ["*.html", "./src/**/*.rs"],"#),
+            "{config}"
+        );
+    }
+}
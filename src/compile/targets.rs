@@ -0,0 +1,164 @@
+use lightningcss::targets::Browsers;
+
+use super::style::browser_lists;
+use crate::{
+    config::Project,
+    ext::anyhow::{Context, Result},
+};
+
+/// The wasm and js features the browsers of the `browser-targets` query all support, which
+/// the front build enables in wasm-bindgen, wasm-opt and esbuild.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WasmFeatures {
+    /// the `externref` table instead of the js heap of the wasm-bindgen glue
+    pub reference_types: bool,
+    /// the `FinalizationRegistry` freeing the rust values of the dropped js objects
+    pub weak_refs: bool,
+}
+
+/// The browsers versions from which the feature is supported, as encoded by Lightning CSS.
+const REFERENCE_TYPES: Browsers = Browsers {
+    android: Some(version(96, 0)),
+    chrome: Some(version(96, 0)),
+    edge: Some(version(96, 0)),
+    firefox: Some(version(79, 0)),
+    ie: None,
+    ios_saf: Some(version(15, 0)),
+    opera: Some(version(82, 0)),
+    safari: Some(version(15, 0)),
+    samsung: Some(version(17, 0)),
+};
+
+const WEAK_REFS: Browsers = Browsers {
+    android: Some(version(84, 0)),
+    chrome: Some(version(84, 0)),
+    edge: Some(version(84, 0)),
+    firefox: Some(version(79, 0)),
+    ie: None,
+    ios_saf: Some(version(14, 5)),
+    opera: Some(version(70, 0)),
+    safari: Some(version(14, 1)),
+    samsung: Some(version(14, 0)),
+};
+
+const fn version(major: u32, minor: u32) -> u32 {
+    (major << 16) | (minor << 8)
+}
+
+/// The oldest versions of the browsers of the `browser-targets` query, when set.
+pub fn browsers(proj: &Project) -> Result<Option<Browsers>> {
+    let Some(query) = &proj.lib.browser_targets else {
+        return Ok(None);
+    };
+    browser_lists(query).context("leptos.browser-targets")
+}
+
+impl WasmFeatures {
+    /// Without targets, wasm-bindgen keeps its defaults.
+    pub fn of_project(proj: &Project) -> Result<Option<Self>> {
+        Ok(browsers(proj)?.map(|browsers| Self::of(&browsers)))
+    }
+
+    pub fn of(browsers: &Browsers) -> Self {
+        Self {
+            reference_types: supports(browsers, &REFERENCE_TYPES),
+            weak_refs: supports(browsers, &WEAK_REFS),
+        }
+    }
+}
+
+/// The esbuild `--target` of the browsers, e.g. `chrome96,firefox79,safari14.1`. Esbuild
+/// doesn't know the android and samsung browsers, which follow chrome.
+pub fn esbuild_target(browsers: &Browsers) -> Option<String> {
+    let target = versions(browsers)
+        .into_iter()
+        .filter_map(|(name, version)| Some((esbuild_name(name)?, version?)))
+        .map(|(name, version)| {
+            let (major, minor) = (version >> 16, (version >> 8) & 0xff);
+            match minor {
+                0 => format!("{name}{major}"),
+                minor => format!("{name}{major}.{minor}"),
+            }
+        })
+        .collect::<Vec<_>>();
+    (!target.is_empty()).then(|| target.join(","))
+}
+
+fn esbuild_name(name: &str) -> Option<&str> {
+    match name {
+        "android" | "samsung" => None,
+        "ios_saf" => Some("ios"),
+        name => Some(name),
+    }
+}
+
+/// Whether all the browsers are at least at the versions supporting the feature.
+fn supports(browsers: &Browsers, since: &Browsers) -> bool {
+    versions(browsers)
+        .into_iter()
+        .zip(versions(since))
+        .all(|((_, version), (_, since))| match (version, since) {
+            (None, _) => true,
+            (Some(version), Some(since)) => version >= since,
+            (Some(_), None) => false,
+        })
+}
+
+fn versions(browsers: &Browsers) -> [(&'static str, Option<u32>); 9] {
+    [
+        ("android", browsers.android),
+        ("chrome", browsers.chrome),
+        ("edge", browsers.edge),
+        ("firefox", browsers.firefox),
+        ("ie", browsers.ie),
+        ("ios_saf", browsers.ios_saf),
+        ("opera", browsers.opera),
+        ("safari", browsers.safari),
+        ("samsung", browsers.samsung),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn features() {
+        let modern = Browsers {
+            chrome: Some(version(110, 0)),
+            firefox: Some(version(102, 0)),
+            safari: Some(version(15, 4)),
+            ..Browsers::default()
+        };
+        assert_eq!(
+            WasmFeatures::of(&modern),
+            WasmFeatures {
+                reference_types: true,
+                weak_refs: true
+            }
+        );
+        assert_eq!(
+            esbuild_target(&modern).as_deref(),
+            Some("chrome110,firefox102,safari15.4")
+        );
+
+        let safari_14 = Browsers {
+            safari: Some(version(14, 1)),
+            ..modern
+        };
+        assert_eq!(
+            WasmFeatures::of(&safari_14),
+            WasmFeatures {
+                reference_types: false,
+                weak_refs: true
+            }
+        );
+
+        let ie = Browsers {
+            ie: Some(version(11, 0)),
+            ..modern
+        };
+        assert_eq!(WasmFeatures::of(&ie), WasmFeatures::default());
+        assert_eq!(esbuild_target(&Browsers::default()), None);
+    }
+}
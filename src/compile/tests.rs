@@ -1,6 +1,7 @@
 use crate::{
     compile::front::build_cargo_front_cmd,
     config::{Config, Opts},
+    ext::PathBufExt,
 };
 use insta::assert_display_snapshot;
 use tokio::process::Command;
@@ -12,6 +13,34 @@ fn release_opts() -> Opts {
         release: true,
         hot_reload: false,
         project: None,
+        split_deps: false,
+        bin_target_triple: None,
+        stats: false,
+        jobs: None,
+        debug_symbols: false,
+        https: false,
+        open: None,
+        timings: false,
+        bin: None,
+        wasm_debug: false,
+        dry_run: false,
+        musl: false,
+        lazy_front: false,
+        env: None,
+        no_toolchain_install: false,
+        watch_extra: Vec::new(),
+        wait_lock: false,
+        verbose_tools: false,
+        frozen_tools: false,
+        bind: None,
+        advertise_host: None,
+        fix_profile: false,
+        runner: None,
+        quiet: false,
+        watchdog_strict: false,
+        reproducible: false,
+        reuse_artifacts: false,
+        force: false,
         verbose: 0,
         features: Vec::new(),
         bin_features: Vec::new(),
@@ -23,6 +52,34 @@ fn dev_opts() -> Opts {
         release: false,
         hot_reload: false,
         project: None,
+        split_deps: false,
+        bin_target_triple: None,
+        stats: false,
+        jobs: None,
+        debug_symbols: false,
+        https: false,
+        open: None,
+        timings: false,
+        bin: None,
+        wasm_debug: false,
+        dry_run: false,
+        musl: false,
+        lazy_front: false,
+        env: None,
+        no_toolchain_install: false,
+        watch_extra: Vec::new(),
+        wait_lock: false,
+        verbose_tools: false,
+        frozen_tools: false,
+        bind: None,
+        advertise_host: None,
+        fix_profile: false,
+        runner: None,
+        quiet: false,
+        watchdog_strict: false,
+        reproducible: false,
+        reuse_artifacts: false,
+        force: false,
         verbose: 0,
         features: Vec::new(),
         bin_features: Vec::new(),
@@ -73,6 +130,63 @@ fn test_project_release() {
     assert_display_snapshot!(cargo, @"cargo build --package=example --lib --target-dir=target/front --target=wasm32-unknown-unknown --no-default-features --features=hydrate --release");
 }
 
+#[test]
+fn test_project_bin_target_triple() {
+    let cli = Opts {
+        bin_target_triple: Some("x86_64-unknown-linux-musl".to_string()),
+        ..release_opts()
+    };
+    let conf = Config::test_load(cli, "examples", "examples/project/Cargo.toml", true);
+    let proj = &conf.projects[0];
+
+    let mut command = Command::new("cargo");
+    let (_, cargo) = build_cargo_server_cmd("build", proj, &mut command);
+
+    assert_display_snapshot!(cargo, @"cargo build --package=example --bin=example --target-dir=target/server --target=x86_64-unknown-linux-musl --no-default-features --features=ssr --release");
+    assert_eq!(
+        proj.bin.exe_file.test_string(),
+        "target/server/x86_64-unknown-linux-musl/release/example"
+    );
+}
+
+#[test]
+fn test_project_bin_target_spec() {
+    let cli = Opts {
+        bin_target_triple: Some("specs/x86_64-custom.json".to_string()),
+        ..release_opts()
+    };
+    let conf = Config::test_load(cli, "examples", "examples/project/Cargo.toml", true);
+    let proj = &conf.projects[0];
+
+    let mut command = Command::new("cargo");
+    let (_, cargo) = build_cargo_server_cmd("build", proj, &mut command);
+
+    // cargo is given the spec file, and writes to the dir named after it
+    assert_display_snapshot!(cargo, @"cargo build --package=example --bin=example --target-dir=target/server --target=specs/x86_64-custom.json --no-default-features --features=ssr --release");
+    assert_eq!(
+        proj.bin.exe_file.test_string(),
+        "target/server/x86_64-custom/release/example"
+    );
+}
+
+#[test]
+fn test_project_musl() {
+    let cli = Opts {
+        musl: true,
+        bin_target_triple: Some("x86_64-unknown-linux-gnu".to_string()),
+        ..release_opts()
+    };
+    let conf = Config::test_load(cli, "examples", "examples/project/Cargo.toml", true);
+    let proj = &conf.projects[0];
+
+    let triple = format!("{}-unknown-linux-musl", crate::ext::os_arch().unwrap().1);
+    assert_eq!(proj.bin.target_triple.as_deref(), Some(triple.as_str()));
+
+    let mut command = Command::new("cargo");
+    let (_, cargo) = build_cargo_server_cmd("build", proj, &mut command);
+    assert!(cargo.contains(&format!("--target={triple}")), "{cargo}");
+}
+
 #[test]
 fn test_workspace_project1() {
     const ENV_REF: &str = if cfg!(windows) {
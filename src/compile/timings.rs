@@ -0,0 +1,192 @@
+use std::time::SystemTime;
+
+use crate::{
+    config::Project,
+    ext::{
+        anyhow::{Context, Result},
+        fs,
+    },
+    logger::GRAY,
+    signal::CycleTimes,
+};
+use camino::Utf8Path;
+use serde::Serialize;
+
+use super::manifest::unix_secs;
+
+/// The timings of a `build --timings`, covering the cargo builds as well as
+/// the cargo-leptos phases (wasm-bindgen, wasm-opt, style and assets).
+#[derive(Debug, Serialize)]
+pub struct TimingsReport {
+    pub cargo_leptos: &'static str,
+    pub project: String,
+    pub release: bool,
+    /// unix timestamp (seconds) of when the report was written
+    pub built_at: u64,
+    /// the wall time of the build
+    pub total_secs: f64,
+    /// the phases run concurrently, so they add up to more than the total
+    pub phases: Vec<PhaseTiming>,
+    /// the runs of the external tools (cargo, sass, wasm-opt, ...)
+    pub tools: Vec<ToolTiming>,
+    /// the html reports of cargo's own `--timings`, relative to the report dir
+    pub cargo_reports: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PhaseTiming {
+    pub phase: &'static str,
+    pub secs: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ToolTiming {
+    pub tool: &'static str,
+    /// the exit code, none if interrupted or killed by a signal
+    pub code: Option<i32>,
+    pub secs: f64,
+}
+
+impl TimingsReport {
+    fn new(proj: &Project, cycle: &CycleTimes, cargo_reports: Vec<String>) -> Self {
+        Self {
+            cargo_leptos: env!("CARGO_PKG_VERSION"),
+            project: proj.name.clone(),
+            release: proj.release,
+            built_at: unix_secs(SystemTime::now()),
+            total_secs: cycle.total().as_secs_f64(),
+            phases: cycle
+                .phases()
+                .into_iter()
+                .map(|(phase, d)| PhaseTiming {
+                    phase,
+                    secs: d.as_secs_f64(),
+                })
+                .collect(),
+            tools: cycle
+                .tools()
+                .iter()
+                .map(|run| ToolTiming {
+                    tool: run.tool,
+                    code: run.code,
+                    secs: run.duration.as_secs_f64(),
+                })
+                .collect(),
+            cargo_reports,
+        }
+    }
+
+    fn to_html(&self) -> String {
+        let max = self
+            .phases
+            .iter()
+            .map(|p| p.secs)
+            .fold(self.total_secs, f64::max)
+            .max(f64::EPSILON);
+        let rows = self
+            .phases
+            .iter()
+            .map(|p| {
+                format!(
+                    r#"<tr><td>{}</td><td class="secs">{:.2}s</td><td><div class="bar" style="width:{:.1}%"></div></td></tr>"#,
+                    p.phase,
+                    p.secs,
+                    p.secs / max * 100.0
+                )
+            })
+            .collect::<String>();
+        let tools = self
+            .tools
+            .iter()
+            .map(|t| {
+                let code = t.code.map_or("-".to_string(), |c| c.to_string());
+                format!(
+                    r#"<tr><td>{}</td><td class="secs">{:.2}s</td><td>exit {code}</td></tr>"#,
+                    t.tool, t.secs
+                )
+            })
+            .collect::<String>();
+        let links = self
+            .cargo_reports
+            .iter()
+            .map(|r| format!(r#"<li><a href="{r}">{r}</a></li>"#))
+            .collect::<String>();
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>cargo-leptos timings: {project}</title>
+<style>
+body {{ font: 14px sans-serif; margin: 2em; }}
+td {{ padding: 2px 8px; }}
+.secs {{ text-align: right; }}
+.bar {{ height: 12px; background: #61afef; }}
+table {{ width: 100%; max-width: 900px; }}
+</style>
+</head>
+<body>
+<h1>cargo-leptos timings: {project}</h1>
+<p>{mode} build with cargo-leptos {version}, total {total:.2}s. The phases run concurrently, so they add up to more than the total.</p>
+<table>{rows}<tr><td><b>total</b></td><td class="secs"><b>{total:.2}s</b></td><td></td></tr></table>
+<h2>Tools</h2>
+<table>{tools}</table>
+<h2>Cargo timings</h2>
+<ul>{links}</ul>
+</body>
+</html>
+"#,
+            project = self.project,
+            mode = if self.release { "Release" } else { "Debug" },
+            version = self.cargo_leptos,
+            total = self.total_secs,
+        )
+    }
+}
+
+/// Writes the timings report of the build to the `target/leptos-timings` dir,
+/// together with copies of the cargo timing reports of the lib and bin builds.
+pub async fn write_timings(proj: &Project, cycle: &CycleTimes) -> Result<()> {
+    let Some(dir) = &proj.timings_dir else {
+        return Ok(());
+    };
+    let dir = dir.join(&proj.name);
+    fs::create_dir_all(&dir).await?;
+
+    let cargo_dirs = [
+        ("lib", proj.lib.target_dir.clone()),
+        ("bin", proj.bin.target_dir.clone()),
+    ];
+    let mut cargo_reports = Vec::new();
+    for (build, target_dir) in cargo_dirs {
+        if let Some(report) = copy_cargo_report(build, &target_dir, &dir).await? {
+            cargo_reports.push(report);
+        }
+    }
+
+    let report = TimingsReport::new(proj, cycle, cargo_reports);
+    let json = serde_json::to_string_pretty(&report).dot()?;
+    // the timestamped copy is kept for comparing builds over time
+    fs::write(dir.join(format!("timing-{}.json", report.built_at)), &json).await?;
+    fs::write(dir.join("timing.json"), &json).await?;
+    let html = dir.join("timing.html");
+    fs::write(&html, report.to_html()).await?;
+    log::info!("Timings report written {}", GRAY.paint(html.as_str()));
+    Ok(())
+}
+
+/// cargo writes the report of the last build to `<target-dir>/cargo-timings/cargo-timing.html`
+async fn copy_cargo_report(
+    build: &str,
+    target_dir: &Utf8Path,
+    dir: &Utf8Path,
+) -> Result<Option<String>> {
+    let src = target_dir.join("cargo-timings").join("cargo-timing.html");
+    if !src.exists() {
+        log::debug!("Timings no cargo report found {}", GRAY.paint(src.as_str()));
+        return Ok(None);
+    }
+    let name = format!("cargo-{build}.html");
+    fs::copy(&src, dir.join(&name)).await?;
+    Ok(Some(name))
+}
@@ -0,0 +1,132 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use camino::Utf8PathBuf;
+use tokio::process::Command;
+
+use crate::ext::{
+    anyhow::{anyhow, bail, Context, Result},
+    ErrorCode,
+};
+
+/// The target the front is built for.
+pub const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+lazy_static::lazy_static! {
+  static ref CHECKED_TARGETS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// The `+<toolchain>` argument that goes before the cargo command, for the toolchains
+/// pinned with `lib-toolchain` and `bin-toolchain`.
+pub fn toolchain_args(toolchain: Option<&str>) -> Vec<String> {
+    toolchain.map(|t| format!("+{t}")).into_iter().collect()
+}
+
+/// Checks that the toolchain (the active one when not given) has the target, once per
+/// target, and adds it with rustup when missing unless `install` is false
+/// (`--no-toolchain-install`).
+pub async fn ensure_target(triple: &str, toolchain: Option<&str>, install: bool) -> Result<()> {
+    let key = format!("{}/{triple}", toolchain.unwrap_or_default());
+    if CHECKED_TARGETS.lock().unwrap().contains(&key) {
+        return Ok(());
+    }
+    if !is_installed(triple, toolchain).await {
+        if !install {
+            let err = anyhow!(
+                "The rust target {triple} is not installed, add it with `rustup target add {}{triple}`",
+                rustup_toolchain_arg(toolchain)
+            );
+            return if triple == WASM_TARGET {
+                Err(err).code(ErrorCode::MissingWasmTarget)
+            } else {
+                Err(err)
+            };
+        }
+        log::info!("Cargo adding the rustup target {triple}");
+        let status = Command::new("rustup")
+            .args(["target", "add", triple])
+            .args(toolchain.map(|t| format!("--toolchain={t}")))
+            .status()
+            .await
+            .context(format!(
+                "Could not run rustup to add the target {triple}, install it with your toolchain"
+            ))?;
+        if !status.success() {
+            bail!(
+                "Could not add the rustup target {triple}, try `rustup target add {}{triple}`",
+                rustup_toolchain_arg(toolchain)
+            )
+        }
+    }
+    CHECKED_TARGETS.lock().unwrap().insert(key);
+    Ok(())
+}
+
+/// Asks rustup for the installed targets. Toolchains not managed by rustup have a
+/// `lib/rustlib/<triple>` dir in the sysroot for each installed target.
+async fn is_installed(triple: &str, toolchain: Option<&str>) -> bool {
+    let rustup = Command::new("rustup")
+        .args(["target", "list", "--installed"])
+        .args(toolchain.map(|t| format!("--toolchain={t}")))
+        .output()
+        .await;
+    if let Some(output) = rustup.ok().filter(|output| output.status.success()) {
+        let installed = String::from_utf8_lossy(&output.stdout);
+        return installed.lines().any(|line| line.trim() == triple);
+    }
+    log::debug!("Cargo could not list the rustup targets, probing the sysroot");
+    let sysroot = Command::new("rustc")
+        .args(toolchain_args(toolchain))
+        .args(["--print", "sysroot"])
+        .output()
+        .await;
+    match sysroot {
+        Ok(output) if output.status.success() => {
+            let sysroot = String::from_utf8_lossy(&output.stdout);
+            Utf8PathBuf::from(sysroot.trim())
+                .join("lib")
+                .join("rustlib")
+                .join(triple)
+                .exists()
+        }
+        // let cargo report the missing toolchain
+        _ => true,
+    }
+}
+
+fn rustup_toolchain_arg(toolchain: Option<&str>) -> String {
+    toolchain
+        .map(|t| format!("--toolchain={t} "))
+        .unwrap_or_default()
+}
+
+/// The host triple of the toolchain (the active one when not given), from `rustc -vV`.
+pub async fn host_triple(toolchain: Option<&str>) -> Result<String> {
+    let output = Command::new("rustc")
+        .args(toolchain_args(toolchain))
+        .arg("-vV")
+        .output()
+        .await
+        .context("Could not run rustc -vV")?;
+    parse_host(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| anyhow!("No host triple in the output of rustc -vV"))
+}
+
+/// The triple of the host line of `rustc -vV`, e.g. `host: x86_64-unknown-linux-gnu`
+fn parse_host(version: &str) -> Option<String> {
+    version
+        .lines()
+        .find_map(|line| line.strip_prefix("host:"))
+        .map(|host| host.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_is_parsed() {
+        let version = "rustc 1.74.0 (79e9716c9 2023-11-13)\nbinary: rustc\nhost: aarch64-apple-darwin\nrelease: 1.74.0\n";
+        assert_eq!(parse_host(version).as_deref(), Some("aarch64-apple-darwin"));
+        assert_eq!(parse_host("rustc 1.74.0"), None);
+    }
+}
@@ -0,0 +1,253 @@
+use camino::Utf8Path;
+use tokio::{process::Command, sync::broadcast};
+
+use crate::{
+    config::Project,
+    ext::{
+        anyhow::{bail, Context, Result},
+        exe::Exe,
+        sync::CommandResult,
+        tool::Tool,
+    },
+    logger::GRAY,
+};
+
+/// Loads the split module asynchronously: on demand with the exported `loadSplit()`, e.g. when
+/// navigating to the pages using its functions, else once the browser is idle after the primary
+/// module started. The placeholders of the primary module forward to the function table the
+/// split module filled, and throw while it isn't loaded, as they can't wait for it.
+const LOADER: &str = r#"
+const __wbg_split_url = new URL('__SPLIT_FILE__', import.meta.url);
+let __wbg_split_loading;
+let __wbg_split_table;
+
+async function __wbg_split_instantiate() {
+    const imports = { primary: wasm };
+    const response = await fetch(__wbg_split_url);
+    try {
+        return await WebAssembly.instantiateStreaming(response.clone(), imports);
+    } catch (e) {
+        // served without the application/wasm mime type
+        if (response.headers.get('Content-Type') === 'application/wasm') {
+            throw e;
+        }
+        return await WebAssembly.instantiate(await response.arrayBuffer(), imports);
+    }
+}
+
+export function loadSplit() {
+    if (!__wbg_split_loading) {
+        __wbg_split_loading = __wbg_split_instantiate()
+            .then(({ module }) => {
+                const table = WebAssembly.Module.imports(module)
+                    .find(i => i.module === 'primary' && i.kind === 'table');
+                __wbg_split_table = wasm[table.name];
+            })
+            .catch(e => {
+                __wbg_split_loading = undefined;
+                throw e;
+            });
+    }
+    return __wbg_split_loading;
+}
+
+function __wbg_split_prefetch() {
+    const idle = globalThis.requestIdleCallback || (f => setTimeout(f, 200));
+    idle(() => loadSplit().catch(e => console.warn('cargo-leptos: could not load the split wasm', e)));
+}
+
+function __wbg_split_placeholders() {
+    return new Proxy({}, {
+        get: (_, index) => (...args) => {
+            if (!__wbg_split_table) {
+                loadSplit();
+                throw new Error('cargo-leptos: a wasm-split function was called before the split wasm loaded, await loadSplit() first');
+            }
+            return __wbg_split_table.get(Number(index))(...args);
+        },
+    });
+}
+"#;
+
+/// Splitting needs the names of the functions, which wasm-opt removes, so it runs before it.
+pub fn is_split(proj: &Project) -> bool {
+    !proj.lib.wasm_split.is_empty() && proj.release && !proj.lib.wasm_debug
+}
+
+/// Moves the split-point functions of the wasm (and the functions only they call) to the split
+/// module with binaryen's wasm-split. The wasm is replaced by the primary module.
+pub async fn split(
+    proj: &Project,
+    interrupt: broadcast::Receiver<()>,
+) -> Result<CommandResult<()>> {
+    if proj.lib.wasm_threads {
+        bail!("The wasm-split functions can't be split out of a wasm-threads build");
+    }
+    let wasm = &proj.lib.wasm_file.dest;
+    let module = walrus::Module::from_file(wasm).context(format!("Reading {wasm}"))?;
+    let names = module.funcs.iter().filter_map(|f| f.name.as_deref());
+    let (funcs, missing) = split_funcs(names, &proj.lib.wasm_split);
+    for point in missing {
+        log::warn!("Front found no wasm-split function {point} in the wasm");
+    }
+    if funcs.is_empty() {
+        bail!("None of the wasm-split functions are in the wasm, check their paths");
+    }
+
+    let wasm_split = wasm_split_bin(proj.wasm_opt_bin.as_deref()).await.dot()?;
+    let mut command = Command::new(wasm_split);
+    command.args(wasm_split_args(
+        wasm,
+        &proj.lib.split_file.dest,
+        &funcs,
+        proj.debug_symbols,
+    ));
+    log::info!("Front splitting {} functions out of the wasm", funcs.len());
+    Ok(
+        match Tool::new("wasm-split", command)
+            .with_timeouts(&proj.tool_timeouts)
+            .output(interrupt)
+            .await?
+        {
+            CommandResult::Success(_) => CommandResult::Success(()),
+            CommandResult::Failure(_) => CommandResult::Failure(()),
+            CommandResult::Interrupted => CommandResult::Interrupted,
+        },
+    )
+}
+
+/// The wasm-split next to the local wasm-opt, when it is configured.
+async fn wasm_split_bin(wasm_opt_bin: Option<&Utf8Path>) -> Result<std::path::PathBuf> {
+    let local = wasm_opt_bin.map(|opt| {
+        let name = if cfg!(windows) {
+            "wasm-split.exe"
+        } else {
+            "wasm-split"
+        };
+        opt.with_file_name(name)
+    });
+    Exe::WasmSplit.get_or_local(local.as_deref()).await
+}
+
+pub fn wasm_split_args(
+    wasm: &Utf8Path,
+    split: &Utf8Path,
+    funcs: &[String],
+    debug_symbols: bool,
+) -> Vec<String> {
+    let mut args = vec![
+        wasm.to_string(),
+        format!("--split-funcs={}", funcs.join(",")),
+        "-o1".to_string(),
+        wasm.to_string(),
+        "-o2".to_string(),
+        split.to_string(),
+    ];
+    if debug_symbols {
+        args.push("-g".to_string());
+    }
+    args
+}
+
+/// The names of the functions of the split points, a split point being the path of a function
+/// with or without the hash of its symbol (e.g. `app::admin::chart` for `app::admin::chart::h1a2b`).
+/// The generic functions, whose names have commas, can't be given to wasm-split.
+fn split_funcs<'a>(
+    names: impl Iterator<Item = &'a str>,
+    points: &'a [String],
+) -> (Vec<String>, Vec<&'a String>) {
+    let mut found = vec![false; points.len()];
+    let mut funcs = Vec::new();
+    for name in names {
+        let Some(i) = points.iter().position(|point| {
+            name == point
+                || name
+                    .strip_prefix(point.as_str())
+                    .is_some_and(|rest| rest.starts_with("::h"))
+        }) else {
+            continue;
+        };
+        if name.contains(',') {
+            log::warn!(
+                "Front can't split the generic function {}",
+                GRAY.paint(name)
+            );
+            continue;
+        }
+        found[i] = true;
+        funcs.push(name.to_string());
+    }
+    let missing = points
+        .iter()
+        .zip(found)
+        .filter(|(_, found)| !found)
+        .map(|(point, _)| point)
+        .collect();
+    (funcs, missing)
+}
+
+/// The js glue giving the placeholders to the primary module and loading the split module.
+pub fn with_loader(js: &str, split_file: &Utf8Path) -> Result<String> {
+    let file_name = split_file.file_name().unwrap_or_default();
+    let imports_end = "    return imports;\n}";
+    let init = "wasm = instance.exports;";
+    if !js.contains(imports_end) || !js.contains(init) {
+        bail!("The wasm-split loader can't be added to the js glue of this wasm-bindgen version");
+    }
+    let js = js
+        .replacen(
+            imports_end,
+            &format!("    imports.placeholder = __wbg_split_placeholders();\n{imports_end}"),
+            1,
+        )
+        .replacen(init, &format!("{init}\n    __wbg_split_prefetch();"), 1);
+    Ok(format!(
+        "{js}{}",
+        LOADER.replace("__SPLIT_FILE__", file_name)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino::Utf8PathBuf;
+
+    #[test]
+    fn split_points() {
+        let names = [
+            "app::admin::chart::h1a2b3c4d",
+            "app::admin::chart_legend::h5e6f",
+            "app::shop::cart",
+            "core::fmt::write::h99",
+            "app::table<a, b>::render::h12",
+        ];
+        let points = vec![
+            "app::admin::chart".to_string(),
+            "app::shop::cart".to_string(),
+            "app::table<a, b>::render".to_string(),
+            "app::missing".to_string(),
+        ];
+        let (funcs, missing) = split_funcs(names.into_iter(), &points);
+        assert_eq!(
+            funcs,
+            vec!["app::admin::chart::h1a2b3c4d", "app::shop::cart"]
+        );
+        assert_eq!(missing, vec!["app::table<a, b>::render", "app::missing"]);
+    }
+
+    #[test]
+    fn loader() {
+        let js = "let wasm;\nfunction __wbg_get_imports() {\n    const imports = {};\n    imports.wbg = {};\n    return imports;\n}\nfunction __wbg_finalize_init(instance, module) {\n    wasm = instance.exports;\n    return wasm;\n}\n";
+        let split = Utf8PathBuf::from("target/site/pkg/app.split.wasm");
+        let js = with_loader(js, &split).unwrap();
+        assert!(js.contains(
+            "    imports.placeholder = __wbg_split_placeholders();\n    return imports;\n}"
+        ));
+        assert!(js.contains("wasm = instance.exports;\n    __wbg_split_prefetch();"));
+        assert!(js.contains("new URL('app.split.wasm', import.meta.url)"));
+        assert!(js.contains("export function loadSplit()"));
+        assert!(js.contains("WebAssembly.instantiateStreaming"));
+        assert!(!js.contains("XMLHttpRequest"));
+        assert!(with_loader("export function start() {}", &split).is_err());
+    }
+}
@@ -0,0 +1,31 @@
+use serde::Deserialize;
+
+/// The opt-in adaptive watch, from `[package.metadata.leptos.adaptive]`: the rebuilds
+/// triggered by file changes wait while the machine is busy or saving power.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AdaptiveConfig {
+    /// the cpu usage (0 to 1) above which the machine is busy
+    #[serde(default = "default_max_load")]
+    pub max_load: f32,
+    /// whether running on battery or in power saver mode counts as busy
+    #[serde(default = "default_true")]
+    pub on_battery: bool,
+    /// the longest a rebuild waits, in seconds, after which it runs with `busy-jobs`
+    #[serde(default = "default_max_delay")]
+    pub max_delay: u64,
+    /// the cargo `--jobs` of the rebuilds that ran while busy. Defaults to half the cpus
+    pub busy_jobs: Option<u16>,
+}
+
+fn default_max_load() -> f32 {
+    0.9
+}
+
+pub(super) fn default_true() -> bool {
+    true
+}
+
+fn default_max_delay() -> u64 {
+    60
+}
@@ -5,14 +5,15 @@ use crate::{
     config::Opts,
     ext::{
         anyhow::{anyhow, bail, Error, Result},
-        MetadataExt, PackageExt, PathBufExt, PathExt,
+        os_arch, MetadataExt, PackageExt, PathBufExt, PathExt,
     },
 };
 
-use super::{project::ProjectDefinition, Profile, ProjectConfig};
+use super::{project::ProjectDefinition, target_dir_name, Profile, ProjectConfig};
 
 pub struct BinPackage {
     pub name: String,
+    pub version: String,
     pub abs_dir: Utf8PathBuf,
     pub rel_dir: Utf8PathBuf,
     pub exe_file: Utf8PathBuf,
@@ -23,8 +24,11 @@ pub struct BinPackage {
     pub src_paths: Vec<Utf8PathBuf>,
     pub profile: Profile,
     pub target_triple: Option<String>,
-    pub target_dir: Option<String>,
+    /// the configured dir, else the `server` dir of the cargo target dir
+    pub target_dir: Utf8PathBuf,
     pub cargo_command: Option<String>,
+    /// the rustup toolchain, passed to cargo as `+<toolchain>`
+    pub toolchain: Option<String>,
 }
 
 impl BinPackage {
@@ -60,11 +64,12 @@ impl BinPackage {
             .filter(|t| t.is_bin())
             .collect::<Vec<&Target>>();
 
-        let target: Target = if !&config.bin_target.is_empty() {
+        let bin_target = cli.bin.as_deref().unwrap_or(&config.bin_target);
+        let target: Target = if !bin_target.is_empty() {
             targets
                 .into_iter()
-                .find(|t| t.name == config.bin_target)
-                .ok_or_else(|| target_not_found(config.bin_target.as_str()))?
+                .find(|t| t.name == bin_target)
+                .ok_or_else(|| target_not_found(bin_target))?
                 .clone()
         } else if targets.len() == 1 {
             targets[0].clone()
@@ -82,20 +87,33 @@ impl BinPackage {
             &config.bin_profile_dev,
         );
 
+        let target_triple = if cli.musl {
+            Some(format!("{}-unknown-linux-musl", os_arch()?.1))
+        } else {
+            cli.bin_target_triple
+                .clone()
+                .or_else(|| config.bin_target_triple.clone())
+        };
+
+        let target_dir: Utf8PathBuf = config
+            .bin_target_dir
+            .as_ref()
+            .map(|dir| dir.into())
+            .unwrap_or_else(|| metadata.rel_target_dir().join("server"));
+
         let exe_file = {
-            let file_ext = if cfg!(target_os = "windows") {
-                "exe"
-            } else {
-                ""
+            // when cross-compiling, the extension depends on the target and not the host
+            let windows = match &target_triple {
+                Some(triple) => triple.contains("windows"),
+                None => cfg!(target_os = "windows"),
             };
-            let mut file = config.bin_target_dir.as_ref()
-                .map(|dir| dir.into())
-                .unwrap_or_else(|| metadata.rel_target_dir().join("server"));
-            if let Some(triple) = &config.bin_target_triple {
-                file = file.join(triple)
+            let file_ext = if windows { "exe" } else { "" };
+            let mut file = target_dir.clone();
+            if let Some(triple) = &target_triple {
+                file = file.join(target_dir_name(triple))
             };
             file.join(profile.to_string())
-                .join(&name)
+                .join(&target.name)
                 .with_extension(file_ext)
         };
 
@@ -107,6 +125,7 @@ impl BinPackage {
         }
         Ok(Self {
             name,
+            version: package.version.to_string(),
             abs_dir,
             rel_dir,
             exe_file,
@@ -115,9 +134,10 @@ impl BinPackage {
             default_features: config.bin_default_features,
             src_paths,
             profile,
-            target_triple: config.bin_target_triple.clone(),
-            target_dir: config.bin_target_dir.clone(),
+            target_triple,
+            target_dir,
             cargo_command: config.bin_cargo_command.clone(),
+            toolchain: config.bin_toolchain.clone(),
         })
     }
 }
@@ -147,11 +167,11 @@ impl std::fmt::Debug for BinPackage {
 
 fn many_targets_found(pkg: &str) -> Error {
     anyhow!(
-        r#"Several bin targets found for member "{pkg}", please specify which one to use with: [[workspace.metadata.leptos]] bin-target = "name""#
+        r#"Several bin targets found for member "{pkg}", please specify which one to use with --bin or: [[workspace.metadata.leptos]] bin-target = "name""#
     )
 }
 fn target_not_found(target: &str) -> Error {
     anyhow!(
-        r#"Could not find the target specified: [[workspace.metadata.leptos]] bin-target = "{target}""#,
+        r#"Could not find the bin target "{target}" (from --bin or the bin-target config)"#,
     )
 }
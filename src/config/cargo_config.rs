@@ -0,0 +1,302 @@
+use std::{collections::HashMap, env};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Deserialize;
+
+use crate::{
+    ext::anyhow::{Context, Result},
+    logger::GRAY,
+};
+
+/// The settings of the cargo config files (`.cargo/config.toml`) that change where cargo writes
+/// the artifacts and how it compiles them. The target dir is resolved by `cargo metadata`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CargoConfig {
+    /// `build.target`, a triple or the path of a target spec file, see [target_dir_name]
+    pub build_target: Option<String>,
+    /// `build.rustflags`
+    pub rustflags: Vec<String>,
+    /// `target.<triple>.rustflags`, which replace the `build.rustflags` of their target
+    pub target_rustflags: HashMap<String, Vec<String>>,
+    /// `[alias]`, the args of the subcommands they name
+    pub aliases: HashMap<String, Vec<String>>,
+}
+
+/// The subcommands of cargo that aren't looked up in the aliases, see [CargoConfig::expand_alias].
+const BUILTIN_COMMANDS: [&str; 18] = [
+    "add", "bench", "build", "check", "clean", "doc", "fetch", "fix", "install", "metadata", "new",
+    "package", "publish", "run", "rustc", "rustdoc", "test", "update",
+];
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    build: BuildSection,
+    #[serde(default)]
+    target: HashMap<String, TargetSection>,
+    #[serde(default)]
+    alias: HashMap<String, Flags>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BuildSection {
+    /// a triple or, with the multitarget feature, a list of triples
+    target: Option<Flags>,
+    rustflags: Option<Flags>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TargetSection {
+    rustflags: Option<Flags>,
+}
+
+/// The flags are a space separated string or an array, as are the build targets.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Flags {
+    Line(String),
+    List(Vec<String>),
+}
+
+impl Flags {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Self::Line(line) => line.split_whitespace().map(str::to_string).collect(),
+            Self::List(list) => list,
+        }
+    }
+}
+
+impl CargoConfig {
+    /// Reads the config files cargo reads when run in the dir: those of the dir and its
+    /// parents, then the one of the cargo home. A file that can't be read is left to cargo to
+    /// report.
+    pub fn load(dir: &Utf8Path) -> Self {
+        let mut files = Vec::new();
+        for dir in dir.ancestors() {
+            files.extend(config_file(&dir.join(".cargo")));
+        }
+        if let Some(home) = cargo_home() {
+            let file = config_file(&home);
+            if !file.as_ref().is_some_and(|file| files.contains(file)) {
+                files.extend(file);
+            }
+        }
+
+        let mut config = Self::default();
+        // the farthest first, the closer ones override its values and append to its arrays
+        for file in files.iter().rev() {
+            let merged = std::fs::read_to_string(file)
+                .context(format!("Reading {file}"))
+                .and_then(|text| config.merge(&text, &config_base(file)));
+            match merged {
+                Ok(()) => log::debug!("Cargo read the config {}", GRAY.paint(file.as_str())),
+                Err(e) => log::warn!("Cargo could not read the config {file}: {e:#}"),
+            }
+        }
+        config
+    }
+
+    /// Merges the config file, read from `text`, with its relative paths based on `base`.
+    fn merge(&mut self, text: &str, base: &Utf8Path) -> Result<()> {
+        let file: ConfigFile = toml::from_str(text)?;
+        if let Some(targets) = file.build.target {
+            // the artifacts of the first target are used
+            if let Some(target) = targets.into_vec().first() {
+                self.build_target = Some(resolve_target(target, base));
+            }
+        }
+        if let Some(flags) = file.build.rustflags {
+            self.rustflags.extend(flags.into_vec());
+        }
+        for (triple, section) in file.target {
+            if let Some(flags) = section.rustflags {
+                self.target_rustflags
+                    .entry(triple)
+                    .or_default()
+                    .extend(flags.into_vec());
+            }
+        }
+        for (name, args) in file.alias {
+            self.aliases.insert(name, args.into_vec());
+        }
+        Ok(())
+    }
+
+    /// The args cargo runs for the subcommand, after following its aliases as cargo does: the
+    /// builtin subcommands aren't aliased, and a recursive alias is left for cargo to report.
+    /// The args after the `--` of an alias, passed to the tool run by cargo, are returned apart
+    /// for them to stay after the other args.
+    pub fn expand_alias(&self, cmd: &str) -> (Vec<String>, Vec<String>) {
+        let mut args = vec![cmd.to_string()];
+        let mut seen = Vec::new();
+        loop {
+            let name = &args[0];
+            if BUILTIN_COMMANDS.contains(&name.as_str()) || seen.contains(name) {
+                break;
+            }
+            let Some(alias) = self.aliases.get(name).filter(|alias| !alias.is_empty()) else {
+                break;
+            };
+            seen.push(name.clone());
+            args.splice(..1, alias.iter().cloned());
+        }
+        match args.iter().position(|arg| arg == "--") {
+            Some(i) => {
+                let tool_args = args.split_off(i + 1);
+                args.pop();
+                (args, tool_args)
+            }
+            None => (args, Vec::new()),
+        }
+    }
+
+    /// The rustflags cargo passes to rustc when building for the target, without the
+    /// `RUSTFLAGS` env var that replaces them.
+    pub fn rustflags(&self, triple: &str) -> &[String] {
+        self.target_rustflags.get(triple).unwrap_or(&self.rustflags)
+    }
+}
+
+/// Cargo prefers `config.toml` to the older `config`.
+fn config_file(dir: &Utf8Path) -> Option<Utf8PathBuf> {
+    ["config.toml", "config"]
+        .into_iter()
+        .map(|name| dir.join(name))
+        .find(|file| file.is_file())
+}
+
+fn cargo_home() -> Option<Utf8PathBuf> {
+    let home = match env::var("CARGO_HOME") {
+        Ok(home) => Utf8PathBuf::from(home),
+        Err(_) => Utf8PathBuf::from_path_buf(dirs::home_dir()?)
+            .ok()?
+            .join(".cargo"),
+    };
+    Some(home)
+}
+
+/// The dir the relative paths of a config file are based on: the parent of its `.cargo` dir.
+fn config_base(file: &Utf8Path) -> Utf8PathBuf {
+    file.parent()
+        .and_then(Utf8Path::parent)
+        .map(Utf8Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// A relative target spec file is relative to the config, and not to the dir cargo runs in.
+fn resolve_target(target: &str, base: &Utf8Path) -> String {
+    if target.ends_with(".json") && Utf8Path::new(target).is_relative() {
+        base.join(target).to_string()
+    } else {
+        target.to_string()
+    }
+}
+
+/// The dir of the artifacts of the target, which is named after the spec file for a custom
+/// target, e.g. `avr-unknown` for `specs/avr-unknown.json`.
+pub fn target_dir_name(target: &str) -> String {
+    match target.strip_suffix(".json") {
+        Some(path) => Utf8Path::new(path).file_name().unwrap_or(path).to_string(),
+        None => target.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge() {
+        let mut config = CargoConfig::default();
+        config
+            .merge(
+                r#"
+[build]
+target = "x86_64-unknown-linux-gnu"
+rustflags = "--cfg home"
+
+[alias]
+lint = "clippy --all-targets"
+"#,
+                Utf8Path::new("/home"),
+            )
+            .unwrap();
+        config
+            .merge(
+                r#"
+[build]
+target = "specs/x86_64-custom.json"
+rustflags = ["--cfg", "project"]
+
+[target.wasm32-unknown-unknown]
+rustflags = ["--cfg=web_sys_unstable_apis"]
+
+[alias]
+b = "build"
+strict = ["lint", "--", "-Dwarnings"]
+"#,
+                Utf8Path::new("/project"),
+            )
+            .unwrap();
+        assert_eq!(
+            config.build_target.as_deref(),
+            Some("/project/specs/x86_64-custom.json")
+        );
+        assert_eq!(
+            config.rustflags("x86_64-unknown-linux-gnu"),
+            ["--cfg", "home", "--cfg", "project"]
+        );
+        assert_eq!(
+            config.rustflags("wasm32-unknown-unknown"),
+            ["--cfg=web_sys_unstable_apis"]
+        );
+        assert_eq!(
+            config.expand_alias("strict"),
+            (
+                vec!["clippy".to_string(), "--all-targets".to_string()],
+                vec!["-Dwarnings".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn expand_alias() {
+        let mut config = CargoConfig::default();
+        config
+            .merge(
+                r#"
+[alias]
+build = "build --release"
+loop = "again"
+again = ["loop", "--quiet"]
+"#,
+                Utf8Path::new("/project"),
+            )
+            .unwrap();
+        // cargo ignores the aliases of its builtin subcommands
+        assert_eq!(config.expand_alias("build").0, ["build"]);
+        assert_eq!(config.expand_alias("clippy").0, ["clippy"]);
+        assert_eq!(config.expand_alias("loop").0, ["loop", "--quiet"]);
+    }
+
+    #[test]
+    fn target_dir_names() {
+        assert_eq!(
+            target_dir_name("/project/specs/x86_64-custom.json"),
+            "x86_64-custom"
+        );
+        assert_eq!(
+            target_dir_name("x86_64-unknown-linux-musl"),
+            "x86_64-unknown-linux-musl"
+        );
+        assert_eq!(
+            resolve_target("specs/avr.json", Utf8Path::new("/project")),
+            "/project/specs/avr.json"
+        );
+        assert_eq!(
+            resolve_target("avr-unknown-gnu-atmega328", Utf8Path::new("/project")),
+            "avr-unknown-gnu-atmega328"
+        );
+    }
+}
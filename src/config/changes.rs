@@ -0,0 +1,104 @@
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+/// The keys of the leptos config that only the builds read, so that watch applies them to its
+/// next build. The others are read by the server, the reload server or the cargo invocations
+/// shared with them (e.g. `site-addr` and the features), and need the watch to start again.
+const LIVE_KEYS: &[&str] = &[
+    "style-file",
+    "style-dev-file",
+    "style-release-file",
+    "tailwind-input-file",
+    "tailwind-config-file",
+    "style-node-package-manager",
+    "style-node-dir",
+    "style-vars",
+    "style-source-map",
+    "browserquery",
+    "browser-targets",
+    "assets-dir",
+    "root-file-stubs",
+    "suppress-warnings",
+    "build-manifest-file",
+    "preload-hints",
+    "prerender-routes",
+    "sitemap",
+    "reuse-artifacts",
+    "tool-timeouts",
+    "pwa",
+    "sass-bin",
+    "tailwind-bin",
+    "wasm-opt-bin",
+];
+
+/// The keys of the leptos config of a project that changed in the Cargo.toml.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ConfigChanges {
+    /// applied to the next build
+    pub live: Vec<String>,
+    /// needing a restart of the watch
+    pub restart: Vec<String>,
+}
+
+impl ConfigChanges {
+    /// The changes between the leptos metadata of the project, as written in the Cargo.toml.
+    pub fn between(old: &Value, new: &Value) -> Self {
+        let keys = |value: &Value| {
+            value
+                .as_object()
+                .map(|table| table.keys().cloned().collect::<BTreeSet<_>>())
+                .unwrap_or_default()
+        };
+        let mut changes = Self::default();
+        for key in keys(old).union(&keys(new)) {
+            if old.get(key) == new.get(key) {
+                continue;
+            }
+            if LIVE_KEYS.contains(&key.as_str()) {
+                changes.live.push(key.clone());
+            } else {
+                changes.restart.push(key.clone());
+            }
+        }
+        changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live.is_empty() && self.restart.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn between() {
+        let old = json!({
+            "style-file": "style/main.scss",
+            "site-addr": "127.0.0.1:3000",
+            "bin-features": ["ssr"],
+            "assets-dir": "public",
+        });
+        let new = json!({
+            "style-file": "style/app.scss",
+            "site-addr": "127.0.0.1:3000",
+            "bin-features": ["ssr", "tracing"],
+            "suppress-warnings": ["dead_code"],
+        });
+        assert_eq!(
+            ConfigChanges::between(&old, &new),
+            ConfigChanges {
+                live: vec![
+                    "assets-dir".to_string(),
+                    "style-file".to_string(),
+                    "suppress-warnings".to_string()
+                ],
+                restart: vec!["bin-features".to_string()],
+            }
+        );
+        assert!(ConfigChanges::between(&old, &old).is_empty());
+    }
+}
@@ -0,0 +1,41 @@
+use serde::Deserialize;
+
+/// The cells checked by `cargo leptos check-all`, from `[package.metadata.leptos.check-matrix]`:
+/// every feature set with every target, but the skipped ones.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct CheckMatrixConfig {
+    /// the feature sets of the lib package, each one a comma separated list of features
+    pub features: Vec<String>,
+    /// `native` and/or `wasm32`
+    pub targets: Vec<CheckTarget>,
+    /// the cells not to check, as `<features>@<target>`, e.g. `ssr@wasm32`
+    pub skip: Vec<String>,
+}
+
+impl Default for CheckMatrixConfig {
+    fn default() -> Self {
+        Self {
+            features: vec!["ssr".to_string(), "hydrate".to_string(), "csr".to_string()],
+            targets: vec![CheckTarget::Native, CheckTarget::Wasm32],
+            // the server dependencies don't build for wasm
+            skip: vec!["ssr@wasm32".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CheckTarget {
+    Native,
+    Wasm32,
+}
+
+impl std::fmt::Display for CheckTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Native => write!(f, "native"),
+            Self::Wasm32 => write!(f, "wasm32"),
+        }
+    }
+}
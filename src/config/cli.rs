@@ -1,6 +1,13 @@
-use crate::command::NewCommand;
+use crate::command::{
+    AnalyzeCommand, BenchPipelineCommand, BuildCommand, CheckAllCommand, ChefCommand, CtlCommand,
+    DaemonCommand, DiffCommand, NewCommand, PackCommand, PrerenderCommand, PruneAssetsCommand,
+    ToolsCommand, UpdateSelfCommand,
+};
+use crate::ext::ErrorFormat;
 use camino::Utf8PathBuf;
 use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use std::net::IpAddr;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 pub enum Log {
@@ -36,11 +43,141 @@ pub struct Opts {
     #[arg(long)]
     pub bin_features: Vec<String>,
 
+    /// The target triple to use when compiling the bin target, overrides the `bin-target-triple` config.
+    #[arg(long)]
+    pub bin_target_triple: Option<String>,
+
+    /// Only build the dependencies, not the workspace crates. For caching them in a separate Docker layer.
+    #[arg(long)]
+    pub split_deps: bool,
+
+    /// Print the average phase timings of the last watch cycles after each rebuild.
+    #[arg(long)]
+    pub stats: bool,
+
+    /// The maximum number of cargo builds to run in parallel (front and server). Defaults to 2.
+    #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: Option<u16>,
+
+    /// Keep the DWARF debug info in the wasm, through wasm-bindgen and wasm-opt, for source-level debugging in the browser.
+    #[arg(long)]
+    pub debug_symbols: bool,
+
+    /// Serve over HTTPS with a local certificate (made by mkcert or openssl), passed to the server in env vars, and use WSS for the reload websocket. Needs the native-tls feature.
+    #[arg(long)]
+    pub https: bool,
+
+    /// Open the site in the default browser once the server is up, optionally at the given path.
+    #[arg(long, num_args = 0..=1, default_missing_value = "/", value_name = "PATH")]
+    pub open: Option<String>,
+
+    /// Pass `--timings` to the cargo builds and write a report of the whole pipeline to `target/leptos-timings`.
+    #[arg(long)]
+    pub timings: bool,
+
+    /// The bin target of the server package to build and run, overrides the `bin-target` config.
+    #[arg(long, value_name = "NAME")]
+    pub bin: Option<String>,
+
+    /// Fast wasm builds for debugging: skip wasm-opt and keep the function names readable in panics.
+    #[arg(long)]
+    pub wasm_debug: bool,
+
+    /// Log the resolved config and the commands the build would run (cargo, wasm-bindgen, wasm-opt, sass, tailwind), then exit without running them.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Build the server as a statically linked musl binary for the host architecture, adding the rustup target if needed. Overrides `--bin-target-triple`.
+    #[arg(long)]
+    pub musl: bool,
+
+    /// With watch, start the server before the front is built: the previous wasm (or a placeholder script) is served until the front build reloads the browser.
+    #[arg(long)]
+    pub lazy_front: bool,
+
+    /// Apply the overrides of the `[package.metadata.leptos.env.<ENV>]` table, e.g. `--env staging`.
+    #[arg(long)]
+    pub env: Option<String>,
+
+    /// Don't add the missing rustup targets (wasm32-unknown-unknown and the --musl one), fail with the command to run instead.
+    #[arg(long)]
+    pub no_toolchain_install: bool,
+
+    /// Extra paths for watch to rebuild on, e.g. a crate checked out next to the project. Can be repeated.
+    /// The path dependencies are watched without it.
+    #[arg(long)]
+    pub watch_extra: Vec<Utf8PathBuf>,
+
+    /// Wait for another cargo-leptos using the same site dir to finish, instead of failing.
+    #[arg(long)]
+    pub wait_lock: bool,
+
+    /// Echo the command line and environment of the external tools (cargo, sass, wasm-opt, ...) as they start.
+    #[arg(long)]
+    pub verbose_tools: bool,
+
+    /// Use only the tools already in the cache at their locked version, never the PATH ones, downloading them or checking for newer versions (e.g. for reproducible CI builds).
+    #[arg(long)]
+    pub frozen_tools: bool,
+
+    /// The ip the server and the reload server listen on, e.g. `0.0.0.0` on a remote VM or in a devcontainer. Overrides the ip of the `site-addr`.
+    #[arg(long, value_name = "IP")]
+    pub bind: Option<IpAddr>,
+
+    /// The host the browser reaches the dev servers at, for the reload client and `--open`, e.g. the name of the VM. Overrides the `advertise-host` config.
+    #[arg(long, value_name = "HOST")]
+    pub advertise_host: Option<String>,
+
+    /// Write the size settings missing from the release profile of the lib (`panic = "abort"`, `opt-level`...) into the Cargo.toml, when the release build checks find some.
+    #[arg(long)]
+    pub fix_profile: bool,
+
+    /// Run the server binary under this command, e.g. `gdbserver :1234`, `rr record` or `valgrind`. Split into words like a shell does, the binary is added as the last argument. Overrides the `server-runner` config.
+    #[arg(long, value_name = "CMD")]
+    pub runner: Option<String>,
+
+    /// Only print the results of the build phases (the cargo, sass and tailwind runs) and the errors.
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// With watch, exit with an error when the watchdog killed leaked processes, e.g. for the CI of cargo-leptos.
+    #[arg(long)]
+    pub watchdog_strict: bool,
+
+    /// Make byte-identical site artifacts for two builds of the same commit: the build manifest and packed archives get the SOURCE_DATE_EPOCH (or the commit time) as timestamps, the archives list their files sorted, and the cargo builds get SOURCE_DATE_EPOCH.
+    #[arg(long)]
+    pub reproducible: bool,
+
+    /// With build, skip the front and server builds whose sources and settings are the ones of the last build (e.g. of a watch) and whose artifacts are unchanged since. Same as the `reuse-artifacts` config.
+    #[arg(long)]
+    pub reuse_artifacts: bool,
+
+    /// Rebuild everything, even with `reuse-artifacts`.
+    #[arg(long)]
+    pub force: bool,
+
     /// Verbosity (none: info, errors & warnings, -v: verbose, --vv: very verbose).
     #[arg(short, action = clap::ArgAction::Count)]
     pub verbose: u8,
 }
 
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Lines for humans, with a condensed summary of the cargo diagnostics
+    #[default]
+    Text,
+    /// A json object per line, for tools
+    Json,
+}
+
 #[derive(Debug, Parser)]
 #[clap(version)]
 pub struct Cli {
@@ -52,16 +189,48 @@ pub struct Cli {
     #[arg(long)]
     pub log: Vec<Log>,
 
+    /// When to color the output: `auto` colors it for a terminal, unless `NO_COLOR` is set
+    /// (or `CLICOLOR_FORCE` is). The choice is passed on to cargo and the other tools.
+    #[arg(long, value_enum, default_value = "auto", global = true)]
+    pub color: ColorChoice,
+
+    /// How a failure is reported on stderr. `json` prints a summary object with the exit code and category.
+    #[arg(long, value_enum, default_value = "short", global = true)]
+    pub error_format: ErrorFormat,
+
+    /// How the logs are written on stderr. `json` writes an object per line, without colors: `{"type": "log", ...}` for the logs and `{"type": "diagnostic", "build": "lib", ...}` for each of the cargo diagnostics.
+    #[arg(long, value_enum, default_value = "text", global = true)]
+    pub log_format: LogFormat,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
 impl Cli {
     pub fn opts(&self) -> Option<Opts> {
-        use Commands::{Build, EndToEnd, New, Serve, Test, Watch};
+        use Commands::{
+            Analyze, BenchPipeline, Build, CheckAll, Chef, Completions, Ctl, Daemon, Diff,
+            EndToEnd, Explain, Man, New, Pack, Prerender, PruneAssets, Serve, ShowConfig, Test,
+            Tools, UpdateSelf, Watch,
+        };
         match &self.command {
-            New(_) => None,
-            Build(opts) | Serve(opts) | Test(opts) | EndToEnd(opts) | Watch(opts) => {
+            New(_) | Completions { .. } | Man | Explain { .. } | UpdateSelf(_) | Diff(_) => None,
+            Chef(chef) => chef.opts(),
+            // a deployment package is always of a release build
+            Pack(pack) => Some(Opts {
+                release: true,
+                ..pack.opts.clone()
+            }),
+            Daemon(daemon) => Some(daemon.opts.clone()),
+            Ctl(ctl) => Some(ctl.opts.clone()),
+            Tools(tools) => Some(tools.opts.clone()),
+            PruneAssets(prune) => Some(prune.opts.clone()),
+            Prerender(prerender) => Some(prerender.opts.clone()),
+            CheckAll(check) => Some(check.opts.clone()),
+            BenchPipeline(bench) => Some(bench.opts.clone()),
+            Analyze(analyze) => Some(analyze.opts.clone()),
+            Build(build) => Some(build.opts.clone()),
+            Serve(opts) | Test(opts) | EndToEnd(opts) | Watch(opts) | ShowConfig(opts) => {
                 Some(opts.clone())
             }
         }
@@ -71,7 +240,7 @@ impl Cli {
 #[derive(Debug, Subcommand, PartialEq)]
 pub enum Commands {
     /// Build the server (feature ssr) and the client (wasm with feature hydrate).
-    Build(Opts),
+    Build(BuildCommand),
     /// Run the cargo tests for app, client and server.
     Test(Opts),
     /// Start the server and end-2-end tests.
@@ -82,4 +251,41 @@ pub enum Commands {
     Watch(Opts),
     /// WIP: Start wizard for creating a new project (using cargo-generate). Ask at Leptos discord before using.
     New(NewCommand),
+    /// Docker layer friendly builds: prepare a recipe of the manifests and cook (build) only the dependencies.
+    Chef(ChefCommand),
+    /// Build and package the server binary, the site dir and an env template for deployment.
+    Pack(PackCommand),
+    /// Watch in the background, with a control socket for `cargo leptos ctl`.
+    Daemon(DaemonCommand),
+    /// Query or control the running daemon: status, rebuild, stop or logs.
+    Ctl(CtlCommand),
+    /// Print the completions script for the given shell, e.g. `cargo leptos completions bash > /etc/bash_completion.d/cargo-leptos`.
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the man page.
+    Man,
+    /// Explain an error code, e.g. `cargo leptos explain L0001`. Lists the codes when none is given.
+    Explain { code: Option<String> },
+    /// Update cargo-leptos to the latest release, using the prebuilt binary when there is one.
+    UpdateSelf(UpdateSelfCommand),
+    /// Manage the tool versions locked in `leptos-tools.lock`.
+    Tools(ToolsCommand),
+    /// Compare the artifacts of two builds (build manifests or site dirs) as a markdown summary, e.g. for pull requests.
+    Diff(DiffCommand),
+    /// Report the files of the assets dir that nothing in the built site references (`--check` fails on them, `--fix` deletes them).
+    PruneAssets(PruneAssetsCommand),
+    /// Build the site, run the server and write the html of its routes (`--routes <FILE>`, `--sitemap`, `--crawl`) into the site dir, for static hosting.
+    Prerender(PrerenderCommand),
+    /// Check (or `--clippy`) the lib package with every feature set and target of the check matrix, e.g. ssr, hydrate and csr for native and wasm32.
+    CheckAll(CheckAllCommand),
+    /// Attribute the size of the wasm to the crates (and their biggest functions), in a table and an HTML treemap.
+    Analyze(AnalyzeCommand),
+    /// Print the leptos config of the projects, with the table of `--env` overlaid and the keys it overrode marked.
+    #[command(name = "config")]
+    ShowConfig(Opts),
+    /// Measure the p50/p95 of the pipeline stages over repeated no-op and single file change builds, for the maintainers.
+    #[command(hide = true)]
+    BenchPipeline(BenchPipelineCommand),
 }
@@ -0,0 +1,76 @@
+use std::net::SocketAddr;
+
+use super::ReloadClient;
+use crate::logger::GRAY;
+
+/// Warns about what the `csp` policy blocks of the cargo-leptos output: the wasm
+/// instantiation, the inline scripts of leptos and, in watch mode, the reload client.
+pub fn check_csp(policy: &str, watch: bool, reload: SocketAddr, client: &ReloadClient) {
+    let directives = policy
+        .split(';')
+        .filter_map(|d| {
+            let mut parts = d.split_whitespace();
+            Some((parts.next()?.to_lowercase(), parts.collect::<Vec<_>>()))
+        })
+        .collect::<Vec<_>>();
+    // the directive, or default-src when it is not set
+    let sources = |name: &str| {
+        directives
+            .iter()
+            .find(|(n, _)| n == name)
+            .or_else(|| directives.iter().find(|(n, _)| n == "default-src"))
+            .map(|(_, sources)| sources.clone())
+    };
+
+    if let Some(scripts) = sources("script-src") {
+        let has = |source: &str| scripts.iter().any(|s| s.eq_ignore_ascii_case(source));
+        if !has("'wasm-unsafe-eval'") && !has("'unsafe-eval'") {
+            log::warn!(
+                "Csp script-src needs {} to instantiate the wasm",
+                GRAY.paint("'wasm-unsafe-eval'")
+            );
+        }
+        let allows_inline = has("'unsafe-inline'")
+            || scripts
+                .iter()
+                .any(|s| s.starts_with("'nonce-") || s.starts_with("'sha"));
+        if !allows_inline {
+            log::warn!(
+                "Csp script-src blocks the inline hydration script, add a nonce, e.g. {} filled in by the server",
+                GRAY.paint("'nonce-{nonce}'")
+            );
+        }
+        if watch && !has("'unsafe-inline'") {
+            let origin = client.script_origin(reload);
+            log::warn!(
+                "Csp script-src blocks the inline reload script of leptos unless it has the nonce. Use {} instead and allow {}",
+                GRAY.paint(format!("<script src=\"{origin}/live_reload/reload.js\">")),
+                GRAY.paint(&origin)
+            );
+        }
+    }
+    if watch {
+        if let Some(connects) = sources("connect-src") {
+            let port = format!(":{}", client.external_port().unwrap_or(reload.port()));
+            // behind a proxy, the default port isn't in the source
+            let host = client.external_url.as_ref().and_then(|url| url.host_str());
+            let allowed = connects.iter().any(|s| {
+                *s == "*"
+                    || s.ends_with(&port)
+                    || host.is_some_and(|host| s.contains(host))
+                    || *s == "ws:"
+                    || *s == "wss:"
+            });
+            if !allowed {
+                log::warn!(
+                    "Csp connect-src blocks the reload websocket, allow {}",
+                    GRAY.paint(
+                        client
+                            .websocket_url()
+                            .unwrap_or_else(|| format!("{}://{reload}", client.ws_protocol))
+                    )
+                );
+            }
+        }
+    }
+}
@@ -40,22 +40,47 @@ fn overlay(conf: &mut ProjectConfig, envs: impl Iterator<Item = (String, String)
             "LEPTOS_OUTPUT_NAME" => conf.output_name = val,
             "LEPTOS_SITE_ROOT" => conf.site_root = Utf8PathBuf::from(val),
             "LEPTOS_SITE_PKG_DIR" => conf.site_pkg_dir = Utf8PathBuf::from(val),
+            "LEPTOS_BASE_HREF" => conf.base_href = Some(val),
+            "LEPTOS_CSP" => conf.csp = Some(val),
             "LEPTOS_STYLE_FILE" => conf.style_file = Some(Utf8PathBuf::from(val)),
             "LEPTOS_ASSETS_DIR" => conf.assets_dir = Some(Utf8PathBuf::from(val)),
             "LEPTOS_SITE_ADDR" => conf.site_addr = val.parse()?,
             "LEPTOS_RELOAD_PORT" => conf.reload_port = val.parse()?,
+            "LEPTOS_PROXY_PORT" => conf.proxy_port = val.parse()?,
+            "LEPTOS_RELOAD_WS_PROTOCOL" => conf.reload_ws_protocol = Some(val.parse()?),
+            "LEPTOS_RELOAD_EXTERNAL_URL" => conf.reload_external_url = Some(val),
+            "LEPTOS_ADVERTISE_HOST" => conf.advertise_host = Some(val),
             "LEPTOS_END2END_CMD" => conf.end2end_cmd = Some(val),
             "LEPTOS_END2END_DIR" => conf.end2end_dir = Some(Utf8PathBuf::from(val)),
             "LEPTOS_BROWSERQUERY" => conf.browserquery = val,
+            "LEPTOS_BROWSER_TARGETS" => conf.browser_targets = Some(val),
+            "LEPTOS_BUILD_MANIFEST_FILE" => conf.build_manifest_file = Some(Utf8PathBuf::from(val)),
             "LEPTOS_BIN_TARGET_TRIPLE" => conf.bin_target_triple = Some(val),
             "LEPTOS_BIN_TARGET_DIR" => conf.bin_target_dir = Some(val),
             "LEPTOS_BIN_CARGO_COMMAND" => conf.bin_cargo_command = Some(val),
+            "LEPTOS_FRONT_TARGET_DIR" => conf.front_target_dir = Some(val),
+            "LEPTOS_RUSTC_WRAPPER" => conf.rustc_wrapper = Some(val),
+            "LEPTOS_SERVER_RUNNER" => conf.server_runner = Some(val),
+            "LEPTOS_LIB_TOOLCHAIN" => conf.lib_toolchain = Some(val),
+            "LEPTOS_BIN_TOOLCHAIN" => conf.bin_toolchain = Some(val),
+            "LEPTOS_SASS_BIN" => conf.sass_bin = Some(Utf8PathBuf::from(val)),
+            "LEPTOS_TAILWIND_BIN" => conf.tailwind_bin = Some(Utf8PathBuf::from(val)),
+            "LEPTOS_WASM_OPT_BIN" => conf.wasm_opt_bin = Some(Utf8PathBuf::from(val)),
+            "LEPTOS_WASM_THREADS" => conf.wasm_threads = val.parse()?,
+            "LEPTOS_WASM_DEBUG" => conf.wasm_debug = val.parse()?,
+            "LEPTOS_BUNDLE_SNIPPETS" => conf.bundle_snippets = val.parse()?,
+            "LEPTOS_JS_MINIFY" => conf.js_minify = val.parse()?,
+            "LEPTOS_JS_SOURCE_MAP" => conf.js_source_map = val.parse()?,
+            "LEPTOS_LOCALES" => {
+                conf.locales = val.split(',').map(|l| l.trim().to_string()).collect()
+            }
             // put these here to suppress the warning, but there's no
             // good way at the moment to pull the ProjectConfig all the way to Exe
             exe::ENV_VAR_LEPTOS_TAILWIND_VERSION => {},
             exe::ENV_VAR_LEPTOS_SASS_VERSION => {},
             exe::ENV_VAR_LEPTOS_CARGO_GENERATE_VERSION => {},
             exe::ENV_VAR_LEPTOS_WASM_OPT_VERSION => {},
+            exe::ENV_VAR_LEPTOS_ESBUILD_VERSION => {},
             _ if key.starts_with("LEPTOS_") => {
                 log::warn!("Env {key} is not used by cargo-leptos")
             }
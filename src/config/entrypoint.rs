@@ -0,0 +1,59 @@
+use serde::Deserialize;
+
+use crate::ext::anyhow::{bail, Result};
+
+/// An extra wasm and js glue pair of the site, from `[[package.metadata.leptos.entrypoints]]`,
+/// e.g. the islands bundle of a group of pages.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EntrypointConfig {
+    /// the output-name of the wasm and js glue in the site pkg dir
+    pub name: String,
+    /// the lib package built, defaults to the lib-package of the project
+    pub lib_package: Option<String>,
+    /// the features of the lib build, defaults to the lib-features of the project
+    #[serde(default)]
+    pub lib_features: Vec<String>,
+}
+
+/// The entrypoints are written next to the front of the project, so their names must differ.
+pub fn check_entrypoints(entrypoints: &[EntrypointConfig], output_name: &str) -> Result<()> {
+    let mut names = vec![output_name];
+    for entry in entrypoints {
+        if entry.name.is_empty() || entry.name.contains(['/', '\\']) {
+            bail!(
+                "The entrypoint name `{}` must be a file name, without a dir",
+                entry.name
+            );
+        }
+        if names.contains(&entry.name.as_str()) {
+            bail!(
+                "The entrypoint name `{}` is used twice, the wasm and js files would overwrite each other",
+                entry.name
+            );
+        }
+        names.push(&entry.name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str) -> EntrypointConfig {
+        EntrypointConfig {
+            name: name.to_string(),
+            lib_package: None,
+            lib_features: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn names() {
+        assert!(check_entrypoints(&[entry("admin"), entry("shop")], "app").is_ok());
+        assert!(check_entrypoints(&[entry("app")], "app").is_err());
+        assert!(check_entrypoints(&[entry("admin"), entry("admin")], "app").is_err());
+        assert!(check_entrypoints(&[entry("islands/admin")], "app").is_err());
+    }
+}
@@ -0,0 +1,13 @@
+use serde::Deserialize;
+
+/// Shell commands run around the build, from `[package.metadata.leptos.hooks]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HooksConfig {
+    /// run before every build
+    pub pre_build: Option<String>,
+    /// run after every successful build
+    pub post_build: Option<String>,
+    /// run before the server is started
+    pub pre_serve: Option<String>,
+}
@@ -18,11 +18,31 @@ pub struct LibPackage {
     pub rel_dir: Utf8PathBuf,
     pub wasm_file: SourcedSiteFile,
     pub js_file: SiteFile,
+    /// the split-point functions moved to the lazily loaded module
+    pub wasm_split: Vec<String>,
+    /// the lazily loaded module of the split-point functions
+    pub split_file: SiteFile,
     pub features: Vec<String>,
     pub default_features: bool,
     pub output_name: String,
     pub src_paths: Vec<Utf8PathBuf>,
     pub profile: Profile,
+    /// the cargo target dir of the wasm build
+    pub target_dir: Utf8PathBuf,
+    /// build with atomics and shared memory
+    pub wasm_threads: bool,
+    /// skip wasm-opt and keep the function names, for readable panics
+    pub wasm_debug: bool,
+    /// bundle the js snippets with esbuild
+    pub bundle_snippets: bool,
+    /// minify the js with esbuild in release builds
+    pub js_minify: bool,
+    /// write the source maps of the minified js
+    pub js_source_map: bool,
+    /// the rustup toolchain, passed to cargo as `+<toolchain>`
+    pub toolchain: Option<String>,
+    /// the browserslist query deciding the wasm-bindgen features and the esbuild target
+    pub browser_targets: Option<String>,
 }
 
 impl LibPackage {
@@ -64,10 +84,14 @@ impl LibPackage {
             &config.lib_profile_dev,
         );
 
+        let target_dir = config
+            .front_target_dir
+            .as_ref()
+            .map(Utf8PathBuf::from)
+            .unwrap_or_else(|| metadata.rel_target_dir().join("front"));
+
         let wasm_file = {
-            let source = metadata
-                .rel_target_dir()
-                .join("front")
+            let source = target_dir
                 .join("wasm32-unknown-unknown")
                 .join(profile.to_string())
                 .join(name.replace('-', "_"))
@@ -86,6 +110,15 @@ impl LibPackage {
             SiteFile { dest, site }
         };
 
+        let split_file = {
+            let site = config
+                .site_pkg_dir
+                .join(format!("{output_name}.split"))
+                .with_extension("wasm");
+            let dest = config.site_root.join(&site);
+            SiteFile { dest, site }
+        };
+
         let mut src_deps = metadata.src_path_dependencies(&package.id);
         if rel_dir == "." {
             src_deps.push("src".into());
@@ -98,11 +131,21 @@ impl LibPackage {
             rel_dir,
             wasm_file,
             js_file,
+            wasm_split: config.wasm_split.clone(),
+            split_file,
             features,
             default_features: config.lib_default_features,
             output_name,
             src_paths: src_deps,
             profile,
+            target_dir,
+            wasm_threads: config.wasm_threads,
+            wasm_debug: cli.wasm_debug || config.wasm_debug,
+            bundle_snippets: config.bundle_snippets,
+            js_minify: config.js_minify,
+            js_source_map: config.js_source_map,
+            toolchain: config.lib_toolchain.clone(),
+            browser_targets: config.browser_targets.clone(),
         })
     }
 }
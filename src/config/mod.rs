@@ -1,38 +1,74 @@
 #[cfg(test)]
 mod tests;
 
+mod adaptive;
 mod assets;
 mod bin_package;
+mod cargo_config;
+mod changes;
+mod check_matrix;
 mod cli;
+mod csp;
 mod dotenvs;
 mod end2end;
+mod entrypoint;
+mod hooks;
 mod lib_package;
 mod profile;
 mod project;
+mod proxy;
+mod pwa;
+mod reload;
+mod server;
+mod sitemap;
 mod style;
 mod tailwind;
+mod tls;
 
 use std::{fmt::Debug, sync::Arc};
 
-pub use self::cli::{Cli, Commands, Log, Opts};
-use crate::ext::{
-    anyhow::{Context, Result},
-    MetadataExt,
+pub use self::cli::{Cli, ColorChoice, Commands, Log, LogFormat, Opts};
+use crate::{
+    ext::{
+        anyhow::{Context, Result},
+        GitState, LeptosError, MetadataExt,
+    },
+    logger::GRAY,
 };
+pub use adaptive::AdaptiveConfig;
 use anyhow::bail;
 use camino::{Utf8Path, Utf8PathBuf};
+pub use cargo_config::{target_dir_name, CargoConfig};
 use cargo_metadata::Metadata;
+pub use changes::ConfigChanges;
+pub use check_matrix::{CheckMatrixConfig, CheckTarget};
+pub use entrypoint::EntrypointConfig;
+pub use hooks::HooksConfig;
 pub use profile::Profile;
-pub use project::{Project, ProjectConfig};
+pub use project::{ConfigKey, Project, ProjectConfig};
+pub use proxy::{ProxyConfig, ProxyRule};
+pub use pwa::PwaConfig;
+pub use reload::{browser_addr, ReloadClient, WsProtocol};
+pub use server::{IntegrationKind, ServerConfig};
+pub use sitemap::SitemapConfig;
 pub use style::StyleConfig;
-pub use tailwind::TailwindConfig;
+pub use tailwind::{NodePackageManager, NodeTailwind, TailwindConfig};
+pub use tls::TlsConfig;
 
+#[derive(Clone)]
 pub struct Config {
     /// absolute path to the working dir
     pub working_dir: Utf8PathBuf,
     pub projects: Vec<Arc<Project>>,
     pub cli: Opts,
     pub watch: bool,
+    /// the current dir and the Cargo.toml the config was loaded from, to load it again
+    pub cwd: Utf8PathBuf,
+    pub manifest_path: Utf8PathBuf,
+    /// the wasm-bindgen version in the Cargo.lock of the workspace
+    pub wasm_bindgen_version: Option<String>,
+    /// the cargo target dir, relative to the working dir when it is under it
+    pub target_dir: Utf8PathBuf,
 }
 
 impl Debug for Config {
@@ -47,8 +83,28 @@ impl Debug for Config {
 
 impl Config {
     pub fn load(cli: Opts, cwd: &Utf8Path, manifest_path: &Utf8Path, watch: bool) -> Result<Self> {
+        Self::load_projects(cli, cwd, manifest_path, watch).categorize(LeptosError::Config {
+            manifest: manifest_path.to_owned(),
+        })
+    }
+
+    fn load_projects(
+        cli: Opts,
+        cwd: &Utf8Path,
+        manifest_path: &Utf8Path,
+        watch: bool,
+    ) -> Result<Self> {
         let metadata = Metadata::load_cleaned(manifest_path)?;
+        Self::from_metadata(cli, cwd, manifest_path, metadata, watch)
+    }
 
+    fn from_metadata(
+        cli: Opts,
+        cwd: &Utf8Path,
+        manifest_path: &Utf8Path,
+        metadata: Metadata,
+        watch: bool,
+    ) -> Result<Self> {
         let mut projects = Project::resolve(&cli, cwd, &metadata, watch).dot()?;
 
         if projects.is_empty() {
@@ -66,14 +122,46 @@ impl Config {
             }
         }
 
+        if cli.release {
+            warn_dirty_tree(&metadata.workspace_root);
+        }
+
+        let wasm_bindgen_version = metadata
+            .packages
+            .iter()
+            .find(|p| p.name == "wasm-bindgen")
+            .map(|p| p.version.to_string());
+
         Ok(Self {
+            wasm_bindgen_version,
+            target_dir: metadata.rel_target_dir(),
             working_dir: metadata.workspace_root,
             projects,
             cli,
             watch,
+            cwd: cwd.to_path_buf(),
+            manifest_path: manifest_path.to_path_buf(),
         })
     }
 
+    /// Loads the project again from the reloaded cargo metadata, for watch.
+    pub fn reload_project(&self, name: &str, metadata: Metadata) -> Result<Arc<Project>> {
+        let config = Self::from_metadata(
+            self.cli.clone(),
+            &self.cwd,
+            &self.manifest_path,
+            metadata,
+            self.watch,
+        )
+        .categorize(LeptosError::Config {
+            manifest: self.manifest_path.clone(),
+        })?;
+        match config.projects.into_iter().find(|proj| proj.name == name) {
+            Some(proj) => Ok(proj),
+            None => bail!("The project {name} is no longer in the Cargo.toml"),
+        }
+    }
+
     #[cfg(test)]
     pub fn test_load(cli: Opts, cwd: &str, manifest_path: &str, watch: bool) -> Self {
         use crate::ext::PathBufExt;
@@ -95,6 +183,30 @@ impl Config {
     }
 }
 
+/// The nearest `Cargo.toml` of the dir or its parents, as cargo finds it when run from a
+/// subdir of the project.
+pub fn locate_manifest(dir: &Utf8Path) -> Result<Utf8PathBuf> {
+    match dir
+        .ancestors()
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|file| file.is_file())
+    {
+        Some(manifest) => Ok(manifest),
+        None => bail!("Could not find Cargo.toml in {dir} or any parent directory"),
+    }
+}
+
+/// The release builds are deployed, and can't be traced back to a commit when built from
+/// uncommitted changes.
+fn warn_dirty_tree(dir: &Utf8Path) {
+    if let Some(git) = GitState::of(dir).filter(|git| git.dirty) {
+        log::warn!(
+            "Git building --release with uncommitted changes on top of {}",
+            GRAY.paint(git.short_sha())
+        );
+    }
+}
+
 fn names(projects: &[Arc<Project>]) -> String {
     projects
         .iter()
@@ -32,6 +32,15 @@ impl Profile {
         }
     }
 
+    /// The name of the profile in Cargo.toml
+    pub fn cargo_name(&self) -> &str {
+        match self {
+            Self::Debug => "dev",
+            Self::Release => "release",
+            Self::Named(name) => name,
+        }
+    }
+
     pub fn add_to_args(&self, args: &mut Vec<String>) {
         match self {
             Self::Debug => {}
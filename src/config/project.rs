@@ -1,8 +1,9 @@
 use crate::{
+    compile::STEP_NAMES,
     config::lib_package::LibPackage,
     ext::{
-        anyhow::{bail, ensure, Result},
-        PackageExt, PathBufExt, PathExt,
+        anyhow::{anyhow, bail, ensure, Result},
+        utf8_path, ErrorCode, GitState, MetadataExt, PackageExt, PathBufExt, PathExt,
     },
     logger::GRAY,
     service::site::Site,
@@ -10,15 +11,34 @@ use crate::{
 use camino::{Utf8Path, Utf8PathBuf};
 use cargo_metadata::{Metadata, Package};
 use serde::Deserialize;
-use std::{fmt::Debug, net::SocketAddr, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    net::SocketAddr,
+    sync::{Arc, OnceLock},
+};
+use tokio::sync::Semaphore;
 
 use super::{
+    adaptive::{default_true, AdaptiveConfig},
     assets::AssetsConfig,
     bin_package::BinPackage,
+    cargo_config::CargoConfig,
+    check_matrix::CheckMatrixConfig,
     cli::Opts,
+    csp::check_csp,
     dotenvs::{load_dotenvs, overlay_env},
     end2end::End2EndConfig,
+    entrypoint::{check_entrypoints, EntrypointConfig},
+    hooks::HooksConfig,
+    proxy::ProxyConfig,
+    pwa::PwaConfig,
+    reload::{ReloadClient, WsProtocol},
+    server::{IntegrationKind, ServerConfig},
+    sitemap::SitemapConfig,
     style::StyleConfig,
+    tailwind::NodePackageManager,
+    tls::TlsConfig,
 };
 
 pub struct Project {
@@ -31,10 +51,98 @@ pub struct Project {
     pub watch: bool,
     pub release: bool,
     pub hot_reload: bool,
+    /// keep the DWARF debug info in the wasm
+    pub debug_symbols: bool,
+    /// print the rolling phase timing averages in watch mode
+    pub stats: bool,
+    /// watch serves the site before the front is built, and builds it in the background
+    pub lazy_front: bool,
+    /// watch fails when its watchdog found leaked processes
+    pub watchdog_strict: bool,
+    /// the seconds after which a hanging tool is killed, by lowercase tool name or `default`
+    pub tool_timeouts: BTreeMap<String, u64>,
+    /// the hashes of the front and server artifacts are recorded after the builds, and a build
+    /// skips the stages with the recorded inputs
+    pub reuse_artifacts: bool,
+    /// with `--reproducible`, the timestamp of the outputs: the `SOURCE_DATE_EPOCH` or the
+    /// time of the commit
+    pub source_date_epoch: Option<u64>,
+    /// add the missing rustup targets of the builds
+    pub toolchain_install: bool,
     pub site: Arc<Site>,
     pub end2end: Option<End2EndConfig>,
     pub assets: Option<AssetsConfig>,
+    /// the conventional dir of the favicons and robots.txt copied to the site root
+    pub root_files_dir: Utf8PathBuf,
+    /// stubs are written for the missing robots.txt and favicon.ico
+    pub root_file_stubs: bool,
     pub js_dir: Utf8PathBuf,
+    /// where the json build manifest is written after every build
+    pub build_manifest_file: Utf8PathBuf,
+    /// the builds get the `LEPTOS_BUILD_*` provenance env vars
+    pub build_info: bool,
+    /// the build steps left out of the builds, by name
+    pub skip_build_steps: Vec<String>,
+    /// the static html files of the site get the preload tags of the wasm and js glue
+    pub preload_hints: bool,
+    /// the size settings missing from the release profile of the lib are written to the Cargo.toml
+    pub fix_profile: bool,
+    /// where the content hashes and outputs of cached build steps are kept
+    pub cache_dir: Utf8PathBuf,
+    /// limits the number of cargo builds running in parallel
+    pub cargo_jobs: Arc<Semaphore>,
+    /// set as RUSTC_WRAPPER for the cargo builds
+    pub rustc_wrapper: Option<String>,
+    /// the server binary is run under it, split into words like a shell does
+    pub server_runner: Option<String>,
+    /// the lints of the cargo warnings left out of the condensed output
+    pub suppress_warnings: Vec<String>,
+    /// the routes written to the site dir by `prerender`
+    pub prerender_routes: Vec<String>,
+    /// the settings of the `.cargo/config.toml` files of the workspace
+    pub cargo_config: Arc<CargoConfig>,
+    /// local sass, tailwind and wasm-opt binaries used instead of the PATH or downloaded ones
+    pub sass_bin: Option<Utf8PathBuf>,
+    pub tailwind_bin: Option<Utf8PathBuf>,
+    pub wasm_opt_bin: Option<Utf8PathBuf>,
+    pub hooks: HooksConfig,
+    pub server: ServerConfig,
+    /// the service worker, web app manifest and icons, when the PWA step is enabled
+    pub pwa: Option<PwaConfig>,
+    /// the sitemap and route manifest written after the build, when enabled
+    pub sitemap: Option<SitemapConfig>,
+    /// long-running processes started alongside watch, by name
+    pub sidecars: BTreeMap<String, String>,
+    /// the dev proxy of serve and watch, when it has rules
+    pub proxy: Option<ProxyConfig>,
+    /// when set, the watch rebuilds wait while the machine is busy
+    pub adaptive: Option<AdaptiveConfig>,
+    /// the feature sets and targets of `check-all`
+    pub check_matrix: CheckMatrixConfig,
+    /// the local certificate, when serving with https
+    pub tls: Option<TlsConfig>,
+    /// where the browser reaches the reload server
+    pub reload_client: ReloadClient,
+    /// the path to open in the browser once the server is up
+    pub open: Option<String>,
+    /// the public url prefix the site is served under, starts and ends with `/`
+    pub base_href: String,
+    /// the Content Security Policy, given to the server
+    pub csp: Option<String>,
+    /// where the `--timings` report is written, if requested
+    pub timings_dir: Option<Utf8PathBuf>,
+    /// where `cargo leptos ctl profile` writes the flamegraphs of the server
+    pub profiles_dir: Utf8PathBuf,
+    /// set for the per-locale variants of the project
+    pub locale: Option<String>,
+    /// the per-locale variants, built into `<site-root>/<locale>`
+    pub locales: Vec<Arc<Project>>,
+    /// the extra wasm entrypoints, whose fronts are built into the site pkg dir
+    pub entrypoints: Vec<Arc<Project>>,
+    /// the `--env` overrides applied to the config
+    pub env: Option<String>,
+    /// the `--watch-extra` paths, rebuilding the lib and bin on changes
+    pub watch_extra: Vec<Utf8PathBuf>,
 }
 
 impl Debug for Project {
@@ -61,41 +169,105 @@ impl Project {
         metadata: &Metadata,
         watch: bool,
     ) -> Result<Vec<Arc<Project>>> {
-        let projects = ProjectDefinition::parse(metadata)?;
+        let projects = ProjectDefinition::parse(metadata, cli.env.as_deref())?;
+        // cargo runs in the workspace root
+        let cargo_config = Arc::new(CargoConfig::load(&metadata.workspace_root));
 
         let mut resolved = Vec::new();
         for (project, mut config) in projects {
             if config.output_name.is_empty() {
                 config.output_name = project.name.to_string();
             }
+            if let Some(ip) = cli.bind {
+                config.site_addr.set_ip(ip);
+            }
+            // the server is written to the dir of the build.target of the cargo config
+            if config.bin_target_triple.is_none() {
+                config.bin_target_triple = cargo_config.build_target.clone();
+            }
 
-            let lib = LibPackage::resolve(cli, metadata, &project, &config)?;
-
-            let js_dir = config
-                .js_dir
-                .clone()
-                .unwrap_or_else(|| Utf8PathBuf::from("src"));
-
-            let proj = Project {
-                working_dir: metadata.workspace_root.clone(),
-                name: project.name.clone(),
-                lib,
-                bin: BinPackage::resolve(cli, metadata, &project, &config)?,
-                style: StyleConfig::new(&config)?,
-                watch,
-                release: cli.release,
-                hot_reload: cli.hot_reload,
-                site: Arc::new(Site::new(&config)),
-                end2end: End2EndConfig::resolve(&config),
-                assets: AssetsConfig::resolve(&config),
-                js_dir,
-            };
+            if config.output_name.contains("{git_short_sha}") {
+                let Some(git) = GitState::of(&metadata.workspace_root) else {
+                    bail!(
+                        "The output-name {} needs a git repository, none found in {}",
+                        config.output_name,
+                        metadata.workspace_root
+                    );
+                };
+                config.output_name = config
+                    .output_name
+                    .replace("{git_short_sha}", git.short_sha());
+            }
+
+            if config.site_pkg_dir.as_str().contains("{version}") {
+                let version = lib_version(metadata, &project.lib_package)?;
+                config.site_pkg_dir = config
+                    .site_pkg_dir
+                    .as_str()
+                    .replace("{version}", &version)
+                    .into();
+            }
+
+            let mut proj = Project::new(cli, metadata, &project, &config, &cargo_config, watch)?;
+            proj.warn_shared_target_dir();
+            proj.watch_extra = cli
+                .watch_extra
+                .iter()
+                .map(|path| watch_path(cwd, &metadata.workspace_root, path))
+                .collect();
+            if !cli.release || proj.lib.wasm_debug {
+                warn_missing_panic_hook(metadata, &proj.lib.name);
+            }
+            if let Some(csp) = &proj.csp {
+                check_csp(csp, watch, proj.site.reload, &proj.reload_client);
+            }
+            for locale in &config.locales {
+                let locale_config = config.for_locale(locale, &proj.lib.target_dir);
+                let mut locale_proj = Project::new(
+                    cli,
+                    metadata,
+                    &project,
+                    &locale_config,
+                    &cargo_config,
+                    watch,
+                )?;
+                locale_proj.cache_dir = proj.cache_dir.join(locale);
+                locale_proj.locale = Some(locale.clone());
+                proj.locales.push(Arc::new(locale_proj));
+            }
+            check_entrypoints(&config.entrypoints, &proj.lib.output_name)?;
+            for entry in &config.entrypoints {
+                let entry_config = config.for_entrypoint(entry, &proj.lib.target_dir);
+                let entry_project = ProjectDefinition {
+                    lib_package: entry
+                        .lib_package
+                        .clone()
+                        .unwrap_or_else(|| project.lib_package.clone()),
+                    ..project.clone()
+                };
+                let mut entry_proj = Project::new(
+                    cli,
+                    metadata,
+                    &entry_project,
+                    &entry_config,
+                    &cargo_config,
+                    watch,
+                )?;
+                entry_proj.cache_dir = proj.cache_dir.join("entrypoints").join(&entry.name);
+                // the cargo builds of the entrypoints count towards the --jobs of the project
+                entry_proj.cargo_jobs = proj.cargo_jobs.clone();
+                proj.entrypoints.push(Arc::new(entry_proj));
+            }
             resolved.push(Arc::new(proj));
         }
 
+        // a package in the cwd, or the one the cwd is in, e.g. its `src` dir
+        let in_cwd = |dir: &Utf8Path| {
+            dir.starts_with(cwd) || (cwd.starts_with(dir) && dir != metadata.workspace_root)
+        };
         let projects_in_cwd = resolved
             .iter()
-            .filter(|p| p.bin.abs_dir.starts_with(cwd) || p.lib.abs_dir.starts_with(cwd))
+            .filter(|p| in_cwd(&p.bin.abs_dir) || in_cwd(&p.lib.abs_dir))
             .collect::<Vec<_>>();
 
         if projects_in_cwd.len() == 1 {
@@ -105,6 +277,207 @@ impl Project {
         }
     }
 
+    fn new(
+        cli: &Opts,
+        metadata: &Metadata,
+        project: &ProjectDefinition,
+        config: &ProjectConfig,
+        cargo_config: &Arc<CargoConfig>,
+        watch: bool,
+    ) -> Result<Self> {
+        let lib = LibPackage::resolve(cli, metadata, project, config)?;
+
+        let js_dir = config
+            .js_dir
+            .clone()
+            .unwrap_or_else(|| Utf8PathBuf::from("src"));
+
+        let server_runner = cli.runner.clone().or(config.server_runner.clone());
+        if let Some(runner) = &server_runner {
+            if let Err(e) = shell_words::split(runner) {
+                bail!("The server-runner `{runner}` can't be split into words: {e}");
+            }
+        }
+
+        let build_manifest_file = config
+            .build_manifest_file
+            .clone()
+            .unwrap_or_else(|| config.site_root.join(".leptos.json"));
+
+        Ok(Project {
+            working_dir: metadata.workspace_root.clone(),
+            name: project.name.clone(),
+            lib,
+            bin: BinPackage::resolve(cli, metadata, project, config)?,
+            style: StyleConfig::new(config, cli.release)?,
+            watch,
+            release: cli.release,
+            hot_reload: cli.hot_reload,
+            debug_symbols: cli.debug_symbols,
+            stats: cli.stats,
+            lazy_front: cli.lazy_front,
+            watchdog_strict: cli.watchdog_strict,
+            tool_timeouts: config.tool_timeouts.clone(),
+            reuse_artifacts: (cli.reuse_artifacts || config.reuse_artifacts) && !cli.force,
+            source_date_epoch: cli
+                .reproducible
+                .then(|| source_date_epoch(&metadata.workspace_root)),
+            env: cli.env.clone(),
+            watch_extra: Vec::new(),
+            toolchain_install: !cli.no_toolchain_install,
+            site: Arc::new(Site::new(config)),
+            end2end: End2EndConfig::resolve(config),
+            assets: AssetsConfig::resolve(config),
+            root_files_dir: config.config_dir.join("assets"),
+            root_file_stubs: config.root_file_stubs,
+            js_dir,
+            build_manifest_file,
+            build_info: config.build_info,
+            skip_build_steps: config.skip_build_steps.clone(),
+            preload_hints: config.preload_hints,
+            fix_profile: cli.fix_profile,
+            cache_dir: metadata
+                .rel_target_dir()
+                .join(".leptos-cache")
+                .join(&project.name),
+            cargo_jobs: Arc::new(Semaphore::new(cli.jobs.unwrap_or(2).into())),
+            rustc_wrapper: config.rustc_wrapper.clone(),
+            server_runner,
+            suppress_warnings: config.suppress_warnings.clone(),
+            prerender_routes: config.prerender_routes.clone(),
+            cargo_config: cargo_config.clone(),
+            sass_bin: config.sass_bin.as_ref().map(|f| config.config_dir.join(f)),
+            tailwind_bin: config
+                .tailwind_bin
+                .as_ref()
+                .map(|f| config.config_dir.join(f)),
+            wasm_opt_bin: config
+                .wasm_opt_bin
+                .as_ref()
+                .map(|f| config.config_dir.join(f)),
+            hooks: config.hooks.clone(),
+            server: config.server.clone(),
+            pwa: config
+                .pwa
+                .as_ref()
+                .map(|pwa| pwa.resolve(&config.config_dir)),
+            sitemap: config.sitemap.clone(),
+            sidecars: config.sidecars.clone(),
+            proxy: ProxyConfig::resolve(config)?,
+            adaptive: config.adaptive.clone(),
+            check_matrix: config.check_matrix.clone(),
+            tls: TlsConfig::resolve(cli)?,
+            reload_client: ReloadClient::resolve(
+                config,
+                cli.https,
+                cli.advertise_host.clone().or(config.advertise_host.clone()),
+            )?,
+            open: cli.open.clone(),
+            base_href: normalize_base_href(config.base_href.as_deref()),
+            csp: config.csp.clone(),
+            timings_dir: cli
+                .timings
+                .then(|| metadata.rel_target_dir().join("leptos-timings")),
+            profiles_dir: metadata.rel_target_dir().join("leptos-profiles"),
+            locale: None,
+            locales: Vec::new(),
+            entrypoints: Vec::new(),
+        })
+    }
+
+    /// The front and server builds use different flags and features, so when they share
+    /// a target dir they keep invalidating each other's incremental cache.
+    fn warn_shared_target_dir(&self) {
+        let server_dir = &self.bin.target_dir;
+        if *server_dir == self.lib.target_dir {
+            log::warn!(
+                "Cargo the front and server builds of {} share the target dir {}. Set a separate `front-target-dir` (or `bin-target-dir`) to keep their caches apart.",
+                self.name,
+                GRAY.paint(server_dir.as_str())
+            );
+        }
+    }
+
+    /// Reads the style vars of the project from the Cargo.toml files again, for watch
+    /// to pick up their changes.
+    pub fn read_style_vars(&self) -> Result<BTreeMap<String, String>> {
+        let metadata = Metadata::load_cleaned(&self.working_dir.join("Cargo.toml"))?;
+        ProjectDefinition::parse(&metadata, self.env.as_deref())?
+            .into_iter()
+            .find(|(def, _)| def.name == self.name)
+            .map(|(_, config)| config.style_vars)
+            .ok_or_else(|| anyhow!("Could not find the project {} in the Cargo.toml", self.name))
+    }
+
+    /// Reads the leptos metadata of the project from the Cargo.toml files again, as written,
+    /// for watch to find the keys that changed.
+    pub fn read_metadata(&self) -> Result<serde_json::Value> {
+        let metadata = Metadata::load_cleaned(&self.working_dir.join("Cargo.toml"))?;
+        self.leptos_metadata_in(&metadata)
+    }
+
+    /// The keys of the leptos config of the project, with the table of the env overlaid.
+    /// Runs cargo metadata, which blocks.
+    pub fn effective_config(&self, env: Option<&str>) -> Result<Vec<ConfigKey>> {
+        let metadata = self.read_metadata()?;
+        effective_keys(&self.working_dir, &metadata, env)
+    }
+
+    /// The leptos metadata of the project in the cargo metadata, as written.
+    pub fn leptos_metadata_in(&self, metadata: &Metadata) -> Result<serde_json::Value> {
+        let in_workspace = leptos_metadata(&metadata.workspace_metadata)
+            .and_then(|md| md.as_array())
+            .and_then(|sections| {
+                sections.iter().find(|section| {
+                    section.get("name").and_then(|n| n.as_str()) == Some(self.name.as_str())
+                })
+            });
+        let in_package = || {
+            metadata
+                .workspace_packages()
+                .into_iter()
+                .find(|package| package.name == self.name)
+                .and_then(|package| leptos_metadata(&package.metadata))
+        };
+        in_workspace
+            .or_else(in_package)
+            .cloned()
+            .ok_or_else(|| anyhow!("Could not find the project {} in the Cargo.toml", self.name))
+    }
+
+    pub fn scheme(&self) -> &'static str {
+        if self.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// The url the site is served at
+    pub fn site_url(&self) -> String {
+        format!("{}://{}", self.scheme(), self.site.addr)
+    }
+
+    /// Whether the css source map is written, in the dev builds only.
+    pub fn style_source_map(&self) -> bool {
+        self.style.source_map && !self.release
+    }
+
+    /// env vars to use when running cargo
+    pub fn to_cargo_envs(&self) -> Vec<(&'static str, String)> {
+        let mut vec = self.to_envs();
+        if let Some(wrapper) = &self.rustc_wrapper {
+            vec.push(("RUSTC_WRAPPER", wrapper.to_string()));
+        }
+        if let Some(locale) = &self.locale {
+            vec.push(("LEPTOS_LOCALE", locale.to_string()));
+        }
+        if let Some(epoch) = self.source_date_epoch {
+            vec.push(("SOURCE_DATE_EPOCH", epoch.to_string()));
+        }
+        vec
+    }
+
     /// env vars to use when running external command
     pub fn to_envs(&self) -> Vec<(&'static str, String)> {
         let mut vec = vec![
@@ -119,11 +492,35 @@ impl Project {
         if self.watch {
             vec.push(("LEPTOS_WATCH", "ON".to_string()))
         }
+        if let Some(csp) = &self.csp {
+            vec.push(("LEPTOS_CSP", csp.to_string()));
+        }
+        if self.base_href != "/" {
+            vec.push(("LEPTOS_BASE_HREF", self.base_href.to_string()));
+        }
+        if !self.locales.is_empty() {
+            let locales = self.locales.iter().filter_map(|l| l.locale.as_deref());
+            vec.push(("LEPTOS_LOCALES", locales.collect::<Vec<_>>().join(",")));
+        }
+        if let Some(tls) = &self.tls {
+            vec.push(("LEPTOS_TLS_CERT_FILE", tls.cert_file.to_string()));
+            vec.push(("LEPTOS_TLS_KEY_FILE", tls.key_file.to_string()));
+        }
+        let reload = &self.reload_client;
+        if reload.ws_protocol == WsProtocol::Wss {
+            vec.push(("LEPTOS_RELOAD_WS_PROTOCOL", "wss".to_string()));
+        }
+        if let Some(port) = reload.external_port() {
+            vec.push(("LEPTOS_RELOAD_EXTERNAL_PORT", port.to_string()));
+        }
+        if let Some(url) = &reload.external_url {
+            vec.push(("LEPTOS_RELOAD_EXTERNAL_URL", url.to_string()));
+        }
         vec
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct ProjectConfig {
     #[serde(default)]
@@ -134,21 +531,58 @@ pub struct ProjectConfig {
     pub site_root: Utf8PathBuf,
     #[serde(default = "default_pkg_dir")]
     pub site_pkg_dir: Utf8PathBuf,
+    /// the public url prefix the site is served under, e.g. `/app/`
+    pub base_href: Option<String>,
+    /// the Content Security Policy the server sets, checked against what the site needs
+    pub csp: Option<String>,
     pub style_file: Option<Utf8PathBuf>,
+    /// the style file of the dev builds, instead of the style-file
+    pub style_dev_file: Option<Utf8PathBuf>,
+    /// the style file of the release builds, instead of the style-file
+    pub style_release_file: Option<Utf8PathBuf>,
     pub tailwind_input_file: Option<Utf8PathBuf>,
     pub tailwind_config_file: Option<Utf8PathBuf>,
+    /// run the tailwindcss (or postcss) of the node_modules with this package manager
+    pub style_node_package_manager: Option<NodePackageManager>,
+    /// the dir of the package.json, relative to the Cargo.toml dir. Defaults to it
+    pub style_node_dir: Option<Utf8PathBuf>,
     /// assets dir. content will be copied to the target/site dir
     pub assets_dir: Option<Utf8PathBuf>,
+    /// write a robots.txt and favicon.ico to the site root when the project has none
+    #[serde(default)]
+    pub root_file_stubs: bool,
     /// js dir. changes triggers rebuilds.
     pub js_dir: Option<Utf8PathBuf>,
     #[serde(default = "default_reload_port")]
     pub reload_port: u16,
+    /// the protocol of the reload websocket, defaults to the one of the reload-external-url
+    pub reload_ws_protocol: Option<WsProtocol>,
+    /// the url the browser reaches the reload server at, e.g. behind a reverse proxy
+    pub reload_external_url: Option<String>,
+    /// the origins (or hosts) of the pages allowed to connect to the reload websocket, besides
+    /// the local and advertised ones
+    #[serde(default)]
+    pub reload_allowed_origins: Vec<String>,
+    /// the host the browser reaches the dev servers at, e.g. the name of a remote VM
+    pub advertise_host: Option<String>,
     /// command for launching end-2-end integration tests
     pub end2end_cmd: Option<String>,
     /// the dir used when launching end-2-end integration tests
     pub end2end_dir: Option<Utf8PathBuf>,
+    /// the file the json build manifest is written to. Defaults to <site-root>/.leptos.json
+    pub build_manifest_file: Option<Utf8PathBuf>,
     #[serde(default = "default_browserquery")]
     pub browserquery: String,
+    /// the seconds after which a hanging tool (e.g. `wasm-opt`) or `hook` is killed, by
+    /// lowercase tool name or `default`, 0 for no limit
+    #[serde(default)]
+    pub tool_timeouts: BTreeMap<String, u64>,
+    /// record the artifacts of the builds, and reuse them in the next build when unchanged
+    #[serde(default)]
+    pub reuse_artifacts: bool,
+    /// the browserslist query deciding the css targets (instead of the browserquery), the
+    /// wasm-bindgen features and the esbuild target
+    pub browser_targets: Option<String>,
     /// the bin target to use for building the server
     #[serde(default)]
     pub bin_target: String,
@@ -158,6 +592,94 @@ pub struct ProjectConfig {
     pub bin_target_dir: Option<String>,
     /// the command to run instead of "cargo" when building the server
     pub bin_cargo_command: Option<String>,
+    /// the directory to put the generated front (wasm) artifacts. Defaults to target/front
+    pub front_target_dir: Option<String>,
+    /// the compiler wrapper, e.g. sccache, set as RUSTC_WRAPPER for the cargo builds
+    pub rustc_wrapper: Option<String>,
+    /// the command the server binary is run under, e.g. `gdbserver :1234`
+    pub server_runner: Option<String>,
+    /// the lints (or their start, e.g. `unused`) of the cargo warnings not logged
+    #[serde(default)]
+    pub suppress_warnings: Vec<String>,
+    /// the routes rendered to static html files by `prerender`
+    #[serde(default)]
+    pub prerender_routes: Vec<String>,
+    /// the rustup toolchain of the front build, e.g. `nightly-2024-06-01`, run as `cargo +<toolchain>`
+    pub lib_toolchain: Option<String>,
+    /// the rustup toolchain of the server build
+    pub bin_toolchain: Option<String>,
+    /// a local sass binary, used instead of looking it up in the PATH or downloading it
+    pub sass_bin: Option<Utf8PathBuf>,
+    /// a local tailwindcss binary
+    pub tailwind_bin: Option<Utf8PathBuf>,
+    /// a local wasm-opt binary
+    pub wasm_opt_bin: Option<Utf8PathBuf>,
+    /// shell commands run before and after the build, and before serving
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    /// how the server binary is launched and checked for readiness
+    #[serde(default)]
+    pub server: ServerConfig,
+    /// generate a service worker precaching the build output
+    pub pwa: Option<PwaConfig>,
+    /// write the sitemap and route manifest of the routes of the server
+    pub sitemap: Option<SitemapConfig>,
+    /// long-running commands started alongside watch, by name
+    #[serde(default)]
+    pub sidecars: BTreeMap<String, String>,
+    /// the backends of the dev proxy of serve and watch, by path prefix
+    #[serde(default)]
+    pub proxy: BTreeMap<String, String>,
+    /// the port of the dev proxy, on the ip of the site-addr
+    #[serde(default = "default_proxy_port")]
+    pub proxy_port: u16,
+    /// delay the watch rebuilds while the machine is busy
+    pub adaptive: Option<AdaptiveConfig>,
+    /// the feature sets and targets checked by `check-all`
+    #[serde(default)]
+    pub check_matrix: CheckMatrixConfig,
+    /// build the wasm with atomics and shared memory, for multithreading
+    #[serde(default)]
+    pub wasm_threads: bool,
+    /// skip wasm-opt and keep the function names in the wasm
+    #[serde(default)]
+    pub wasm_debug: bool,
+    /// the functions moved to a lazily loaded wasm module in the release builds
+    #[serde(default)]
+    pub wasm_split: Vec<String>,
+    /// bundle the imports of the wasm-bindgen js snippets with esbuild
+    #[serde(default)]
+    pub bundle_snippets: bool,
+    /// minify the wasm-bindgen js glue and snippets with esbuild in release builds
+    #[serde(default)]
+    pub js_minify: bool,
+    /// write the source maps of the minified js
+    #[serde(default)]
+    pub js_source_map: bool,
+    /// the dirs the sass `@use` and `@import` rules load from, relative to the Cargo.toml dir
+    #[serde(default)]
+    pub style_load_paths: Vec<Utf8PathBuf>,
+    /// css custom properties (and sass variables) defined for the style, by name
+    #[serde(default)]
+    pub style_vars: BTreeMap<String, String>,
+    /// write the css source map in the dev builds
+    #[serde(default = "default_true")]
+    pub style_source_map: bool,
+    /// give the git sha, timestamp, cargo-leptos version and profile to the builds as env vars
+    #[serde(default)]
+    pub build_info: bool,
+    /// the build steps to leave out: front, style, assets, server or entrypoints
+    #[serde(default)]
+    pub skip_build_steps: Vec<String>,
+    /// add the preload tags of the wasm and js glue to the static html files of the site
+    #[serde(default = "default_true")]
+    pub preload_hints: bool,
+    /// build the front, style and assets once per locale, into `<site-root>/<locale>`
+    #[serde(default)]
+    pub locales: Vec<String>,
+    /// the extra wasm and js glue pairs of the site
+    #[serde(default)]
+    pub entrypoints: Vec<EntrypointConfig>,
     #[serde(default)]
     pub features: Vec<String>,
     #[serde(default)]
@@ -180,8 +702,17 @@ pub struct ProjectConfig {
 }
 
 impl ProjectConfig {
-    fn parse(dir: &Utf8Path, metadata: &serde_json::Value) -> Result<Self> {
-        let mut conf: ProjectConfig = serde_json::from_value(metadata.clone())?;
+    fn parse(dir: &Utf8Path, metadata: &serde_json::Value, env: Option<&str>) -> Result<Self> {
+        let metadata = match env {
+            Some(env) => env_metadata(dir, metadata, env)?,
+            None => metadata.clone(),
+        };
+        let mut unknown = Vec::new();
+        let mut conf: ProjectConfig =
+            serde_ignored::deserialize(metadata, |path| unknown.push(path.to_string()))?;
+        for key in unknown.iter().filter(|k| !OTHER_KEYS.contains(&k.as_str())) {
+            warn_unknown_key(dir, key);
+        }
         conf.config_dir = dir.to_path_buf();
         let dotenvs = load_dotenvs(dir)?;
         overlay_env(&mut conf, dotenvs)?;
@@ -197,11 +728,204 @@ impl ProjectConfig {
                 conf.reload_port
             );
         }
+        if conf.server.integration == IntegrationKind::Custom && conf.server.command.is_none() {
+            bail!("The custom server integration needs a `command` in [package.metadata.leptos.server]");
+        }
+        if let Some(step) = conf
+            .skip_build_steps
+            .iter()
+            .find(|step| !STEP_NAMES.contains(&step.as_str()))
+        {
+            bail!(
+                "Unknown build step `{step}` in skip-build-steps, the steps are: {}",
+                STEP_NAMES.join(", ")
+            );
+        }
         Ok(conf)
     }
+
+    /// The config of a locale variant: the site is built into `<site-root>/<locale>`
+    /// and the wasm into its own target dir, so that the locales don't invalidate each other.
+    fn for_locale(&self, locale: &str, front_target_dir: &Utf8Path) -> Self {
+        let mut conf = self.clone();
+        conf.site_root = self.site_root.join(locale);
+        conf.front_target_dir = Some(front_target_dir.join(locale).to_string());
+        conf
+    }
+
+    /// The config of an entrypoint: its wasm is built into its own target dir, so that the
+    /// fronts built with other features don't overwrite each other.
+    fn for_entrypoint(&self, entry: &EntrypointConfig, front_target_dir: &Utf8Path) -> Self {
+        let mut conf = self.clone();
+        conf.output_name = entry.name.clone();
+        if !entry.lib_features.is_empty() {
+            conf.lib_features = entry.lib_features.clone();
+        }
+        conf.front_target_dir = Some(
+            front_target_dir
+                .join("entrypoints")
+                .join(&entry.name)
+                .to_string(),
+        );
+        conf.locales.clear();
+        conf.entrypoints.clear();
+        conf
+    }
 }
 
-#[derive(Debug, Deserialize)]
+/// The metadata with the `env.<name>` table of the env overlaid, the tables being merged
+/// key by key (e.g. a single hook can be overridden). The .env file, the env vars and the
+/// command line still take precedence.
+pub(super) fn env_metadata(
+    dir: &Utf8Path,
+    metadata: &serde_json::Value,
+    env: &str,
+) -> Result<serde_json::Value> {
+    let cargo_toml = dir.join("Cargo.toml");
+    // a string `env` is the DEV or PROD of leptos_config
+    let envs = metadata
+        .get("env")
+        .and_then(|envs| envs.as_object())
+        .map(|envs| {
+            envs.iter()
+                .filter(|(_, table)| table.is_object())
+                .map(|(name, table)| (name.as_str(), table))
+                .collect::<BTreeMap<_, _>>()
+        })
+        .unwrap_or_default();
+    let Some(overrides) = envs.get(env) else {
+        let names = envs.keys().copied().collect::<Vec<_>>();
+        bail!(
+            "Config env `{env}` is not defined in {cargo_toml}, add a [package.metadata.leptos.env.{env}] table. The defined envs are: [{}]",
+            names.join(", ")
+        );
+    };
+    let keys = overrides
+        .as_object()
+        .map(|o| o.keys().map(|k| k.as_str()).collect::<Vec<_>>())
+        .unwrap_or_default();
+    log::info!(
+        "Config env {env} overrides {} {}",
+        keys.join(", "),
+        GRAY.paint(cargo_toml.as_str())
+    );
+    let mut metadata = metadata.clone();
+    merge_json(&mut metadata, overrides);
+    Ok(metadata)
+}
+
+/// A key of the leptos config, dotted for the keys of the tables (e.g. `hooks.pre-build`),
+/// with the `env.<name>` table it is from when overridden.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigKey {
+    pub key: String,
+    pub value: serde_json::Value,
+    pub env: Option<String>,
+}
+
+/// The keys of the metadata with the table of the env overlaid, without the env tables.
+pub(super) fn effective_keys(
+    dir: &Utf8Path,
+    metadata: &serde_json::Value,
+    env: Option<&str>,
+) -> Result<Vec<ConfigKey>> {
+    let merged = match env {
+        Some(env) => env_metadata(dir, metadata, env)?,
+        None => metadata.clone(),
+    };
+    let overrides = env.and_then(|env| metadata.get("env")?.get(env));
+    let mut keys = Vec::new();
+    flatten_keys("", &merged, overrides, env, &mut keys);
+    Ok(keys)
+}
+
+fn flatten_keys(
+    prefix: &str,
+    value: &serde_json::Value,
+    overrides: Option<&serde_json::Value>,
+    env: Option<&str>,
+    keys: &mut Vec<ConfigKey>,
+) {
+    let serde_json::Value::Object(table) = value else {
+        keys.push(ConfigKey {
+            key: prefix.to_string(),
+            value: value.clone(),
+            env: overrides.and(env).map(|env| format!("env.{env}")),
+        });
+        return;
+    };
+    for (name, value) in table {
+        // a string `env` is the DEV or PROD of leptos_config
+        if prefix.is_empty() && name == "env" && value.is_object() {
+            continue;
+        }
+        let key = match prefix {
+            "" => name.clone(),
+            prefix => format!("{prefix}.{name}"),
+        };
+        flatten_keys(&key, value, overrides.and_then(|o| o.get(name)), env, keys);
+    }
+}
+
+fn merge_json(base: &mut serde_json::Value, overrides: &serde_json::Value) {
+    match (base, overrides) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                merge_json(base.entry(key).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
+
+/// The `--watch-extra` path as the watcher reports it: relative to the workspace root when
+/// inside it, otherwise absolute. The path is relative to the dir cargo-leptos was run from.
+fn watch_path(cwd: &Utf8Path, workspace_root: &Utf8Path, path: &Utf8Path) -> Utf8PathBuf {
+    let path = cwd.join(path);
+    let path = dunce::canonicalize(&path)
+        .ok()
+        .and_then(|p| utf8_path(p).ok())
+        .unwrap_or(path);
+    path.unbase(workspace_root).unwrap_or(path)
+}
+
+/// `app`, `/app` and `/app/` all become `/app/`
+fn normalize_base_href(base_href: Option<&str>) -> String {
+    let trimmed = base_href.unwrap_or_default().trim_matches('/');
+    if trimmed.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{trimmed}/")
+    }
+}
+
+/// The version of the lib package, for the `{version}` placeholder of `site-pkg-dir`.
+fn lib_version(metadata: &Metadata, lib_package: &str) -> Result<String> {
+    metadata
+        .workspace_packages()
+        .iter()
+        .find(|p| p.name == lib_package)
+        .map(|p| p.version.to_string())
+        .ok_or_else(|| anyhow!(r#"Could not find the project lib-package "{lib_package}""#))
+}
+
+/// Without `console_error_panic_hook` a panic in the browser only shows "unreachable executed".
+/// Warned once, not again on each config reload of watch.
+fn warn_missing_panic_hook(metadata: &Metadata, name: &str) {
+    static WARNED: OnceLock<()> = OnceLock::new();
+    let missing = !metadata
+        .packages
+        .iter()
+        .any(|p| p.name == "console_error_panic_hook");
+    if missing && WARNED.set(()).is_ok() {
+        log::warn!(
+            "Front {name} does not depend on console_error_panic_hook. Add it and call {} in the hydrate function to see panic messages in the browser console.",
+            GRAY.paint("console_error_panic_hook::set_once()")
+        );
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct ProjectDefinition {
     name: String,
@@ -212,11 +936,12 @@ impl ProjectDefinition {
     fn from_workspace(
         metadata: &serde_json::Value,
         dir: &Utf8Path,
+        env: Option<&str>,
     ) -> Result<Vec<(Self, ProjectConfig)>> {
         let mut found = Vec::new();
         if let Some(arr) = metadata.as_array() {
             for section in arr {
-                let conf = ProjectConfig::parse(dir, section)?;
+                let conf = ProjectConfig::parse(dir, section, env)?;
                 let def: Self = serde_json::from_value(section.clone())?;
                 found.push((def, conf))
             }
@@ -228,8 +953,9 @@ impl ProjectDefinition {
         package: &Package,
         metadata: &serde_json::Value,
         dir: &Utf8Path,
+        env: Option<&str>,
     ) -> Result<(Self, ProjectConfig)> {
-        let conf = ProjectConfig::parse(dir, metadata)?;
+        let conf = ProjectConfig::parse(dir, metadata, env)?;
 
         ensure!(
             package.cdylib_target().is_some(),
@@ -252,11 +978,11 @@ impl ProjectDefinition {
         ))
     }
 
-    fn parse(metadata: &Metadata) -> Result<Vec<(Self, ProjectConfig)>> {
+    fn parse(metadata: &Metadata, env: Option<&str>) -> Result<Vec<(Self, ProjectConfig)>> {
         let workspace_dir = &metadata.workspace_root;
         let mut found: Vec<(Self, ProjectConfig)> =
             if let Some(md) = leptos_metadata(&metadata.workspace_metadata) {
-                Self::from_workspace(md, &Utf8PathBuf::default())?
+                Self::from_workspace(md, &Utf8PathBuf::default(), env)?
             } else {
                 Default::default()
             };
@@ -265,13 +991,40 @@ impl ProjectDefinition {
             let dir = package.manifest_path.unbase(workspace_dir)?.without_last();
 
             if let Some(metadata) = leptos_metadata(&package.metadata) {
-                found.push(Self::from_project(package, metadata, &dir)?);
+                found.push(Self::from_project(package, metadata, &dir, env)?);
             }
         }
         Ok(found)
     }
 }
 
+/// The keys of the leptos metadata that are not cargo-leptos config: the project
+/// definition of a workspace, and the ones read by `leptos_config` at runtime.
+const OTHER_KEYS: &[&str] = &[
+    "name",
+    "bin-package",
+    "lib-package",
+    "env",
+    "reload-ws-protocol",
+    "reload-external-port",
+    "not-found-path",
+    "hash-files",
+    "hash-file-name",
+];
+
+fn warn_unknown_key(dir: &Utf8Path, key: &str) {
+    let suggestion = if key.contains('_') {
+        format!(" (did you mean `{}`?)", key.replace('_', "-"))
+    } else {
+        String::new()
+    };
+    log::warn!(
+        "Config unknown key `{key}`{suggestion} in {} {}",
+        GRAY.paint(dir.join("Cargo.toml").as_str()),
+        ErrorCode::ConfigKey.hint()
+    );
+}
+
 fn leptos_metadata(metadata: &serde_json::Value) -> Option<&serde_json::Value> {
     metadata.as_object().and_then(|o| o.get("leptos"))
 }
@@ -292,6 +1045,22 @@ fn default_reload_port() -> u16 {
     3001
 }
 
+fn default_proxy_port() -> u16 {
+    3002
+}
+
+/// The `SOURCE_DATE_EPOCH` when set, else the time of the last commit, else the unix epoch.
+fn source_date_epoch(dir: &Utf8Path) -> u64 {
+    let env = std::env::var("SOURCE_DATE_EPOCH").ok();
+    if let Some(secs) = env.and_then(|secs| secs.trim().parse().ok()) {
+        return secs;
+    }
+    GitState::commit_time(dir).unwrap_or_else(|| {
+        log::warn!("Reproducible found no SOURCE_DATE_EPOCH nor commit, using the unix epoch");
+        0
+    })
+}
+
 fn default_browserquery() -> String {
     "defaults".to_string()
 }
@@ -0,0 +1,146 @@
+use std::net::SocketAddr;
+
+use reqwest::Url;
+
+use super::ProjectConfig;
+use crate::ext::anyhow::{bail, Context, Result};
+
+/// The dev proxy of `serve` and `watch`, from `[package.metadata.leptos.proxy]`: the requests
+/// whose path starts with the prefix of a rule are forwarded to its backend, WebSockets
+/// included, and the others to the site server. The pages of the site are opened at the
+/// proxy, so that they reach the backends on the same origin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    /// the `proxy-port`, on the ip of the site-addr
+    pub addr: SocketAddr,
+    /// the longest prefixes first
+    pub rules: Vec<ProxyRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyRule {
+    /// the path prefix, starting with a `/`
+    pub prefix: String,
+    /// the http origin of the backend, with the path the request paths are appended to
+    pub backend: Url,
+}
+
+impl ProxyConfig {
+    /// None when there are no proxy rules.
+    pub fn resolve(conf: &ProjectConfig) -> Result<Option<Self>> {
+        if conf.proxy.is_empty() {
+            return Ok(None);
+        }
+        let mut rules = conf
+            .proxy
+            .iter()
+            .map(|(prefix, backend)| ProxyRule::new(prefix, backend))
+            .collect::<Result<Vec<_>>>()?;
+        rules.sort_by(|a, b| b.prefix.len().cmp(&a.prefix.len()));
+        let addr = SocketAddr::new(conf.site_addr.ip(), conf.proxy_port);
+        if addr == conf.site_addr || conf.proxy_port == conf.reload_port {
+            bail!(
+                "The proxy-port {} is the port of the site or of the reload server",
+                conf.proxy_port
+            );
+        }
+        Ok(Some(Self { addr, rules }))
+    }
+
+    /// The rule of the longest prefix of the path.
+    pub fn route(&self, path: &str) -> Option<&ProxyRule> {
+        self.rules.iter().find(|rule| rule.matches(path))
+    }
+}
+
+impl ProxyRule {
+    fn new(prefix: &str, backend: &str) -> Result<Self> {
+        if !prefix.starts_with('/') {
+            bail!("The proxy prefix {prefix:?} must start with a /");
+        }
+        let mut url = Url::parse(backend).context(format!("The proxy backend of {prefix}"))?;
+        // the websockets are upgraded from http requests
+        let scheme = match url.scheme() {
+            "http" | "ws" => "http",
+            scheme => {
+                bail!("The proxy backend {backend} of {prefix} must be http or ws, not {scheme}")
+            }
+        };
+        _ = url.set_scheme(scheme);
+        if url.host_str().is_none() {
+            bail!("The proxy backend {backend} of {prefix} has no host");
+        }
+        Ok(Self {
+            prefix: prefix.trim_end_matches('/').to_string(),
+            backend: url,
+        })
+    }
+
+    /// The prefix matches whole path segments: `/api` matches `/api` and `/api/users`, and
+    /// not `/apiary`.
+    pub fn matches(&self, path: &str) -> bool {
+        match path.strip_prefix(&self.prefix) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/') || self.prefix.is_empty(),
+            None => false,
+        }
+    }
+
+    /// The url of the request on the backend: the path is kept, after the path of the backend.
+    pub fn url(&self, path_and_query: &str) -> String {
+        let base = self.backend.as_str().trim_end_matches('/');
+        format!("{base}{path_and_query}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxy(rules: &[(&str, &str)]) -> ProxyConfig {
+        let mut rules = rules
+            .iter()
+            .map(|(prefix, backend)| ProxyRule::new(prefix, backend).unwrap())
+            .collect::<Vec<_>>();
+        rules.sort_by(|a, b| b.prefix.len().cmp(&a.prefix.len()));
+        ProxyConfig {
+            addr: "127.0.0.1:3002".parse().unwrap(),
+            rules,
+        }
+    }
+
+    #[test]
+    fn route() {
+        let proxy = proxy(&[
+            ("/api", "http://localhost:8000"),
+            ("/api/ws/", "ws://localhost:8001"),
+            ("/auth", "http://127.0.0.1:9000/v1/"),
+        ]);
+        let backend = |path| proxy.route(path).map(|rule| rule.url(path));
+
+        assert_eq!(
+            backend("/api/users?page=2").as_deref(),
+            Some("http://localhost:8000/api/users?page=2")
+        );
+        assert_eq!(
+            backend("/api").as_deref(),
+            Some("http://localhost:8000/api")
+        );
+        assert_eq!(
+            backend("/api/ws/chat").as_deref(),
+            Some("http://localhost:8001/api/ws/chat")
+        );
+        assert_eq!(
+            backend("/auth/login").as_deref(),
+            Some("http://127.0.0.1:9000/v1/auth/login")
+        );
+        assert_eq!(backend("/apiary"), None);
+        assert_eq!(backend("/"), None);
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert!(ProxyRule::new("api", "http://localhost:8000").is_err());
+        assert!(ProxyRule::new("/api", "https://localhost:8000").is_err());
+        assert!(ProxyRule::new("/api", "localhost:8000").is_err());
+    }
+}
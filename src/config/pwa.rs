@@ -0,0 +1,25 @@
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Deserialize;
+
+/// The opt-in PWA step, from `[package.metadata.leptos.pwa]`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PwaConfig {
+    /// a service worker to inject the precache manifest into, instead of the generated one
+    pub sw_file: Option<Utf8PathBuf>,
+    /// the web app manifest, copied to the site root
+    pub manifest_file: Option<Utf8PathBuf>,
+    /// a dir of icons, copied to the site root
+    pub icons_dir: Option<Utf8PathBuf>,
+}
+
+impl PwaConfig {
+    /// The paths are relative to the configuration file
+    pub fn resolve(&self, config_dir: &Utf8Path) -> Self {
+        Self {
+            sw_file: self.sw_file.as_ref().map(|f| config_dir.join(f)),
+            manifest_file: self.manifest_file.as_ref().map(|f| config_dir.join(f)),
+            icons_dir: self.icons_dir.as_ref().map(|d| config_dir.join(d)),
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::net::{IpAddr, SocketAddr};
+
+use reqwest::Url;
+use serde::Deserialize;
+
+use super::ProjectConfig;
+use crate::ext::anyhow::{bail, Context, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WsProtocol {
+    Ws,
+    Wss,
+}
+
+impl std::fmt::Display for WsProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ws => write!(f, "ws"),
+            Self::Wss => write!(f, "wss"),
+        }
+    }
+}
+
+impl std::str::FromStr for WsProtocol {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ws" => Ok(Self::Ws),
+            "wss" => Ok(Self::Wss),
+            _ => bail!("The reload-ws-protocol must be ws or wss, not {s}"),
+        }
+    }
+}
+
+/// Where the browser reaches the reload server, which differs from the local `reload-port`
+/// behind a reverse proxy or a port mapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReloadClient {
+    pub ws_protocol: WsProtocol,
+    /// the `reload-external-url`
+    pub external_url: Option<Url>,
+    /// the host the browser reaches the dev servers at, when not the site-addr one
+    pub advertise_host: Option<String>,
+    /// the `reload-allowed-origins`: origins, hosts or `*`
+    pub allowed_origins: Vec<String>,
+    /// the ip of the site-addr, unless listening on all interfaces
+    site_ip: Option<IpAddr>,
+}
+
+impl ReloadClient {
+    pub fn resolve(
+        conf: &ProjectConfig,
+        tls: bool,
+        advertise_host: Option<String>,
+    ) -> Result<Self> {
+        let mut client = Self::new(
+            conf.reload_external_url.as_deref(),
+            conf.reload_ws_protocol,
+            tls,
+        )?;
+        client.advertise_host = advertise_host;
+        client.allowed_origins = conf.reload_allowed_origins.clone();
+        client.site_ip = Some(conf.site_addr.ip()).filter(|ip| !ip.is_unspecified());
+        Ok(client)
+    }
+
+    /// The protocol defaults to the one of the external url, or to wss when serving with https.
+    fn new(external_url: Option<&str>, ws_protocol: Option<WsProtocol>, tls: bool) -> Result<Self> {
+        let external_url = external_url.map(parse_external_url).transpose()?;
+        let secure_url = external_url
+            .as_ref()
+            .map(|url| matches!(url.scheme(), "https" | "wss"));
+        let ws_protocol = match (ws_protocol, secure_url) {
+            (Some(protocol), _) => protocol,
+            (None, Some(true)) => WsProtocol::Wss,
+            (None, Some(false)) => WsProtocol::Ws,
+            (None, None) if tls => WsProtocol::Wss,
+            (None, None) => WsProtocol::Ws,
+        };
+        Ok(Self {
+            ws_protocol,
+            external_url,
+            advertise_host: None,
+            allowed_origins: Vec::new(),
+            site_ip: None,
+        })
+    }
+
+    /// The external port, as the leptos integrations read it from `LEPTOS_RELOAD_EXTERNAL_PORT`.
+    pub fn external_port(&self) -> Option<u16> {
+        self.external_url
+            .as_ref()
+            .and_then(|url| url.port_or_known_default())
+    }
+
+    /// The url of the reload websocket given to the external reload script, if any.
+    pub fn websocket_url(&self) -> Option<String> {
+        let url = self.external_url.as_ref()?;
+        let path = url.path().trim_end_matches('/');
+        Some(format!(
+            "{}://{}{path}/live_reload",
+            self.ws_protocol,
+            authority(url)
+        ))
+    }
+
+    /// The http origin serving the reload scripts, e.g. `http://127.0.0.1:3001`.
+    pub fn script_origin(&self, reload: SocketAddr) -> String {
+        let scheme = match self.ws_protocol {
+            WsProtocol::Ws => "http",
+            WsProtocol::Wss => "https",
+        };
+        match &self.external_url {
+            Some(url) => format!(
+                "{scheme}://{}{}",
+                authority(url),
+                url.path().trim_end_matches('/')
+            ),
+            None => format!(
+                "{scheme}://{}",
+                browser_addr(reload, self.advertise_host.as_deref())
+            ),
+        }
+    }
+
+    /// Whether a page of the origin may connect to the reload websocket: the pages of the local,
+    /// site-addr, advertised or reload-external-url hosts, and of the `reload-allowed-origins`.
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        let Some(host) = Url::parse(origin)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string()))
+        else {
+            return false;
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let external_host = self.external_url.as_ref().and_then(|url| url.host_str());
+        matches!(host, "localhost" | "127.0.0.1" | "::1")
+            || self.site_ip.is_some_and(|ip| ip.to_string() == host)
+            || self.advertise_host.as_deref() == Some(host)
+            || external_host == Some(host)
+            || self.allowed_origins.iter().any(|allowed| {
+                allowed == "*" || allowed == host || allowed.trim_end_matches('/') == origin
+            })
+    }
+}
+
+/// The address the browser reaches a local server at: with the advertised host, or with the
+/// loopback ip when the server listens on all interfaces.
+pub fn browser_addr(addr: SocketAddr, advertise_host: Option<&str>) -> String {
+    match advertise_host {
+        Some(host) if host.contains(':') => format!("[{host}]:{}", addr.port()),
+        Some(host) => format!("{host}:{}", addr.port()),
+        None if addr.ip().is_unspecified() => format!("127.0.0.1:{}", addr.port()),
+        None => addr.to_string(),
+    }
+}
+
+fn authority(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    }
+}
+
+fn parse_external_url(url: &str) -> Result<Url> {
+    let parsed = Url::parse(url).context(format!("Invalid reload-external-url {url}"))?;
+    if !matches!(parsed.scheme(), "http" | "https" | "ws" | "wss") || parsed.host().is_none() {
+        bail!("The reload-external-url must be a http(s) or ws(s) url with a host, not {url}");
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(url: Option<&str>, protocol: Option<WsProtocol>, tls: bool) -> ReloadClient {
+        ReloadClient::new(url, protocol, tls).unwrap()
+    }
+
+    #[test]
+    fn external_url() {
+        let reload = "127.0.0.1:3001".parse().unwrap();
+        let local = client(None, None, false);
+        assert_eq!(local.websocket_url(), None);
+        assert_eq!(local.script_origin(reload), "http://127.0.0.1:3001");
+        assert_eq!(
+            client(None, None, true).script_origin(reload),
+            "https://127.0.0.1:3001"
+        );
+
+        let proxied = client(Some("https://dev.example.com/reload/"), None, false);
+        assert_eq!(
+            proxied.websocket_url().as_deref(),
+            Some("wss://dev.example.com/reload/live_reload")
+        );
+        assert_eq!(proxied.external_port(), Some(443));
+        assert_eq!(
+            proxied.script_origin(reload),
+            "https://dev.example.com/reload"
+        );
+
+        let mapped = client(Some("ws://localhost:13001"), None, false);
+        assert_eq!(
+            mapped.websocket_url().as_deref(),
+            Some("ws://localhost:13001/live_reload")
+        );
+        assert_eq!(mapped.external_port(), Some(13001));
+        let forced = client(Some("http://localhost:13001"), Some(WsProtocol::Wss), false);
+        assert_eq!(
+            forced.websocket_url().as_deref(),
+            Some("wss://localhost:13001/live_reload")
+        );
+
+        let mut remote = client(None, None, false);
+        remote.advertise_host = Some("devbox.lan".to_string());
+        let all: SocketAddr = "0.0.0.0:3001".parse().unwrap();
+        assert_eq!(remote.script_origin(all), "http://devbox.lan:3001");
+        assert_eq!(local.script_origin(all), "http://127.0.0.1:3001");
+
+        assert!(parse_external_url("ftp://example.com").is_err());
+        assert!(parse_external_url("localhost:3001").is_err());
+    }
+
+    #[test]
+    fn origins() {
+        let mut client = client(Some("https://dev.example.com/reload"), None, false);
+        client.advertise_host = Some("devbox.lan".to_string());
+        client.allowed_origins = vec!["http://192.168.1.20:8080".to_string(), "wsl".to_string()];
+        assert!(client.allows_origin("http://localhost:3000"));
+        assert!(client.allows_origin("http://[::1]:3000"));
+        assert!(client.allows_origin("http://devbox.lan:3000"));
+        assert!(client.allows_origin("https://dev.example.com"));
+        assert!(client.allows_origin("http://192.168.1.20:8080"));
+        assert!(client.allows_origin("http://wsl:3000"));
+        assert!(!client.allows_origin("http://192.168.1.20:3000"));
+        assert!(!client.allows_origin("https://evil.example.com"));
+        assert!(!client.allows_origin("null"));
+
+        client.allowed_origins = vec!["*".to_string()];
+        assert!(client.allows_origin("https://evil.example.com"));
+    }
+}
@@ -0,0 +1,27 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+/// How the server binary is run, from `[package.metadata.leptos.server]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ServerConfig {
+    #[serde(default)]
+    pub integration: IntegrationKind,
+    /// custom: the shell command that starts the server, instead of running the server binary
+    pub command: Option<String>,
+    /// custom: a path that answers with a success status once the server is ready
+    pub health_path: Option<String>,
+    /// custom: extra env vars for the server
+    #[serde(default)]
+    pub envs: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IntegrationKind {
+    #[default]
+    Axum,
+    Actix,
+    Custom,
+}
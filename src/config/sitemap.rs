@@ -0,0 +1,23 @@
+use serde::Deserialize;
+
+/// The opt-in sitemap step, from `[package.metadata.leptos.sitemap]`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SitemapConfig {
+    /// the origin the urls of the sitemap start with, e.g. `https://example.com`
+    pub base_url: String,
+    /// the routes left out of the sitemap, a trailing `*` matching the ones starting with it
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl SitemapConfig {
+    pub fn excludes(&self, path: &str) -> bool {
+        self.exclude
+            .iter()
+            .any(|exclude| match exclude.strip_suffix('*') {
+                Some(prefix) => path.starts_with(prefix),
+                None => path == exclude,
+            })
+    }
+}
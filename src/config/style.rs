@@ -1,6 +1,8 @@
 use super::{ProjectConfig, TailwindConfig};
 use crate::service::site::{SiteFile, SourcedSiteFile};
 use anyhow::Result;
+use camino::Utf8PathBuf;
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone)]
 pub struct StyleConfig {
@@ -8,10 +10,16 @@ pub struct StyleConfig {
     pub browserquery: String,
     pub tailwind: Option<TailwindConfig>,
     pub site_file: SiteFile,
+    /// emitted as css custom properties on `:root` and as sass variables
+    pub vars: BTreeMap<String, String>,
+    /// the source map is written next to the css in the dev builds
+    pub source_map: bool,
+    /// the dirs the sass loads from, watched in watch mode
+    pub load_paths: Vec<Utf8PathBuf>,
 }
 
 impl StyleConfig {
-    pub fn new(config: &ProjectConfig) -> Result<Self> {
+    pub fn new(config: &ProjectConfig, release: bool) -> Result<Self> {
         let site_rel = config
             .site_pkg_dir
             .join(&config.output_name)
@@ -21,7 +29,12 @@ impl StyleConfig {
             dest: config.site_root.join(&site_rel),
             site: site_rel,
         };
-        let style_file = config.style_file.as_ref().map(|file| {
+        // the style of the profile, e.g. with the debug helpers in the dev builds only
+        let file = match release {
+            true => config.style_release_file.as_ref(),
+            false => config.style_dev_file.as_ref(),
+        };
+        let style_file = file.or(config.style_file.as_ref()).map(|file| {
             // relative to the configuration file
             let source = config.config_dir.join(file);
             let site = config
@@ -33,9 +46,20 @@ impl StyleConfig {
         });
         Ok(Self {
             file: style_file,
-            browserquery: config.browserquery.clone(),
+            // one query for the css, wasm and js targets
+            browserquery: config
+                .browser_targets
+                .clone()
+                .unwrap_or_else(|| config.browserquery.clone()),
             tailwind: TailwindConfig::new(config)?,
             site_file,
+            vars: config.style_vars.clone(),
+            source_map: config.style_source_map,
+            load_paths: config
+                .style_load_paths
+                .iter()
+                .map(|path| config.config_dir.join(path))
+                .collect(),
         })
     }
 }
@@ -1,12 +1,118 @@
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Deserialize;
 
 use super::ProjectConfig;
+use crate::logger::GRAY;
 use anyhow::{bail, Result};
 
+/// The config files looked up when `tailwind-config-file` isn't set, in order.
+const CONFIG_FILES: &[&str] = &[
+    "tailwind.config.js",
+    "tailwind.config.cjs",
+    "tailwind.config.mjs",
+    "tailwind.config.ts",
+];
+
+/// The postcss config files that make the node mode run postcss instead of tailwindcss.
+const POSTCSS_CONFIG_FILES: &[&str] = &[
+    "postcss.config.js",
+    "postcss.config.cjs",
+    "postcss.config.mjs",
+];
+
 #[derive(Clone, Debug)]
 pub struct TailwindConfig {
     pub input_file: Utf8PathBuf,
     pub config_file: Utf8PathBuf,
+    /// the dir of the input file, watched for the css it imports, unless it's the project root
+    pub input_dir: Option<Utf8PathBuf>,
+    /// runs the tailwindcss or postcss of the node_modules instead of the standalone binary
+    pub node: Option<NodeTailwind>,
+}
+
+/// The `style-node-package-manager`, running the node toolchain of the project, e.g. for the
+/// tailwind plugins that need it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodePackageManager {
+    Npm,
+    Pnpm,
+    Yarn,
+}
+
+impl NodePackageManager {
+    /// The program running the binaries of the node_modules.
+    pub fn program(&self) -> &'static str {
+        match self {
+            Self::Npm => "npx",
+            Self::Pnpm => "pnpm",
+            Self::Yarn => "yarn",
+        }
+    }
+
+    /// The arguments before the binary name. npx must not download a missing package.
+    pub fn exec_args(&self) -> &'static [&'static str] {
+        match self {
+            Self::Npm => &["--no"],
+            Self::Pnpm => &["exec"],
+            Self::Yarn => &["run"],
+        }
+    }
+
+    pub fn install_command(&self) -> &'static str {
+        match self {
+            Self::Npm => "npm install",
+            Self::Pnpm => "pnpm install",
+            Self::Yarn => "yarn install",
+        }
+    }
+}
+
+impl std::fmt::Display for NodePackageManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Npm => write!(f, "npm"),
+            Self::Pnpm => write!(f, "pnpm"),
+            Self::Yarn => write!(f, "yarn"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NodeTailwind {
+    pub package_manager: NodePackageManager,
+    /// the dir of the package.json, the tools run in it
+    pub dir: Utf8PathBuf,
+    /// postcss runs instead of tailwindcss when the dir has a postcss config
+    pub postcss_config: Option<Utf8PathBuf>,
+}
+
+impl NodeTailwind {
+    fn new(conf: &ProjectConfig) -> Option<Self> {
+        let package_manager = conf.style_node_package_manager?;
+        let dir = match &conf.style_node_dir {
+            Some(dir) => conf.config_dir.join(dir),
+            None => conf.config_dir.clone(),
+        };
+        let postcss_config = POSTCSS_CONFIG_FILES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|file| file.exists());
+        Some(Self {
+            package_manager,
+            dir,
+            postcss_config,
+        })
+    }
+
+    /// The binary run from the node_modules.
+    pub fn bin(&self) -> &'static str {
+        if self.postcss_config.is_some() {
+            "postcss"
+        } else {
+            "tailwindcss"
+        }
+    }
 }
 
 impl TailwindConfig {
@@ -20,15 +126,36 @@ impl TailwindConfig {
             return Ok(None);
         };
 
-        let config_file = conf.config_dir.join(
-            conf.tailwind_config_file
-                .clone()
-                .unwrap_or_else(|| Utf8PathBuf::from("tailwind.config.js")),
-        );
+        let input_dir = input_file
+            .parent()
+            .filter(|dir| !dir.as_str().is_empty() && *dir != conf.config_dir)
+            .map(Utf8Path::to_path_buf);
+
+        let config_file = match &conf.tailwind_config_file {
+            Some(file) => conf.config_dir.join(file),
+            None => {
+                let mut dirs = vec![conf.config_dir.as_path()];
+                dirs.extend(input_dir.as_deref());
+                // generated on the first build when not found
+                detect_config_file(&dirs).unwrap_or_else(|| conf.config_dir.join(CONFIG_FILES[0]))
+            }
+        };
 
         Ok(Some(Self {
             input_file,
             config_file,
+            input_dir,
+            node: NodeTailwind::new(conf),
         }))
     }
 }
+
+/// The first of the standard config files found in the dirs.
+fn detect_config_file(dirs: &[&Utf8Path]) -> Option<Utf8PathBuf> {
+    let file = dirs
+        .iter()
+        .flat_map(|dir| CONFIG_FILES.iter().map(|name| dir.join(name)))
+        .find(|file| file.exists())?;
+    log::debug!("Tailwind detected the config {}", GRAY.paint(file.as_str()));
+    Some(file)
+}
@@ -5,6 +5,34 @@ fn opts(project: Option<&str>) -> crate::config::Opts {
         release: false,
         hot_reload: false,
         project: project.map(|s| s.to_string()),
+        split_deps: false,
+        bin_target_triple: None,
+        stats: false,
+        jobs: None,
+        debug_symbols: false,
+        https: false,
+        open: None,
+        timings: false,
+        bin: None,
+        wasm_debug: false,
+        dry_run: false,
+        musl: false,
+        lazy_front: false,
+        env: None,
+        no_toolchain_install: false,
+        watch_extra: Vec::new(),
+        wait_lock: false,
+        verbose_tools: false,
+        frozen_tools: false,
+        bind: None,
+        advertise_host: None,
+        fix_profile: false,
+        runner: None,
+        quiet: false,
+        watchdog_strict: false,
+        reproducible: false,
+        reuse_artifacts: false,
+        force: false,
         verbose: 0,
         features: Vec::new(),
         bin_features: Vec::new(),
@@ -61,3 +89,104 @@ fn test_workspace_in_subdir_project2() {
 
     insta::assert_debug_snapshot!(conf);
 }
+
+#[test]
+fn manifest_of_subdir() {
+    use camino::Utf8PathBuf;
+
+    let dir = Utf8PathBuf::from("examples/workspace/project2/src")
+        .canonicalize_utf8()
+        .unwrap();
+    let manifest = super::locate_manifest(&dir).unwrap();
+    assert!(manifest.ends_with("examples/workspace/project2/Cargo.toml"));
+
+    let conf = Config::load(opts(None), &dir, &manifest, true).unwrap();
+    assert!(conf.working_dir.ends_with("examples/workspace"));
+    assert_eq!(conf.projects.len(), 1);
+    assert_eq!(conf.projects[0].name, "project2");
+}
+
+#[test]
+fn env_overrides() {
+    let metadata = serde_json::json!({
+        "site-addr": "127.0.0.1:3000",
+        "features": ["a"],
+        "hooks": { "pre-build": "make", "post-build": "notify" },
+        "env": {
+            "staging": {
+                "site-addr": "0.0.0.0:8080",
+                "hooks": { "pre-build": "make staging" },
+            },
+        },
+    });
+    let dir = camino::Utf8Path::new("app");
+    let conf = super::project::env_metadata(dir, &metadata, "staging").unwrap();
+    assert_eq!(conf["site-addr"], "0.0.0.0:8080");
+    assert_eq!(conf["features"], serde_json::json!(["a"]));
+    assert_eq!(conf["hooks"]["pre-build"], "make staging");
+    assert_eq!(conf["hooks"]["post-build"], "notify");
+
+    let err = super::project::env_metadata(dir, &metadata, "prod").unwrap_err();
+    assert!(
+        err.to_string().contains("The defined envs are: [staging]"),
+        "{err}"
+    );
+}
+
+#[test]
+fn profile_style_files() {
+    let conf: super::ProjectConfig = serde_json::from_value(serde_json::json!({
+        "output-name": "app",
+        "style-file": "style/main.scss",
+        "style-dev-file": "style/dev.scss",
+    }))
+    .unwrap();
+    let source = |release| {
+        let style = super::StyleConfig::new(&conf, release).unwrap();
+        let file = style.file.unwrap();
+        assert_eq!(file.site, "pkg/app.css");
+        file.source
+    };
+    assert_eq!(source(false), "style/dev.scss");
+    assert_eq!(source(true), "style/main.scss");
+}
+
+#[test]
+fn effective_keys() {
+    let metadata = serde_json::json!({
+        "site-addr": "127.0.0.1:3000",
+        "hooks": { "pre-build": "make", "post-build": "notify" },
+        "env": {
+            "staging": {
+                "site-addr": "0.0.0.0:8080",
+                "hooks": { "pre-build": "make staging" },
+            },
+        },
+    });
+    let dir = camino::Utf8Path::new("app");
+    let keys = super::project::effective_keys(dir, &metadata, Some("staging")).unwrap();
+    let keys = keys
+        .iter()
+        .map(|k| (k.key.as_str(), k.value.to_string(), k.env.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        keys,
+        vec![
+            ("hooks.post-build", r#""notify""#.to_string(), None),
+            (
+                "hooks.pre-build",
+                r#""make staging""#.to_string(),
+                Some("env.staging")
+            ),
+            (
+                "site-addr",
+                r#""0.0.0.0:8080""#.to_string(),
+                Some("env.staging")
+            ),
+        ]
+    );
+
+    let keys = super::project::effective_keys(dir, &metadata, None).unwrap();
+    assert!(keys.iter().all(|k| k.env.is_none()));
+    assert_eq!(keys.len(), 3);
+}
@@ -0,0 +1,25 @@
+use camino::Utf8PathBuf;
+
+use crate::ext::{anyhow::Result, exe::get_cache_dir, utf8_path};
+
+use super::Opts;
+
+/// The local certificate used when serving with `--https`
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub cert_file: Utf8PathBuf,
+    pub key_file: Utf8PathBuf,
+}
+
+impl TlsConfig {
+    pub fn resolve(cli: &Opts) -> Result<Option<Self>> {
+        if !cli.https {
+            return Ok(None);
+        }
+        let dir = utf8_path(get_cache_dir()?.join("certs"))?;
+        Ok(Some(Self {
+            cert_file: dir.join("localhost.pem"),
+            key_file: dir.join("localhost-key.pem"),
+        }))
+    }
+}
@@ -2,6 +2,8 @@ use core::convert::Infallible;
 use std::fmt::Display;
 use std::panic::Location;
 
+use super::{ErrorCode, LeptosError};
+
 /// re-exports
 pub use anyhow::{anyhow, bail, ensure};
 pub use anyhow::{Chain, Error, Ok, Result};
@@ -18,6 +20,12 @@ pub trait Context<T, E> {
 
     /// like google map red dot, only record the location info without any context message.
     fn dot(self) -> Result<T>;
+
+    /// tags the error with a code that `cargo leptos explain` has a remediation text for.
+    fn code(self, code: ErrorCode) -> Result<T>;
+
+    /// tags the error with its category, which decides the exit code.
+    fn categorize(self, error: LeptosError) -> Result<T>;
 }
 
 impl<T, E> Context<T, E> for Result<T, E>
@@ -77,6 +85,16 @@ where
             ),
         )
     }
+
+    #[inline]
+    fn code(self, code: ErrorCode) -> Result<T> {
+        anyhow::Context::context(self, code)
+    }
+
+    #[inline]
+    fn categorize(self, error: LeptosError) -> Result<T> {
+        anyhow::Context::context(self, error)
+    }
 }
 
 impl<T> Context<T, Infallible> for Option<T>
@@ -135,4 +153,14 @@ where
             ),
         )
     }
+
+    #[inline]
+    fn code(self, code: ErrorCode) -> Result<T> {
+        anyhow::Context::context(self, code)
+    }
+
+    #[inline]
+    fn categorize(self, error: LeptosError) -> Result<T> {
+        anyhow::Context::context(self, error)
+    }
 }
@@ -68,11 +68,13 @@ impl MetadataExt for Metadata {
         Ok(metadata)
     }
 
+    /// Relative to the workspace root, unless the target-dir of the cargo config (or
+    /// CARGO_TARGET_DIR) is outside of it.
     fn rel_target_dir(&self) -> Utf8PathBuf {
         self.target_directory
             .clone()
             .unbase(&self.workspace_root)
-            .unwrap()
+            .unwrap_or_else(|_| self.target_directory.clone())
     }
 
     fn package_for(&self, id: &PackageId) -> Option<&Package> {
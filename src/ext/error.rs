@@ -0,0 +1,169 @@
+use camino::Utf8PathBuf;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use super::{anyhow::Error, ErrorCode};
+use crate::logger::{self, eprint_above_status};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// The error and its causes, for humans
+    #[default]
+    Short,
+    /// A json summary object, for CI
+    Json,
+}
+
+/// The categories of failures, kept in the anyhow error chain so that the CLI
+/// can exit with a distinct code for each, e.g. for CI to tell a compile error
+/// from a broken config. Attached with [super::anyhow::Context::categorize].
+#[derive(Debug, thiserror::Error)]
+pub enum LeptosError {
+    #[error("Could not download {name} from {url}")]
+    ToolDownload { name: String, url: String },
+    #[error("Could not extract {name}")]
+    Extraction { name: String },
+    #[error("Invalid leptos config in {manifest}")]
+    Config { manifest: Utf8PathBuf },
+    #[error("Failed to build {project}")]
+    Compile { project: String },
+    #[error("The file watcher failed")]
+    Watch,
+    #[error("The server failed")]
+    Server,
+}
+
+impl LeptosError {
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::ToolDownload { .. } => "tool-download",
+            Self::Extraction { .. } => "extraction",
+            Self::Config { .. } => "config",
+            Self::Compile { .. } => "compile",
+            Self::Watch => "watch",
+            Self::Server => "server",
+        }
+    }
+
+    /// The process exit code of the failure (1 is left to the uncategorized errors).
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Compile { .. } => 101,
+            Self::Config { .. } => 102,
+            Self::ToolDownload { .. } | Self::Extraction { .. } => 103,
+            Self::Watch => 104,
+            Self::Server => 105,
+        }
+    }
+
+    /// The outermost category in the error chain.
+    pub fn find(error: &Error) -> Option<&Self> {
+        error.downcast_ref::<Self>()
+    }
+}
+
+/// The exit code for the error: the one of its category, or 1.
+pub fn exit_code(error: &Error) -> i32 {
+    LeptosError::find(error).map_or(1, LeptosError::exit_code)
+}
+
+/// The final summary of a failure, printed on stderr with `--error-format json`.
+#[derive(Debug, Serialize)]
+pub struct ErrorSummary {
+    pub error: String,
+    pub causes: Vec<String>,
+    /// the [LeptosError] category, none for the uncategorized errors
+    pub category: Option<&'static str>,
+    /// the [ErrorCode] explained by `cargo leptos explain`
+    pub code: Option<&'static str>,
+    pub exit_code: i32,
+}
+
+impl ErrorSummary {
+    pub fn new(error: &Error) -> Self {
+        // the location-only contexts added by `.dot()` are left out
+        let mut messages = error
+            .chain()
+            .map(|e| e.to_string())
+            .filter(|m| !m.starts_with("at `"));
+        Self {
+            error: messages.next().unwrap_or_else(|| error.to_string()),
+            causes: messages.collect(),
+            category: LeptosError::find(error).map(LeptosError::category),
+            code: ErrorCode::find(error).map(|c| c.code()),
+            exit_code: exit_code(error),
+        }
+    }
+}
+
+/// The summary of `--log-format json`, as its last line.
+#[derive(Serialize)]
+struct JsonError {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(flatten)]
+    summary: ErrorSummary,
+}
+
+/// Prints the error on stderr, above the status line, in the format and returns the exit
+/// code. The json logs end with the json summary.
+pub fn report_error(error: &Error, format: ErrorFormat) -> i32 {
+    if logger::json() {
+        logger::eprint_json(&JsonError {
+            kind: "error",
+            summary: ErrorSummary::new(error),
+        });
+        return exit_code(error);
+    }
+    match format {
+        ErrorFormat::Short => {
+            let mut text = format!("Error: {error:?}\n");
+            if let Some(code) = ErrorCode::find(error) {
+                text.push_str(&format!("\n{}\n", code.hint()));
+            }
+            eprint_above_status(&text);
+        }
+        ErrorFormat::Json => logger::eprint_json(&ErrorSummary::new(error)),
+    }
+    exit_code(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{exit_code, ErrorSummary, LeptosError};
+    use crate::ext::anyhow::{anyhow, Context, Result};
+
+    #[test]
+    fn test_exit_code_of_category() {
+        let res: Result<()> = Err(anyhow!("missing field `name`"));
+        let err = res
+            .categorize(LeptosError::Config {
+                manifest: "Cargo.toml".into(),
+            })
+            .dot()
+            .unwrap_err();
+        assert_eq!(exit_code(&err), 102);
+
+        let err = LeptosError::Compile {
+            project: "example".to_string(),
+        }
+        .into();
+        assert_eq!(exit_code(&err), 101);
+        assert_eq!(exit_code(&anyhow!("other")), 1);
+    }
+
+    #[test]
+    fn test_error_summary() {
+        let res: Result<()> = Err(anyhow!("connection refused"));
+        let err = res
+            .categorize(LeptosError::ToolDownload {
+                name: "sass-1.58.3".to_string(),
+                url: "https://example.com/sass.tar.gz".to_string(),
+            })
+            .unwrap_err();
+        let summary = ErrorSummary::new(&err);
+        assert_eq!(summary.category, Some("tool-download"));
+        assert_eq!(summary.exit_code, 103);
+        assert_eq!(summary.causes, vec!["connection refused"]);
+    }
+}
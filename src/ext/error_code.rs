@@ -0,0 +1,137 @@
+use std::fmt;
+
+use super::anyhow::Error;
+
+/// The codes of the common cargo-leptos failures, explained by `cargo leptos explain <code>`.
+/// They are attached to errors with [super::anyhow::Context::code].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    MissingWasmTarget,
+    ToolDownload,
+    ConfigKey,
+    PortInUse,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 4] = [
+        ErrorCode::MissingWasmTarget,
+        ErrorCode::ToolDownload,
+        ErrorCode::ConfigKey,
+        ErrorCode::PortInUse,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MissingWasmTarget => "L0001",
+            Self::ToolDownload => "L0002",
+            Self::ConfigKey => "L0003",
+            Self::PortInUse => "L0004",
+        }
+    }
+
+    pub fn parse(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.code().eq_ignore_ascii_case(code.trim()))
+    }
+
+    /// The first code attached to the error or any of its causes.
+    pub fn find(error: &Error) -> Option<Self> {
+        error.downcast_ref::<ErrorCode>().copied()
+    }
+
+    pub fn summary(&self) -> &'static str {
+        match self {
+            Self::MissingWasmTarget => "the wasm32-unknown-unknown target is not installed",
+            Self::ToolDownload => "a tool could not be downloaded",
+            Self::ConfigKey => "unknown key in the leptos metadata",
+            Self::PortInUse => "the address is already in use",
+        }
+    }
+
+    /// The pointer to `explain`, appended to the logs of the failure.
+    pub fn hint(&self) -> String {
+        format!("[{0}] run `cargo leptos explain {0}` for more", self.code())
+    }
+
+    /// The extended remediation text.
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            Self::MissingWasmTarget => {
+                "The front (the lib package compiled with the `hydrate` feature) is built for the
+wasm32-unknown-unknown target, which the active rust toolchain doesn't have.
+
+cargo-leptos adds it with rustup when it's missing, unless --no-toolchain-install is given.
+Install it with:
+
+    rustup target add wasm32-unknown-unknown
+
+If the project pins a toolchain in rust-toolchain.toml, add the target there:
+
+    [toolchain]
+    targets = [\"wasm32-unknown-unknown\"]"
+            }
+            Self::ToolDownload => {
+                "cargo-leptos downloads the tools it needs (sass, tailwindcss, wasm-opt,
+esbuild, cargo-generate) to its cache dir when they are not found on the PATH. The download failed,
+usually because the machine is offline, GitHub is rate-limiting or a proxy is in the way.
+
+Either install the tool with your OS's package manager so that it is found on the PATH,
+or pin a version that is known to exist with its env var, e.g.
+
+    LEPTOS_SASS_VERSION=1.58.3
+    LEPTOS_TAILWIND_VERSION=v3.3.3
+    LEPTOS_WASM_OPT_VERSION=version_112
+
+Run with -vv to see the download urls and the cache dir."
+            }
+            Self::ConfigKey => {
+                "A key in [package.metadata.leptos] (or [[workspace.metadata.leptos]]) is not one
+cargo-leptos knows, so it would have been ignored. It is usually a typo or a key with
+underscores instead of dashes, e.g. `site_root` instead of `site-root`.
+
+The keys are listed in the README, under \"Parameters reference\"."
+            }
+            Self::PortInUse => {
+                "The site-addr or the reload-port is already used by another process, often an
+earlier `cargo leptos watch` that is still running.
+
+Stop the other process, or change the ports in [package.metadata.leptos]:
+
+    site-addr = \"127.0.0.1:3001\"
+    reload-port = 3002
+
+or with the LEPTOS_SITE_ADDR and LEPTOS_RELOAD_PORT env vars."
+            }
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.code(), self.summary())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ErrorCode;
+    use crate::ext::anyhow::{anyhow, Context, Result};
+
+    #[test]
+    fn test_find_code_through_context() {
+        let res: Result<()> = Err(anyhow!("connection refused"));
+        let err = res
+            .code(ErrorCode::ToolDownload)
+            .context("Could not get sass")
+            .unwrap_err();
+        assert_eq!(ErrorCode::find(&err), Some(ErrorCode::ToolDownload));
+        assert_eq!(ErrorCode::find(&anyhow!("other")), None);
+    }
+
+    #[test]
+    fn test_parse_code() {
+        assert_eq!(ErrorCode::parse("l0004"), Some(ErrorCode::PortInUse));
+        assert_eq!(ErrorCode::parse("L9999"), None);
+    }
+}
@@ -1,13 +1,21 @@
 use crate::{
-    ext::anyhow::{bail, Context, Result},
+    ext::{
+        anyhow::{bail, Context, Result},
+        http::{self, Downloader, HttpDownloader, Request},
+        utf8_path, ErrorCode, FileLock, LeptosError, ToolsLock, TOOLS_LOCK_FILE,
+    },
     logger::GRAY,
 };
 use bytes::Bytes;
+use camino::Utf8Path;
 use std::{
     fs::{self, File},
     io::{Cursor, Write},
-    path::{Path, PathBuf},
-    sync::Once,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Once,
+    },
 };
 
 use std::env;
@@ -19,7 +27,7 @@ use super::util::{is_linux_musl_env, os_arch};
 #[cfg(target_family = "unix")]
 use std::os::unix::prelude::PermissionsExt;
 use std::time::{Duration, SystemTime};
-use reqwest::ClientBuilder;
+use reqwest::{header::{HeaderMap, ETAG}, StatusCode};
 
 use semver::{Version};
 
@@ -30,6 +38,8 @@ pub struct ExeMeta {
     url: String,
     exe: String,
     manual: String,
+    /// the version is set by the env var, instead of the tools lock or default
+    pinned: bool,
 }
 
 lazy_static::lazy_static!{
@@ -40,12 +50,33 @@ pub const ENV_VAR_LEPTOS_CARGO_GENERATE_VERSION: &str = "LEPTOS_CARGO_GENERATE_V
 pub const ENV_VAR_LEPTOS_TAILWIND_VERSION: &str = "LEPTOS_TAILWIND_VERSION";
 pub const ENV_VAR_LEPTOS_SASS_VERSION: &str = "LEPTOS_SASS_VERSION";
 pub const ENV_VAR_LEPTOS_WASM_OPT_VERSION: &str = "LEPTOS_WASM_OPT_VERSION";
+pub const ENV_VAR_LEPTOS_ESBUILD_VERSION: &str = "LEPTOS_ESBUILD_VERSION";
+/// Replaces the tool cache dir, e.g. with one seeded by CI.
+pub const ENV_VAR_LEPTOS_CACHE_DIR: &str = "LEPTOS_CACHE_DIR";
+
+static FROZEN: AtomicBool = AtomicBool::new(false);
+
+/// For `--frozen-tools`: the tools are only taken from the cache, at their locked version, and
+/// the latest versions are never checked. The PATH is skipped, as the version of the tool found
+/// there isn't the locked one.
+pub fn set_frozen(frozen: bool) {
+    FROZEN.store(frozen, Ordering::Relaxed);
+}
+
+fn is_frozen() -> bool {
+    FROZEN.load(Ordering::Relaxed)
+}
 
 
 impl ExeMeta {
 
+    /// The tool on the PATH, unless the tools are frozen to their locked versions.
     #[allow(clippy::wrong_self_convention)]
     fn from_global_path(&self) -> Option<PathBuf> {
+        if is_frozen() {
+            log::trace!("Command [{}] NOT looking in the PATH for the frozen tools", self.name);
+            return None;
+        }
         which::which(self.name).ok()
     }
 
@@ -55,29 +86,57 @@ impl ExeMeta {
 
     async fn cached(&self) -> Result<PathBuf> {
         let cache_dir = get_cache_dir()?.join(self.get_name());
-        self._with_cache_dir(&cache_dir).await
+        self._with_cache_dir(&cache_dir, &HttpDownloader).await
     }
 
-    async fn _with_cache_dir(&self, cache_dir: &Path) -> Result<PathBuf> {
+    async fn _with_cache_dir(&self, cache_dir: &Path, downloader: &dyn Downloader) -> Result<PathBuf> {
         let exe_dir = cache_dir.join(self.get_name());
         let c = ExeCache {
             meta: self,
             exe_dir,
+            downloader,
         };
         c.get().await
     }
 
+    /// The tool already installed in the cache, for `--frozen-tools`.
+    fn frozen(&self) -> Result<PathBuf> {
+        if !self.pinned && ToolsLock::locked(self.name).is_none() {
+            bail!(
+                "{} is not locked in {TOOLS_LOCK_FILE}, which --frozen-tools requires. Lock it with `cargo leptos tools update`",
+                self.name
+            );
+        }
+        self.frozen_in(&get_cache_dir()?.join(self.get_name()))
+    }
+
+    fn frozen_in(&self, cache_dir: &Path) -> Result<PathBuf> {
+        let c = ExeCache {
+            meta: self,
+            exe_dir: cache_dir.join(self.get_name()),
+            downloader: &HttpDownloader,
+        };
+        c.exe_in_cache().context(format!(
+            "{} {} is not installed and --frozen-tools forbids downloading it",
+            self.name, self.version
+        ))
+    }
+
     #[cfg(test)]
     pub async fn with_cache_dir(&self, cache_dir: &Path) -> Result<PathBuf> {
-        self._with_cache_dir(cache_dir).await
+        self._with_cache_dir(cache_dir, &HttpDownloader).await
     }
 }
 
 pub struct ExeCache<'a> {
     exe_dir: PathBuf,
     meta: &'a ExeMeta,
+    downloader: &'a dyn Downloader,
 }
 
+/// Written in the exe dir once the tool is completely installed.
+const INSTALLED_MARKER: &str = ".ok";
+
 impl<'a> ExeCache<'a> {
     fn exe_in_cache(&self) -> Result<PathBuf> {
         let exe_path = self.exe_dir.join(PathBuf::from(&self.meta.exe));
@@ -85,10 +144,21 @@ impl<'a> ExeCache<'a> {
         if !exe_path.exists() {
             bail!("The path {exe_path:?} doesn't exist");
         }
+        // an interrupted install of an older cargo-leptos, which extracted in place
+        if !self.exe_dir.join(INSTALLED_MARKER).exists() {
+            bail!("The install in {:?} is incomplete", self.exe_dir);
+        }
 
         Ok(exe_path)
     }
 
+    /// The dir the tool is extracted to, before being moved to the exe dir.
+    fn partial_dir(&self) -> PathBuf {
+        let mut dir = self.exe_dir.clone().into_os_string();
+        dir.push(".partial");
+        PathBuf::from(dir)
+    }
+
     async fn fetch_archive(&self) -> Result<Bytes> {
         log::debug!(
             "Install downloading {} {}",
@@ -96,93 +166,314 @@ impl<'a> ExeCache<'a> {
             GRAY.paint(&self.meta.url)
         );
 
-        let response = reqwest::get(&self.meta.url).await?;
+        let response = self.downloader.get(Request::new(&self.meta.url)).await?;
 
-        match response.status().is_success() {
-            true => Ok(response.bytes().await?),
-            false => bail!("Could not download from {}", self.meta.url),
+        match response.status.is_success() {
+            true => Ok(response.body),
+            false => bail!("The download responded with {}", response.status),
         }
     }
 
-    fn extract_downloaded(&self, data: &Bytes) -> Result<()> {
-        if self.meta.url.ends_with(".zip") {
-            extract_zip(data, &self.exe_dir)?;
-        } else if self.meta.url.ends_with(".tar.gz") {
-            extract_tar(data, &self.exe_dir)?;
-        } else {
-            self.write_binary(data)
-                .context(format!("Could not write binary {}", self.meta.get_name()))?;
+    fn extract_downloaded(&self, data: &Bytes, dir: &Path) -> Result<()> {
+        let content = Cursor::new(data);
+        match Archive::of(&self.meta.url, data) {
+            Archive::Zip => extract_zip(data, dir)?,
+            Archive::TarGz => extract_tar(flate2::read::GzDecoder::new(content), dir)?,
+            Archive::TarXz => extract_tar(xz2::read::XzDecoder::new(content), dir)?,
+            Archive::TarZst => extract_tar(zstd::Decoder::new(content)?, dir)?,
+            Archive::Binary => self
+                .write_binary(data, dir)
+                .context(format!("Could not write binary {}", self.meta.get_name()))?,
+        }
+        // the archive may not have the permissions, e.g. zipped on windows
+        let exe = dir.join(&self.meta.exe);
+        if exe.is_file() {
+            set_exe_permissions(&exe)?;
         }
 
         log::debug!(
             "Install decompressing {} {}",
             self.meta.name,
-            GRAY.paint(self.exe_dir.to_string_lossy())
+            GRAY.paint(dir.to_string_lossy())
         );
 
         Ok(())
     }
 
-    fn write_binary(&self, data: &Bytes) -> Result<()> {
-        fs::create_dir_all(&self.exe_dir).unwrap();
-        let path = self.exe_dir.join(Path::new(&self.meta.exe));
+    /// Extracts to the partial dir, and moves it to the exe dir once complete, so that
+    /// an interrupted install is never mistaken for an installed tool.
+    fn install(&self, data: &Bytes) -> Result<()> {
+        let partial = self.partial_dir();
+        if partial.exists() {
+            fs::remove_dir_all(&partial).context(format!("Could not remove {partial:?}"))?;
+        }
+        let res = self.extract_downloaded(data, &partial).and_then(|_| {
+            if !partial.join(&self.meta.exe).exists() {
+                bail!(
+                    "Binary downloaded and extracted but could still not be found at {:?}",
+                    partial.join(&self.meta.exe)
+                );
+            }
+            File::create(partial.join(INSTALLED_MARKER))?;
+            if self.exe_dir.exists() {
+                fs::remove_dir_all(&self.exe_dir)
+                    .context(format!("Could not remove the incomplete {:?}", self.exe_dir))?;
+            }
+            fs::rename(&partial, &self.exe_dir)
+                .context(format!("Could not move {partial:?} to {:?}", self.exe_dir))
+        });
+        if res.is_err() && partial.exists() {
+            _ = fs::remove_dir_all(&partial);
+        }
+        res
+    }
+
+    fn write_binary(&self, data: &Bytes, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir).unwrap();
+        let path = dir.join(Path::new(&self.meta.exe));
         let mut file = File::create(&path).unwrap();
         file.write_all(data)
             .context(format!("Error writing binary file: {:?}", path))?;
-
-        #[cfg(target_family = "unix")]
-        {
-            let mut perm = fs::metadata(&path)?.permissions();
-            // https://chmod-calculator.com
-            // read and execute for owner and group
-            perm.set_mode(0o550);
-            fs::set_permissions(&path, perm)?;
-        }
         Ok(())
     }
 
     async fn download(&self) -> Result<PathBuf> {
         log::info!("Command installing {} ...", self.meta.get_name());
 
-        let data = self
-            .fetch_archive()
-            .await
-            .context(format!("Could not download {}", self.meta.get_name()))?;
+        let data = self.fetch_archive().await.categorize(LeptosError::ToolDownload {
+            name: self.meta.get_name(),
+            url: self.meta.url.clone(),
+        })?;
 
-        self.extract_downloaded(&data)
-            .context(format!("Could not extract {}", self.meta.get_name()))?;
+        self.install(&data)
+            .categorize(LeptosError::Extraction {
+                name: self.meta.get_name(),
+            })?;
 
-        let binary_path = self.exe_in_cache().context(format!(
-            "Binary downloaded and extracted but could still not be found at {:?}",
-            self.exe_dir
-        ))?;
+        let binary_path = self.exe_in_cache()?;
         log::info!("Command {} installed.", self.meta.get_name());
         Ok(binary_path)
     }
 
     async fn get(&self) -> Result<PathBuf> {
         if let Ok(path) = self.exe_in_cache() {
-            Ok(path)
+            return Ok(path);
+        }
+        // another cargo-leptos may be downloading the same tool
+        let mut lock_file = self.exe_dir.clone().into_os_string();
+        lock_file.push(".lock");
+        let what = format!("{} download", self.meta.get_name());
+        let _lock = FileLock::acquire(&utf8_path(lock_file)?, &what, true).await?;
+        match self.exe_in_cache() {
+            Ok(path) => Ok(path),
+            Err(_) => self.download().await,
+        }
+    }
+}
+
+/// The format of a downloaded tool.
+#[derive(Debug, PartialEq, Eq)]
+enum Archive {
+    Zip,
+    TarGz,
+    TarXz,
+    TarZst,
+    /// the executable itself
+    Binary,
+}
+
+impl Archive {
+    /// By the extension of the url or, when it has none of the known ones,
+    /// by the magic bytes at the start of the data.
+    fn of(url: &str, data: &[u8]) -> Self {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        if path.ends_with(".zip") {
+            Self::Zip
+        } else if path.ends_with(".tar.gz") || path.ends_with(".tgz") {
+            Self::TarGz
+        } else if path.ends_with(".tar.xz") || path.ends_with(".txz") {
+            Self::TarXz
+        } else if path.ends_with(".tar.zst") || path.ends_with(".tzst") {
+            Self::TarZst
+        } else if data.starts_with(b"PK\x03\x04") {
+            Self::Zip
+        } else if data.starts_with(&[0x1f, 0x8b]) {
+            Self::TarGz
+        } else if data.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Self::TarXz
+        } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Self::TarZst
         } else {
-            self.download().await
+            Self::Binary
         }
     }
 }
 
+fn set_exe_permissions(path: &Path) -> Result<()> {
+    #[cfg(target_family = "unix")]
+    {
+        let mut perm = fs::metadata(path)?.permissions();
+        // https://chmod-calculator.com
+        // read and execute for owner and group
+        perm.set_mode(0o550);
+        fs::set_permissions(path, perm)?;
+    }
+    #[cfg(not(target_family = "unix"))]
+    let _ = path;
+    Ok(())
+}
+
+/// The path of an archive entry relative to the extraction dir, with the `.` and `..`
+/// resolved. `None` if it is absolute or escapes the extraction dir (zip-slip).
+fn enclosed_path(path: &Path) -> Option<PathBuf> {
+    let mut enclosed = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => enclosed.push(name),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !enclosed.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(enclosed)
+}
+
+/// A symlink of the archive must point inside the extraction dir.
+fn check_link(entry: &Path, target: &Path) -> Result<()> {
+    let resolved = entry.parent().map(|dir| dir.join(target));
+    if target.is_absolute() || resolved.and_then(|path| enclosed_path(&path)).is_none() {
+        bail!("The archive link {entry:?} points outside of the extraction dir, to {target:?}");
+    }
+    Ok(())
+}
+
+/// Creates the dir and its missing parents, checking before each one that its parent is in the
+/// extraction dir `root` (canonical), as a link extracted before may point outside of it
+/// (e.g. `a -> .` then `a/b -> ..`).
+fn create_dir_inside(root: &Path, dir: &Path) -> Result<()> {
+    let mut missing = Vec::new();
+    let mut ancestor = dir;
+    while ancestor.symlink_metadata().is_err() {
+        missing.push(ancestor);
+        match ancestor.parent() {
+            Some(parent) => ancestor = parent,
+            None => break,
+        }
+    }
+    for dir in missing.into_iter().rev() {
+        if let Some(parent) = dir.parent() {
+            check_inside(root, parent)?;
+        }
+        fs::create_dir(dir).context(format!("Could not create dir {dir:?}"))?;
+    }
+    check_inside(root, dir)
+}
+
+/// The path, with its links followed, must be in the extraction dir `root` (canonical).
+fn check_inside(root: &Path, path: &Path) -> Result<()> {
+    let canonical = path.canonicalize().context(format!("Could not resolve {path:?}"))?;
+    if !canonical.starts_with(root) {
+        bail!("The archive entry {path:?} is outside of the extraction dir, through a link");
+    }
+    Ok(())
+}
+
+/// On windows, the extended-length form of the absolute path, which isn't limited to
+/// the 260 chars of `MAX_PATH` (some tools nest deeply in their archives).
+fn long_path(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        let path_str = path.as_os_str().to_string_lossy();
+        if path.is_absolute() && !path_str.starts_with(r"\\?\") {
+            return match path_str.strip_prefix(r"\\") {
+                Some(unc) => PathBuf::from(format!(r"\\?\UNC\{unc}")),
+                None => PathBuf::from(format!(r"\\?\{path_str}")),
+            };
+        }
+    }
+    path.to_path_buf()
+}
+
 // there's a issue in the tar crate: https://github.com/alexcrichton/tar-rs/issues/295
 // It doesn't handle TAR sparse extensions, with data ending up in a GNUSparseFile.0 sub-folder
-fn extract_tar(src: &Bytes, dest: &Path) -> Result<()> {
-    let content = Cursor::new(src);
-    let dec = flate2::read::GzDecoder::new(content);
+fn extract_tar(dec: impl std::io::Read, dest: &Path) -> Result<()> {
+    let dest = long_path(dest);
+    // unpack_in resolves the dest, which must exist
+    fs::create_dir_all(&dest).context(format!("Could not create dir {dest:?}"))?;
     let mut arch = tar::Archive::new(dec);
-    arch.unpack(dest).dot()?;
+    for entry in arch.entries().dot()? {
+        let mut entry = entry.dot()?;
+        let path = entry.path().dot()?.into_owned();
+        let Some(rel) = enclosed_path(&path) else {
+            bail!("The archive entry {path:?} is outside of the extraction dir");
+        };
+        let kind = entry.header().entry_type();
+        let target = entry.link_name().dot()?.map(|target| target.into_owned());
+        match target {
+            Some(target) if kind.is_symlink() => check_link(&rel, &target)?,
+            // the target of a hard link is relative to the archive root
+            Some(target) if kind.is_hard_link() && enclosed_path(&target).is_none() => {
+                bail!("The archive link {path:?} points outside of the extraction dir, to {target:?}")
+            }
+            _ => {}
+        }
+        // unlike unpack, it checks that the entry's parent dirs, with the links extracted
+        // before followed, are in the dest
+        let unpacked = entry
+            .unpack_in(&dest)
+            .context(format!("Could not extract {path:?}"))?;
+        if !unpacked {
+            bail!("The archive entry {path:?} is outside of the extraction dir");
+        }
+    }
     Ok(())
 }
 
 fn extract_zip(src: &Bytes, dest: &Path) -> Result<()> {
     let content = Cursor::new(src);
     let mut arch = ZipArchive::new(content).dot()?;
-    arch.extract(dest).dot().dot()?;
+    fs::create_dir_all(dest).context(format!("Could not create dir {dest:?}"))?;
+    let root = long_path(dest)
+        .canonicalize()
+        .context(format!("Could not resolve {dest:?}"))?;
+    for i in 0..arch.len() {
+        let mut entry = arch.by_index(i).dot()?;
+        let Some(rel) = enclosed_path(Path::new(entry.name())) else {
+            bail!("The archive entry {:?} is outside of the extraction dir", entry.name());
+        };
+        let file = long_path(&dest.join(&rel));
+        if entry.is_dir() {
+            create_dir_inside(&root, &file)?;
+            continue;
+        }
+        if let Some(dir) = file.parent() {
+            create_dir_inside(&root, dir)?;
+        }
+        // creating the file would follow a link of the archive
+        if file.symlink_metadata().is_ok_and(|meta| meta.file_type().is_symlink()) {
+            bail!("The archive entry {:?} replaces a link", entry.name());
+        }
+        let mode = entry.unix_mode();
+        // symlinks are stored with their target as content
+        if mode.is_some_and(|mode| mode & 0o170000 == 0o120000) {
+            let mut target = String::new();
+            std::io::Read::read_to_string(&mut entry, &mut target)?;
+            check_link(&rel, Path::new(&target))?;
+            #[cfg(target_family = "unix")]
+            std::os::unix::fs::symlink(&target, &file).context(format!("Could not link {file:?}"))?;
+            continue;
+        }
+        let mut out = File::create(&file).context(format!("Could not create {file:?}"))?;
+        std::io::copy(&mut entry, &mut out).context(format!("Could not extract {file:?}"))?;
+        #[cfg(target_family = "unix")]
+        if let Some(mode) = mode {
+            fs::set_permissions(&file, fs::Permissions::from_mode(mode & 0o777))?;
+        }
+    }
     Ok(())
 }
 
@@ -196,10 +487,15 @@ fn extract_zip(src: &Bytes, dest: &Path) -> Result<()> {
 /// | Linux    | /home/alice/.cache/NAME           |
 /// | macOS    | /Users/Alice/Library/Caches/NAME  |
 /// | Windows  | C:\Users\Alice\AppData\Local\NAME |
-fn get_cache_dir() -> Result<PathBuf> {
-    let dir = dirs::cache_dir()
-        .ok_or_else(|| anyhow::anyhow!("Cache directory does not exist"))?
-        .join("cargo-leptos");
+///
+/// The `LEPTOS_CACHE_DIR` env var replaces it.
+pub fn get_cache_dir() -> Result<PathBuf> {
+    let dir = match env::var_os(ENV_VAR_LEPTOS_CACHE_DIR) {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::cache_dir()
+            .ok_or_else(|| anyhow::anyhow!("Cache directory does not exist"))?
+            .join("cargo-leptos"),
+    };
 
     if !dir.exists() {
         fs::create_dir_all(&dir).context(format!("Could not create dir {dir:?}"))?;
@@ -217,7 +513,9 @@ pub enum Exe {
     CargoGenerate,
     Sass,
     WasmOpt,
+    WasmSplit,
     Tailwind,
+    Esbuild,
 }
 
 impl Exe {
@@ -228,8 +526,14 @@ impl Exe {
             path
         } else if cfg!(feature = "no_downloads") {
             bail!("{} is required but was not found. Please install it using your OS's tool of choice", &meta.name);
+        } else if is_frozen() {
+            meta.frozen()?
         } else {
-            meta.cached().await.context(meta.manual)?
+            let path = meta.cached().await.context(meta.manual).code(ErrorCode::ToolDownload)?;
+            if !meta.pinned {
+                ToolsLock::record(meta.name, &meta.version);
+            }
+            path
         };
 
         log::debug!(
@@ -242,6 +546,46 @@ impl Exe {
         Ok(path)
     }
 
+    /// The `local` binary configured for the project (e.g. `sass-bin`) when set,
+    /// without looking in the PATH or downloading. Otherwise as [Exe::get].
+    pub async fn get_or_local(&self, local: Option<&Utf8Path>) -> Result<PathBuf> {
+        let Some(local) = local else {
+            return self.get().await;
+        };
+        if !local.is_file() {
+            bail!(
+                "The local {} binary configured for the project was not found at {local}",
+                self.name()
+            );
+        }
+        log::debug!(
+            "Command using the local {} {}",
+            self.name(),
+            GRAY.paint(local.as_str())
+        );
+        Ok(local.into())
+    }
+
+    /// The path of the tool, without downloading it when missing (for `--dry-run`).
+    pub async fn locate(&self, local: Option<&Utf8Path>) -> Result<String> {
+        if let Some(local) = local {
+            return Ok(local.to_string());
+        }
+        let meta = self.meta().await?;
+        if let Some(path) = meta.from_global_path() {
+            return Ok(path.to_string_lossy().to_string());
+        }
+        let exe_dir = get_cache_dir()?
+            .join(meta.get_name())
+            .join(meta.get_name());
+        let exe = exe_dir.join(&meta.exe);
+        if exe.exists() && exe_dir.join(INSTALLED_MARKER).exists() {
+            Ok(exe.to_string_lossy().to_string())
+        } else {
+            Ok(format!("{} (not installed, would be downloaded from {})", meta.name, meta.url))
+        }
+    }
+
     pub async fn meta(&self) -> Result<ExeMeta> {
         let (target_os, target_arch) = os_arch().unwrap();
 
@@ -254,11 +598,37 @@ impl Exe {
             Exe::CargoGenerate => CommandCargoGenerate.exe_meta(target_os, target_arch).await.dot()?,
             Exe::Sass => CommandSass.exe_meta(target_os, target_arch).await.dot()?,
             Exe::WasmOpt => CommandWasmOpt.exe_meta(target_os, target_arch).await.dot()?,
+            Exe::WasmSplit => CommandWasmSplit.exe_meta(target_os, target_arch).await.dot()?,
             Exe::Tailwind => CommandTailwind.exe_meta(target_os, target_arch).await.dot()?,
+            Exe::Esbuild => CommandEsbuild.exe_meta(target_os, target_arch).await.dot()?,
         };
 
         Ok(exe)
     }
+
+    /// The name the tool is locked as, in the `leptos-tools.lock`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Exe::CargoGenerate => CommandCargoGenerate.name(),
+            Exe::Sass => CommandSass.name(),
+            Exe::WasmOpt => CommandWasmOpt.name(),
+            Exe::WasmSplit => CommandWasmSplit.name(),
+            Exe::Tailwind => CommandTailwind.name(),
+            Exe::Esbuild => CommandEsbuild.name(),
+        }
+    }
+
+    /// The version of the latest GitHub release of the tool.
+    pub async fn latest_version(&self) -> Option<String> {
+        match self {
+            Exe::CargoGenerate => CommandCargoGenerate.check_for_latest_version().await,
+            Exe::Sass => CommandSass.check_for_latest_version().await,
+            Exe::WasmOpt => CommandWasmOpt.check_for_latest_version().await,
+            Exe::WasmSplit => CommandWasmSplit.check_for_latest_version().await,
+            Exe::Tailwind => CommandTailwind.check_for_latest_version().await,
+            Exe::Esbuild => CommandEsbuild.check_for_latest_version().await,
+        }
+    }
 }
 
 /// Tailwind uses the 'vMaj.Min.Pat' format.
@@ -297,14 +667,273 @@ fn normalize_version( ver_string: &str) -> Option<Version> {
 }
 
 
+/// Looks up the tag of the latest GitHub release of the repo. The `releases/latest` api
+/// skips pre-releases, so they are listed and the newest one taken when `prerelease` is set.
+async fn github_release(name: &str, owner: &str, repo: &str, prerelease: bool) -> Option<String> {
+    let cache_dir = get_cache_dir().ok();
+    github_release_at(&HttpDownloader, &http::github_api_url(), cache_dir.as_deref(), name, owner, repo, prerelease).await
+}
+
+/// The responses are cached in the `cache_dir`, when set.
+async fn github_release_at(
+    downloader: &dyn Downloader,
+    api_url: &str,
+    cache_dir: Option<&Path>,
+    name: &str,
+    owner: &str,
+    repo: &str,
+    prerelease: bool,
+) -> Option<String> {
+    log::debug!("Command [{name}] checking for the latest available version");
+
+    let url = if prerelease {
+        format!("{api_url}/repos/{owner}/{repo}/releases?per_page=1")
+    } else {
+        format!("{api_url}/repos/{owner}/{repo}/releases/latest")
+    };
+
+    let cache_file = cache_dir.map(|dir| ApiResponse::file(dir, &url));
+    let cached = match &cache_file {
+        Some(file) => ApiResponse::read(file),
+        None => None,
+    };
+
+    let mut request = Request::new(url);
+    request.bearer_token = github_token();
+    // the check must never slow down the build
+    request.timeout = Some(Duration::from_secs(5));
+    // a 304 doesn't count against the rate limit
+    request.if_none_match = cached.as_ref().map(|cached| cached.etag.clone());
+
+    if let Ok(response) = downloader.get(request).await {
+        let body = match cached {
+            Some(cached) if response.status == StatusCode::NOT_MODIFIED => {
+                log::debug!("Command [{name}] GitHub API response unchanged since the last check");
+                cached.body.into_bytes()
+            }
+            Some(cached) if is_rate_limited(response.status, &response.headers) => {
+                log::debug!("Command [{name}] GitHub API rate limit reached, using the cached response");
+                cached.body.into_bytes()
+            }
+            _ => {
+                if is_rate_limited(response.status, &response.headers) {
+                    log::warn!(
+                        "Command [{name}] GitHub API rate limit reached, skipping the version check. \
+                        Set the GITHUB_TOKEN or LEPTOS_GITHUB_TOKEN env var for a higher limit"
+                    );
+                    return None
+                }
+                if !response.status.is_success() {
+                    log::error!("Command [{name}] GitHub API request failed: {}", response.status);
+                    return None
+                }
+                if let Some(file) = &cache_file {
+                    ApiResponse::write(file, &response);
+                }
+                response.body.to_vec()
+            }
+        };
+
+        #[derive(serde::Deserialize)]
+        struct Github {
+            tag_name: String, // this is the version number, not the git tag
+        }
+
+        let github = if prerelease {
+            serde_json::from_slice::<Vec<Github>>(&body).map(|list| list.into_iter().next())
+        } else {
+            serde_json::from_slice::<Github>(&body).map(Some)
+        };
+
+        match github {
+            Ok(Some(github)) => Some(github.tag_name),
+            Ok(None) => {
+                log::debug!("Command [{name}] no releases found on GitHub");
+                None
+            }
+            Err(e) => {
+                log::debug!("Command [{name}] failed to parse the response JSON from the GitHub API: {e}");
+                None
+            }
+        }
+    } else {
+        log::debug!("Command [{name}] failed to check for the latest version");
+        None
+    }
+}
+
+/// A GitHub API response with its ETag, kept in the cache dir so that the checks of all the
+/// projects of the machine send conditional requests, and still work when rate limited.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct ApiResponse {
+    etag: String,
+    body: String,
+}
+
+impl ApiResponse {
+    fn file(cache_dir: &Path, url: &str) -> PathBuf {
+        cache_dir.join("github-api").join(format!("{:016x}.json", seahash::hash(url.as_bytes())))
+    }
+
+    fn read(file: &Path) -> Option<Self> {
+        let data = fs::read(file).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    /// Only the responses with an ETag, as the others can't be revalidated.
+    fn write(file: &Path, response: &http::Response) {
+        let Some(etag) = response.headers.get(ETAG).and_then(|etag| etag.to_str().ok()) else {
+            return;
+        };
+        let Ok(body) = String::from_utf8(response.body.to_vec()) else {
+            return;
+        };
+        let cached = Self { etag: etag.to_string(), body };
+        let written = file
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| write_replacing(file, &serde_json::to_vec(&cached).unwrap_or_default()));
+        if let Err(e) = written {
+            log::debug!("Command could not write {}: {e}", file.display());
+        }
+    }
+}
+
+/// Writes a file of the cache dir shared by the parallel jobs of a machine through a temporary
+/// file of the process, so that the others read either the previous content or the new one.
+fn write_replacing(file: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut tmp = file.to_path_buf().into_os_string();
+    tmp.push(format!(".{}.tmp", std::process::id()));
+    fs::write(&tmp, data)?;
+    let renamed = fs::rename(&tmp, file);
+    if renamed.is_err() {
+        _ = fs::remove_file(&tmp);
+    }
+    renamed
+}
+
+/// The token for the GitHub API, which has a much higher rate limit than the anonymous one
+/// (that shared CI runners quickly reach).
+fn github_token() -> Option<String> {
+    ["LEPTOS_GITHUB_TOKEN", "GITHUB_TOKEN"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|token| !token.trim().is_empty())
+}
+
+/// GitHub answers 403 (or 429) without any remaining requests when rate limited.
+fn is_rate_limited(status: StatusCode, headers: &HeaderMap) -> bool {
+    let exhausted = headers
+        .get("x-ratelimit-remaining")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim() == "0");
+    status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN && (exhausted || headers.contains_key("retry-after")))
+}
+
+const ONE_DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// The content of the version check marker file: the unix timestamp (in millis) of the
+/// last check and, on the next line, the latest version it found.
+#[derive(Debug, Default, PartialEq)]
+struct VersionMarker {
+    checked: u64,
+    latest: Option<String>,
+}
+
+impl VersionMarker {
+    async fn read(file: &Path) -> Self {
+        match tokio::fs::read_to_string(file).await {
+            Ok(content) => Self::parse(&content),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut lines = content.lines().map(str::trim);
+        Self {
+            checked: lines.next().and_then(|l| l.parse().ok()).unwrap_or_default(),
+            latest: lines.next().filter(|l| !l.is_empty()).map(str::to_string),
+        }
+    }
+
+    fn is_due(&self, now: u64) -> bool {
+        now.saturating_sub(self.checked) > ONE_DAY_MILLIS
+    }
+
+    /// The last version found, else the default one of the tool.
+    fn version_or(&self, default_version: &str) -> String {
+        self.latest.clone().unwrap_or_else(|| default_version.to_string())
+    }
+}
+
+impl std::fmt::Display for VersionMarker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.checked)?;
+        if let Some(latest) = &self.latest {
+            write!(f, "\n{latest}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The GitHub repo the prebuilt cargo-leptos binaries are released on.
+const SELF_GITHUB_OWNER: &str = "leptos-rs";
+const SELF_GITHUB_REPO: &str = "cargo-leptos";
+
+/// The latest cargo-leptos release, including the pre-releases when `nightly` is set.
+pub async fn latest_self_release(nightly: bool) -> Option<String> {
+    github_release("cargo-leptos", SELF_GITHUB_OWNER, SELF_GITHUB_REPO, nightly).await
+}
+
+/// True when the `latest` release version is newer than the `current` one.
+pub fn is_newer_version(latest: &str, current: &str) -> bool {
+    match (normalize_version(latest), normalize_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// Downloads the prebuilt cargo-leptos binary of the release to the cache dir
+/// and returns its path.
+pub async fn download_self(version: &str) -> Result<PathBuf> {
+    let (target_os, target_arch) = os_arch()?;
+    let (triple, ext) = match (target_os, target_arch) {
+        ("linux", arch) if is_linux_musl_env() => (format!("{arch}-unknown-linux-musl"), "tar.gz"),
+        ("linux", arch) => (format!("{arch}-unknown-linux-gnu"), "tar.gz"),
+        ("macos", arch) => (format!("{arch}-apple-darwin"), "tar.gz"),
+        ("windows", arch) => (format!("{arch}-pc-windows-msvc"), "zip"),
+        _ => bail!("No prebuilt cargo-leptos for {target_os} {target_arch}"),
+    };
+    let meta = ExeMeta {
+        name: "cargo-leptos",
+        version: version.to_string(),
+        url: format!(
+            "https://github.com/{SELF_GITHUB_OWNER}/{SELF_GITHUB_REPO}/releases/download/{version}/cargo-leptos-{triple}.{ext}"
+        ),
+        exe: if target_os == "windows" { "cargo-leptos.exe" } else { "cargo-leptos" }.to_string(),
+        manual: "Try installing with: cargo install cargo-leptos --locked --force".to_string(),
+        pinned: true,
+    };
+    meta.cached().await
+}
+
 // fallback to this crate until rust stable includes async traits
 // https://github.com/dtolnay/async-trait
 use async_trait::async_trait;
 
 struct CommandTailwind;
 struct CommandWasmOpt;
+struct CommandWasmSplit;
 struct CommandSass;
 struct CommandCargoGenerate;
+struct CommandEsbuild;
 
 #[async_trait]
 impl Command for CommandTailwind {
@@ -389,6 +1018,33 @@ impl Command for CommandWasmOpt {
     }
 }
 
+/// Another tool of the binaryen release of wasm-opt, with the same version.
+#[async_trait]
+impl Command for CommandWasmSplit {
+    fn name(&self) -> &'static str { "wasm-split" }
+    fn default_version(&self) -> &'static str { CommandWasmOpt.default_version() }
+    fn env_var_version_name(&self) -> &'static str { ENV_VAR_LEPTOS_WASM_OPT_VERSION }
+    fn github_owner(&self) -> &'static str { CommandWasmOpt.github_owner() }
+    fn github_repo(&self) -> &'static str { CommandWasmOpt.github_repo() }
+
+    fn download_url(&self, target_os: &str, target_arch: &str, version: &str) -> Result<String> {
+        CommandWasmOpt.download_url(target_os, target_arch, version)
+    }
+
+    fn executable_name(&self, target_os: &str, _target_arch: &str, version: Option<&str>) -> Result<String> {
+        if version.is_none() { bail!("Version is required for WASM Split, none provided")};
+
+        Ok(match target_os {
+            "windows" => format!("binaryen-{}/bin/{}.exe", version.unwrap_or_default(), self.name()),
+            _ => format!("binaryen-{}/bin/{}", version.unwrap_or_default(), self.name()),
+        })
+    }
+
+    fn manual_install_instructions(&self) -> String {
+        CommandWasmOpt.manual_install_instructions()
+    }
+}
+
 #[async_trait]
 impl Command for CommandSass {
     fn name(&self) -> &'static str { "sass" }
@@ -492,6 +1148,42 @@ impl Command for CommandCargoGenerate {
     }
 }
 
+/// Released on GitHub, but the binaries are only published as the npm packages of the platforms,
+/// which need no node to run.
+#[async_trait]
+impl Command for CommandEsbuild {
+    fn name(&self) -> &'static str { "esbuild" }
+    fn default_version(&self) -> &'static str { "v0.19.5" }
+    fn env_var_version_name(&self) -> &'static str { ENV_VAR_LEPTOS_ESBUILD_VERSION }
+    fn github_owner(&self) -> &'static str { "evanw" }
+    fn github_repo(&self) -> &'static str { "esbuild" }
+
+    fn download_url(&self, target_os: &str, target_arch: &str, version: &str) -> Result<String> {
+        let platform = match (target_os, target_arch) {
+            ("linux", "x86_64") => "linux-x64",
+            ("linux", "aarch64") => "linux-arm64",
+            ("macos", "x86_64") => "darwin-x64",
+            ("macos", "aarch64") => "darwin-arm64",
+            ("windows", "x86_64") => "win32-x64",
+            ("windows", "aarch64") => "win32-arm64",
+            _ => bail!("No esbuild package found for {target_os} {target_arch}"),
+        };
+        let version = version.trim_start_matches('v');
+        Ok(format!("https://registry.npmjs.org/@esbuild/{platform}/-/{platform}-{version}.tgz"))
+    }
+
+    fn executable_name(&self, target_os: &str, _target_arch: &str, _version: Option<&str>) -> Result<String> {
+        Ok(match target_os {
+            "windows" => "package/esbuild.exe".to_string(),
+            _ => "package/bin/esbuild".to_string(),
+        })
+    }
+
+    fn manual_install_instructions(&self) -> String {
+        "Try manually installing esbuild: https://esbuild.github.io/getting-started/#install-esbuild".to_string()
+    }
+}
+
 #[async_trait]
 /// Template trait, implementors should only fill in
 /// the command-specific logic. Handles caching, latest
@@ -535,92 +1227,39 @@ trait Command {
             url: url.to_owned(),
             exe: exe.to_string(),
             manual: self.manual_install_instructions(),
+            pinned: env::var(self.env_var_version_name()).is_ok(),
         })
     }
 
-    /// Returns true if the command should check for a new version
-    /// Returns false in case of any errors (no check)
-    async fn should_check_for_new_version(&self) -> bool {
+    /// The marker file of the last version check, in the cache directory.
+    /// `None` (no check) in case of any errors
+    fn version_marker_file(&self) -> Option<PathBuf> {
         match get_cache_dir() {
             Ok(dir) => {
                 let marker = dir.join(format!(".{}_last_checked", self.name()));
-                return match (marker.exists(), marker.is_dir()) {
-                    (_, true) => { // conflicting dir instead of a marker file, bail
-                        log::warn!("Command [{}] encountered a conflicting dir in the cache, please delete {}",
-                            self.name(), marker.display());
-
-                            false
-                    },
-                    (true, _) => { // existing marker file, read and check if last checked > 1 DAY
-                        let contents = tokio::fs::read_to_string(&marker).await;
-                        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH);
-                        if let Some(timestamp) =
-                            contents.ok()
-                                .map(|s| s.parse::<u64>().ok().unwrap_or_default()) {
-                            let last_checked = Duration::from_millis(timestamp);
-                            let one_day = Duration::from_secs(24 * 60 * 60);
-                            if let Ok(now) = now {
-                                match (now - last_checked) > one_day {
-                                    true => tokio::fs::write(&marker, now.as_millis().to_string()).await.is_ok(),
-                                    false => false,
-                                }
-                            } else { false }
-                        } else { false }
-                    },
-                    (false, _) => { // no marker file yet, record and hint to check
-                        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH);
-                        return if let Ok(unix_timestamp) = now {
-                            tokio::fs::write(marker, unix_timestamp.as_millis().to_string()).await.is_ok()
-                        } else {
-                            false
-                        }
-                    }
+                if marker.is_dir() {
+                    // conflicting dir instead of a marker file, bail
+                    log::warn!("Command [{}] encountered a conflicting dir in the cache, please delete {}",
+                        self.name(), marker.display());
+                    return None;
                 }
+                Some(marker)
             },
             Err(e) => {
                 log::warn!("Command {} failed to get cache dir: {}", self.name(), e);
-                false
+                None
             }
         }
     }
 
-
     async fn check_for_latest_version(&self) -> Option<String> {
-        log::debug!("Command [{}] checking for the latest available version", self.name());
-
-        let client = ClientBuilder::default()
-            // this github api allows anonymous, but requires a user-agent header be set
-            .user_agent("cargo-leptos")
-            .build()
-            .unwrap_or_default();
-
-        if let Ok(response) = client.get(
-            format!("https://api.github.com/repos/{}/{}/releases/latest", self.github_owner(), self.github_repo()))
-            .send().await {
-
-            if !response.status().is_success() {
-                log::error!("Command [{}] GitHub API request failed: {}", self.name(), response.status());
-                return None
-            }
-
-            #[derive(serde::Deserialize)]
-            struct Github {
-                tag_name: String, // this is the version number, not the git tag
-            }
-
-            let github: Github = match response.json().await {
-                Ok(json) => json,
-                Err(e) => {
-                    log::debug!("Command [{}] failed to parse the response JSON from the GitHub API: {}", self.name(), e);
-                    return None
-                }
-            };
+        let cache_dir = get_cache_dir().ok();
+        self.latest_version_from(&HttpDownloader, &http::github_api_url(), cache_dir.as_deref()).await
+    }
 
-            Some(github.tag_name)
-        } else {
-            log::debug!("Command [{}] failed to check for the latest version", self.name());
-            None
-        }
+    /// The latest release of the tool, according to the GitHub API at `api_url`.
+    async fn latest_version_from(&self, downloader: &dyn Downloader, api_url: &str, cache_dir: Option<&Path>) -> Option<String> {
+        github_release_at(downloader, api_url, cache_dir, self.name(), self.github_owner(), self.github_repo(), false).await
     }
 
     /// get the latest version from github api
@@ -634,17 +1273,48 @@ trait Command {
         log::trace!("Command [{}] is_force_pin_version: {} - {:?}",
             self.name(), is_force_pin_version, env::var(self.env_var_version_name()));
 
-        if !is_force_pin_version && !self.should_check_for_new_version().await {
-            log::trace!("Command [{}] NOT checking for the latest available version", &self.name());
-            return self.default_version().into();
+        if !is_force_pin_version {
+            if let Some(locked) = ToolsLock::locked(self.name()) {
+                log::trace!("Command [{}] using the locked version {locked}", self.name());
+                return locked;
+            }
         }
 
-        let version =
-            env::var(self.env_var_version_name())
-                .unwrap_or_else(|_| self.default_version().into()).to_owned();
+        if is_frozen() {
+            log::trace!("Command [{}] NOT checking for the latest version of the frozen tools", self.name());
+            return env::var(self.env_var_version_name()).unwrap_or_else(|_| self.default_version().into());
+        }
+
+        let marker_file = self.version_marker_file();
+        let marker = match &marker_file {
+            Some(file) => VersionMarker::read(file).await,
+            None => VersionMarker::default(),
+        };
+        let now = now_millis();
+        // checks at most once a day, or every time for a pinned version
+        let check = is_force_pin_version || (marker_file.is_some() && marker.is_due(now));
+
+        if !check {
+            log::trace!("Command [{}] NOT checking for the latest available version", &self.name());
+            return marker.version_or(self.default_version());
+        }
 
         let latest = self.check_for_latest_version().await;
 
+        // a failed (offline or rate limited) check is recorded too, so it isn't retried until the
+        // next day, and falls back to the last version found
+        let marker = VersionMarker { checked: now, latest: latest.or(marker.latest) };
+        if let Some(file) = &marker_file {
+            if let Err(e) = write_replacing(file, marker.to_string().as_bytes()) {
+                log::debug!("Command [{}] could not write {}: {e}", self.name(), file.display());
+            }
+        }
+
+        let Ok(version) = env::var(self.env_var_version_name()) else {
+            return marker.version_or(self.default_version());
+        };
+        let latest = marker.latest;
+
         match latest {
             Some(latest) => {
                 let norm_latest = normalize_version(latest.as_str());
@@ -678,6 +1348,43 @@ mod tests {
     use cargo_metadata::semver::Version;
     use super::*;
 
+    #[test]
+    fn test_version_marker() {
+        // the marker of older versions only has the timestamp
+        let marker = VersionMarker::parse("1000");
+        assert_eq!(marker, VersionMarker { checked: 1000, latest: None });
+        assert!(marker.is_due(1000 + ONE_DAY_MILLIS + 1));
+        assert!(!marker.is_due(2000));
+
+        assert_eq!(marker.version_or("v3.3.3"), "v3.3.3");
+
+        let marker = VersionMarker { checked: 1000, latest: Some("v3.4.0".to_string()) };
+        assert_eq!(VersionMarker::parse(&marker.to_string()), marker);
+        // the cached answer is used until the next check
+        assert!(!marker.is_due(2000));
+        assert_eq!(marker.version_or("v3.3.3"), "v3.4.0");
+    }
+
+    #[test]
+    fn test_rate_limited() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_rate_limited(StatusCode::FORBIDDEN, &headers));
+        assert!(is_rate_limited(StatusCode::TOO_MANY_REQUESTS, &headers));
+        headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+        assert!(is_rate_limited(StatusCode::FORBIDDEN, &headers));
+        assert!(!is_rate_limited(StatusCode::OK, &headers));
+    }
+
+    #[test]
+    fn test_esbuild_package() {
+        assert_eq!(
+            CommandEsbuild.download_url("macos", "aarch64", "v0.19.5").unwrap(),
+            "https://registry.npmjs.org/@esbuild/darwin-arm64/-/darwin-arm64-0.19.5.tgz"
+        );
+        assert_eq!(CommandEsbuild.executable_name("linux", "x86_64", None).unwrap(), "package/bin/esbuild");
+        assert_eq!(CommandEsbuild.executable_name("windows", "x86_64", None).unwrap(), "package/esbuild.exe");
+    }
+
     #[test]
     fn test_sanitize_version_prefix() {
         let version = sanitize_version_prefix("v1.2.3");
@@ -719,9 +1426,431 @@ mod tests {
         }));
     }
 
+    #[test]
+    fn test_is_newer_version() {
+        assert!(is_newer_version("v0.2.0", "0.1.12"));
+        assert!(is_newer_version("0.1.12", "0.1.12-alpha"));
+        assert!(!is_newer_version("v0.1.12", "0.1.12"));
+        assert!(!is_newer_version("0.1.11", "0.1.12-alpha"));
+    }
+
     #[test]
     fn test_invalid_versions() {
         let version = normalize_version("1a-test");
         assert_eq!(version, None);
     }
+
+    /// Serves the GitHub API and a download on a local port, returning its url.
+    fn mock_github() -> String {
+        use axum::{routing::get, Json, Router};
+        let app = Router::new()
+            .route("/repos/o/r/releases/latest", get(|| async {
+                Json(serde_json::json!({ "tag_name": "v1.2.3" }))
+            }))
+            .route("/repos/o/r/releases", get(|| async {
+                Json(serde_json::json!([{ "tag_name": "v1.3.0-beta" }]))
+            }))
+            .route("/download/tool", get(|| async { "binary" }));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = axum::Server::from_tcp(listener).unwrap();
+        tokio::spawn(server.serve(app.into_make_service()));
+        url
+    }
+
+    #[tokio::test]
+    async fn test_mock_github() {
+        let url = mock_github();
+        let latest = github_release_at(&HttpDownloader, &url, None, "tool", "o", "r", false).await;
+        assert_eq!(latest.as_deref(), Some("v1.2.3"));
+        let latest = github_release_at(&HttpDownloader, &url, None, "tool", "o", "r", true).await;
+        assert_eq!(latest.as_deref(), Some("v1.3.0-beta"));
+        let latest = github_release_at(&HttpDownloader, &url, None, "tool", "o", "none", false).await;
+        assert_eq!(latest, None);
+
+        let meta = tool_meta(&format!("{url}/download/tool"), "tool");
+        let cache = ExeCache { exe_dir: PathBuf::new(), meta: &meta, downloader: &HttpDownloader };
+        assert_eq!(cache.fetch_archive().await.unwrap().as_ref(), b"binary");
+    }
+
+    /// Answers the requests with the fixture of their url, or 404, and 304 to the ones with its ETag.
+    #[derive(Default)]
+    struct Fixtures(std::collections::HashMap<String, http::Response>);
+
+    impl Fixtures {
+        fn with(mut self, url: &str, status: StatusCode, body: impl Into<Bytes>) -> Self {
+            let response = http::Response { status, headers: HeaderMap::new(), body: body.into() };
+            self.0.insert(url.to_string(), response);
+            self
+        }
+    }
+
+    #[async_trait]
+    impl Downloader for Fixtures {
+        async fn get(&self, request: Request) -> Result<http::Response> {
+            let response = self.0.get(&request.url);
+            let etag = response.and_then(|response| response.headers.get(ETAG));
+            if etag.is_some_and(|etag| request.if_none_match.as_deref() == etag.to_str().ok()) {
+                return Ok(http::Response {
+                    status: StatusCode::NOT_MODIFIED,
+                    headers: HeaderMap::new(),
+                    body: Bytes::new(),
+                });
+            }
+            Ok(response.cloned().unwrap_or(http::Response {
+                status: StatusCode::NOT_FOUND,
+                headers: HeaderMap::new(),
+                body: Bytes::new(),
+            }))
+        }
+    }
+
+    /// Counts the downloads, each taking a while so that the concurrent installs overlap.
+    struct Slow<'a>(&'a Fixtures, std::sync::atomic::AtomicUsize);
+
+    #[async_trait]
+    impl Downloader for Slow<'_> {
+        async fn get(&self, request: Request) -> Result<http::Response> {
+            self.1.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            self.0.get(request).await
+        }
+    }
+
+    fn tool_meta(url: &str, exe: &str) -> ExeMeta {
+        ExeMeta {
+            name: "tool",
+            version: "v1.2.3".to_string(),
+            url: url.to_string(),
+            exe: exe.to_string(),
+            manual: String::new(),
+            pinned: true,
+        }
+    }
+
+    async fn install(fixtures: &Fixtures, url: &str, exe: &str) -> (temp_dir::TempDir, Result<PathBuf>) {
+        let dir = temp_dir::TempDir::new().unwrap();
+        let path = tool_meta(url, exe)._with_cache_dir(dir.path(), fixtures).await;
+        (dir, path)
+    }
+
+    fn tar(path: &str, data: &[u8]) -> Vec<u8> {
+        let mut tar = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        tar.append_data(&mut header, path, data).unwrap();
+        tar.into_inner().unwrap()
+    }
+
+    fn tar_gz(path: &str, data: &[u8]) -> Vec<u8> {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&tar(path, data)).unwrap();
+        gz.finish().unwrap()
+    }
+
+    fn tar_xz(path: &str, data: &[u8]) -> Vec<u8> {
+        let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+        xz.write_all(&tar(path, data)).unwrap();
+        xz.finish().unwrap()
+    }
+
+    fn tar_zst(path: &str, data: &[u8]) -> Vec<u8> {
+        zstd::encode_all(Cursor::new(tar(path, data)), 0).unwrap()
+    }
+
+    fn zip(path: &str, data: &[u8]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(path, zip::write::FileOptions::default()).unwrap();
+        zip.write_all(data).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_install_tar_gz() {
+        let url = "https://dl.test/tool.tar.gz";
+        let fixtures = Fixtures::default().with(url, StatusCode::OK, tar_gz("tool-v1/tool", b"exe"));
+        let (_dir, path) = install(&fixtures, url, "tool-v1/tool").await;
+        assert_eq!(fs::read(path.unwrap()).unwrap(), b"exe");
+    }
+
+    #[tokio::test]
+    async fn test_install_tar_xz_and_zst() {
+        let xz = "https://dl.test/tool.tar.xz";
+        let zst = "https://dl.test/tool.tar.zst";
+        let fixtures = Fixtures::default()
+            .with(xz, StatusCode::OK, tar_xz("tool-v1/tool", b"xz"))
+            .with(zst, StatusCode::OK, tar_zst("tool-v1/tool", b"zst"));
+        let (_dir, path) = install(&fixtures, xz, "tool-v1/tool").await;
+        assert_eq!(fs::read(path.unwrap()).unwrap(), b"xz");
+        let (_dir, path) = install(&fixtures, zst, "tool-v1/tool").await;
+        assert_eq!(fs::read(path.unwrap()).unwrap(), b"zst");
+    }
+
+    #[test]
+    fn test_archive_sniffing() {
+        let url = "https://dl.test/releases/latest/download?asset=tool";
+        assert_eq!(Archive::of(url, &zip("tool", b"exe")), Archive::Zip);
+        assert_eq!(Archive::of(url, &tar_gz("tool", b"exe")), Archive::TarGz);
+        assert_eq!(Archive::of(url, &tar_xz("tool", b"exe")), Archive::TarXz);
+        assert_eq!(Archive::of(url, &tar_zst("tool", b"exe")), Archive::TarZst);
+        assert_eq!(Archive::of(url, b"\x7fELF..."), Archive::Binary);
+        // the extension wins over the content
+        assert_eq!(Archive::of("https://dl.test/tool.tar.xz?x=1", b"PK\x03\x04"), Archive::TarXz);
+    }
+
+    /// A tar with the entry at the given path, which the tar builder would refuse.
+    fn crafted_tar(path: &str, kind: tar::EntryType, link: Option<&str>) -> Vec<u8> {
+        let mut header = tar::Header::new_old();
+        header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+        if let Some(link) = link {
+            header.as_old_mut().linkname[..link.len()].copy_from_slice(link.as_bytes());
+        }
+        header.set_entry_type(kind);
+        header.set_size(if kind.is_file() { 4 } else { 0 });
+        header.set_mode(0o644);
+        header.set_cksum();
+        let mut tar = tar::Builder::new(Vec::new());
+        let data: &[u8] = if kind.is_file() { b"evil" } else { b"" };
+        tar.append(&header, data).unwrap();
+        tar.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_enclosed_path() {
+        assert_eq!(enclosed_path(Path::new("a/./b/../c")), Some(PathBuf::from("a/c")));
+        assert_eq!(enclosed_path(Path::new("a/../../c")), None);
+        assert_eq!(enclosed_path(Path::new("/etc/passwd")), None);
+    }
+
+    #[test]
+    fn test_malicious_archives() {
+        let dir = temp_dir::TempDir::new().unwrap();
+        let dest = dir.path().join("dest");
+        let extract = |tar: Vec<u8>| extract_tar(Cursor::new(tar), &dest);
+
+        let err = extract(crafted_tar("../evil", tar::EntryType::Regular, None)).unwrap_err();
+        assert!(err.to_string().contains("outside of the extraction dir"), "{err}");
+        assert!(!dir.path().join("evil").exists());
+
+        let symlink = |link| crafted_tar("link", tar::EntryType::Symlink, Some(link));
+        assert!(extract(symlink("../..")).is_err());
+        assert!(extract(symlink("/etc/passwd")).is_err());
+        let hard_link = crafted_tar("link", tar::EntryType::Link, Some("../outside"));
+        assert!(extract(hard_link).is_err());
+
+        let err = extract_zip(&Bytes::from(zip("a/../../evil", b"evil")), &dest).unwrap_err();
+        assert!(err.to_string().contains("outside of the extraction dir"), "{err}");
+        assert!(!dir.path().join("evil").exists());
+
+        // a chain of links, each pointing inside on its own
+        extract(symlink(".")).unwrap();
+        extract(crafted_tar("link/up", tar::EntryType::Symlink, Some(".."))).unwrap();
+        let err = extract(crafted_tar("link/up/evil", tar::EntryType::Regular, None)).unwrap_err();
+        assert!(format!("{err:?}").contains("outside of destination"), "{err:?}");
+        assert!(!dir.path().join("evil").exists());
+
+        // the links inside are fine
+        extract(crafted_tar("bin/tool", tar::EntryType::Regular, None)).unwrap();
+        extract(crafted_tar("tool", tar::EntryType::Symlink, Some("bin/tool"))).unwrap();
+        #[cfg(target_family = "unix")]
+        assert_eq!(fs::read(dest.join("tool")).unwrap(), b"evil");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_zip_link_chain() {
+        let dir = temp_dir::TempDir::new().unwrap();
+        let dest = dir.path().join("dest");
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        zip.add_symlink("link", ".", options).unwrap();
+        zip.add_symlink("link/up", "..", options).unwrap();
+        zip.start_file("link/up/evil", options).unwrap();
+        zip.write_all(b"evil").unwrap();
+        let zip = zip.finish().unwrap().into_inner();
+
+        let err = extract_zip(&Bytes::from(zip), &dest).unwrap_err();
+        assert!(err.to_string().contains("through a link"), "{err}");
+        assert!(!dir.path().join("evil").exists());
+    }
+
+    #[tokio::test]
+    async fn test_install_zip() {
+        let url = "https://dl.test/tool.zip";
+        let fixtures = Fixtures::default().with(url, StatusCode::OK, zip("tool-v1/tool.exe", b"exe"));
+        let (_dir, path) = install(&fixtures, url, "tool-v1/tool.exe").await;
+        let path = path.unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"exe");
+        #[cfg(target_family = "unix")]
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o550);
+    }
+
+    #[tokio::test]
+    async fn test_install_binary() {
+        let url = "https://dl.test/tool-linux-x64";
+        let fixtures = Fixtures::default().with(url, StatusCode::OK, &b"exe"[..]);
+        let (_dir, path) = install(&fixtures, url, "tool").await;
+        let path = path.unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"exe");
+        #[cfg(target_family = "unix")]
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o550);
+    }
+
+    #[tokio::test]
+    async fn test_install_cached() {
+        let dir = temp_dir::TempDir::new().unwrap();
+        let meta = tool_meta("https://dl.test/tool", "tool");
+        let exe_dir = dir.path().join(meta.get_name());
+        fs::create_dir_all(&exe_dir).unwrap();
+        fs::write(exe_dir.join("tool"), b"cached").unwrap();
+        fs::write(exe_dir.join(INSTALLED_MARKER), b"").unwrap();
+        // nothing to download from
+        let path = meta._with_cache_dir(dir.path(), &Fixtures::default()).await.unwrap();
+        assert_eq!(fs::read(path).unwrap(), b"cached");
+    }
+
+    #[tokio::test]
+    async fn test_install_concurrent() {
+        let url = "https://dl.test/tool-v1.tar.gz";
+        let fixtures = Fixtures::default().with(url, StatusCode::OK, tar_gz("tool", b"exe"));
+        let slow = Slow(&fixtures, Default::default());
+        let dir = temp_dir::TempDir::new().unwrap();
+        let meta = tool_meta(url, "tool");
+        // the second waits for the lock of the first, and finds the tool installed
+        let (first, second) = tokio::join!(
+            meta._with_cache_dir(dir.path(), &slow),
+            meta._with_cache_dir(dir.path(), &slow)
+        );
+        assert_eq!(first.unwrap(), second.unwrap());
+        assert_eq!(slow.1.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_install_incomplete() {
+        let dir = temp_dir::TempDir::new().unwrap();
+        let url = "https://dl.test/tool";
+        let meta = tool_meta(url, "tool");
+        // left by an install interrupted during the extraction
+        let exe_dir = dir.path().join(meta.get_name());
+        fs::create_dir_all(&exe_dir).unwrap();
+        fs::write(exe_dir.join("tool"), b"trunc").unwrap();
+
+        let fixtures = Fixtures::default().with(url, StatusCode::OK, &b"exe"[..]);
+        let path = meta._with_cache_dir(dir.path(), &fixtures).await.unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"exe");
+        assert!(exe_dir.join(INSTALLED_MARKER).exists());
+        assert!(!dir.path().join(format!("{}.partial", meta.get_name())).exists());
+
+        // a failed extraction leaves nothing behind
+        let url = "https://dl.test/tool.tar.gz";
+        let meta = tool_meta(url, "tool");
+        let fixtures = Fixtures::default().with(url, StatusCode::OK, &b"not gzip"[..]);
+        let dir = temp_dir::TempDir::new().unwrap();
+        assert!(meta._with_cache_dir(dir.path(), &fixtures).await.is_err());
+        assert!(!dir.path().join(meta.get_name()).exists());
+        assert!(!dir.path().join(format!("{}.partial", meta.get_name())).exists());
+    }
+
+    #[tokio::test]
+    async fn test_install_failures() {
+        let (_dir, res) = install(&Fixtures::default(), "https://dl.test/tool.tar.gz", "tool").await;
+        let err = format!("{:#}", res.unwrap_err());
+        assert!(err.contains("404"), "{err}");
+
+        let url = "https://dl.test/tool.tar.gz";
+        let fixtures = Fixtures::default().with(url, StatusCode::OK, &b"not gzip"[..]);
+        let (_dir, res) = install(&fixtures, url, "tool").await;
+        assert!(res.is_err());
+
+        let url = "https://dl.test/tool.zip";
+        let fixtures = Fixtures::default().with(url, StatusCode::OK, zip("other", b"exe"));
+        let (_dir, res) = install(&fixtures, url, "tool").await;
+        let err = format!("{:#}", res.unwrap_err());
+        assert!(err.contains("could still not be found"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_local_binary() {
+        let dir = temp_dir::TempDir::new().unwrap();
+        let local = Utf8Path::from_path(dir.path()).unwrap().join("sass");
+        fs::write(&local, b"local").unwrap();
+        // neither looked up in the PATH nor downloaded
+        let path = Exe::Sass.get_or_local(Some(&local)).await.unwrap();
+        assert_eq!(path, local.as_std_path());
+        assert_eq!(Exe::Sass.locate(Some(&local)).await.unwrap(), local.as_str());
+
+        let missing = local.with_file_name("missing");
+        let err = Exe::Sass.get_or_local(Some(&missing)).await.unwrap_err();
+        assert!(err.to_string().contains("missing"), "{err}");
+    }
+
+    #[test]
+    fn test_frozen() {
+        let dir = temp_dir::TempDir::new().unwrap();
+        let meta = tool_meta("https://dl.test/tool", "tool");
+        let err = meta.frozen_in(dir.path()).unwrap_err();
+        assert!(format!("{err:#}").contains("--frozen-tools"), "{err:#}");
+
+        let exe_dir = dir.path().join(meta.get_name());
+        fs::create_dir_all(&exe_dir).unwrap();
+        fs::write(exe_dir.join("tool"), b"cached").unwrap();
+        fs::write(exe_dir.join(INSTALLED_MARKER), b"").unwrap();
+        assert_eq!(meta.frozen_in(dir.path()).unwrap(), exe_dir.join("tool"));
+    }
+
+    #[tokio::test]
+    async fn test_latest_version() {
+        let api = "https://api.test";
+        let url = format!("{api}/repos/tailwindlabs/tailwindcss/releases/latest");
+
+        let fixtures = Fixtures::default().with(&url, StatusCode::OK, r#"{"tag_name":"v3.4.0"}"#);
+        let latest = CommandTailwind.latest_version_from(&fixtures, api, None).await;
+        assert_eq!(latest.as_deref(), Some("v3.4.0"));
+
+        let fixtures = Fixtures::default().with(&url, StatusCode::OK, "not json");
+        assert_eq!(CommandTailwind.latest_version_from(&fixtures, api, None).await, None);
+
+        let mut fixtures = Fixtures::default().with(&url, StatusCode::FORBIDDEN, "");
+        let limited = fixtures.0.get_mut(&url).unwrap();
+        limited.headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+        assert_eq!(CommandTailwind.latest_version_from(&fixtures, api, None).await, None);
+
+        assert_eq!(CommandTailwind.latest_version_from(&Fixtures::default(), api, None).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_cached_latest_version() {
+        let dir = temp_dir::TempDir::new().unwrap();
+        let cache = Some(dir.path());
+        let api = "https://api.test";
+        let url = format!("{api}/repos/tailwindlabs/tailwindcss/releases/latest");
+
+        let mut fixtures = Fixtures::default().with(&url, StatusCode::OK, r#"{"tag_name":"v3.4.0"}"#);
+        let response = fixtures.0.get_mut(&url).unwrap();
+        response.headers.insert(ETAG, "\"abc\"".parse().unwrap());
+        let latest = CommandTailwind.latest_version_from(&fixtures, api, cache).await;
+        assert_eq!(latest.as_deref(), Some("v3.4.0"));
+        let cached = ApiResponse::read(&ApiResponse::file(dir.path(), &url)).unwrap();
+        assert_eq!(cached, ApiResponse { etag: "\"abc\"".to_string(), body: r#"{"tag_name":"v3.4.0"}"#.to_string() });
+
+        // the fixture answers 304 to the cached etag
+        let response = fixtures.0.get_mut(&url).unwrap();
+        response.body = Bytes::new();
+        let latest = CommandTailwind.latest_version_from(&fixtures, api, cache).await;
+        assert_eq!(latest.as_deref(), Some("v3.4.0"));
+
+        let mut fixtures = Fixtures::default().with(&url, StatusCode::FORBIDDEN, "");
+        let limited = fixtures.0.get_mut(&url).unwrap();
+        limited.headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+        let latest = CommandTailwind.latest_version_from(&fixtures, api, cache).await;
+        assert_eq!(latest.as_deref(), Some("v3.4.0"));
+
+        // without an etag, nothing is cached
+        let other = temp_dir::TempDir::new().unwrap();
+        let fixtures = Fixtures::default().with(&url, StatusCode::OK, r#"{"tag_name":"v3.4.0"}"#);
+        CommandTailwind.latest_version_from(&fixtures, api, Some(other.path())).await;
+        assert!(!other.path().join("github-api").exists());
+    }
 }
@@ -1,5 +1,5 @@
 use crate::ext::anyhow::{Context, Result};
-use camino::{Utf8Path, Utf8PathBuf};
+use camino::Utf8Path;
 use std::{collections::VecDeque, path::Path};
 use tokio::fs::{self, ReadDir};
 
@@ -102,7 +102,7 @@ pub async fn remove_dir_all<P: AsRef<Path>>(path: P) -> Result<()> {
         .context(format!("Could not remove dir {:?}", path.as_ref()))
 }
 
-pub async fn copy_dir_all(src: impl AsRef<Utf8Path>, dst: impl AsRef<Path>) -> Result<()> {
+pub async fn copy_dir_all(src: impl AsRef<Utf8Path>, dst: impl AsRef<Utf8Path>) -> Result<()> {
     cp_dir_all(&src, &dst).await.context(format!(
         "Copy dir recursively from {:?} to {:?}",
         src.as_ref(),
@@ -110,11 +110,11 @@ pub async fn copy_dir_all(src: impl AsRef<Utf8Path>, dst: impl AsRef<Path>) -> R
     ))
 }
 
-async fn cp_dir_all(src: impl AsRef<Utf8Path>, dst: impl AsRef<Path>) -> Result<()> {
+async fn cp_dir_all(src: impl AsRef<Utf8Path>, dst: impl AsRef<Utf8Path>) -> Result<()> {
     let src = src.as_ref();
-    let dst = Utf8PathBuf::from_path_buf(dst.as_ref().to_path_buf()).unwrap();
+    let dst = dst.as_ref();
 
-    self::create_dir_all(&dst).await?;
+    self::create_dir_all(dst).await?;
 
     let mut dirs = VecDeque::new();
     dirs.push_back(src.to_owned());
@@ -124,7 +124,7 @@ async fn cp_dir_all(src: impl AsRef<Utf8Path>, dst: impl AsRef<Path>) -> Result<
 
         while let Some(Ok(entry)) = entries.next() {
             let from = entry.path().to_owned();
-            let to = from.rebase(src, &dst)?;
+            let to = from.rebase(src, dst)?;
 
             if entry.file_type()?.is_dir() {
                 self::create_dir(&to).await?;
@@ -0,0 +1,69 @@
+use std::process::Command;
+
+use camino::Utf8Path;
+
+/// The commit and state of the git repository of a dir, found by shelling out to git.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitState {
+    pub sha: String,
+    /// the tracked files have uncommitted changes
+    pub dirty: bool,
+}
+
+impl GitState {
+    /// The state of the repository of the dir, none when it isn't in one or git is missing.
+    pub fn of(dir: &Utf8Path) -> Option<Self> {
+        let sha = git(dir, &["rev-parse", "HEAD"])?;
+        let status = git(dir, &["status", "--porcelain", "--untracked-files=no"])?;
+        Some(Self {
+            sha,
+            dirty: !status.is_empty(),
+        })
+    }
+
+    /// Like [GitState::of], with git run on a blocking thread for the async callers.
+    pub async fn load(dir: &Utf8Path) -> Option<Self> {
+        let dir = dir.to_owned();
+        tokio::task::spawn_blocking(move || Self::of(&dir))
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// The unix timestamp (seconds) of the commit of the dir.
+    pub fn commit_time(dir: &Utf8Path) -> Option<u64> {
+        git(dir, &["log", "-1", "--format=%ct"])?.parse().ok()
+    }
+
+    /// The abbreviated sha, as shown by `git log --oneline`.
+    pub fn short_sha(&self) -> &str {
+        &self.sha[..self.sha.len().min(7)]
+    }
+}
+
+fn git(dir: &Utf8Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        log::debug!("Git found no repository in {dir}");
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_sha() {
+        let state = GitState {
+            sha: "9a2ce40c1f3b2d6e".to_string(),
+            dirty: false,
+        };
+        assert_eq!(state.short_sha(), "9a2ce40");
+    }
+}
@@ -0,0 +1,133 @@
+use std::{env, time::Duration};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::{
+    header::{HeaderMap, IF_NONE_MATCH},
+    Client, ClientBuilder, Proxy, StatusCode,
+};
+
+use crate::ext::anyhow::Result;
+
+/// Overrides the GitHub API, e.g. for a GitHub Enterprise mirror or a mock server.
+pub const ENV_VAR_LEPTOS_GITHUB_API_URL: &str = "LEPTOS_GITHUB_API_URL";
+/// The timeout (in seconds) of a whole request, including the download of the body.
+pub const ENV_VAR_LEPTOS_HTTP_TIMEOUT: &str = "LEPTOS_HTTP_TIMEOUT";
+/// A proxy for all the requests, instead of the one of the `HTTPS_PROXY` env vars.
+pub const ENV_VAR_LEPTOS_HTTP_PROXY: &str = "LEPTOS_HTTP_PROXY";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+lazy_static::lazy_static! {
+    /// Shared by the tool downloads and the GitHub API checks, so that the connections
+    /// (often to the same github hosts) are kept alive and reused.
+    static ref CLIENT: Client = build_client();
+}
+
+pub fn client() -> &'static Client {
+    &CLIENT
+}
+
+/// The base url of the GitHub API, without trailing `/`.
+pub fn github_api_url() -> String {
+    env::var(ENV_VAR_LEPTOS_GITHUB_API_URL)
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .unwrap_or_else(|| "https://api.github.com".to_string())
+}
+
+fn build_client() -> Client {
+    let mut builder = ClientBuilder::new()
+        // the github api allows anonymous requests, but requires a user-agent header
+        .user_agent(concat!("cargo-leptos/", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(CONNECT_TIMEOUT)
+        .tcp_keepalive(Duration::from_secs(60));
+
+    if let Some(secs) = env::var(ENV_VAR_LEPTOS_HTTP_TIMEOUT)
+        .ok()
+        .and_then(|secs| secs.trim().parse::<u64>().ok())
+    {
+        builder = builder.timeout(Duration::from_secs(secs));
+    }
+
+    // without it, the proxy of the HTTPS_PROXY, HTTP_PROXY and NO_PROXY env vars is used
+    if let Ok(proxy) = env::var(ENV_VAR_LEPTOS_HTTP_PROXY) {
+        match Proxy::all(&proxy) {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(e) => log::warn!("Install ignoring the invalid {ENV_VAR_LEPTOS_HTTP_PROXY}: {e}"),
+        }
+    }
+
+    #[cfg(feature = "rustls")]
+    {
+        builder = builder.use_rustls_tls();
+    }
+
+    builder.build().unwrap_or_else(|e| {
+        log::warn!("Install could not configure the http client, using the defaults: {e}");
+        Client::new()
+    })
+}
+
+/// A GET request of a tool download or of the GitHub API.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub url: String,
+    pub bearer_token: Option<String>,
+    /// overrides the timeout of the client
+    pub timeout: Option<Duration>,
+    /// the ETag of the cached response, answered with a 304 when still current
+    pub if_none_match: Option<String>,
+}
+
+impl Request {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            bearer_token: None,
+            timeout: None,
+            if_none_match: None,
+        }
+    }
+}
+
+/// The answer to a [Request], with the whole body.
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+/// The transport of the tool downloads and the GitHub API checks,
+/// replaced by fixtures in the tests.
+#[async_trait]
+pub trait Downloader: Send + Sync {
+    async fn get(&self, request: Request) -> Result<Response>;
+}
+
+/// Downloads with the shared [client].
+pub struct HttpDownloader;
+
+#[async_trait]
+impl Downloader for HttpDownloader {
+    async fn get(&self, request: Request) -> Result<Response> {
+        let mut builder = client().get(&request.url);
+        if let Some(token) = &request.bearer_token {
+            builder = builder.bearer_auth(token);
+        }
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(etag) = &request.if_none_match {
+            builder = builder.header(IF_NONE_MATCH, etag);
+        }
+        let response = builder.send().await?;
+        Ok(Response {
+            status: response.status(),
+            headers: response.headers().clone(),
+            body: response.bytes().await?,
+        })
+    }
+}
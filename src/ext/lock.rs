@@ -0,0 +1,118 @@
+use std::{
+    env,
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use fs2::FileExt;
+
+use crate::{
+    ext::anyhow::{bail, Context, Result},
+    logger::GRAY,
+};
+
+/// An advisory lock file, so that concurrent cargo-leptos processes (e.g. a `build` while
+/// `watch` runs, or two CI jobs sharing a dir) don't write the same output. The lock is
+/// released when dropped, or by the OS when the process exits.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    path: Utf8PathBuf,
+}
+
+impl FileLock {
+    /// Takes the lock. When another process holds it, waits for it with `wait`, otherwise fails
+    /// naming that process. `what` describes the locked output for the messages.
+    pub async fn acquire(path: &Utf8Path, what: &str, wait: bool) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context(format!("Could not create {dir}"))?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .context(format!("Could not open the lock file {path}"))?;
+
+        if let Err(e) = file.try_lock_exclusive() {
+            if e.raw_os_error() != fs2::lock_contended_error().raw_os_error() {
+                return Err(e).context(format!("Could not lock {path}"));
+            }
+            let owner = read_owner(&mut file);
+            if !wait {
+                bail!(
+                    "The {what} is used by another cargo-leptos ({owner}). \
+                    Wait for it to finish, or run with --wait-lock. Lock file: {path}"
+                );
+            }
+            log::info!(
+                "Lock waiting for {owner} to release the {what} {}",
+                GRAY.paint(path.as_str())
+            );
+            file = tokio::task::spawn_blocking(move || file.lock_exclusive().map(|_| file))
+                .await?
+                .context(format!("Could not lock {path}"))?;
+        }
+
+        // tells the other processes who holds the lock
+        let owner = format!(
+            "pid {} running `cargo {}`",
+            std::process::id(),
+            env::args().skip(1).collect::<Vec<_>>().join(" ")
+        );
+        file.set_len(0)
+            .and_then(|_| file.write_all(owner.as_bytes()))
+            .context(format!("Could not write the lock file {path}"))?;
+        log::trace!("Lock acquired {}", GRAY.paint(path.as_str()));
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // not the std File::unlock of newer rust versions
+        if let Err(e) = FileExt::unlock(&self.file) {
+            log::debug!("Lock could not release {}: {e}", self.path);
+        }
+    }
+}
+
+/// The process holding the lock, as written in the file. Windows doesn't allow reading
+/// a file locked by another process.
+fn read_owner(file: &mut File) -> String {
+    let mut owner = String::new();
+    match file.read_to_string(&mut owner) {
+        Ok(_) if !owner.trim().is_empty() => owner.trim().to_string(),
+        _ => "another process".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileLock;
+    use camino::Utf8PathBuf;
+
+    #[tokio::test]
+    async fn second_lock_fails_naming_the_owner() {
+        let dir = temp_dir::TempDir::new().unwrap();
+        let path = Utf8PathBuf::from_path_buf(dir.path().join("site.lock")).unwrap();
+
+        let lock = FileLock::acquire(&path, "site", false).await.unwrap();
+        let err = FileLock::acquire(&path, "site", false).await.unwrap_err();
+        #[cfg(unix)]
+        assert!(
+            err.to_string()
+                .contains(&format!("pid {}", std::process::id())),
+            "{err}"
+        );
+        assert!(err.to_string().contains("--wait-lock"), "{err}");
+
+        drop(lock);
+        FileLock::acquire(&path, "site", false).await.unwrap();
+    }
+}
@@ -3,15 +3,27 @@ mod tests;
 
 pub mod anyhow;
 mod cargo;
+mod error;
+mod error_code;
 pub mod exe;
 pub mod fs;
+mod git;
+mod http;
+mod lock;
 mod path;
 pub mod sync;
+pub mod tool;
+mod tools_lock;
 mod util;
 
 pub use cargo::{MetadataExt, PackageExt};
+pub use error::{exit_code, report_error, ErrorFormat, ErrorSummary, LeptosError};
+pub use error_code::ErrorCode;
 pub use exe::{Exe, ExeMeta};
+pub use git::GitState;
+pub use lock::FileLock;
 pub use path::{
-    append_str_to_filename, determine_pdb_filename, remove_nested, PathBufExt, PathExt,
+    append_str_to_filename, determine_pdb_filename, remove_nested, utf8_path, PathBufExt, PathExt,
 };
-pub use util::{os_arch, StrAdditions};
+pub use tools_lock::{ToolsLock, TOOLS_LOCK_FILE};
+pub use util::{is_linux_musl_env, os_arch, StrAdditions};
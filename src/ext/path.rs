@@ -1,5 +1,6 @@
 use crate::ext::anyhow::{anyhow, Context, Result};
 use camino::{Utf8Path, Utf8PathBuf};
+use std::path::PathBuf;
 
 pub trait PathExt {
     /// converts this absolute path to relative if the start matches
@@ -10,6 +11,15 @@ pub trait PathExt {
 
     /// removes base from path (making sure they match)
     fn unbase(&self, base: &Utf8Path) -> Result<Utf8PathBuf>;
+
+    /// like `starts_with`, but ignoring the unc prefix and, on windows, the case and separators
+    fn starts_with_path(&self, base: &Utf8Path) -> bool;
+
+    /// like `==`, normalized as in [PathExt::starts_with_path]
+    fn is_same_path(&self, other: &Utf8Path) -> bool;
+
+    /// the path with `/` separators, for site-relative urls
+    fn to_url_path(&self) -> String;
 }
 
 pub trait PathBufExt: PathExt {
@@ -52,6 +62,18 @@ impl PathExt for Utf8Path {
             Ok(path)
         }
     }
+
+    fn starts_with_path(&self, base: &Utf8Path) -> bool {
+        path_starts_with(self.as_str(), base.as_str(), cfg!(windows))
+    }
+
+    fn is_same_path(&self, other: &Utf8Path) -> bool {
+        path_key(self.as_str(), cfg!(windows)) == path_key(other.as_str(), cfg!(windows))
+    }
+
+    fn to_url_path(&self) -> String {
+        url_path(self.as_str())
+    }
 }
 
 impl PathBufExt for Utf8PathBuf {
@@ -70,7 +92,7 @@ impl PathBufExt for Utf8PathBuf {
     }
 
     fn starts_with_any(&self, of: &[Utf8PathBuf]) -> bool {
-        of.iter().any(|p| self.starts_with(p))
+        of.iter().any(|p| self.starts_with_path(p))
     }
 
     fn is_ext_any(&self, of: &[&str]) -> bool {
@@ -164,6 +186,51 @@ impl PathExt for Utf8PathBuf {
     fn unbase(&self, base: &Utf8Path) -> Result<Utf8PathBuf> {
         self.as_path().unbase(base)
     }
+
+    fn starts_with_path(&self, base: &Utf8Path) -> bool {
+        self.as_path().starts_with_path(base)
+    }
+
+    fn is_same_path(&self, other: &Utf8Path) -> bool {
+        self.as_path().is_same_path(other)
+    }
+
+    fn to_url_path(&self) -> String {
+        self.as_path().to_url_path()
+    }
+}
+
+/// The path as compared on the platform: windows paths lose their unc (`\\?\`) prefix,
+/// use `/` separators and are lowercased, since the windows file systems are case-insensitive.
+fn path_key(path: &str, windows: bool) -> String {
+    if !windows {
+        return path.to_string();
+    }
+    let path = match path.strip_prefix(r"\\?\UNC\") {
+        Some(share) => format!(r"\\{share}"),
+        None => path.strip_prefix(r"\\?\").unwrap_or(path).to_string(),
+    };
+    path.replace('\\', "/").trim_end_matches('/').to_lowercase()
+}
+
+fn path_starts_with(path: &str, base: &str, windows: bool) -> bool {
+    Utf8Path::new(&path_key(path, windows)).starts_with(path_key(base, windows))
+}
+
+fn url_path(path: &str) -> String {
+    let path = path.strip_prefix(r"\\?\").unwrap_or(path);
+    path.replace('\\', "/")
+}
+
+/// Converts a path from the OS (current dir, file watcher, directory listings) without loss,
+/// failing with a clear error for paths that aren't valid UTF-8.
+pub fn utf8_path(path: impl Into<PathBuf>) -> Result<Utf8PathBuf> {
+    Utf8PathBuf::from_path_buf(path.into()).map_err(|path| {
+        anyhow!(
+            "The path {} is not valid UTF-8, which cargo-leptos requires for the project paths",
+            path.display()
+        )
+    })
 }
 
 pub fn remove_nested(paths: impl Iterator<Item = Utf8PathBuf>) -> Vec<Utf8PathBuf> {
@@ -233,3 +300,54 @@ pub fn determine_pdb_filename(path: &Utf8PathBuf) -> Option<Utf8PathBuf> {
         None => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_paths_match() {
+        let matches = |path, base| path_starts_with(path, base, true);
+        assert!(matches(r"\\?\C:\proj\src\lib.rs", r"C:\Proj\src"));
+        assert!(matches(r"c:/proj/src/lib.rs", r"C:\proj\src\"));
+        assert!(matches(r"\\?\UNC\server\share\a.rs", r"\\server\share"));
+        assert!(!matches(r"C:\proj\src2\lib.rs", r"C:\proj\src"));
+
+        let key = path_key(r"\\?\C:\Proj\Style.SCSS", true);
+        assert_eq!(key, "c:/proj/style.scss");
+    }
+
+    #[test]
+    fn unix_paths_match() {
+        assert!(path_starts_with("/proj/src/lib.rs", "/proj/src", false));
+        assert!(!path_starts_with("/proj/src/lib.rs", "/Proj/src", false));
+        assert!(!path_starts_with("/proj/src2/lib.rs", "/proj/src", false));
+    }
+
+    #[test]
+    fn url_paths() {
+        assert_eq!(url_path(r"pkg\app.css"), "pkg/app.css");
+        assert_eq!(url_path(r"\\?\C:\site\pkg\app.css"), "C:/site/pkg/app.css");
+        assert_eq!(url_path("pkg/app.css"), "pkg/app.css");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_path() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let path = PathBuf::from(OsStr::from_bytes(b"proj/\xff/src"));
+        assert!(utf8_path(path).is_err());
+        assert_eq!(utf8_path("proj/src").unwrap(), "proj/src");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_path_ext() {
+        let path = Utf8PathBuf::from(r"\\?\C:\Proj\src\lib.rs");
+        assert!(path.starts_with_path(Utf8Path::new(r"c:\proj\SRC")));
+        assert!(path.is_same_path(Utf8Path::new(r"C:\proj\src\LIB.rs")));
+        let url = Utf8PathBuf::from(r"pkg\app.css").to_url_path();
+        assert_eq!(url, "pkg/app.css");
+    }
+}
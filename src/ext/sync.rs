@@ -1,13 +1,8 @@
-use crate::ext::anyhow::{bail, Context, Result};
-use std::{
-    net::SocketAddr,
-    process::{Output, Stdio},
-    time::Duration,
-};
+use std::{net::SocketAddr, process::Output, time::Duration};
 use tokio::{
     net::TcpStream,
-    process::{Child, Command},
-    sync::broadcast,
+    process::Command,
+    sync::broadcast::{self, error::TryRecvError},
     time::sleep,
 };
 
@@ -36,68 +31,33 @@ impl OutputExt for Output {
         self.stdout.len() > 1
     }
 }
+/// A command running the given line in the system shell.
+pub fn shell_command(cmd: &str) -> Command {
+    if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(cmd);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(cmd);
+        command
+    }
+}
+
 pub enum CommandResult<T> {
     Success(T),
     Failure(T),
     Interrupted,
 }
 
-pub async fn wait_interruptible(
-    name: &str,
-    mut process: Child,
-    mut interrupt_rx: broadcast::Receiver<()>,
-) -> Result<CommandResult<()>> {
-    tokio::select! {
-        res = process.wait() => match res {
-            Ok(exit) => {
-                if exit.success() {
-                    log::trace!("{name} process finished with success");
-                    Ok(CommandResult::Success(()))
-                } else {
-                    log::trace!("{name} process finished with code {:?}", exit.code());
-                    Ok(CommandResult::Failure(()))
-                }
-            }
-            Err(e) => bail!("Command failed due to: {e}"),
-        },
-        _ = interrupt_rx.recv() => {
-            process.kill().await.context("Could not kill process")?;
-            log::trace!("{name} process interrupted");
-            Ok(CommandResult::Interrupted)
-        }
-    }
+/// True if an interrupt was received, without waiting for one.
+pub fn is_interrupted(interrupt_rx: &mut broadcast::Receiver<()>) -> bool {
+    matches!(
+        interrupt_rx.try_recv(),
+        Ok(()) | Err(TryRecvError::Lagged(_))
+    )
 }
 
-pub async fn wait_piped_interruptible(
-    name: &str,
-    mut cmd: Command,
-    mut interrupt_rx: broadcast::Receiver<()>,
-) -> Result<CommandResult<Output>> {
-    // see: https://docs.rs/tokio/latest/tokio/process/index.html
-
-    cmd.kill_on_drop(true);
-    cmd.stdout(Stdio::piped());
-    cmd.stderr(Stdio::piped());
-    let process = cmd.spawn()?;
-    tokio::select! {
-        res = process.wait_with_output() => match res {
-            Ok(output) => {
-                if output.status.success() {
-                    log::trace!("{name} process finished with success");
-                    Ok(CommandResult::Success(output))
-                } else {
-                    log::trace!("{name} process finished with code {:?}", output.status.code());
-                    Ok(CommandResult::Failure(output))
-                }
-            }
-            Err(e) => bail!("Command failed due to: {e}"),
-        },
-        _ = interrupt_rx.recv() => {
-            log::trace!("{name} process interrupted");
-            Ok(CommandResult::Interrupted)
-        }
-    }
-}
 pub async fn wait_for_socket(name: &str, addr: SocketAddr) -> bool {
     let duration = Duration::from_millis(500);
 
@@ -0,0 +1,574 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::Write,
+    process::{ExitStatus, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::{
+    ext::{
+        anyhow::{Context, Result},
+        sync::CommandResult,
+    },
+    logger::{strip_ansi, GRAY},
+    signal::{Resources, Status, Timings, ToolRun, Tracked},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader},
+    process::{Child, ChildStdout, Command},
+    sync::broadcast,
+    task::JoinHandle,
+};
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Echo the command line and environment of each tool invocation, for `--verbose-tools`.
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+/// An invocation of an external tool of the build: cargo, sass, tailwind, wasm-opt or esbuild.
+/// The stderr of the tool is logged with its name, and the exit status and duration are
+/// recorded with the build timings. (wasm-bindgen runs in-process, in the bindgen phase.)
+pub struct Tool {
+    name: &'static str,
+    cmd: Command,
+    /// killed when running longer, see [timeout]
+    timeout: Option<Duration>,
+    /// whether [Tool::output] logs the stderr, else the caller does
+    log_stderr: bool,
+    /// where [Tool::spawn] writes the whole stderr, see [Tool::log_output_to]
+    output_log: Option<Utf8PathBuf>,
+}
+
+impl Tool {
+    /// The tool, with the default limit of its [timeout].
+    pub fn new(name: &'static str, cmd: Command) -> Self {
+        Self {
+            name,
+            cmd,
+            timeout: timeout(name, &BTreeMap::new()),
+            log_stderr: true,
+            output_log: None,
+        }
+    }
+
+    /// With the limit of the `tool-timeouts` of the project running it.
+    pub fn with_timeouts(mut self, timeouts: &BTreeMap<String, u64>) -> Self {
+        self.timeout = timeout(self.name, timeouts);
+        self
+    }
+
+    /// For the tools whose caller decides from the output how to log the stderr, so that it
+    /// is logged once.
+    pub fn stderr_logged_by_caller(mut self) -> Self {
+        self.log_stderr = false;
+        self
+    }
+
+    /// Writes the output of the spawned tool to the file, without the colors, as the log
+    /// only gets a summary of it. The stderr is written as it comes, the stdout by the reader
+    /// of [RunningTool::take_stdout] with [RunningTool::output_log].
+    pub fn log_output_to(mut self, file: Utf8PathBuf) -> Self {
+        self.output_log = Some(file);
+        self
+    }
+
+    /// Runs the tool to completion with its output captured. The stderr is logged,
+    /// as warnings if the tool failed.
+    pub async fn output(self, interrupt: broadcast::Receiver<()>) -> Result<CommandResult<Output>> {
+        self.run_captured(None, Some(interrupt)).await
+    }
+
+    /// Like [Tool::output], with the input written to the stdin of the tool. Not interruptible.
+    pub async fn output_with_input(self, input: &[u8]) -> Result<CommandResult<Output>> {
+        self.run_captured(Some(input.to_vec()), None).await
+    }
+
+    async fn run_captured(
+        mut self,
+        input: Option<Vec<u8>>,
+        interrupt: Option<broadcast::Receiver<()>>,
+    ) -> Result<CommandResult<Output>> {
+        self.cmd
+            .kill_on_drop(true)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if input.is_some() {
+            self.cmd.stdin(Stdio::piped());
+        }
+        own_group(&mut self.cmd);
+        let started = self.start();
+        let mut child = self
+            .cmd
+            .spawn()
+            .context(format!("Could not spawn {}", self.name))?;
+        Status::start_tool(self.name);
+        let stdout = child.stdout.take().map(read_all);
+        let stderr = child.stderr.take().map(read_all);
+        // written while the output is read, as the tool may fill the pipes of its output before
+        // it has read all of its input (dropping the stdin closes it)
+        let stdin = match (input, child.stdin.take()) {
+            (Some(input), Some(mut stdin)) => {
+                Some(tokio::spawn(async move { stdin.write_all(&input).await }))
+            }
+            _ => None,
+        };
+
+        let interrupted = async {
+            match interrupt {
+                Some(mut rx) => {
+                    let _ = rx.recv().await;
+                }
+                None => std::future::pending::<()>().await,
+            }
+        };
+        let timeout = self.timeout;
+        let (status, timed_out) = tokio::select! {
+            res = child.wait() => (res.context(format!("{} failed", self.name))?, false),
+            _ = expiry(timeout) => {
+                kill_group(child.id()).await;
+                child.kill().await.context("Could not kill process")?;
+                (child.wait().await?, true)
+            }
+            _ = interrupted => {
+                kill_group(child.id()).await;
+                if let Some(writer) = stdin {
+                    writer.abort();
+                }
+                finished(self.name, started, None);
+                return Ok(CommandResult::Interrupted);
+            }
+        };
+        // a tool that exits before reading all of its input fails the write, its stderr tells why
+        let input_error = match stdin {
+            Some(writer) if timed_out => {
+                writer.abort();
+                None
+            }
+            Some(writer) => writer.await.ok().and_then(|written| written.err()),
+            None => None,
+        };
+        if let Some(e) = input_error {
+            log::debug!("{} did not read all of its input: {e}", self.name);
+        }
+        let output = if timed_out {
+            // a process the group kill missed (e.g. one that left the group) may still hold
+            // the pipes open
+            for reader in [stdout, stderr].into_iter().flatten() {
+                reader.abort();
+            }
+            Output {
+                status,
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            }
+        } else {
+            Output {
+                status,
+                stdout: joined(stdout).await,
+                stderr: joined(stderr).await,
+            }
+        };
+        let success = status.success() && !timed_out;
+        if self.log_stderr {
+            log_lines(
+                self.name,
+                &String::from_utf8_lossy(&output.stderr),
+                !success,
+            );
+        }
+        finished(self.name, started, Some(status));
+        if let (true, Some(timeout)) = (timed_out, timeout) {
+            report_timeout(self.name, &self.line(), timeout);
+        }
+        Ok(if success {
+            CommandResult::Success(output)
+        } else {
+            CommandResult::Failure(output)
+        })
+    }
+
+    /// Spawns the tool with its stderr logged line by line as it runs. The stdout is
+    /// left as configured by the command, e.g. piped for reading the cargo json messages.
+    pub fn spawn(mut self) -> Result<RunningTool> {
+        self.cmd.stderr(Stdio::piped());
+        let output_log = match &self.output_log {
+            Some(file) => Some(Arc::new(create_log(file)?)),
+            None => None,
+        };
+        let started = self.start();
+        let mut child = self
+            .cmd
+            .spawn()
+            .context(format!("Could not spawn {}", self.name))?;
+        Status::start_tool(self.name);
+        let tracked = child.id().map(|pid| Resources::child(self.name, pid));
+
+        let name = self.name;
+        let log = output_log.clone();
+        let stderr = child.stderr.take().map(|stderr| {
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if let Some(log) = &log {
+                        write_log(log, &line);
+                    }
+                    let line = line.trim();
+                    if !line.is_empty() {
+                        log::info!("{name} {line}");
+                    }
+                }
+            })
+        });
+        Ok(RunningTool {
+            name,
+            line: self.line(),
+            timeout: self.timeout,
+            child,
+            started,
+            stderr,
+            output_log,
+            finished: false,
+            _tracked: tracked,
+        })
+    }
+
+    /// The command line of the tool.
+    fn line(&self) -> String {
+        let cmd = self.cmd.as_std();
+        std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn start(&self) -> Instant {
+        let cmd = self.cmd.as_std();
+        let line = self.line();
+        if VERBOSE.load(Ordering::Relaxed) {
+            log::info!("{} running {}", self.name, GRAY.paint(line));
+            if let Some(dir) = cmd.get_current_dir() {
+                log::info!("{} dir {}", self.name, GRAY.paint(dir.to_string_lossy()));
+            }
+            for (key, val) in cmd.get_envs() {
+                let key = key.to_string_lossy();
+                match val {
+                    Some(val) => log::info!("{} env {key}={}", self.name, val.to_string_lossy()),
+                    None => log::info!("{} env {key} removed", self.name),
+                }
+            }
+        } else {
+            log::trace!("{} running {}", self.name, GRAY.paint(line));
+        }
+        Timings::start()
+    }
+}
+
+/// A tool started with [Tool::spawn].
+pub struct RunningTool {
+    name: &'static str,
+    line: String,
+    timeout: Option<Duration>,
+    child: Child,
+    started: Instant,
+    stderr: Option<JoinHandle<()>>,
+    output_log: Option<Arc<File>>,
+    /// the run was recorded by [RunningTool::wait]
+    finished: bool,
+    _tracked: Option<Tracked>,
+}
+
+impl Drop for RunningTool {
+    fn drop(&mut self) {
+        // dropped without waiting, as when the build is cancelled
+        if self.finished {
+            return;
+        }
+        Status::finish_tool(self.name);
+        if let (Some(pid), Ok(None)) = (self.child.id(), self.child.try_wait()) {
+            // killed through the handle, before tokio reaps it
+            _ = self.child.start_kill();
+            Resources::leaked(self.name, pid);
+        }
+    }
+}
+
+impl RunningTool {
+    /// The pid of the tool, while it runs.
+    pub fn id(&self) -> Option<u32> {
+        self.child.id()
+    }
+
+    /// The stdout of the tool, if the command piped it.
+    pub fn take_stdout(&mut self) -> Option<ChildStdout> {
+        self.child.stdout.take()
+    }
+
+    /// The file of [Tool::log_output_to], for the reader of the stdout to write to.
+    pub fn output_log(&self) -> Option<Arc<File>> {
+        self.output_log.clone()
+    }
+
+    pub async fn wait(
+        mut self,
+        mut interrupt: broadcast::Receiver<()>,
+    ) -> Result<CommandResult<()>> {
+        let timeout = self.timeout;
+        tokio::select! {
+            res = self.child.wait() => {
+                let status = res.context(format!("{} failed", self.name))?;
+                // all of the stderr is logged before the result
+                if let Some(stderr) = self.stderr.take() {
+                    stderr.await?;
+                }
+                self.finished = true;
+                finished(self.name, self.started, Some(status));
+                Ok(if status.success() {
+                    CommandResult::Success(())
+                } else {
+                    CommandResult::Failure(())
+                })
+            },
+            _ = expiry(timeout) => {
+                self.child.kill().await.context("Could not kill process")?;
+                let status = self.child.wait().await?;
+                // the children of the tool may still hold its stderr
+                if let Some(stderr) = self.stderr.take() {
+                    stderr.abort();
+                }
+                self.finished = true;
+                finished(self.name, self.started, Some(status));
+                report_timeout(self.name, &self.line, timeout.unwrap_or_default());
+                Ok(CommandResult::Failure(()))
+            }
+            _ = interrupt.recv() => {
+                self.child.kill().await.context("Could not kill process")?;
+                self.finished = true;
+                finished(self.name, self.started, None);
+                Ok(CommandResult::Interrupted)
+            }
+        }
+    }
+}
+
+/// The limit of the tool: the one of the `tool-timeouts`, in seconds by lowercase tool name
+/// (`cargo`, `wasm-opt`, `sass`, `hook`...) or `default`, 0 for no limit. Else an hour for cargo
+/// and ten minutes for the others, which are generous for a tool that isn't hanging.
+pub fn timeout(name: &str, timeouts: &BTreeMap<String, u64>) -> Option<Duration> {
+    let key = name.to_lowercase();
+    let secs = match (timeouts.get(&key), timeouts.get("default")) {
+        (Some(secs), _) | (None, Some(secs)) => *secs,
+        (None, None) if key == "cargo" => 3600,
+        (None, None) => 600,
+    };
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Sleeps until the timeout, forever without one.
+pub async fn expiry(timeout: Option<Duration>) {
+    match timeout {
+        Some(timeout) => tokio::time::sleep(timeout).await,
+        None => std::future::pending().await,
+    }
+}
+
+pub fn report_timeout(name: &str, line: &str, timeout: Duration) {
+    log::error!(
+        "{name} killed after hanging for {}s: {}",
+        timeout.as_secs(),
+        GRAY.paint(line)
+    );
+    log::info!(
+        "{name} raise its limit with tool-timeouts = {{ {} = <seconds> }} in the leptos config, 0 for none",
+        name.to_lowercase()
+    );
+}
+
+/// Starts the process in a process group of its own, for [kill_group] to reach the processes
+/// it starts, e.g. the node of npx or the command of a `sh -c`.
+pub fn own_group(cmd: &mut Command) {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.as_std_mut().process_group(0);
+    }
+    #[cfg(not(target_family = "unix"))]
+    let _ = cmd;
+}
+
+/// Kills the processes of the group of the process started with [own_group], or its process
+/// tree on windows. The process itself is still to be killed through its handle, so that it
+/// is reaped.
+pub async fn kill_group(pid: Option<u32>) {
+    let Some(pid) = pid else {
+        return;
+    };
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("taskkill");
+        command.args(["/T", "/F", "/PID", &pid.to_string()]);
+        command
+    } else {
+        let mut command = Command::new("kill");
+        command.args(["-KILL", &format!("-{pid}")]);
+        command
+    };
+    if let Err(e) = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+    {
+        log::debug!("Could not kill the process group of {pid}: {e}");
+    }
+}
+
+fn read_all(mut pipe: impl AsyncRead + Unpin + Send + 'static) -> JoinHandle<Vec<u8>> {
+    tokio::spawn(async move {
+        let mut data = Vec::new();
+        _ = pipe.read_to_end(&mut data).await;
+        data
+    })
+}
+
+async fn joined(reader: Option<JoinHandle<Vec<u8>>>) -> Vec<u8> {
+    match reader {
+        Some(reader) => reader.await.unwrap_or_default(),
+        None => Vec::new(),
+    }
+}
+
+fn create_log(file: &Utf8Path) -> Result<File> {
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir).context(format!("Could not create {dir}"))?;
+    }
+    File::create(file).context(format!("Could not create {file}"))
+}
+
+/// Appends the text to the output log of [Tool::log_output_to], as a line.
+pub fn write_log(log: &File, text: &str) {
+    let mut log = log;
+    let text = strip_ansi(text);
+    _ = if text.ends_with('\n') {
+        log.write_all(text.as_bytes())
+    } else {
+        writeln!(log, "{text}")
+    };
+}
+
+/// Logs the lines of the tool output, tagged with the tool name.
+pub fn log_lines(name: &str, text: &str, warn: bool) {
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if warn {
+            log::warn!("{name} {line}");
+        } else {
+            log::debug!("{name} {line}");
+        }
+    }
+}
+
+/// The status is `None` when the tool was interrupted.
+fn finished(name: &'static str, started: Instant, status: Option<ExitStatus>) {
+    let run = ToolRun {
+        tool: name,
+        code: status.and_then(|s| s.code()),
+        interrupted: status.is_none(),
+        duration: started.elapsed(),
+    };
+    log::debug!("{name} {run}");
+    Status::finish_tool(name);
+    Timings::record_tool(run);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::sync::OutputExt;
+
+    #[tokio::test]
+    async fn output_is_captured() {
+        let mut cmd = Command::new("cargo");
+        cmd.arg("--version");
+        let (_tx, rx) = broadcast::channel(1);
+        let result = Tool::new("Cargo", cmd).output(rx).await.unwrap();
+        let CommandResult::Success(output) = result else {
+            panic!("cargo --version failed");
+        };
+        assert!(output.stdout().starts_with("cargo "));
+
+        let mut cmd = Command::new("cargo");
+        cmd.arg("--no-such-flag");
+        let (_tx, rx) = broadcast::channel(1);
+        let result = Tool::new("Cargo", cmd).output(rx).await.unwrap();
+        assert!(matches!(result, CommandResult::Failure(_)));
+    }
+
+    #[tokio::test]
+    async fn hanging_tool_is_killed() {
+        let defaults = BTreeMap::new();
+        assert_eq!(timeout("Cargo", &defaults), Some(Duration::from_secs(3600)));
+        assert_eq!(
+            timeout("Wasm-opt", &defaults),
+            Some(Duration::from_secs(600))
+        );
+
+        let timeouts = BTreeMap::from([("sleep".to_string(), 1)]);
+        let mut cmd = Command::new("sleep");
+        cmd.arg("30");
+        let (_tx, rx) = broadcast::channel(1);
+        let started = Instant::now();
+        let result = Tool::new("Sleep", cmd)
+            .with_timeouts(&timeouts)
+            .output(rx)
+            .await
+            .unwrap();
+        assert!(matches!(result, CommandResult::Failure(_)));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    /// The sleep started by the shell holds the pipes open after the shell is killed.
+    #[cfg(target_family = "unix")]
+    #[tokio::test]
+    async fn hanging_grandchild_is_killed() {
+        let timeouts = BTreeMap::from([("sh".to_string(), 1)]);
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "sleep 30; echo done"]);
+        let (_tx, rx) = broadcast::channel(1);
+        let started = Instant::now();
+        let result = Tool::new("Sh", cmd)
+            .with_timeouts(&timeouts)
+            .output(rx)
+            .await
+            .unwrap();
+        assert!(matches!(result, CommandResult::Failure(_)));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn dropped_tool_is_killed() {
+        let mut cmd = Command::new("sleep");
+        cmd.arg("30");
+        let tool = Tool::new("Leaked", cmd).spawn().unwrap();
+        let pid = tool.id().unwrap();
+        assert!(Status::get().tools_running.contains(&"Leaked"));
+        drop(tool);
+
+        assert!(!Status::get().tools_running.contains(&"Leaked"));
+        assert!(Resources::take_leaks().contains(&(pid, "Leaked")));
+        #[cfg(target_os = "linux")]
+        {
+            // killed, and a zombie until tokio reaps it
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).unwrap_or_default();
+            assert!(stat.is_empty() || stat.contains(") Z "), "{stat}");
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::{
+    ext::anyhow::{Context, Result},
+    logger::GRAY,
+};
+
+pub const TOOLS_LOCK_FILE: &str = "leptos-tools.lock";
+
+lazy_static::lazy_static! {
+  static ref TOOLS_LOCK: Mutex<Option<ToolsLock>> = Mutex::new(None);
+}
+
+/// The tool versions (sass, tailwind, wasm-opt, wasm-split, esbuild and the wasm-bindgen of the
+/// project) used for the builds, locked in the `leptos-tools.lock` file of the project root so that the builds
+/// on other machines and in CI use the same ones.
+///
+/// The file is a flat toml table of the tool names and versions.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ToolsLock {
+    file: Utf8PathBuf,
+    /// false for `--dry-run`, which doesn't write anything
+    write: bool,
+    tools: BTreeMap<String, String>,
+}
+
+impl ToolsLock {
+    /// Reads the lock file of the project root, the tool versions are then resolved from it.
+    pub fn load(root: &Utf8Path, write: bool) -> Result<()> {
+        let file = root.join(TOOLS_LOCK_FILE);
+        let tools = if file.exists() {
+            let content =
+                std::fs::read_to_string(&file).context(format!("Could not read {file}"))?;
+            parse(&content).context(format!("Could not parse {file}"))?
+        } else {
+            BTreeMap::new()
+        };
+        log::debug!("Tools locked {tools:?} {}", GRAY.paint(file.as_str()));
+        *TOOLS_LOCK.lock().unwrap() = Some(Self { file, write, tools });
+        Ok(())
+    }
+
+    /// The locked version of the tool, if a lock file was loaded and has one.
+    pub fn locked(name: &str) -> Option<String> {
+        TOOLS_LOCK
+            .lock()
+            .unwrap()
+            .as_ref()?
+            .tools
+            .get(name)
+            .cloned()
+    }
+
+    /// Locks the version of a tool that isn't locked yet.
+    pub fn record(name: &str, version: &str) {
+        if Self::locked(name).is_none() {
+            Self::set(&[(name, version)]);
+        }
+    }
+
+    /// Locks the versions, replacing the ones of the same tools.
+    pub fn set(versions: &[(&str, &str)]) {
+        let mut lock = TOOLS_LOCK.lock().unwrap();
+        let Some(lock) = lock.as_mut() else {
+            return;
+        };
+        let mut changed = false;
+        for (name, version) in versions {
+            let old = lock.tools.insert(name.to_string(), version.to_string());
+            if old.as_deref() != Some(*version) {
+                log::info!(
+                    "Tools locked {name} {version} in {}",
+                    GRAY.paint(TOOLS_LOCK_FILE)
+                );
+                changed = true;
+            }
+        }
+        if changed && lock.write {
+            if let Err(e) = std::fs::write(&lock.file, lock.to_string()) {
+                log::warn!("Tools could not write {}: {e}", lock.file);
+            }
+        }
+    }
+
+    /// The wasm-bindgen version comes from the Cargo.lock of the project, so it can't be
+    /// enforced, but a change since it was locked is reported.
+    pub fn check_wasm_bindgen(version: &str) {
+        match Self::locked("wasm-bindgen") {
+            Some(locked) if locked != version => log::warn!(
+                "Tools wasm-bindgen {version} differs from the locked {locked}, \
+                run `cargo leptos tools update` to lock it"
+            ),
+            Some(_) => {}
+            None => Self::record("wasm-bindgen", version),
+        }
+    }
+}
+
+impl std::fmt::Display for ToolsLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# The tool versions used by cargo-leptos.")?;
+        writeln!(f, "# Update them with `cargo leptos tools update`.")?;
+        for (name, version) in &self.tools {
+            writeln!(f, "{name} = {version:?}")?;
+        }
+        Ok(())
+    }
+}
+
+fn parse(content: &str) -> Result<BTreeMap<String, String>> {
+    let mut tools = BTreeMap::new();
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, version) = line
+            .split_once('=')
+            .with_context(|| format!("Expected `tool = \"version\"`, found {line:?}"))?;
+        let version = version.trim().trim_matches('"');
+        tools.insert(name.trim().to_string(), version.to_string());
+    }
+    Ok(tools)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_file_round_trip() {
+        let mut lock = ToolsLock::default();
+        lock.tools.insert("sass".to_string(), "1.58.3".to_string());
+        lock.tools
+            .insert("wasm-opt".to_string(), "version_112".to_string());
+
+        let content = lock.to_string();
+        assert!(
+            content.contains("wasm-opt = \"version_112\"\n"),
+            "{content}"
+        );
+        assert_eq!(parse(&content).unwrap(), lock.tools);
+        assert!(parse("sass 1.58.3").is_err());
+    }
+}
@@ -11,49 +11,120 @@ pub mod signal;
 
 use crate::config::Commands;
 use crate::ext::anyhow::{Context, Result};
-use crate::ext::PathBufExt;
+use crate::ext::{utf8_path, FileLock, PathBufExt, ToolsLock};
 use crate::logger::GRAY;
-use camino::Utf8PathBuf;
 use config::{Cli, Config};
 use ext::fs;
 use signal::Interrupt;
 use std::env;
 
 pub async fn run(args: Cli) -> Result<()> {
-    let verbose = args.opts().map(|o| o.verbose).unwrap_or(0);
-    logger::setup(verbose, &args.log);
+    let (verbose, quiet) = args
+        .opts()
+        .map(|o| (o.verbose, o.quiet))
+        .unwrap_or_default();
+    logger::setup(verbose, quiet, &args.log, args.color, args.log_format);
 
-    if let New(new) = &args.command {
-        return new.run().await;
+    match &args.command {
+        New(new) => return new.run().await,
+        Completions { shell } => {
+            command::completions(*shell);
+            return Ok(());
+        }
+        Man => return command::man(),
+        Explain { code } => return command::explain(code.as_deref()),
+        UpdateSelf(update) => return update.run().await,
+        Diff(diff) => return diff.run().await,
+        _ => {}
     }
 
-    let manifest_path = args
-        .manifest_path
-        .to_owned()
-        .unwrap_or_else(|| Utf8PathBuf::from("Cargo.toml"))
-        .resolve_home_dir()
-        .context(format!("manifest_path: {:?}", &args.manifest_path))?;
-    let mut cwd = Utf8PathBuf::from_path_buf(env::current_dir().unwrap()).unwrap();
+    let mut cwd = utf8_path(env::current_dir().context("Could not read the current dir")?)?;
     cwd.clean_windows_path();
 
+    // the relative paths of the config are resolved against the dir of the manifest, as the
+    // working dir is set to its workspace root
+    let manifest_path = match &args.manifest_path {
+        Some(path) => cwd.join(
+            path.to_owned()
+                .resolve_home_dir()
+                .context(format!("manifest_path: {path:?}"))?,
+        ),
+        None => config::locate_manifest(&cwd).dot()?,
+    };
+
+    if let Chef(chef) = &args.command {
+        if chef.run_without_config(&manifest_path).await? {
+            return Ok(());
+        }
+    }
+
     let opts = args.opts().unwrap();
+    ext::tool::set_verbose(opts.verbose_tools);
+    ext::exe::set_frozen(opts.frozen_tools);
 
-    let watch = matches!(args.command, Commands::Watch(_));
-    let config = Config::load(opts, &cwd, &manifest_path, watch).dot()?;
+    let watch = matches!(args.command, Commands::Watch(_) | Commands::Daemon(_));
+    // cargo metadata and git (the output-name sha and the dirty tree warning) are blocking
+    let config =
+        tokio::task::spawn_blocking(move || Config::load(opts, &cwd, &manifest_path, watch))
+            .await?
+            .dot()?;
     env::set_current_dir(&config.working_dir).dot()?;
     log::debug!(
         "Path working dir {}",
         GRAY.paint(config.working_dir.as_str())
     );
 
+    ToolsLock::load(&config.working_dir, !config.cli.dry_run)?;
+    if let Some(version) = &config.wasm_bindgen_version {
+        ToolsLock::check_wasm_bindgen(version);
+    }
+
+    if config.cli.dry_run {
+        return command::dry_run_all(&config).await;
+    }
+
+    // the commands writing the site, the daemon does so from its foreground process
+    let writes_site = match &args.command {
+        Build(_) | Serve(_) | EndToEnd(_) | Watch(_) | Pack(_) | PruneAssets(_) | Prerender(_)
+        | BenchPipeline(_) | Analyze(_) => true,
+        Daemon(daemon) => daemon.foreground,
+        _ => false,
+    };
+    let mut _site_locks = Vec::new();
+    if writes_site {
+        for proj in &config.projects {
+            let what = format!("site of {}", proj.name);
+            let path = proj.cache_dir.join("site.lock");
+            _site_locks.push(FileLock::acquire(&path, &what, config.cli.wait_lock).await?);
+        }
+    }
+
     let _monitor = Interrupt::run_ctrl_c_monitor();
-    use Commands::{Build, EndToEnd, New, Serve, Test, Watch};
+    let _progress = service::progress::start(&config.cli);
+    use Commands::{
+        Analyze, BenchPipeline, Build, CheckAll, Chef, Completions, Ctl, Daemon, Diff, EndToEnd,
+        Explain, Man, New, Pack, Prerender, PruneAssets, Serve, ShowConfig, Test, Tools,
+        UpdateSelf, Watch,
+    };
     match args.command {
-        New(_) => panic!(),
-        Build(_) => command::build_all(&config).await,
+        New(_) | Completions { .. } | Man | Explain { .. } | UpdateSelf(_) | Diff(_) => {
+            unreachable!("handled before the config is loaded")
+        }
+        Chef(_) => command::build_deps_all(&config).await,
+        Pack(pack) => command::pack_all(&config, &pack).await,
+        Build(build) => command::build_all(&config, &build).await,
         Serve(_) => command::serve(&config.current_project()?).await,
         Test(_) => command::test_all(&config).await,
         EndToEnd(_) => command::end2end_all(&config).await,
-        Watch(_) => command::watch(&config.current_project()?).await,
+        Watch(_) => command::watch(&config).await,
+        Daemon(daemon) => command::daemon(&config, &daemon).await,
+        Ctl(ctl) => command::ctl(&config.current_project()?, &ctl).await,
+        Tools(tools) => command::tools(&config, &tools).await,
+        PruneAssets(prune) => command::prune_assets_all(&config, &prune).await,
+        Prerender(prerender) => command::prerender_all(&config, &prerender).await,
+        CheckAll(check) => command::check_all(&config, &check).await,
+        BenchPipeline(bench) => command::bench_pipeline(&config, &bench).await,
+        Analyze(analyze) => command::analyze_all(&config, &analyze).await,
+        ShowConfig(_) => command::show_config(&config).await,
     }
 }
@@ -1,28 +1,143 @@
-use ansi_term::{Colour::Fixed, Style};
+use ansi_term::{ANSIGenericString, Colour::Fixed, Style};
 use flexi_logger::{
     filter::{LogLineFilter, LogLineWriter},
+    writers::LogWriter,
     DeferredNow, Level, Record,
 };
-use std::io::Write;
-use std::sync::OnceLock;
+use std::borrow::Cow;
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
 
-use crate::{config::Log, ext::StrAdditions};
 use crate::ext::anyhow::Context;
+use crate::{
+    config::{ColorChoice, Log, LogFormat},
+    ext::StrAdditions,
+};
+use serde::Serialize;
 
 // https://gist.github.com/fnky/458719343aabd01cfb17a3a4f7296797
 lazy_static::lazy_static! {
-   static ref ERR_RED: ansi_term::Color = Fixed(196);
-   static ref WARN_YELLOW: ansi_term::Color = Fixed(214);
-   pub static ref INFO_GREEN: ansi_term::Color = Fixed(77);
-   static ref DBG_BLUE: ansi_term::Color = Fixed(26);
-   static ref TRACE_VIOLET: ansi_term::Color = Fixed(98);
-
-   pub static ref GRAY: ansi_term::Color = Fixed(241);
-   pub static ref BOLD: ansi_term::Style = Style::new().bold();
+   static ref ERR_RED: Paint = Paint::new(Fixed(196).normal());
+   static ref WARN_YELLOW: Paint = Paint::new(Fixed(214).normal());
+   pub static ref INFO_GREEN: Paint = Paint::new(Fixed(77).normal());
+   static ref DBG_BLUE: Paint = Paint::new(Fixed(26).normal());
+   static ref TRACE_VIOLET: Paint = Paint::new(Fixed(98).normal());
+
+   pub static ref GRAY: Paint = Paint::new(Fixed(241).normal());
+   pub static ref BOLD: Paint = Paint::new(Style::new().bold());
    static ref LOG_SELECT: OnceLock<LogFlag> = OnceLock::new();
 }
 
-pub fn setup(verbose: u8, logs: &[Log]) {
+/// Whether the output has ansi colors, decided by [setup].
+static COLORED: AtomicBool = AtomicBool::new(true);
+
+/// The status line drawn below the logs, see [set_status_line].
+static STATUS_LINE: Mutex<Option<String>> = Mutex::new(None);
+
+/// The number of warnings logged so far.
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+/// With `--quiet` only the errors and the [RESULT] logs are written.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// With `--log-format json` the logs are written as json objects, see [eprint_json].
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// The target of the logs of the results of the build phases, e.g. `Cargo finished`, which
+/// `--quiet` keeps.
+pub const RESULT: &str = "cargo_leptos::result";
+
+/// A color or style of the output, which paints nothing when the colors are off.
+#[derive(Debug, Clone, Copy)]
+pub struct Paint(Style);
+
+impl Paint {
+    pub fn new(style: Style) -> Self {
+        Self(style)
+    }
+
+    pub fn paint<'a, I, S>(&self, input: I) -> ANSIGenericString<'a, S>
+    where
+        I: Into<Cow<'a, S>>,
+        S: 'a + ToOwned + ?Sized,
+        <S as ToOwned>::Owned: fmt::Debug,
+    {
+        if colored() {
+            self.0.paint(input)
+        } else {
+            Style::new().paint(input)
+        }
+    }
+}
+
+pub fn colored() -> bool {
+    COLORED.load(Ordering::Relaxed)
+}
+
+pub fn json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// The text without its ansi escape codes when the colors are off, e.g. the diagnostics
+/// rendered by rustc.
+pub fn uncolored(text: &str) -> Cow<'_, str> {
+    if colored() {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(strip_ansi(text))
+    }
+}
+
+pub fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // a CSI sequence ends with its first letter, e.g. `\x1b[1;31m`
+            if chars.next() == Some('[') {
+                chars.by_ref().find(|c| c.is_ascii_alphabetic());
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// `NO_COLOR` turns the colors off and `CLICOLOR_FORCE` on (see https://no-color.org and
+/// https://bixense.com/clicolors), otherwise they are on when stderr, where the logs go, is
+/// a terminal.
+fn use_colors(choice: ColorChoice) -> bool {
+    let var = |name| env::var_os(name).filter(|val| !val.is_empty());
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto if var("NO_COLOR").is_some() => false,
+        ColorChoice::Auto if var("CLICOLOR_FORCE").is_some_and(|val| val != "0") => true,
+        ColorChoice::Auto => std::io::stderr().is_terminal(),
+    }
+}
+
+pub fn setup(verbose: u8, quiet: bool, logs: &[Log], color: ColorChoice, format: LogFormat) {
+    QUIET.store(quiet, Ordering::Relaxed);
+    let json = format == LogFormat::Json;
+    JSON.store(json, Ordering::Relaxed);
+    let colored = !json && use_colors(color);
+    COLORED.store(colored, Ordering::Relaxed);
+    if color != ColorChoice::Auto {
+        // cargo and the other tools follow the choice
+        let value = if colored { "always" } else { "never" };
+        env::set_var("CARGO_TERM_COLOR", value);
+        if colored {
+            env::set_var("CLICOLOR_FORCE", "1");
+        } else {
+            env::set_var("NO_COLOR", "1");
+        }
+    }
+
     let log_level = match verbose {
         0 => "info",
         1 => "debug",
@@ -35,7 +150,7 @@ pub fn setup(verbose: u8, logs: &[Log]) {
             .with_context(|| "Logger setup failed")
             .unwrap()
             .filter(Box::new(Filter))
-            .format(format)
+            .log_to_writer(Box::new(StderrWriter))
             .start()
             .unwrap();
 
@@ -43,6 +158,100 @@ pub fn setup(verbose: u8, logs: &[Log]) {
     });
 }
 
+/// Whether a status line can be drawn below the logs: they go to stderr, which must be a
+/// terminal that understands the escape codes clearing the line.
+pub fn can_draw_status_line() -> bool {
+    !json() && io::stderr().is_terminal() && env::var("TERM").map_or(true, |term| term != "dumb")
+}
+
+/// Replaces the status line drawn below the logs, `None` removes it. The log lines are
+/// written above it.
+pub fn set_status_line(line: Option<String>) {
+    let mut status = STATUS_LINE.lock().unwrap();
+    let mut stderr = io::stderr().lock();
+    if status.is_some() || line.is_some() {
+        _ = write!(stderr, "\r\x1b[2K{}", line.as_deref().unwrap_or_default());
+        _ = stderr.flush();
+    }
+    *status = line;
+}
+
+/// Writes the text to stderr as is, above the status line, e.g. the diagnostics rendered by
+/// rustc.
+pub fn eprint_above_status(text: &str) {
+    let status = STATUS_LINE.lock().unwrap();
+    let mut stderr = io::stderr().lock();
+    if status.is_some() {
+        _ = write!(stderr, "\r\x1b[2K");
+    }
+    _ = stderr.write_all(text.as_bytes());
+    if let Some(status) = status.as_deref() {
+        _ = write!(stderr, "{status}");
+    }
+    _ = stderr.flush();
+}
+
+/// Writes the value to stderr as a json line, above the status line, for `--log-format json`.
+pub fn eprint_json<T: Serialize>(value: &T) {
+    match serde_json::to_string(value) {
+        Ok(json) => eprint_above_status(&format!("{json}\n")),
+        Err(e) => log::error!("Log could not write the json: {e}"),
+    }
+}
+
+/// A log line of `--log-format json`.
+#[derive(Serialize)]
+struct JsonLog<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    level: String,
+    target: &'a str,
+    message: String,
+}
+
+/// The number of warnings logged since the start.
+pub fn warnings() -> usize {
+    WARNINGS.load(Ordering::Relaxed)
+}
+
+/// Writes the log lines to stderr, above the status line when there is one.
+struct StderrWriter;
+
+impl LogWriter for StderrWriter {
+    fn write(&self, now: &mut DeferredNow, record: &Record) -> io::Result<()> {
+        if record.level() == Level::Warn {
+            WARNINGS.fetch_add(1, Ordering::Relaxed);
+        }
+        let mut line = Vec::new();
+        if json() {
+            let log = JsonLog {
+                kind: "log",
+                level: record.level().as_str().to_lowercase(),
+                target: record.target(),
+                message: record.args().to_string(),
+            };
+            serde_json::to_writer(&mut line, &log)?;
+        } else {
+            format(&mut line, now, record)?;
+        }
+        let status = STATUS_LINE.lock().unwrap();
+        let mut stderr = io::stderr().lock();
+        if status.is_some() {
+            write!(stderr, "\r\x1b[2K")?;
+        }
+        stderr.write_all(&line)?;
+        writeln!(stderr)?;
+        if let Some(status) = status.as_deref() {
+            write!(stderr, "{status}")?;
+        }
+        stderr.flush()
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct LogFlag(u8);
 
@@ -124,7 +333,11 @@ impl LogLineFilter for Filter {
         log_line_writer: &dyn LogLineWriter,
     ) -> std::io::Result<()> {
         let target = record.target();
-        if record.level() == Level::Error
+        if QUIET.load(Ordering::Relaxed) {
+            if record.level() == Level::Error || target == RESULT {
+                log_line_writer.write(now, record)?;
+            }
+        } else if record.level() == Level::Error
             || target.starts_with("cargo_leptos")
             // LOG_SELECT will have been initialized by now, get_or_init() not required
             || LOG_SELECT.get().is_some_and(|flag| flag.matches(target))
@@ -136,11 +349,11 @@ impl LogLineFilter for Filter {
 }
 
 trait LevelExt {
-    fn color(&self) -> ansi_term::Color;
+    fn color(&self) -> Paint;
 }
 
 impl LevelExt for Level {
-    fn color(&self) -> ansi_term::Color {
+    fn color(&self) -> Paint {
         match self {
             Level::Error => *ERR_RED,
             Level::Warn => *WARN_YELLOW,
@@ -150,3 +363,17 @@ impl LevelExt for Level {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip() {
+        assert_eq!(
+            strip_ansi("\x1b[0m\x1b[1m\x1b[38;5;9merror[E0425]\x1b[0m: cannot find `x`"),
+            "error[E0425]: cannot find `x`"
+        );
+        assert_eq!(strip_ansi("plain → text"), "plain → text");
+    }
+}
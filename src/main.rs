@@ -1,9 +1,9 @@
-use cargo_leptos::{config::Cli, ext::anyhow::Result, run};
+use cargo_leptos::{config::Cli, ext::report_error, run};
 use clap::Parser;
-use std::env;
+use std::{env, process};
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     let mut args: Vec<String> = env::args().collect();
     // when running as cargo leptos, the second argument is "leptos" which
     // clap doesn't expect
@@ -12,5 +12,8 @@ async fn main() -> Result<()> {
     }
 
     let args = Cli::parse_from(&args);
-    run(args).await
+    let error_format = args.error_format;
+    if let Err(e) = run(args).await {
+        process::exit(report_error(&e, error_format));
+    }
 }
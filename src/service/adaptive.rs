@@ -0,0 +1,268 @@
+use std::{
+    sync::atomic::{AtomicU16, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{config::AdaptiveConfig, signal::Interrupt};
+
+/// How often the load and power state are checked while a rebuild waits.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The time the cpu usage is sampled over.
+#[cfg(target_os = "linux")]
+const CPU_SAMPLE: Duration = Duration::from_millis(500);
+
+/// The cargo `--jobs` of the current rebuild, 0 when not limited.
+static BUSY_JOBS: AtomicU16 = AtomicU16::new(0);
+
+/// The `--jobs` for the cargo builds, when the rebuild runs on a busy machine.
+pub fn cargo_jobs() -> Option<u16> {
+    match BUSY_JOBS.load(Ordering::Relaxed) {
+        0 => None,
+        jobs => Some(jobs),
+    }
+}
+
+/// Waits until the machine is not busy any more, or for at most the `max-delay`, after which the
+/// rebuild runs with fewer cargo jobs. The rebuilds requested with `cargo leptos ctl rebuild`
+/// don't wait.
+pub async fn wait_while_busy(conf: &AdaptiveConfig, requested: bool) {
+    BUSY_JOBS.store(0, Ordering::Relaxed);
+    if requested {
+        return;
+    }
+    let started = Instant::now();
+    let max_delay = Duration::from_secs(conf.max_delay);
+    let mut shutdown = Interrupt::subscribe_shutdown();
+    let mut waiting = false;
+    loop {
+        let Some(reason) = busy_reason(conf, load_per_cpu().await, on_battery().await) else {
+            if waiting {
+                log::info!("Watch machine not busy any more, rebuilding");
+            }
+            return;
+        };
+        if started.elapsed() >= max_delay {
+            let jobs = conf.busy_jobs.unwrap_or_else(half_the_cpus).max(1);
+            log::info!("Watch rebuilding on a busy machine ({reason}) with {jobs} cargo jobs");
+            BUSY_JOBS.store(jobs, Ordering::Relaxed);
+            return;
+        }
+        if !waiting {
+            log::info!(
+                "Watch delaying the rebuild for up to {}s, {reason}",
+                conf.max_delay
+            );
+            waiting = true;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = shutdown.recv() => return,
+        }
+    }
+}
+
+/// Why the machine is busy according to the config, if it is. The unknown load or power state
+/// (on unsupported platforms) doesn't make it busy.
+fn busy_reason(
+    conf: &AdaptiveConfig,
+    load: Option<f32>,
+    battery: Option<PowerState>,
+) -> Option<String> {
+    if let Some(load) = load.filter(|load| *load > conf.max_load) {
+        return Some(format!("cpu load {load:.2} is above {:.2}", conf.max_load));
+    }
+    match battery {
+        Some(PowerState::Battery) if conf.on_battery => Some("running on battery".to_string()),
+        Some(PowerState::Saver) if conf.on_battery => Some("power saver mode".to_string()),
+        _ => None,
+    }
+}
+
+fn half_the_cpus() -> u16 {
+    let cpus = std::thread::available_parallelism().map_or(2, |n| n.get());
+    (cpus / 2).try_into().unwrap_or(u16::MAX)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(any(target_os = "linux", target_os = "macos")), allow(dead_code))]
+enum PowerState {
+    Mains,
+    Battery,
+    Saver,
+}
+
+/// The share of the cpu time that was busy during the sample, from 0 to 1. The load average
+/// would still count the build that just finished for a minute.
+#[cfg(target_os = "linux")]
+async fn load_per_cpu() -> Option<f32> {
+    let before = cpu_times().await?;
+    tokio::time::sleep(CPU_SAMPLE).await;
+    busy_share(before, cpu_times().await?)
+}
+
+#[cfg(target_os = "linux")]
+async fn cpu_times() -> Option<CpuTimes> {
+    let stat = tokio::fs::read_to_string("/proc/stat").await.ok()?;
+    parse_cpu_times(&stat)
+}
+
+/// The 1 minute load average divided by the number of cpus.
+#[cfg(target_os = "macos")]
+async fn load_per_cpu() -> Option<f32> {
+    // e.g. `{ 1.54 1.61 1.70 }`
+    let output = tokio::process::Command::new("sysctl")
+        .args(["-n", "vm.loadavg"])
+        .output()
+        .await
+        .ok()?;
+    let load = parse_load(String::from_utf8_lossy(&output.stdout).trim_start_matches(['{', ' ']))?;
+    let cpus = std::thread::available_parallelism().ok()?.get();
+    Some(load / cpus as f32)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+async fn load_per_cpu() -> Option<f32> {
+    None
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn parse_load(text: &str) -> Option<f32> {
+    text.split_whitespace().next()?.parse().ok()
+}
+
+/// The busy and total cpu time of all the cpus since boot, in ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg(any(target_os = "linux", test))]
+struct CpuTimes {
+    busy: u64,
+    total: u64,
+}
+
+/// From the `cpu` line of `/proc/stat`: user, nice, system, idle, iowait, irq, softirq and steal
+/// (the guest times are part of user and nice).
+#[cfg(any(target_os = "linux", test))]
+fn parse_cpu_times(stat: &str) -> Option<CpuTimes> {
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let ticks = line
+        .split_whitespace()
+        .skip(1)
+        .take(8)
+        .map(|n| n.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let idle = ticks.get(3)? + ticks.get(4).unwrap_or(&0);
+    let total = ticks.iter().sum::<u64>();
+    Some(CpuTimes {
+        busy: total.saturating_sub(idle),
+        total,
+    })
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn busy_share(before: CpuTimes, after: CpuTimes) -> Option<f32> {
+    let total = after.total.checked_sub(before.total).filter(|t| *t > 0)?;
+    let busy = after.busy.saturating_sub(before.busy);
+    Some(busy as f32 / total as f32)
+}
+
+#[cfg(target_os = "linux")]
+async fn on_battery() -> Option<PowerState> {
+    // set by power-profiles-daemon and the laptop vendor tools
+    if let Ok(profile) = tokio::fs::read_to_string("/sys/firmware/acpi/platform_profile").await {
+        if profile.trim() == "low-power" {
+            return Some(PowerState::Saver);
+        }
+    }
+    let mut supplies = tokio::fs::read_dir("/sys/class/power_supply").await.ok()?;
+    let mut state = None;
+    while let Ok(Some(supply)) = supplies.next_entry().await {
+        let read =
+            |name: &str| std::fs::read_to_string(supply.path().join(name)).unwrap_or_default();
+        match read("type").trim() {
+            "Mains" if read("online").trim() == "1" => return Some(PowerState::Mains),
+            "Battery" if read("status").trim() == "Discharging" => {
+                state = Some(PowerState::Battery)
+            }
+            "Battery" => state = state.or(Some(PowerState::Mains)),
+            _ => {}
+        }
+    }
+    state
+}
+
+#[cfg(target_os = "macos")]
+async fn on_battery() -> Option<PowerState> {
+    // e.g. `Now drawing from 'Battery Power'`
+    let output = tokio::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .await
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    Some(if text.contains("'Battery Power'") {
+        PowerState::Battery
+    } else {
+        PowerState::Mains
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+async fn on_battery() -> Option<PowerState> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conf() -> AdaptiveConfig {
+        AdaptiveConfig {
+            max_load: 0.8,
+            on_battery: true,
+            max_delay: 60,
+            busy_jobs: None,
+        }
+    }
+
+    #[test]
+    fn busy_reasons() {
+        assert_eq!(busy_reason(&conf(), None, None), None);
+        assert_eq!(
+            busy_reason(&conf(), Some(0.5), Some(PowerState::Mains)),
+            None
+        );
+        let reason = busy_reason(&conf(), Some(0.95), None).unwrap();
+        assert_eq!(reason, "cpu load 0.95 is above 0.80");
+        assert!(busy_reason(&conf(), None, Some(PowerState::Battery)).is_some());
+        assert!(busy_reason(&conf(), None, Some(PowerState::Saver)).is_some());
+
+        let conf = AdaptiveConfig {
+            on_battery: false,
+            ..conf()
+        };
+        assert_eq!(busy_reason(&conf, None, Some(PowerState::Battery)), None);
+    }
+
+    #[test]
+    fn load_is_parsed() {
+        assert_eq!(parse_load("1.54 1.61 1.70 }"), Some(1.54));
+        assert_eq!(parse_load(""), None);
+    }
+
+    #[test]
+    fn cpu_usage_is_sampled() {
+        let before = "cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 50 0 25 400 25 0 0 0 0 0\n";
+        let before = parse_cpu_times(before).unwrap();
+        assert_eq!(
+            before,
+            CpuTimes {
+                busy: 150,
+                total: 1000
+            }
+        );
+        let after = parse_cpu_times("cpu  250 0 100 1000 50 0 0 0 0 0\n").unwrap();
+        assert_eq!(busy_share(before, after), Some(0.5));
+        assert_eq!(busy_share(after, after), None);
+        assert_eq!(parse_cpu_times("intr 1 2 3\n"), None);
+    }
+}
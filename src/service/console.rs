@@ -0,0 +1,83 @@
+use std::{
+    io::{self, BufRead, IsTerminal},
+    sync::{Arc, OnceLock},
+};
+
+use tokio::{
+    sync::{
+        mpsc::{self, UnboundedReceiver},
+        Mutex,
+    },
+    task::JoinHandle,
+};
+
+use crate::{config::Project, logger::GRAY, service::profile, signal::Interrupt};
+
+/// The lines typed in the terminal, read by a single thread for all the watch restarts.
+static LINES: OnceLock<Mutex<UnboundedReceiver<String>>> = OnceLock::new();
+
+/// Runs the commands typed in the terminal during watch, one per line: `:profile [seconds]`
+/// (or `p`) records a flamegraph of the running server. Not started when the stdin isn't a
+/// terminal, e.g. in the daemon.
+pub fn spawn(proj: &Arc<Project>) -> Option<JoinHandle<()>> {
+    if !io::stdin().is_terminal() {
+        return None;
+    }
+    let lines = LINES.get_or_init(|| {
+        let (tx, rx) = mpsc::unbounded_channel();
+        // a thread rather than a task, as a blocking read would hold up the exit of the runtime
+        std::thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Mutex::new(rx)
+    });
+    log::debug!(
+        "Watch reads the commands {}",
+        GRAY.paint(":profile [seconds]")
+    );
+
+    let proj = proj.clone();
+    let mut int = Interrupt::subscribe_services_stop();
+    Some(tokio::spawn(async move {
+        let mut lines = lines.lock().await;
+        loop {
+            let line = tokio::select! {
+                line = lines.recv() => match line {
+                    Some(line) => line,
+                    None => return,
+                },
+                _ = int.recv() => return,
+            };
+            run(&proj, &line);
+        }
+    }))
+}
+
+fn run(proj: &Arc<Project>, line: &str) {
+    let mut words = line.split_whitespace();
+    match words.next() {
+        None => {}
+        Some(":profile" | "p") => {
+            let seconds = words
+                .next()
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(profile::DEFAULT_SECONDS);
+            // recorded in the background, for the watch to go on meanwhile
+            let proj = proj.clone();
+            tokio::spawn(async move {
+                if let Err(e) = profile::capture(&proj, seconds).await {
+                    log::warn!("{e:#}");
+                }
+            });
+        }
+        Some(other) => log::warn!(
+            "Watch unknown command {other:?}, expected {}",
+            GRAY.paint(":profile [seconds]")
+        ),
+    }
+}
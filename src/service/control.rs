@@ -0,0 +1,223 @@
+use std::{io::Write, net::SocketAddr, process, sync::Arc};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+use crate::{
+    config::Project,
+    ext::{
+        anyhow::{anyhow, Context, Result},
+        fs,
+    },
+    logger::GRAY,
+    service::profile,
+    signal::{BuildStatus, Interrupt, Status},
+};
+
+/// Where the running daemon of the project writes its [DaemonInfo], readable by its user only.
+pub fn info_file(proj: &Project) -> Utf8PathBuf {
+    proj.cache_dir.join("daemon.json")
+}
+
+/// Where the detached daemon of the project writes its logs.
+pub fn log_file(proj: &Project) -> Utf8PathBuf {
+    proj.cache_dir.join("daemon.log")
+}
+
+/// How `cargo leptos ctl` finds the running daemon.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DaemonInfo {
+    pub pid: u32,
+    pub project: String,
+    /// the local address of the control socket
+    pub addr: SocketAddr,
+    pub log_file: Utf8PathBuf,
+    /// the first word of the control commands, as any local user can connect to the socket
+    pub token: String,
+}
+
+/// Starts the control socket of the daemon, on a free local port written to the [info_file].
+///
+/// The protocol is one command line per connection, the token of the info file followed by the
+/// command (`status`, `rebuild`, `stop`, `logs <lines>` or `profile <seconds>`), answered with
+/// a json object (`status`) or text, after which the connection is closed.
+pub async fn spawn(proj: &Arc<Project>) -> Result<JoinHandle<()>> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let info = DaemonInfo {
+        pid: process::id(),
+        project: proj.name.clone(),
+        addr: listener.local_addr()?,
+        log_file: log_file(proj),
+        token: new_token()?,
+    };
+    let file = info_file(proj);
+    fs::create_dir_all(&proj.cache_dir).await?;
+    write_private(&file, &serde_json::to_string_pretty(&info)?)?;
+    let token = info.token;
+    log::info!(
+        "Daemon control socket on {} {}",
+        info.addr,
+        GRAY.paint(file.as_str())
+    );
+
+    let proj = proj.clone();
+    Ok(tokio::spawn(async move {
+        let mut int = Interrupt::subscribe_shutdown();
+        loop {
+            tokio::select! {
+                res = listener.accept() => match res {
+                    Ok((stream, _)) => {
+                        let proj = proj.clone();
+                        let token = token.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = handle(&proj, &token, stream).await {
+                                log::warn!("Daemon control request failed: {e}");
+                            }
+                        });
+                    }
+                    Err(e) => log::warn!("Daemon could not accept a control connection: {e}"),
+                },
+                _ = int.recv() => break,
+            }
+        }
+        if let Err(e) = fs::remove_file(&file).await {
+            log::debug!("Daemon could not remove {file}: {e}");
+        }
+    }))
+}
+
+/// Writes the file readable and writable by the user only.
+fn write_private(file: &Utf8Path, content: &str) -> Result<()> {
+    // the mode only applies to a new file
+    _ = std::fs::remove_file(file);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(file)
+        .and_then(|mut f| f.write_all(content.as_bytes()))
+        .context(format!("Could not write to {file}"))
+}
+
+fn new_token() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| anyhow!("Could not generate the daemon control token: {e}"))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Compares all the bytes, so that the time taken doesn't tell how much of the token matched.
+fn token_matches(token: &str, given: &str) -> bool {
+    token.len() == given.len()
+        && token
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn handle(proj: &Project, token: &str, stream: TcpStream) -> Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut line = String::new();
+    BufReader::new(read).read_line(&mut line).await?;
+    let mut words = line.split_whitespace();
+    let Some(given) = words.next() else {
+        // a connection without a command checks that the daemon is running
+        return Ok(());
+    };
+    if !token_matches(token, given) {
+        log::warn!("Daemon refused a control command without the token of the info file");
+        write
+            .write_all(
+                b"Unauthorized, the command must start with the token of the daemon info file\n",
+            )
+            .await?;
+        write.shutdown().await.dot()?;
+        return Ok(());
+    }
+    let Some(command) = words.next() else {
+        return Ok(());
+    };
+    log::debug!("Daemon control command {command}");
+
+    let response = match command {
+        "status" => {
+            #[derive(Serialize)]
+            struct StatusResponse {
+                pid: u32,
+                project: String,
+                #[serde(flatten)]
+                status: BuildStatus,
+            }
+            serde_json::to_string_pretty(&StatusResponse {
+                pid: process::id(),
+                project: proj.name.clone(),
+                status: Status::get(),
+            })?
+        }
+        "rebuild" => {
+            tokio::task::spawn_blocking(Interrupt::request_rebuild).await?;
+            "Rebuild requested".to_string()
+        }
+        "stop" => {
+            Interrupt::request_shutdown().await;
+            "Daemon stopping".to_string()
+        }
+        "logs" => {
+            let lines = words.next().and_then(|n| n.parse().ok()).unwrap_or(50);
+            tail(&log_file(proj), lines).await?
+        }
+        "profile" => {
+            let seconds = words
+                .next()
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(profile::DEFAULT_SECONDS);
+            match profile::capture(proj, seconds).await {
+                Ok(file) => format!("Profile written to {file}"),
+                Err(e) => format!("{e:#}"),
+            }
+        }
+        other => {
+            format!("Unknown command {other:?}, expected status, rebuild, stop, logs or profile")
+        }
+    };
+    write.write_all(response.as_bytes()).await?;
+    write.write_all(b"\n").await?;
+    write.shutdown().await.dot()?;
+    Ok(())
+}
+
+async fn tail(file: &Utf8PathBuf, lines: usize) -> Result<String> {
+    if !file.exists() {
+        return Ok(format!(
+            "No log file {file}, the daemon runs in the foreground"
+        ));
+    }
+    let content = fs::read_to_string(file).await?;
+    let all = content.lines().collect::<Vec<_>>();
+    Ok(all[all.len().saturating_sub(lines)..].join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token() {
+        let token = new_token().unwrap();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, new_token().unwrap());
+        assert!(token_matches(&token, &token.clone()));
+        assert!(!token_matches(&token, &token[1..]));
+        assert!(!token_matches(&token, &"0".repeat(64)));
+    }
+}
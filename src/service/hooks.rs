@@ -0,0 +1,123 @@
+use std::{fmt, process::Stdio};
+
+use crate::{
+    config::Project,
+    ext::{
+        anyhow::{Context, Result},
+        sync::shell_command,
+        tool,
+    },
+    logger::GRAY,
+    signal::Interrupt,
+};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    PreBuild,
+    PostBuild,
+    PreServe,
+}
+
+impl fmt::Display for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PreBuild => write!(f, "pre-build"),
+            Self::PostBuild => write!(f, "post-build"),
+            Self::PreServe => write!(f, "pre-serve"),
+        }
+    }
+}
+
+impl Hook {
+    fn command<'a>(&self, proj: &'a Project) -> Option<&'a str> {
+        let hooks = &proj.hooks;
+        match self {
+            Self::PreBuild => hooks.pre_build.as_deref(),
+            Self::PostBuild => hooks.post_build.as_deref(),
+            Self::PreServe => hooks.pre_serve.as_deref(),
+        }
+    }
+}
+
+/// Runs the configured hook command, if any. Returns false if the command failed
+/// or was interrupted.
+pub async fn run(proj: &Project, hook: Hook) -> Result<bool> {
+    let Some(cmd) = hook.command(proj) else {
+        return Ok(true);
+    };
+    log::info!("Hook {hook} running {}", GRAY.paint(cmd));
+
+    let mut command = shell_command(cmd);
+    command
+        .current_dir(&proj.working_dir)
+        .envs(hook_envs(proj, hook))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // for the commands started by the shell to be killed with it
+    tool::own_group(&mut command);
+    let mut process = command
+        .spawn()
+        .context(format!("Could not spawn the {hook} hook {cmd:?}"))?;
+    let pid = process.id();
+
+    let stdout = BufReader::new(process.stdout.take().unwrap());
+    let stderr = BufReader::new(process.stderr.take().unwrap());
+    let mut int = Interrupt::subscribe_shutdown();
+
+    let finished = async {
+        tokio::join!(log_lines(hook, stdout), log_lines(hook, stderr));
+        process.wait().await
+    };
+    // the shell is killed on drop, its group before
+    let timeout = tool::timeout("hook", &proj.tool_timeouts);
+    let status = tokio::select! {
+        status = finished => status?,
+        _ = tool::expiry(timeout) => {
+            tool::kill_group(pid).await;
+            tool::report_timeout("Hook", cmd, timeout.unwrap_or_default());
+            return Ok(false);
+        }
+        _ = int.recv() => {
+            tool::kill_group(pid).await;
+            log::trace!("Hook {hook} interrupted");
+            return Ok(false);
+        }
+    };
+
+    if status.success() {
+        log::debug!("Hook {hook} finished");
+        Ok(true)
+    } else {
+        log::warn!("Hook {hook} failed with {status}");
+        Ok(false)
+    }
+}
+
+async fn log_lines(hook: Hook, reader: impl AsyncBufRead + Unpin) {
+    let mut lines = reader.lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        log::info!("Hook {hook} {line}");
+    }
+}
+
+/// The project env vars, plus the profile and output paths of the build.
+fn hook_envs(proj: &Project, hook: Hook) -> Vec<(&'static str, String)> {
+    let mut envs = proj.to_envs();
+    envs.extend([
+        ("LEPTOS_HOOK", hook.to_string()),
+        ("LEPTOS_RELEASE", proj.release.to_string()),
+        ("LEPTOS_LIB_PROFILE", proj.lib.profile.to_string()),
+        ("LEPTOS_BIN_PROFILE", proj.bin.profile.to_string()),
+        ("LEPTOS_BIN_EXE_FILE", proj.bin.exe_file.to_string()),
+        ("LEPTOS_WASM_FILE", proj.lib.wasm_file.dest.to_string()),
+        ("LEPTOS_JS_FILE", proj.lib.js_file.dest.to_string()),
+        ("LEPTOS_CSS_FILE", proj.style.site_file.dest.to_string()),
+        (
+            "LEPTOS_BUILD_MANIFEST_FILE",
+            proj.build_manifest_file.to_string(),
+        ),
+    ]);
+    envs
+}
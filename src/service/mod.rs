@@ -1,5 +1,17 @@
+pub mod adaptive;
+pub mod console;
+pub mod control;
+pub mod hooks;
 pub mod notify;
+pub mod open;
 pub mod patch;
+pub mod profile;
+pub mod progress;
+pub mod proxy;
 pub mod reload;
 pub mod serve;
+pub mod server;
+pub mod sidecar;
 pub mod site;
+pub mod tls;
+pub mod watchdog;
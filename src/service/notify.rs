@@ -1,19 +1,46 @@
 use crate::compile::Change;
 use crate::config::Project;
-use crate::ext::anyhow::{anyhow, Result};
-use crate::signal::Interrupt;
+use crate::ext::anyhow::Result;
+use crate::signal::{Interrupt, Resources};
 use crate::{
-    ext::{remove_nested, PathBufExt, PathExt},
+    ext::{remove_nested, utf8_path, PathBufExt, PathExt},
     logger::GRAY,
 };
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use itertools::Itertools;
 use notify::{DebouncedEvent, RecursiveMode, Watcher};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::{fmt::Display, time::Duration};
-use tokio::task::JoinHandle;
+use tokio::{
+    sync::mpsc::{self, UnboundedSender},
+    task::JoinHandle,
+};
+
+lazy_static::lazy_static! {
+    /// the files loaded by the sass style, as found by its last build
+    static ref STYLE_DEPS: Mutex<BTreeSet<Utf8PathBuf>> = Mutex::new(BTreeSet::new());
+    /// adds the files to the running watcher
+    static ref WATCH_FILES: Mutex<Option<UnboundedSender<Utf8PathBuf>>> = Mutex::new(None);
+}
+
+/// Watches the files loaded by the sass style, e.g. from the `style-load-paths` or relative
+/// dirs outside the dir of the style file, as found by each style build.
+pub fn watch_style_deps(deps: &BTreeSet<Utf8PathBuf>) {
+    let new = {
+        let mut known = STYLE_DEPS.lock().unwrap();
+        deps.iter()
+            .filter(|dep| known.insert((*dep).clone()))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    if let Some(tx) = WATCH_FILES.lock().unwrap().as_ref() {
+        for file in new {
+            _ = tx.send(file);
+        }
+    }
+}
 
 pub async fn spawn(proj: &Arc<Project>) -> Result<JoinHandle<()>> {
     let mut set: HashSet<Utf8PathBuf> = HashSet::from_iter(vec![]);
@@ -21,20 +48,37 @@ pub async fn spawn(proj: &Arc<Project>) -> Result<JoinHandle<()>> {
     set.extend(proj.lib.src_paths.clone());
     set.extend(proj.bin.src_paths.clone());
     set.insert(proj.js_dir.clone());
+    for entry in &proj.entrypoints {
+        set.extend(entry.lib.src_paths.clone());
+    }
 
     if let Some(file) = &proj.style.file {
         set.insert(file.source.clone().without_last());
+        set.extend(proj.style.load_paths.clone());
     }
 
     if let Some(tailwind) = &proj.style.tailwind {
         set.insert(tailwind.config_file.clone());
         set.insert(tailwind.input_file.clone());
+        set.extend(tailwind.input_dir.clone());
     }
 
     if let Some(assets) = &proj.assets {
         set.insert(assets.dir.clone());
     }
 
+    set.extend(manifests(proj));
+
+    for extra in &proj.watch_extra {
+        if !extra.exists() {
+            log::warn!(
+                "Notify --watch-extra path not found {}",
+                GRAY.paint(extra.as_str())
+            );
+        }
+        set.insert(extra.clone());
+    }
+
     let paths = remove_nested(set.into_iter().filter(|path| Path::new(path).exists()));
 
     log::info!(
@@ -63,6 +107,7 @@ async fn run(paths: &[Utf8PathBuf], proj: Arc<Project>) {
 
     let mut watcher = notify::watcher(sync_tx, Duration::from_millis(200))
         .expect("failed to build file system watcher");
+    let _tracked = Resources::watcher();
 
     for path in paths {
         if let Err(e) = watcher.watch(path, RecursiveMode::Recursive) {
@@ -70,9 +115,59 @@ async fn run(paths: &[Utf8PathBuf], proj: Arc<Project>) {
         }
     }
 
-    if let Err(e) = Interrupt::subscribe_shutdown().recv().await {
-        log::trace!("Notify stopped due to: {e:?}");
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    // the style files found by the builds before the watcher started
+    for file in STYLE_DEPS.lock().unwrap().iter() {
+        _ = tx.send(file.clone());
     }
+    *WATCH_FILES.lock().unwrap() = Some(tx);
+
+    let mut int = Interrupt::subscribe_services_stop();
+    loop {
+        tokio::select! {
+            Some(file) = rx.recv() => {
+                if file.starts_with_any(paths) {
+                    continue;
+                }
+                match watcher.watch(&file, RecursiveMode::NonRecursive) {
+                    Ok(()) => log::debug!("Notify watching style {}", GRAY.paint(file.as_str())),
+                    Err(e) => log::error!("Notify could not watch {file:?} due to {e:?}"),
+                }
+            }
+            res = int.recv() => {
+                if let Err(e) = res {
+                    log::trace!("Notify stopped due to: {e:?}");
+                }
+                break;
+            }
+        }
+    }
+    *WATCH_FILES.lock().unwrap() = None;
+}
+
+/// The Cargo.toml files with the leptos config and features of the project.
+fn manifests(proj: &Project) -> Vec<Utf8PathBuf> {
+    let mut files = [&proj.working_dir, &proj.lib.abs_dir, &proj.bin.abs_dir]
+        .map(|dir| {
+            let file = dir.join("Cargo.toml");
+            // relative to the working dir, as the paths of the events
+            file.unbase(&proj.working_dir).unwrap_or(file)
+        })
+        .to_vec();
+    files.dedup();
+    files
+}
+
+/// The build output, VCS data and editor temporary files under a `--watch-extra` path.
+fn is_ignored(rel_path: &Utf8Path) -> bool {
+    let ignored_dir = rel_path
+        .components()
+        .any(|c| matches!(c.as_str(), "target" | ".git" | "node_modules"));
+    let name = rel_path.file_name().unwrap_or_default();
+    ignored_dir
+        || name.ends_with('~')
+        || name.starts_with(".#")
+        || matches!(rel_path.extension(), Some("swp" | "swx" | "tmp"))
 }
 
 fn handle(watched: Watched, proj: Arc<Project>) {
@@ -88,15 +183,25 @@ fn handle(watched: Watched, proj: Arc<Project>) {
 
     let mut changes = Vec::new();
 
+    if manifests(&proj).iter().any(|file| path.is_same_path(file)) {
+        log::debug!("Notify config change {}", GRAY.paint(watched.to_string()));
+        changes.push(Change::Conf);
+    }
+
     if let Some(assets) = &proj.assets {
-        if path.starts_with(&assets.dir) {
+        if path.starts_with_path(&assets.dir) {
             log::debug!("Notify asset change {}", GRAY.paint(watched.to_string()));
             changes.push(Change::Asset(watched.clone()));
         }
     }
 
-    let lib_rs = path.starts_with_any(&proj.lib.src_paths) && path.is_ext_any(&["rs"]);
-    let lib_js = path.starts_with(&proj.js_dir) && path.is_ext_any(&["js"]);
+    let lib_rs = (path.starts_with_any(&proj.lib.src_paths)
+        || proj
+            .entrypoints
+            .iter()
+            .any(|entry| path.starts_with_any(&entry.lib.src_paths)))
+        && path.is_ext_any(&["rs"]);
+    let lib_js = path.starts_with_path(&proj.js_dir) && path.is_ext_any(&["js"]);
 
     if lib_rs || lib_js {
         log::debug!(
@@ -116,21 +221,46 @@ fn handle(watched: Watched, proj: Arc<Project>) {
 
     if let Some(file) = &proj.style.file {
         let src = file.source.clone().without_last();
-        if path.starts_with(src) && path.is_ext_any(&["scss", "sass", "css"]) {
+        let style_dir = path.starts_with_path(&src) || path.starts_with_any(&proj.style.load_paths);
+        let loaded = STYLE_DEPS
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|dep| path.is_same_path(dep));
+        if (style_dir && path.is_ext_any(&["scss", "sass", "css"])) || loaded {
             log::debug!("Notify style change {}", GRAY.paint(watched.to_string()));
             changes.push(Change::Style)
         }
     }
 
     if let Some(tailwind) = &proj.style.tailwind {
-        if path.as_path() == tailwind.config_file.as_path()
-            || path.as_path() == tailwind.input_file.as_path()
+        // the css files imported by the input
+        let imported = tailwind
+            .input_dir
+            .as_ref()
+            .is_some_and(|dir| path.starts_with_path(dir) && path.is_ext_any(&["css"]));
+        if path.is_same_path(&tailwind.config_file)
+            || path.is_same_path(&tailwind.input_file)
+            || imported
         {
             log::debug!("Notify style change {}", GRAY.paint(watched.to_string()));
             changes.push(Change::Style)
         }
     }
 
+    let extra = proj
+        .watch_extra
+        .iter()
+        .find(|extra| path.starts_with_path(extra));
+    if let Some(extra) = extra {
+        if is_ignored(path.strip_prefix(extra).unwrap_or(path)) {
+            log::trace!("Notify ignored {}", GRAY.paint(watched.to_string()));
+        } else {
+            log::debug!("Notify extra change {}", GRAY.paint(watched.to_string()));
+            changes.extend([Change::LibSource, Change::BinSource]);
+        }
+    }
+
     if !changes.is_empty() {
         Interrupt::send(&changes);
     } else {
@@ -151,8 +281,9 @@ pub enum Watched {
 }
 
 fn convert(p: &Path, proj: &Project) -> Result<Utf8PathBuf> {
-    let p = Utf8PathBuf::from_path_buf(p.to_path_buf())
-        .map_err(|e| anyhow!("Could not convert to a Utf8PathBuf: {e:?}"))?;
+    let mut p = utf8_path(p)?;
+    // the watcher can report windows paths with the unc prefix, which the project paths don't have
+    p.clean_windows_path();
     Ok(p.unbase(&proj.working_dir).unwrap_or(p))
 }
 
@@ -193,8 +324,8 @@ impl Watched {
 
     pub fn path_starts_with(&self, path: &Utf8PathBuf) -> bool {
         match self {
-            Self::Write(p) | Self::Create(p) | Self::Remove(p) => p.starts_with(path),
-            Self::Rename(fr, to) => fr.starts_with(path) || to.starts_with(path),
+            Self::Write(p) | Self::Create(p) | Self::Remove(p) => p.starts_with_path(path),
+            Self::Rename(fr, to) => fr.starts_with_path(path) || to.starts_with_path(path),
             Self::Rescan => false,
         }
     }
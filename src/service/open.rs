@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use crate::{
+    config::{browser_addr, Project},
+    ext::sync::wait_for_socket,
+    logger::GRAY,
+};
+use tokio::process::Command;
+
+/// Opens the site in the default browser once the server accepts connections.
+pub fn spawn(proj: &Arc<Project>) {
+    let Some(path) = &proj.open else {
+        return;
+    };
+    let url = browser_url(proj, path);
+    let addr = proj.site.addr;
+    tokio::spawn(async move {
+        if !wait_for_socket("Open", addr).await {
+            return;
+        }
+        log::info!("Open {}", GRAY.paint(&url));
+        if let Err(e) = open_command(&url).spawn() {
+            log::warn!("Open could not launch the browser: {e}");
+        }
+    });
+}
+
+/// The site is opened at the dev proxy when there is one, for its pages to reach the backends.
+fn browser_url(proj: &Project, path: &str) -> String {
+    let addr = match &proj.proxy {
+        Some(proxy) if proj.tls.is_none() => proxy.addr,
+        _ => proj.site.addr,
+    };
+    let addr = browser_addr(addr, proj.reload_client.advertise_host.as_deref());
+    // the path is relative to the base-href
+    let path = path.trim_start_matches('/');
+    format!("{}://{addr}{}{path}", proj.scheme(), proj.base_href)
+}
+
+fn open_command(url: &str) -> Command {
+    if cfg!(target_os = "windows") {
+        // not `cmd /C start`, as cmd would run what follows a `&` of the url as a command
+        let mut command = Command::new("rundll32");
+        command.args(["url.dll,FileProtocolHandler", url]);
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg(url);
+        command
+    } else {
+        let mut command = Command::new("xdg-open");
+        command.arg(url);
+        command
+    }
+}
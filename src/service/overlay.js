@@ -0,0 +1,92 @@
+// Shows the output of failed cargo-leptos builds on top of the page, until the next successful build.
+// Include it in dev mode with:
+// <script src="http://127.0.0.1:3001/live_reload/overlay.js"></script>
+(function () {
+  const src = new URL(document.currentScript.src);
+  const protocol = src.protocol === "https:" ? "wss:" : "ws:";
+  // the websocket of the reload-external-url, filled in by cargo-leptos when set
+  const external = "__LEPTOS_RELOAD_URL__";
+  const url = external.startsWith("ws") ? external : `${protocol}//${src.host}/live_reload`;
+  const id = "cargo-leptos-overlay";
+
+  const COLORS = {
+    30: "#5c6370",
+    31: "#e06c75",
+    32: "#98c379",
+    33: "#e5c07b",
+    34: "#61afef",
+    35: "#c678dd",
+    36: "#56b6c2",
+    37: "#dcdfe4",
+  };
+
+  function escape(text) {
+    return text.replace(/&/g, "&amp;").replace(/</g, "&lt;").replace(/>/g, "&gt;");
+  }
+
+  function ansiToHtml(text) {
+    let html = "";
+    let open = false;
+    const parts = text.split(/\x1b\[([0-9;]*)m/);
+    for (let i = 0; i < parts.length; i++) {
+      if (i % 2 === 0) {
+        html += escape(parts[i]);
+        continue;
+      }
+      if (open) {
+        html += "</span>";
+        open = false;
+      }
+      let style = "";
+      for (const code of parts[i].split(";").map(Number)) {
+        if (code === 1) style += "font-weight:bold;";
+        else if (COLORS[code]) style += `color:${COLORS[code]};`;
+        else if (COLORS[code - 60]) style += `color:${COLORS[code - 60]};`;
+      }
+      if (style) {
+        html += `<span style="${style}">`;
+        open = true;
+      }
+    }
+    return open ? html + "</span>" : html;
+  }
+
+  function linkify(html, workingDir) {
+    return html.replace(/([\w.\/\\-]+\.rs):(\d+):(\d+)/g, (found, file, line, col) => {
+      const path = file.startsWith("/") ? file : `${workingDir}/${file}`;
+      return `<a href="vscode://file/${path}:${line}:${col}" style="color:inherit">${found}</a>`;
+    });
+  }
+
+  function show(error) {
+    hide();
+    const overlay = document.createElement("div");
+    overlay.id = id;
+    overlay.style.cssText =
+      "position:fixed;inset:0;z-index:2147483647;overflow:auto;padding:24px;" +
+      "background:rgba(24,26,31,0.96);color:#dcdfe4;font:13px/1.5 monospace;";
+    overlay.innerHTML =
+      '<div style="display:flex;justify-content:space-between;color:#e06c75;font-size:16px">' +
+      `<span>${error.title}</span><button style="cursor:pointer">✕</button></div>` +
+      `<pre style="white-space:pre-wrap">${linkify(ansiToHtml(error.output), error.working_dir)}</pre>`;
+    overlay.querySelector("button").onclick = hide;
+    (document.body || document.documentElement).appendChild(overlay);
+  }
+
+  function hide() {
+    const overlay = document.getElementById(id);
+    if (overlay) overlay.remove();
+  }
+
+  function connect() {
+    const ws = new WebSocket(url);
+    ws.onmessage = (event) => {
+      const msg = JSON.parse(event.data);
+      if (msg.build_error) show(msg.build_error);
+      if (msg.clear_build_error) hide();
+    };
+    ws.onclose = () => setTimeout(connect, 1000);
+  }
+
+  connect();
+})();
@@ -1,6 +1,6 @@
 use crate::config::Project;
 use crate::ext::anyhow::Result;
-use crate::signal::{Interrupt, ReloadSignal};
+use crate::signal::{Interrupt, ReloadSignal, Resources};
 use crate::{
     ext::{remove_nested, PathBufExt},
     logger::GRAY,
@@ -52,6 +52,7 @@ async fn run(paths: &[Utf8PathBuf], proj: Arc<Project>, view_macros: ViewMacros)
 
     let mut watcher = notify::watcher(sync_tx, Duration::from_millis(200))
         .expect("failed to build file system watcher");
+    let _tracked = Resources::watcher();
 
     for path in paths {
         if let Err(e) = watcher.watch(path, RecursiveMode::Recursive) {
@@ -59,7 +60,7 @@ async fn run(paths: &[Utf8PathBuf], proj: Arc<Project>, view_macros: ViewMacros)
         }
     }
 
-    if let Err(e) = Interrupt::subscribe_shutdown().recv().await {
+    if let Err(e) = Interrupt::subscribe_services_stop().recv().await {
         log::trace!("Notify stopped due to: {e:?}");
     }
 }
@@ -0,0 +1,83 @@
+use std::time::{Duration, SystemTime};
+
+use camino::Utf8PathBuf;
+use tokio::{process::Command, time::sleep};
+
+use crate::{
+    compile::unix_secs,
+    config::Project,
+    ext::{
+        anyhow::{bail, Result},
+        fs,
+        sync::CommandResult,
+        tool::Tool,
+    },
+    logger::GRAY,
+    signal::{Interrupt, Status},
+};
+
+/// The seconds a profile records when not given.
+pub const DEFAULT_SECONDS: u64 = 10;
+
+/// Records the running server for the seconds with `flamegraph` (of cargo-flamegraph, which
+/// attaches perf on linux and dtrace on macos), and writes the svg to `target/leptos-profiles`.
+pub async fn capture(proj: &Project, seconds: u64) -> Result<Utf8PathBuf> {
+    if cfg!(not(target_family = "unix")) {
+        bail!("Profile is only supported on linux and macos");
+    }
+    let Some(pid) = Status::get().server_pid else {
+        bail!("Profile found no running server");
+    };
+    let Ok(flamegraph) = which::which("flamegraph") else {
+        bail!("Profile needs flamegraph, install it with: cargo install flamegraph");
+    };
+    fs::create_dir_all(&proj.profiles_dir).await?;
+    let file = proj.profiles_dir.join(format!(
+        "{}-{}.svg",
+        proj.name,
+        unix_secs(SystemTime::now())
+    ));
+
+    let mut command = Command::new(flamegraph);
+    command
+        .arg("--pid")
+        .arg(pid.to_string())
+        .arg("--output")
+        .arg(&file);
+    // perf and dtrace stop recording on ctrl-c, which a terminal sends to the process group
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::process::CommandExt;
+        command.as_std_mut().process_group(0);
+    }
+    log::info!("Profile recording the server (pid {pid}) for {seconds}s");
+    let tool = Tool::new("Flamegraph", command)
+        .with_timeouts(&proj.tool_timeouts)
+        .spawn()?;
+    let stop = tool.id().map(|group| {
+        tokio::spawn(async move {
+            sleep(Duration::from_secs(seconds)).await;
+            if let Err(e) = Command::new("kill")
+                .args(["-INT", &format!("-{group}")])
+                .status()
+                .await
+            {
+                log::warn!("Profile could not stop flamegraph: {e}");
+            }
+        })
+    });
+    let res = tool.wait(Interrupt::subscribe_shutdown()).await;
+    if let Some(stop) = stop {
+        stop.abort();
+    }
+    match res? {
+        CommandResult::Success(()) if file.exists() => {
+            log::info!("Profile written to {}", GRAY.paint(file.as_str()));
+            Ok(file)
+        }
+        CommandResult::Interrupted => bail!("Profile interrupted"),
+        _ => bail!(
+            "Profile flamegraph failed, perf needs a kernel.perf_event_paranoid of 1 or less and dtrace needs root"
+        ),
+    }
+}
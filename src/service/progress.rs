@@ -0,0 +1,111 @@
+use std::time::{Duration, Instant};
+
+use tokio::{task::JoinHandle, time::interval};
+
+use crate::{
+    config::Opts,
+    ext::StrAdditions,
+    logger::{self, INFO_GREEN},
+    signal::{BuildEvent, Bus, Status},
+};
+
+const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+const TICK: Duration = Duration::from_millis(120);
+
+/// The status line of the running build, drawn below the logs so that a long cargo build
+/// doesn't look frozen. It is removed when dropped.
+pub struct Progress {
+    handle: JoinHandle<()>,
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.handle.abort();
+        logger::set_status_line(None);
+    }
+}
+
+/// Draws the status line during the builds, without `-v` (the debug logs are the progress
+/// then) or `--quiet`, and when stderr is a terminal.
+pub fn start(opts: &Opts) -> Option<Progress> {
+    if opts.verbose > 0 || opts.quiet || !logger::can_draw_status_line() {
+        return None;
+    }
+    let handle = tokio::spawn(async move {
+        let mut events = Bus::build().subscribe();
+        let mut ticks = interval(TICK);
+        // the start of the build and the warnings logged before it
+        let mut build: Option<(Instant, usize)> = None;
+        let mut frame = 0;
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Some(BuildEvent::Started) => build = Some((Instant::now(), logger::warnings())),
+                    Some(BuildEvent::Finished(_)) => {
+                        build = None;
+                        logger::set_status_line(None);
+                    }
+                    Some(_) => {}
+                    None => break,
+                },
+                _ = ticks.tick(), if build.is_some() => {
+                    let (started, warnings) = build.unwrap_or((Instant::now(), 0));
+                    frame = (frame + 1) % FRAMES.len();
+                    let status = Status::get();
+                    let text = status_text(
+                        FRAMES[frame],
+                        &status.tools_running,
+                        &status.phases_done,
+                        started.elapsed(),
+                        logger::warnings() - warnings,
+                    );
+                    let label = INFO_GREEN.paint("Building".pad_left_to(12));
+                    logger::set_status_line(Some(format!("{label} {text}")));
+                }
+            }
+        }
+    });
+    Some(Progress { handle })
+}
+
+/// I.e. `⠹ Cargo, Sass 12.3s, 2 warnings`, with the last finished phase when no tool runs.
+fn status_text(
+    frame: char,
+    tools: &[&str],
+    phases_done: &[&str],
+    elapsed: Duration,
+    warnings: usize,
+) -> String {
+    let what = match (tools, phases_done.last()) {
+        ([], Some(phase)) => format!("after {phase}"),
+        ([], None) => "starting".to_string(),
+        (tools, _) => tools.join(", "),
+    };
+    let mut text = format!("{frame} {what} {:.1}s", elapsed.as_secs_f32());
+    match warnings {
+        0 => {}
+        1 => text.push_str(", 1 warning"),
+        n => text.push_str(&format!(", {n} warnings")),
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text() {
+        let elapsed = Duration::from_millis(12_340);
+        assert_eq!(
+            status_text('⠹', &["Cargo", "Sass"], &[], elapsed, 2),
+            "⠹ Cargo, Sass 12.3s, 2 warnings"
+        );
+        assert_eq!(
+            status_text('⠋', &[], &["server", "bindgen"], elapsed, 1),
+            "⠋ after bindgen 12.3s, 1 warning"
+        );
+        assert_eq!(status_text('⠋', &[], &[], elapsed, 0), "⠋ starting 12.3s");
+    }
+}
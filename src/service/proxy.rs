@@ -0,0 +1,218 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    body::Body,
+    http::{header, HeaderValue, Request, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Router,
+};
+use hyper::{client::HttpConnector, upgrade::OnUpgrade, Client};
+use tokio::{net::TcpStream, task::JoinHandle};
+
+use crate::{
+    config::{browser_addr, Project, ProxyConfig},
+    ext::anyhow::{Context, Result},
+    logger::GRAY,
+    signal::Interrupt,
+};
+
+type HttpClient = Client<HttpConnector, Body>;
+
+/// Starts the dev proxy of the project, when it has proxy rules. It is stopped with the other
+/// services.
+pub async fn spawn(proj: &Arc<Project>) -> Option<JoinHandle<()>> {
+    let proxy = proj.proxy.clone()?;
+    if proj.tls.is_some() {
+        log::warn!("Proxy not started, as it doesn't serve https");
+        return None;
+    }
+    let site = proj.site.addr;
+    let url = format!(
+        "http://{}{}",
+        browser_addr(proxy.addr, proj.reload_client.advertise_host.as_deref()),
+        proj.base_href
+    );
+    let mut int = Interrupt::subscribe_services_stop();
+    Some(tokio::spawn(async move {
+        let addr = proxy.addr;
+        if TcpStream::connect(addr).await.is_ok() {
+            log::error!("Proxy port {addr} already in use, set another proxy-port");
+            return;
+        }
+        for rule in &proxy.rules {
+            log::info!(
+                "Proxy {}/* -> {}",
+                rule.prefix,
+                GRAY.paint(rule.backend.as_str())
+            );
+        }
+        log::info!("Proxy serving the site at {}", GRAY.paint(url));
+
+        match axum::Server::bind(&addr)
+            .serve(router(Arc::new(proxy), site).into_make_service())
+            .with_graceful_shutdown(async move {
+                _ = int.recv().await;
+            })
+            .await
+        {
+            Ok(_) => log::debug!("Proxy stopped"),
+            Err(e) => log::error!("Proxy {e}"),
+        }
+    }))
+}
+
+/// Forwards the requests of the rules to their backends and the others to the site server.
+fn router(proxy: Arc<ProxyConfig>, site: SocketAddr) -> Router {
+    let client = HttpClient::new();
+    Router::new()
+        .fallback(move |req: Request<Body>| forward(proxy.clone(), site, client.clone(), req))
+}
+
+async fn forward(
+    proxy: Arc<ProxyConfig>,
+    site: SocketAddr,
+    client: HttpClient,
+    req: Request<Body>,
+) -> Response {
+    let path_and_query = req
+        .uri()
+        .path_and_query()
+        .map_or("/", |path| path.as_str())
+        .to_string();
+    let url = match proxy.route(req.uri().path()) {
+        Some(rule) => rule.url(&path_and_query),
+        None => format!("http://{site}{path_and_query}"),
+    };
+    log::trace!("Proxy {} {}", req.method(), GRAY.paint(&url));
+    match send(&client, req, &url).await {
+        Ok(res) => res,
+        Err(e) => {
+            log::warn!("Proxy {url} failed: {e:#}");
+            (StatusCode::BAD_GATEWAY, format!("{e:#}")).into_response()
+        }
+    }
+}
+
+/// Sends the request to the url, with the host of the url like the browser would. A websocket
+/// (or other upgrade) accepted by the backend is tunneled once both sides are upgraded.
+async fn send(client: &HttpClient, mut req: Request<Body>, url: &str) -> Result<Response> {
+    let uri: Uri = url.parse().context("Invalid proxied url")?;
+    if let Some(host) = req.headers_mut().remove(header::HOST) {
+        req.headers_mut().insert("x-forwarded-host", host);
+    }
+    if let Some(authority) = uri.authority() {
+        req.headers_mut()
+            .insert(header::HOST, HeaderValue::from_str(authority.as_str())?);
+    }
+    *req.uri_mut() = uri;
+
+    let upgrade = req
+        .headers()
+        .contains_key(header::UPGRADE)
+        .then(|| hyper::upgrade::on(&mut req));
+    let mut res = client
+        .request(req)
+        .await
+        .context("The backend did not respond")?;
+    if let Some(upgrade) = upgrade {
+        if res.status() == StatusCode::SWITCHING_PROTOCOLS {
+            tokio::spawn(tunnel(upgrade, hyper::upgrade::on(&mut res)));
+        }
+    }
+    Ok(res.into_response())
+}
+
+/// Copies the bytes both ways until a side closes, or the services stop.
+async fn tunnel(browser: OnUpgrade, backend: OnUpgrade) {
+    let (mut browser, mut backend) = match tokio::try_join!(browser, backend) {
+        Ok(upgraded) => upgraded,
+        Err(e) => {
+            log::debug!("Proxy upgrade failed: {e}");
+            return;
+        }
+    };
+    let mut int = Interrupt::subscribe_services_stop();
+    tokio::select! {
+        res = tokio::io::copy_bidirectional(&mut browser, &mut backend) => {
+            if let Err(e) = res {
+                log::debug!("Proxy tunnel closed: {e}");
+            }
+        }
+        _ = int.recv() => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProxyRule;
+    use axum::routing::get;
+    use reqwest::Url;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn serve(router: Router) -> SocketAddr {
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(router.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    /// Upgrades the connection, then echoes the first 4 bytes.
+    async fn echo(mut req: Request<Body>) -> Response {
+        let upgrade = hyper::upgrade::on(&mut req);
+        tokio::spawn(async move {
+            let mut io = upgrade.await.unwrap();
+            let mut buf = [0; 4];
+            io.read_exact(&mut buf).await.unwrap();
+            io.write_all(&buf).await.unwrap();
+        });
+        (
+            StatusCode::SWITCHING_PROTOCOLS,
+            [(header::CONNECTION, "upgrade"), (header::UPGRADE, "echo")],
+        )
+            .into_response()
+    }
+
+    async fn get_text(addr: SocketAddr, path: &str) -> String {
+        let uri: Uri = format!("http://{addr}{path}").parse().unwrap();
+        let res = HttpClient::new().get(uri).await.unwrap();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn proxied_requests() {
+        let backend = serve(
+            Router::new()
+                .route("/api/hello", get(|| async { "backend" }))
+                .route("/api/echo", get(echo)),
+        );
+        let site = serve(Router::new().fallback(|| async { "site" }));
+        let proxy = ProxyConfig {
+            addr: "127.0.0.1:0".parse().unwrap(),
+            rules: vec![ProxyRule {
+                prefix: "/api".to_string(),
+                backend: Url::parse(&format!("http://{backend}")).unwrap(),
+            }],
+        };
+        let proxy = serve(router(Arc::new(proxy), site));
+
+        assert_eq!(get_text(proxy, "/api/hello").await, "backend");
+        assert_eq!(get_text(proxy, "/apiary").await, "site");
+        assert_eq!(get_text(proxy, "/").await, "site");
+
+        let req = Request::get(format!("http://{proxy}/api/echo"))
+            .header(header::CONNECTION, "upgrade")
+            .header(header::UPGRADE, "echo")
+            .body(Body::empty())
+            .unwrap();
+        let res = HttpClient::new().request(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::SWITCHING_PROTOCOLS);
+        let mut io = hyper::upgrade::on(res).await.unwrap();
+        io.write_all(b"ping").await.unwrap();
+        let mut buf = [0; 4];
+        io.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+    }
+}
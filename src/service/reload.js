@@ -0,0 +1,34 @@
+// The live-reload client as an external script, for pages with a strict
+// Content Security Policy that blocks the inline one of leptos. Include it in dev mode with:
+// <script src="http://127.0.0.1:3001/live_reload/reload.js"></script>
+// View patches (hot-reload) are only applied by the inline client.
+(function () {
+  const src = new URL(document.currentScript.src);
+  const protocol = src.protocol === "https:" ? "wss:" : "ws:";
+  // the websocket of the reload-external-url, filled in by cargo-leptos when set
+  const external = "__LEPTOS_RELOAD_URL__";
+  const url = external.startsWith("ws") ? external : `${protocol}//${src.host}/live_reload`;
+
+  function updateCss(css) {
+    let found = false;
+    document.querySelectorAll("link").forEach((link) => {
+      if (link.getAttribute("href").includes(css)) {
+        link.setAttribute("href", `/${css}?version=${Date.now()}`);
+        found = true;
+      }
+    });
+    if (!found) console.warn(`CSS hot-reload: Could not find a <link href=/"${css}"> element`);
+  }
+
+  function connect() {
+    const ws = new WebSocket(url);
+    ws.onmessage = (event) => {
+      const msg = JSON.parse(event.data);
+      if (msg.all) window.location.reload();
+      if (msg.css) updateCss(msg.css);
+    };
+    ws.onclose = () => setTimeout(connect, 1000);
+  }
+
+  connect();
+})();
@@ -1,40 +1,40 @@
-use crate::config::Project;
-use crate::ext::sync::wait_for_socket;
+use crate::compile::{last_diagnostics, Diagnostic};
+use crate::config::{Project, ReloadClient};
+use crate::ext::{sync::wait_for_socket, PathExt};
 use crate::logger::GRAY;
 use crate::signal::Interrupt;
-use crate::signal::{ReloadSignal, ReloadType};
+use crate::signal::{BuildStatus, ReloadSignal, ReloadType, Resources, Status};
 use axum::{
     extract::ws::{Message, WebSocket, WebSocketUpgrade},
-    response::IntoResponse,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::get,
-    Router,
+    Json, Router,
 };
 use serde::Serialize;
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 use std::{fmt::Display, net::SocketAddr};
 use tokio::{net::TcpStream, select, sync::RwLock, task::JoinHandle};
 
 lazy_static::lazy_static! {
   static ref SITE_ADDR: RwLock<SocketAddr> = RwLock::new(SocketAddr::new([127,0,0,1].into(), 3000));
   static ref CSS_LINK: RwLock<String> = RwLock::new(String::default());
+  static ref WORKING_DIR: RwLock<String> = RwLock::new(String::default());
 }
 
+const OVERLAY_JS: &str = include_str!("overlay.js");
+const RELOAD_JS: &str = include_str!("reload.js");
+/// Replaced in the scripts by the websocket url of the `reload-external-url`.
+const RELOAD_URL_PLACEHOLDER: &str = "__LEPTOS_RELOAD_URL__";
+
 pub async fn spawn(proj: &Arc<Project>) -> JoinHandle<()> {
     let proj = proj.clone();
 
-    let mut site_addr = SITE_ADDR.write().await;
-    *site_addr = proj.site.addr;
-    if let Some(file) = &proj.style.file {
-        let mut css_link = CSS_LINK.write().await;
-        // Always use `/` as separator in links
-        *css_link = file
-            .site
-            .components()
-            .map(|c| c.as_str())
-            .collect::<Vec<_>>()
-            .join("/");
-    }
+    *SITE_ADDR.write().await = proj.site.addr;
+    *WORKING_DIR.write().await = proj.working_dir.to_string();
+    update_css_link(&proj).await;
 
+    let mut int = Interrupt::subscribe_services_stop();
     tokio::spawn(async move {
         let _change = ReloadSignal::subscribe();
 
@@ -48,15 +48,44 @@ pub async fn spawn(proj: &Arc<Project>) -> JoinHandle<()> {
 
             return;
         }
-        let route = Router::new().route("/live_reload", get(websocket_handler));
+        let overlay_js = with_reload_url(OVERLAY_JS, &proj.reload_client);
+        let reload_js = with_reload_url(RELOAD_JS, &proj.reload_client);
+        let route = Router::new()
+            .route("/live_reload", {
+                let proj = proj.clone();
+                get(move |ws, headers| websocket_handler(ws, headers, proj.clone()))
+            })
+            .route(
+                "/live_reload/overlay.js",
+                get(move || script(overlay_js.clone())),
+            )
+            .route(
+                "/live_reload/reload.js",
+                get(move || script(reload_js.clone())),
+            )
+            .route("/live_reload/status", {
+                let proj = proj.clone();
+                get(move || build_status(proj.clone()))
+            });
 
         log::debug!(
             "Reload server started {}",
             GRAY.paint(reload_addr.to_string())
         );
 
+        if let Some(tls) = &proj.tls {
+            if let Err(e) = super::tls::serve(tls, reload_addr, route).await {
+                log::error!("Reload {e}");
+            }
+            return;
+        }
+
+        // stopped on shutdown, for the watch to start again on a change of the config
         match axum::Server::bind(&reload_addr)
             .serve(route.into_make_service())
+            .with_graceful_shutdown(async move {
+                _ = int.recv().await;
+            })
             .await
         {
             Ok(_) => log::debug!("Reload server stopped"),
@@ -65,34 +94,116 @@ pub async fn spawn(proj: &Arc<Project>) -> JoinHandle<()> {
     })
 }
 
-async fn websocket_handler(ws: WebSocketUpgrade) -> impl IntoResponse {
-    ws.on_upgrade(websocket)
+/// The link of the style sheet the browsers reload, which a reloaded config may change.
+pub async fn update_css_link(proj: &Project) {
+    if let Some(file) = &proj.style.file {
+        // Always use `/` as separator in links
+        *CSS_LINK.write().await = file.site.to_url_path();
+    }
+}
+
+/// The pages of other origins can't connect, as the reload server may listen on all interfaces.
+/// The clients that aren't browsers send no origin.
+async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    proj: Arc<Project>,
+) -> Response {
+    if let Some(origin) = headers.get(header::ORIGIN).and_then(|o| o.to_str().ok()) {
+        if !proj.reload_client.allows_origin(origin) {
+            log::warn!(
+                "Reload refused the websocket of the page at {}, allow it with {}",
+                GRAY.paint(origin),
+                GRAY.paint(format!("reload-allowed-origins = [\"{origin}\"]"))
+            );
+            return StatusCode::FORBIDDEN.into_response();
+        }
+    }
+    ws.on_upgrade(websocket).into_response()
+}
+
+async fn script(js: String) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/javascript")], js)
+}
+
+/// The scripts connect to the host they are loaded from, unless the browser reaches the reload
+/// server at another url.
+fn with_reload_url(js: &str, client: &ReloadClient) -> String {
+    match client.websocket_url() {
+        Some(url) => js.replace(RELOAD_URL_PLACEHOLDER, &url),
+        None => js.to_string(),
+    }
+}
+
+/// The build state, the diagnostics of the last build and the build manifest (with the
+/// artifact hashes), for editor integrations.
+async fn build_status(proj: Arc<Project>) -> impl IntoResponse {
+    #[derive(Serialize)]
+    struct StatusResponse {
+        project: String,
+        #[serde(flatten)]
+        status: BuildStatus,
+        diagnostics: BTreeMap<String, Vec<Diagnostic>>,
+        manifest: Option<serde_json::Value>,
+    }
+    let manifest = tokio::fs::read_to_string(&proj.build_manifest_file)
+        .await
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok());
+    Json(StatusResponse {
+        project: proj.name.clone(),
+        status: Status::get(),
+        diagnostics: last_diagnostics(),
+        manifest,
+    })
 }
 
 async fn websocket(mut stream: WebSocket) {
     let mut rx = ReloadSignal::subscribe();
-    let mut int = Interrupt::subscribe_any();
+    let mut int = Interrupt::subscribe_services_stop();
 
     log::trace!("Reload websocket connected");
     tokio::spawn(async move {
+        let _tracked = Resources::reload_connection();
+        match ReloadSignal::last_build_error() {
+            Some(ReloadType::BuildError(output)) => {
+                send(&mut stream, BrowserMessage::build_error(output).await).await;
+            }
+            Some(ReloadType::ServerCrash(output)) => {
+                send(&mut stream, BrowserMessage::server_crash(output).await).await;
+            }
+            _ => {}
+        }
         loop {
             select! {
                 res = rx.recv() =>{
                     match res {
-                        Ok(ReloadType::Full) => {
+                        Some(ReloadType::Full) => {
                             send_and_close(stream, BrowserMessage::all()).await;
                             return
                         }
-                        Ok(ReloadType::Style) => {
+                        Some(ReloadType::Style) => {
                             send(&mut stream, BrowserMessage::css().await).await;
                         },
-                        Ok(ReloadType::ViewPatches(data)) => {
+                        Some(ReloadType::ViewPatches(data)) => {
                             send(&mut stream, BrowserMessage::view(data)).await;
                         }
-                        Err(e) => log::debug!("Reload recive error {e}")
+                        Some(ReloadType::BuildError(output)) => {
+                            send(&mut stream, BrowserMessage::build_error(output).await).await;
+                        }
+                        Some(ReloadType::ServerCrash(output)) => {
+                            send(&mut stream, BrowserMessage::server_crash(output).await).await;
+                        }
+                        Some(ReloadType::ClearBuildError) => {
+                            send(&mut stream, BrowserMessage::clear_build_error()).await;
+                        }
+                        None => {
+                            log::trace!("Reload websocket closed");
+                            return
+                        }
                     }
                 }
-                _ = int.recv(), if Interrupt::is_shutdown_requested().await => {
+                _ = int.recv() => {
                     log::trace!("Reload websocket closed");
                     return
                 },
@@ -103,7 +214,8 @@ async fn websocket(mut stream: WebSocket) {
 
 async fn send(stream: &mut WebSocket, msg: BrowserMessage) {
     let site_addr = *SITE_ADDR.read().await;
-    if !wait_for_socket("Reload", site_addr).await {
+    // the errors are shown without waiting for the server, which may be the one that crashed
+    if msg.build_error.is_none() && !wait_for_socket("Reload", site_addr).await {
         log::warn!(r#"Reload could not send "{msg}" to websocket"#);
     }
 
@@ -124,11 +236,24 @@ async fn send_and_close(mut stream: WebSocket, msg: BrowserMessage) {
     log::trace!("Reload websocket closed");
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Default)]
 struct BrowserMessage {
     css: Option<String>,
     view: Option<String>,
     all: bool,
+    /// the output of the failed build, shown by the error overlay
+    #[serde(skip_serializing_if = "Option::is_none")]
+    build_error: Option<BuildError>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    clear_build_error: bool,
+}
+
+#[derive(Serialize)]
+struct BuildError {
+    title: &'static str,
+    output: String,
+    /// for making absolute file links of the relative paths in the output
+    working_dir: String,
 }
 
 impl BrowserMessage {
@@ -141,6 +266,7 @@ impl BrowserMessage {
             css: Some(link),
             view: None,
             all: false,
+            ..Default::default()
         }
     }
 
@@ -149,6 +275,7 @@ impl BrowserMessage {
             css: None,
             view: Some(data),
             all: false,
+            ..Default::default()
         }
     }
 
@@ -157,6 +284,33 @@ impl BrowserMessage {
             css: None,
             view: None,
             all: true,
+            ..Default::default()
+        }
+    }
+
+    async fn build_error(output: String) -> Self {
+        Self::error("Build failed", output).await
+    }
+
+    async fn server_crash(output: String) -> Self {
+        Self::error("Server crashed", output).await
+    }
+
+    async fn error(title: &'static str, output: String) -> Self {
+        Self {
+            build_error: Some(BuildError {
+                title,
+                output,
+                working_dir: WORKING_DIR.read().await.clone(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn clear_build_error() -> Self {
+        Self {
+            clear_build_error: true,
+            ..Default::default()
         }
     }
 }
@@ -165,6 +319,10 @@ impl Display for BrowserMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Some(css) = &self.css {
             write!(f, "reload {}", css)
+        } else if let Some(error) = &self.build_error {
+            write!(f, "{}", error.title.to_lowercase())
+        } else if self.clear_build_error {
+            write!(f, "clear build error")
         } else {
             write!(f, "reload all")
         }
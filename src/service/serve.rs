@@ -1,34 +1,120 @@
-use std::sync::Arc;
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    process::{ExitStatus, Stdio},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use crate::{
     config::Project,
-    ext::{anyhow::Result, append_str_to_filename, determine_pdb_filename, fs},
-    logger::GRAY,
-    signal::{Interrupt, ReloadSignal, ServerRestart},
+    ext::{
+        anyhow::{bail, Context, Result},
+        append_str_to_filename, determine_pdb_filename, fs,
+        sync::wait_for_socket,
+        LeptosError,
+    },
+    logger::{Paint, GRAY},
+    service::{
+        hooks::{self, Hook},
+        server::{self, ServerIntegration},
+    },
+    signal::{
+        Bus, Interrupt, ReloadSignal, Resources, ServerEvent, ServerRestart, Status, Tracked,
+    },
 };
+use ansi_term::Colour::Fixed;
 use camino::Utf8PathBuf;
+use reqwest::header::{HeaderMap, HeaderName, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE};
 use tokio::{
-    process::{Child, Command},
+    io::{AsyncBufReadExt, BufReader},
+    process::{Child, ChildStderr},
     select,
+    sync::{broadcast, Notify},
     task::JoinHandle,
+    time::{sleep_until, Instant},
 };
 
+/// The first delay before restarting a crashed server in watch mode, doubled with every crash.
+const RESTART_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(30);
+/// A server running this long before crashing is restarted after the first delay again.
+const STABLE_RUN: Duration = Duration::from_secs(10);
+/// The stderr lines of the server shown by the error overlay when it crashes.
+const CRASH_LINES: usize = 40;
+
 pub async fn spawn(proj: &Arc<Project>) -> JoinHandle<Result<()>> {
-    let mut int = Interrupt::subscribe_shutdown();
+    spawn_stoppable(proj, Arc::new(Notify::new())).await
+}
+
+/// Like [spawn], with the server also stopped by notifying `stop`, without a shutdown, e.g.
+/// by `prerender` before the next project.
+pub async fn spawn_stoppable(proj: &Arc<Project>, stop: Arc<Notify>) -> JoinHandle<Result<()>> {
+    let mut int = Interrupt::subscribe_services_stop();
     let proj = proj.clone();
     let mut change = ServerRestart::subscribe();
     tokio::spawn(async move {
-        let mut server = ServerProcess::start_new(&proj).await?;
+        if !hooks::run(&proj, Hook::PreServe).await? {
+            bail!("The pre-serve hook failed")
+        }
+        let mut server = ServerProcess::start_new(&proj)
+            .await
+            .categorize(LeptosError::Server)?;
+        Bus::server().publish(ServerEvent::Started);
+        if proj.lib.wasm_threads {
+            tokio::spawn(check_isolation_headers(proj.site.addr, proj.site_url()));
+        }
+        tokio::spawn(check_wasm_content_type(
+            proj.site.addr,
+            wasm_url(&proj),
+            server.integration.name(),
+        ));
+        let mut backoff = RESTART_BACKOFF;
+        let mut restart_at = None;
         loop {
             select! {
               res = change.recv() => {
-                if let Ok(()) = res {
-                      server.restart().await?;
+                if let Some(ServerEvent::Restart) = res {
+                      restart_at = None;
+                      backoff = RESTART_BACKOFF;
+                      server.restart().await.categorize(LeptosError::Server)?;
+                      server.wait_ready().await;
+                      Bus::server().publish(ServerEvent::Started);
                       ReloadSignal::send_full();
                 }
               },
-              _ = int.recv() => {
+              status = server.exited() => {
+                Bus::server().publish(ServerEvent::Stopped);
+                if status.success() {
+                    log::info!("Serve {} server exited", server.integration.name());
+                    continue;
+                }
+                log::error!("Serve {} server crashed with {status}", server.integration.name());
+                if !proj.watch {
+                    continue;
+                }
+                ReloadSignal::send_server_crash(&server.crash_output(status));
+                if server.ran_for() > STABLE_RUN {
+                    backoff = RESTART_BACKOFF;
+                }
+                log::warn!("Serve restarting the server in {backoff:?}");
+                restart_at = Some(Instant::now() + backoff);
+                backoff = (backoff * 2).min(MAX_RESTART_BACKOFF);
+              },
+              _ = sleep_until(restart_at.unwrap_or_else(Instant::now)), if restart_at.is_some() => {
+                restart_at = None;
+                server.start().await.categorize(LeptosError::Server)?;
+                let ready = server.wait_ready().await;
+                Bus::server().publish(ServerEvent::Started);
+                // the overlay shows the crash until the restarted server is up
+                if ready {
+                    ReloadSignal::send_clear_server_crash();
+                    ReloadSignal::send_full();
+                }
+              },
+              _ = stopped(&mut int, &stop) => {
                     server.kill().await;
+                    Bus::server().publish(ServerEvent::Stopped);
                     return Ok(())
               },
             }
@@ -36,21 +122,195 @@ pub async fn spawn(proj: &Arc<Project>) -> JoinHandle<Result<()>> {
     })
 }
 
+async fn stopped(int: &mut broadcast::Receiver<()>, stop: &Notify) {
+    select! {
+        _ = int.recv() => {},
+        _ = stop.notified() => {},
+    }
+}
+
+/// Shared memory, which wasm threads use, is only available to cross-origin isolated pages.
+async fn check_isolation_headers(addr: SocketAddr, url: String) {
+    if !wait_for_socket("Serve", addr).await {
+        return;
+    }
+    let headers = match get_headers(url).await {
+        Ok(headers) => headers,
+        Err(e) => {
+            log::debug!("Serve could not check the COOP/COEP headers: {e}");
+            return;
+        }
+    };
+    let has = |name: &str, value: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.eq_ignore_ascii_case(value))
+            .unwrap_or(false)
+    };
+    if !has("cross-origin-opener-policy", "same-origin")
+        || !has("cross-origin-embedder-policy", "require-corp")
+    {
+        log::warn!(
+            "Serve wasm-threads needs the server to set the headers {} and {}",
+            GRAY.paint("Cross-Origin-Opener-Policy: same-origin"),
+            GRAY.paint("Cross-Origin-Embedder-Policy: require-corp")
+        );
+    }
+}
+
+/// `WebAssembly.instantiateStreaming` only compiles the wasm served as `application/wasm`,
+/// including its precompressed variants, otherwise the js glue falls back to compiling it once
+/// downloaded.
+async fn check_wasm_content_type(addr: SocketAddr, url: String, integration: &'static str) {
+    if !wait_for_socket("Serve", addr).await {
+        return;
+    }
+    let headers = match get_headers_with(&url, &[(ACCEPT_ENCODING, "br, gzip")]).await {
+        Ok(headers) => headers,
+        Err(e) => {
+            log::debug!("Serve could not check the content type of the wasm: {e}");
+            return;
+        }
+    };
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if !content_type.starts_with("application/wasm") {
+        let encoding = headers
+            .get(CONTENT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .map(|encoding| format!(" {encoding} encoded"))
+            .unwrap_or_default();
+        log::warn!(
+            "Serve the {integration} server serves the{encoding} wasm as {} instead of {}, so it isn't compiled while downloading",
+            GRAY.paint(if content_type.is_empty() { "none" } else { content_type }),
+            GRAY.paint("application/wasm")
+        );
+    }
+}
+
+fn wasm_url(proj: &Project) -> String {
+    format!(
+        "{}{}{}",
+        proj.site_url(),
+        proj.base_href,
+        proj.lib.wasm_file.site
+    )
+}
+
+async fn get_headers(url: String) -> reqwest::Result<HeaderMap> {
+    get_headers_with(&url, &[]).await
+}
+
+async fn get_headers_with(url: &str, headers: &[(HeaderName, &str)]) -> reqwest::Result<HeaderMap> {
+    // the local https certificate may not be trusted
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+    let mut request = client.get(url);
+    for (name, value) in headers {
+        request = request.header(name, *value);
+    }
+    Ok(request.send().await?.headers().clone())
+}
+
 struct ServerProcess {
     process: Option<Child>,
-    envs: Vec<(&'static str, String)>,
+    tracked: Option<Tracked>,
+    integration: Box<dyn ServerIntegration>,
+    envs: Vec<(String, String)>,
     binary: Utf8PathBuf,
+    addr: SocketAddr,
+    site_url: String,
+    runner: Option<String>,
+    /// the stderr of the server is captured in watch mode, for the crash overlay
+    capture_stderr: bool,
+    stderr_tail: Arc<Mutex<VecDeque<String>>>,
+    started: Instant,
+}
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        // the serve task stopped without killing it
+        if let Some(process) = self.process.as_mut() {
+            if let (Some(pid), Ok(None)) = (process.id(), process.try_wait()) {
+                _ = process.start_kill();
+                Resources::leaked("Server", pid);
+            }
+        }
+    }
 }
 
 impl ServerProcess {
     fn new(proj: &Project) -> Self {
+        let integration = server::integration(proj);
         Self {
             process: None,
-            envs: proj.to_envs(),
+            tracked: None,
+            envs: integration.envs(proj),
+            integration,
             binary: proj.bin.exe_file.clone(),
+            addr: proj.site.addr,
+            site_url: proj.site_url(),
+            runner: proj.server_runner.clone(),
+            capture_stderr: proj.watch,
+            stderr_tail: Arc::default(),
+            started: Instant::now(),
         }
     }
 
+    /// The exit status of the server, once it exits by itself.
+    async fn exited(&mut self) -> ExitStatus {
+        let Some(process) = self.process.as_mut() else {
+            return std::future::pending().await;
+        };
+        match process.wait().await {
+            Ok(status) => {
+                self.process = None;
+                self.tracked = None;
+                Status::set_server_pid(None);
+                status
+            }
+            Err(e) => {
+                log::error!("Serve error waiting for the server process: {e}");
+                self.process = None;
+                std::future::pending().await
+            }
+        }
+    }
+
+    fn ran_for(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    fn crash_output(&self, status: ExitStatus) -> String {
+        let tail = self.stderr_tail.lock().unwrap();
+        let mut output = tail.iter().cloned().collect::<Vec<_>>().join("\n");
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(&format!(
+            "The {} server exited with {status}, it is restarted automatically",
+            self.integration.name()
+        ));
+        output
+    }
+
+    /// Whether the server is running and passed the health check.
+    async fn wait_ready(&self) -> bool {
+        let ready = self.process.is_some()
+            && self
+                .integration
+                .health_check(self.addr, &self.site_url)
+                .await;
+        if ready {
+            log::debug!("Serve {} server ready", self.integration.name());
+        }
+        ready
+    }
+
     async fn start_new(proj: &Project) -> Result<Self> {
         let mut me = Self::new(proj);
         me.start().await?;
@@ -65,6 +325,8 @@ impl ServerProcess {
                 log::trace!("Serve stopped");
             }
             self.process = None;
+            self.tracked = None;
+            Status::set_server_pid(None);
         }
     }
 
@@ -104,21 +366,29 @@ impl ServerProcess {
                 bin.clone()
             };
 
-            log::debug!("Serve running {}", GRAY.paint(bin_path.as_str()));
-            let cmd = Some(Command::new(bin_path).envs(self.envs.clone()).spawn()?);
-            let port = self
-                .envs
-                .iter()
-                .find_map(|(k, v)| {
-                    if k == &"LEPTOS_SITE_ADDR" {
-                        Some(v.to_string())
-                    } else {
-                        None
-                    }
-                })
-                .unwrap_or_default();
-            log::info!("Serving at http://{port}");
-            cmd
+            log::debug!(
+                "Serve running {} with the {} integration",
+                GRAY.paint(bin_path.as_str()),
+                self.integration.name()
+            );
+            if let Some(runner) = &self.runner {
+                log::info!("Serve running the server under {}", GRAY.paint(runner));
+            }
+            let mut command = self.integration.command(&bin_path, self.runner.as_deref());
+            command.envs(self.envs.clone());
+            if self.capture_stderr {
+                command.stderr(Stdio::piped());
+            }
+            let mut child = command.spawn()?;
+            if let Some(stderr) = child.stderr.take() {
+                self.stderr_tail.lock().unwrap().clear();
+                tokio::spawn(forward_stderr(stderr, self.stderr_tail.clone()));
+            }
+            self.started = Instant::now();
+            Status::set_server_pid(child.id());
+            self.tracked = child.id().map(|pid| Resources::child("Server", pid));
+            log::info!("Serving at {}", self.site_url);
+            Some(child)
         } else {
             log::debug!("Serve no exe found {}", GRAY.paint(bin.as_str()));
             None
@@ -127,3 +397,18 @@ impl ServerProcess {
         Ok(())
     }
 }
+
+/// Logs the stderr lines of the server with their own label, keeping the last ones for the
+/// crash overlay.
+async fn forward_stderr(stderr: ChildStderr, tail: Arc<Mutex<VecDeque<String>>>) {
+    let label = Paint::new(Fixed(203).bold()).paint("Server").to_string();
+    let mut lines = BufReader::new(stderr).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        log::info!("{label} {line}");
+        let mut tail = tail.lock().unwrap();
+        if tail.len() == CRASH_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+}
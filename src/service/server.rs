@@ -0,0 +1,174 @@
+use std::{net::SocketAddr, time::Duration};
+
+use crate::{
+    config::{IntegrationKind, Project},
+    ext::sync::{shell_command, wait_for_socket},
+    logger::GRAY,
+};
+use async_trait::async_trait;
+use camino::Utf8Path;
+use tokio::{process::Command, time::sleep};
+
+/// How the server binary is launched, which env vars it gets and how to
+/// tell that it is ready for requests.
+#[async_trait]
+pub trait ServerIntegration: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// The command that starts the server, given the server binary and the command it is run
+    /// under, e.g. `gdbserver :1234`.
+    fn command(&self, bin: &Utf8Path, runner: Option<&str>) -> Command {
+        // checked when resolving the config
+        let words = runner
+            .and_then(|runner| shell_words::split(runner).ok())
+            .unwrap_or_default();
+        let mut runner = words.iter();
+        match runner.next() {
+            Some(program) => {
+                let mut command = Command::new(program);
+                command.args(runner).arg(bin);
+                command
+            }
+            None => Command::new(bin),
+        }
+    }
+
+    /// The env vars given to the server.
+    fn envs(&self, proj: &Project) -> Vec<(String, String)> {
+        proj.to_envs()
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect()
+    }
+
+    /// Waits until the server is ready, returns false if it timed out.
+    async fn health_check(&self, addr: SocketAddr, _site_url: &str) -> bool {
+        wait_for_socket("Serve", addr).await
+    }
+}
+
+/// The leptos_axum and leptos_actix integrations read the LEPTOS_* env vars
+/// with `get_configuration`, so running the binary is all they need. Named after the
+/// configured integration in the logs.
+pub struct Leptos {
+    name: &'static str,
+}
+
+#[async_trait]
+impl ServerIntegration for Leptos {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// A server started by a shell command from the `[package.metadata.leptos.server]` config.
+pub struct Custom {
+    command: String,
+    health_path: Option<String>,
+    envs: Vec<(String, String)>,
+}
+
+#[async_trait]
+impl ServerIntegration for Custom {
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+
+    /// The custom command runs the binary itself, so it gets the runner as an env var.
+    fn command(&self, bin: &Utf8Path, runner: Option<&str>) -> Command {
+        let mut command = shell_command(&self.command);
+        command.env("LEPTOS_BIN_EXE_FILE", bin.as_str());
+        if let Some(runner) = runner {
+            command.env("LEPTOS_SERVER_RUNNER", runner);
+        }
+        command
+    }
+
+    fn envs(&self, proj: &Project) -> Vec<(String, String)> {
+        let mut envs = proj
+            .to_envs()
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect::<Vec<_>>();
+        envs.extend(self.envs.iter().cloned());
+        envs
+    }
+
+    async fn health_check(&self, addr: SocketAddr, site_url: &str) -> bool {
+        let Some(path) = &self.health_path else {
+            return wait_for_socket("Serve", addr).await;
+        };
+        let url = format!("{site_url}{path}");
+        // the local https certificate may not be trusted
+        let Ok(client) = reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+        else {
+            return false;
+        };
+        for _ in 0..20 {
+            match client.get(&url).send().await {
+                Ok(resp) if resp.status().is_success() => {
+                    log::debug!("Serve health check {} passed", GRAY.paint(&url));
+                    return true;
+                }
+                Ok(resp) => log::trace!("Serve health check returned {}", resp.status()),
+                Err(e) => log::trace!("Serve health check failed: {e}"),
+            }
+            sleep(Duration::from_millis(500)).await;
+        }
+        log::warn!("Serve timed out waiting for {}", GRAY.paint(&url));
+        false
+    }
+}
+
+/// The server integration configured for the project.
+pub fn integration(proj: &Project) -> Box<dyn ServerIntegration> {
+    let conf = &proj.server;
+    match conf.integration {
+        IntegrationKind::Axum => Box::new(Leptos { name: "axum" }),
+        IntegrationKind::Actix => Box::new(Leptos { name: "actix" }),
+        IntegrationKind::Custom => Box::new(Custom {
+            // checked when parsing the config
+            command: conf.command.clone().unwrap_or_default(),
+            health_path: conf.health_path.clone(),
+            envs: conf
+                .envs
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(command: &Command) -> Vec<String> {
+        let command = command.as_std();
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn runner() {
+        let bin = Utf8Path::new("target/debug/app");
+        let leptos = Leptos { name: "axum" };
+        assert_eq!(args(&leptos.command(bin, None)), vec!["target/debug/app"]);
+        assert_eq!(
+            args(&leptos.command(bin, Some("valgrind  --leak-check=full"))),
+            vec!["valgrind", "--leak-check=full", "target/debug/app"]
+        );
+        assert_eq!(
+            args(&leptos.command(bin, Some(" "))),
+            vec!["target/debug/app"]
+        );
+        assert_eq!(
+            args(&leptos.command(bin, Some(r#"rr record --output "my traces""#))),
+            vec!["rr", "record", "--output", "my traces", "target/debug/app"]
+        );
+    }
+}
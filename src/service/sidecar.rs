@@ -0,0 +1,84 @@
+use std::{process::Stdio, sync::Arc, time::Duration};
+
+use crate::{
+    config::Project,
+    ext::{anyhow::Result, sync::shell_command},
+    logger::{Paint, GRAY},
+    signal::{Interrupt, Resources},
+};
+use ansi_term::Colour::Fixed;
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, BufReader},
+    task::JoinHandle,
+    time::sleep,
+};
+
+/// The prefix colors, cycled through in the order of the sidecars
+const COLORS: [u8; 6] = [39, 170, 178, 43, 141, 209];
+
+/// Delay before restarting a crashed sidecar
+const RESTART_DELAY: Duration = Duration::from_secs(2);
+
+/// Starts the long-running sidecar processes of the project (e.g. `tsc --watch`).
+/// They are restarted when they exit and killed on shutdown.
+pub async fn spawn(proj: &Arc<Project>) -> Vec<JoinHandle<()>> {
+    proj.sidecars
+        .iter()
+        .enumerate()
+        .map(|(i, (name, cmd))| {
+            let proj = proj.clone();
+            let prefix = Paint::new(Fixed(COLORS[i % COLORS.len()]).bold());
+            let (name, cmd) = (name.clone(), cmd.clone());
+            tokio::spawn(async move {
+                if let Err(e) = run(&proj, &name, &cmd, prefix).await {
+                    log::error!("Sidecar {name} stopped: {e}");
+                }
+            })
+        })
+        .collect()
+}
+
+async fn run(proj: &Project, name: &str, cmd: &str, prefix: Paint) -> Result<()> {
+    let mut int = Interrupt::subscribe_services_stop();
+    loop {
+        log::info!("Sidecar {name} starting {}", GRAY.paint(cmd));
+        let mut process = shell_command(cmd)
+            .current_dir(&proj.working_dir)
+            .envs(proj.to_envs())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let _tracked = process.id().map(|pid| Resources::child("Sidecar", pid));
+
+        let stdout = BufReader::new(process.stdout.take().unwrap());
+        let stderr = BufReader::new(process.stderr.take().unwrap());
+        let label = prefix.paint(name).to_string();
+        let finished = async {
+            tokio::join!(log_lines(&label, stdout), log_lines(&label, stderr));
+            process.wait().await
+        };
+
+        tokio::select! {
+            status = finished => {
+                log::warn!("Sidecar {name} exited with {}, restarting", status?);
+            }
+            _ = int.recv() => {
+                log::trace!("Sidecar {name} stopped");
+                return Ok(());
+            }
+        }
+
+        tokio::select! {
+            _ = sleep(RESTART_DELAY) => {}
+            _ = int.recv() => return Ok(()),
+        }
+    }
+}
+
+async fn log_lines(label: &str, reader: impl AsyncBufRead + Unpin) {
+    let mut lines = reader.lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        log::info!("{label} {line}");
+    }
+}
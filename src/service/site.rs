@@ -1,7 +1,8 @@
 use std::{
     collections::HashMap,
     fmt::{self, Display},
-    net::SocketAddr,
+    io::ErrorKind,
+    net::{SocketAddr, TcpListener},
 };
 
 use camino::{Utf8Path, Utf8PathBuf};
@@ -10,8 +11,8 @@ use tokio::sync::RwLock;
 use crate::{
     config::ProjectConfig,
     ext::{
-        anyhow::{Context, Result},
-        fs, PathBufExt,
+        anyhow::{anyhow, Context, Result},
+        fs, ErrorCode, PathBufExt,
     },
 };
 
@@ -112,6 +113,25 @@ impl Site {
     pub fn root_relative_pkg_dir(&self) -> Utf8PathBuf {
         self.root_dir.join(&self.pkg_dir)
     }
+
+    /// Fails with [ErrorCode::PortInUse] when another process already listens on
+    /// the site address, or on the reload address when watching.
+    pub fn ensure_addrs_free(&self, watch: bool) -> Result<()> {
+        let mut addrs = vec![("site-addr", self.addr)];
+        if watch {
+            addrs.push(("reload-port", self.reload));
+        }
+        for (name, addr) in addrs {
+            if let Err(e) = TcpListener::bind(addr) {
+                if e.kind() == ErrorKind::AddrInUse {
+                    return Err(anyhow!("The {name} {addr} is already in use"))
+                        .code(ErrorCode::PortInUse);
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// check if the file changed
     pub async fn did_external_file_change(&self, to: &Utf8Path) -> Result<bool> {
         let new_hash = file_hash(to).await.dot()?;
@@ -0,0 +1,130 @@
+use std::net::SocketAddr;
+
+use crate::{
+    config::{Project, TlsConfig},
+    ext::{
+        anyhow::{bail, Context, Result},
+        FileLock,
+    },
+    logger::GRAY,
+};
+use axum::Router;
+use tokio::process::Command;
+
+/// Creates the local certificate if it isn't cached yet. Uses mkcert when installed, so that the
+/// certificate is trusted by the browsers, and falls back to a self-signed one made by openssl.
+pub async fn ensure_cert(proj: &Project) -> Result<()> {
+    let Some(tls) = &proj.tls else {
+        return Ok(());
+    };
+    if cfg!(not(feature = "native-tls")) {
+        bail!("Serving with --https needs cargo-leptos built with the native-tls feature");
+    }
+    if tls.cert_file.exists() && tls.key_file.exists() {
+        log::debug!("Tls using cached {}", GRAY.paint(tls.cert_file.as_str()));
+        return Ok(());
+    }
+    // shared by the cargo-leptos processes of the machine (the lock creates its dir)
+    let _lock =
+        FileLock::acquire(&tls.cert_file.with_extension("lock"), "certificate", true).await?;
+    if tls.cert_file.exists() && tls.key_file.exists() {
+        log::debug!(
+            "Tls using {} created meanwhile",
+            GRAY.paint(tls.cert_file.as_str())
+        );
+        return Ok(());
+    }
+
+    let mut command = if let Ok(mkcert) = which::which("mkcert") {
+        let mut command = Command::new(mkcert);
+        command
+            .arg("-cert-file")
+            .arg(&tls.cert_file)
+            .arg("-key-file")
+            .arg(&tls.key_file)
+            .args(["localhost", "127.0.0.1", "::1"]);
+        command
+    } else if let Ok(openssl) = which::which("openssl") {
+        log::warn!(
+            "Tls mkcert not found, the generated certificate will not be trusted by browsers"
+        );
+        let mut command = Command::new(openssl);
+        command
+            .args(["req", "-x509", "-newkey", "rsa:2048", "-nodes", "-sha256"])
+            .args(["-days", "825", "-subj", "/CN=localhost"])
+            .args([
+                "-addext",
+                "subjectAltName=DNS:localhost,IP:127.0.0.1,IP:::1",
+            ])
+            .arg("-keyout")
+            .arg(&tls.key_file)
+            .arg("-out")
+            .arg(&tls.cert_file);
+        command
+    } else {
+        bail!(
+            "Serving with --https needs mkcert (recommended) or openssl to create the certificate"
+        )
+    };
+
+    let output = command
+        .output()
+        .await
+        .context("Could not create the certificate")?;
+    if !output.status.success() {
+        bail!(
+            "Could not create the certificate: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+    }
+    log::info!("Tls created {}", GRAY.paint(tls.cert_file.as_str()));
+    Ok(())
+}
+
+/// Serves the router over TLS, one connection at a time per task.
+#[cfg(feature = "native-tls")]
+pub async fn serve(tls: &TlsConfig, addr: SocketAddr, router: Router) -> Result<()> {
+    use crate::{ext::fs, signal::Interrupt};
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+    use tokio_native_tls::{native_tls, TlsAcceptor};
+
+    let cert = fs::read(&tls.cert_file).await?;
+    let key = fs::read(&tls.key_file).await?;
+    let identity = native_tls::Identity::from_pkcs8(&cert, &key)
+        .context(format!("Could not load the certificate {}", tls.cert_file))?;
+    let acceptor = Arc::new(TlsAcceptor::from(native_tls::TlsAcceptor::new(identity)?));
+
+    let listener = TcpListener::bind(addr).await?;
+    let mut int = Interrupt::subscribe_services_stop();
+    loop {
+        let (stream, _) = tokio::select! {
+            res = listener.accept() => res?,
+            _ = int.recv() => return Ok(()),
+        };
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::debug!("Tls handshake failed: {e}");
+                    return;
+                }
+            };
+            if let Err(e) = hyper::server::conn::Http::new()
+                .serve_connection(stream, router)
+                .with_upgrades()
+                .await
+            {
+                log::debug!("Tls connection error: {e}");
+            }
+        });
+    }
+}
+
+/// Without a TLS backend, [ensure_cert] already refused `--https`.
+#[cfg(not(feature = "native-tls"))]
+pub async fn serve(_tls: &TlsConfig, _addr: SocketAddr, _router: Router) -> Result<()> {
+    bail!("Serving with --https needs cargo-leptos built with the native-tls feature")
+}
@@ -0,0 +1,130 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use tokio::{task::JoinHandle, time::interval};
+
+use crate::{
+    ext::anyhow::{bail, Result},
+    signal::{Interrupt, ResourceCounts, Resources},
+};
+
+/// How often the leaked processes and the resources are logged.
+const INTERVAL: Duration = Duration::from_secs(30);
+
+/// The leaked processes killed since the start of the watch.
+static LEAKS: AtomicUsize = AtomicUsize::new(0);
+
+/// Reports the processes left running by the cancelled builds, killed when their handle
+/// was dropped, and logs the resources of the watch at debug level, until shutdown.
+pub fn spawn() -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut int = Interrupt::subscribe_services_stop();
+        let mut ticks = interval(INTERVAL);
+        loop {
+            tokio::select! {
+                _ = ticks.tick() => {
+                    report_leaks();
+                    log::debug!(
+                        "Watchdog {}",
+                        summary(&Resources::counts(), open_fds(), rss_kb())
+                    );
+                }
+                _ = int.recv() => return,
+            }
+        }
+    })
+}
+
+/// Reports the last leaked processes, and with `--watchdog-strict` fails when any leaked.
+pub async fn check(strict: bool) -> Result<()> {
+    report_leaks();
+    let leaks = LEAKS.load(Ordering::Relaxed);
+    if strict && leaks > 0 {
+        bail!("Watchdog killed {leaks} leaked processes");
+    }
+    Ok(())
+}
+
+fn report_leaks() {
+    for (pid, name) in Resources::take_leaks() {
+        LEAKS.fetch_add(1, Ordering::Relaxed);
+        log::warn!("Watchdog killed the leaked {name} process (pid {pid})");
+    }
+}
+
+/// I.e. `2 processes (Cargo, Server), 1 watcher, 3 reload connections, 42 fds, 81 MB`.
+fn summary(counts: &ResourceCounts, fds: Option<usize>, rss_kb: Option<u64>) -> String {
+    let plural = |n: usize, one: &str, many: &str| match n {
+        1 => format!("1 {one}"),
+        n => format!("{n} {many}"),
+    };
+    let mut text = plural(counts.children.len(), "process", "processes");
+    if !counts.children.is_empty() {
+        text.push_str(&format!(" ({})", counts.children.join(", ")));
+    }
+    text.push_str(&format!(
+        ", {}, {}",
+        plural(counts.watchers, "watcher", "watchers"),
+        plural(
+            counts.reload_connections,
+            "reload connection",
+            "reload connections"
+        )
+    ));
+    if let Some(fds) = fds {
+        text.push_str(&format!(", {fds} fds"));
+    }
+    if let Some(kb) = rss_kb {
+        text.push_str(&format!(", {} MB", kb / 1024));
+    }
+    text
+}
+
+/// The open file descriptors of the process, on linux.
+fn open_fds() -> Option<usize> {
+    Some(std::fs::read_dir("/proc/self/fd").ok()?.count())
+}
+
+/// The resident memory of the process, on linux.
+fn rss_kb() -> Option<u64> {
+    vm_rss(&std::fs::read_to_string("/proc/self/status").ok()?)
+}
+
+/// The `VmRSS:   83364 kB` line of `/proc/self/status`.
+fn vm_rss(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text() {
+        let counts = ResourceCounts {
+            children: vec!["Cargo", "Server"],
+            watchers: 1,
+            reload_connections: 3,
+        };
+        assert_eq!(
+            summary(&counts, Some(42), Some(83_364)),
+            "2 processes (Cargo, Server), 1 watcher, 3 reload connections, 42 fds, 81 MB"
+        );
+        assert_eq!(
+            summary(&ResourceCounts::default(), None, None),
+            "0 processes, 0 watchers, 0 reload connections"
+        );
+        assert_eq!(
+            vm_rss("Name:\tcargo-leptos\nVmRSS:\t   83364 kB\nThreads:\t9\n"),
+            Some(83_364)
+        );
+    }
+}
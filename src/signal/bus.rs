@@ -0,0 +1,142 @@
+use tokio::sync::broadcast::{self, error::RecvError, error::TryRecvError};
+
+use std::time::Duration;
+
+use super::{BuildResult, Phase, ReloadType, ToolRun};
+use crate::compile::Change;
+
+lazy_static::lazy_static! {
+  static ref WATCH: Topic<Vec<Change>> = Topic::new("watch", 64);
+  static ref BUILD: Topic<BuildEvent> = Topic::new("build", 64);
+  static ref SERVER: Topic<ServerEvent> = Topic::new("server", 16);
+  static ref RELOAD: Topic<ReloadType> = Topic::new("reload", 16);
+}
+
+/// The lifecycle of a build cycle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildEvent {
+    Started,
+    PhaseFinished(&'static str),
+    /// the time the phase took, collected by [super::CycleRecorder]
+    PhaseTimed(Phase, Duration),
+    ToolFinished(ToolRun),
+    Finished(BuildResult),
+}
+
+/// The lifecycle of the served binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerEvent {
+    /// asks the server to restart with the newly built binary
+    Restart,
+    /// the server process started (or restarted) and is ready
+    Started,
+    Stopped,
+}
+
+/// The typed topics the subsystems publish to, each with its own bounded channel.
+pub struct Bus {}
+
+impl Bus {
+    /// The file changes picked up by the watcher.
+    pub fn watch() -> &'static Topic<Vec<Change>> {
+        &WATCH
+    }
+
+    pub fn build() -> &'static Topic<BuildEvent> {
+        &BUILD
+    }
+
+    pub fn server() -> &'static Topic<ServerEvent> {
+        &SERVER
+    }
+
+    /// What the browsers connected to the reload server should do.
+    pub fn reload() -> &'static Topic<ReloadType> {
+        &RELOAD
+    }
+}
+
+pub struct Topic<T> {
+    name: &'static str,
+    sender: broadcast::Sender<T>,
+}
+
+impl<T: Clone> Topic<T> {
+    /// A topic keeping up to `capacity` messages for its slowest subscriber.
+    pub fn new(name: &'static str, capacity: usize) -> Self {
+        Self {
+            name,
+            sender: broadcast::channel(capacity).0,
+        }
+    }
+
+    pub fn subscribe(&self) -> Subscriber<T> {
+        Subscriber {
+            name: self.name,
+            receiver: self.sender.subscribe(),
+        }
+    }
+
+    /// Sends the message to the current subscribers, a topic without any isn't an error.
+    pub fn publish(&self, msg: T) {
+        if self.sender.send(msg).is_err() {
+            log::trace!("Bus {} has no subscribers", self.name);
+        }
+    }
+}
+
+pub struct Subscriber<T> {
+    name: &'static str,
+    receiver: broadcast::Receiver<T>,
+}
+
+impl<T: Clone> Subscriber<T> {
+    /// The next message, or `None` when the topic is closed.
+    ///
+    /// A subscriber that falls more than the topic capacity behind skips the oldest
+    /// messages (with a warning) and continues with the ones still queued.
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(msg) => return Some(msg),
+                Err(RecvError::Lagged(n)) => self.lagged(n),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// The next queued message, without waiting.
+    pub fn try_recv(&mut self) -> Option<T> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(msg) => return Some(msg),
+                Err(TryRecvError::Lagged(n)) => self.lagged(n),
+                Err(_) => return None,
+            }
+        }
+    }
+
+    fn lagged(&self, n: u64) {
+        log::warn!(
+            "Bus {} subscriber fell behind, skipped {n} messages",
+            self.name
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn lagging_subscriber_continues() {
+        let topic = Topic::new("test", 2);
+        let mut sub = topic.subscribe();
+        for i in 0..5 {
+            topic.publish(i);
+        }
+        assert_eq!(sub.recv().await, Some(3));
+        assert_eq!(sub.try_recv(), Some(4));
+        assert_eq!(sub.try_recv(), None);
+    }
+}
@@ -1,19 +1,26 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
 use tokio::{
     signal,
     sync::{broadcast, RwLock},
     task::JoinHandle,
 };
 
+use super::Bus;
 use crate::compile::{Change, ChangeSet};
 
 lazy_static::lazy_static! {
   static ref ANY_INTERRUPT: broadcast::Sender<()> = broadcast::channel(10).0;
   static ref SHUTDOWN: broadcast::Sender<()> = broadcast::channel(1).0;
+  static ref SERVICES_STOP: broadcast::Sender<()> = broadcast::channel(1).0;
 
   static ref SHUTDOWN_REQUESTED: RwLock<bool> = RwLock::new(false);
   static ref SOURCE_CHANGES: RwLock<ChangeSet> = RwLock::new(ChangeSet::default());
 }
 
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static REBUILD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
 pub struct Interrupt {}
 
 impl Interrupt {
@@ -29,6 +36,22 @@ impl Interrupt {
         SHUTDOWN.subscribe()
     }
 
+    /// Stops the services of the watch (the server, the reload server, the file watchers and
+    /// the sidecars), on shutdown or for the watch to start again with a reloaded config.
+    pub fn subscribe_services_stop() -> broadcast::Receiver<()> {
+        SERVICES_STOP.subscribe()
+    }
+
+    pub fn stop_services() {
+        _ = SERVICES_STOP.send(());
+    }
+
+    /// Increases with every interrupt, so that a build can tell if newer changes
+    /// arrived while it ran and its output is outdated.
+    pub fn generation() -> u64 {
+        GENERATION.load(Ordering::SeqCst)
+    }
+
     pub async fn get_source_changes() -> ChangeSet {
         SOURCE_CHANGES.read().await.clone()
     }
@@ -46,6 +69,17 @@ impl Interrupt {
         Self::send_any()
     }
 
+    /// A rebuild of everything asked for by the user, which isn't delayed by the adaptive watch.
+    pub fn request_rebuild() {
+        REBUILD_REQUESTED.store(true, Ordering::SeqCst);
+        Self::send_all_changed()
+    }
+
+    /// Whether a rebuild was requested since the last call.
+    pub fn take_rebuild_requested() -> bool {
+        REBUILD_REQUESTED.swap(false, Ordering::SeqCst)
+    }
+
     pub fn send(changes: &[Change]) {
         let mut ch = SOURCE_CHANGES.blocking_write();
         let mut did_change = false;
@@ -53,6 +87,7 @@ impl Interrupt {
             did_change |= ch.add(change.clone());
         }
         drop(ch);
+        Bus::watch().publish(changes.to_vec());
 
         if did_change {
             Self::send_any();
@@ -62,6 +97,7 @@ impl Interrupt {
     }
 
     fn send_any() {
+        GENERATION.fetch_add(1, Ordering::SeqCst);
         if let Err(e) = ANY_INTERRUPT.send(()) {
             log::error!("Interrupt error could not send due to: {e}");
         } else {
@@ -74,9 +110,16 @@ impl Interrupt {
             *SHUTDOWN_REQUESTED.write().await = true;
         }
         _ = SHUTDOWN.send(());
+        _ = SERVICES_STOP.send(());
         _ = ANY_INTERRUPT.send(());
     }
 
+    /// Lets the next command run in the same process, e.g. in the fixture tests.
+    #[cfg(test)]
+    pub async fn reset_shutdown() {
+        *SHUTDOWN_REQUESTED.write().await = false;
+    }
+
     pub fn run_ctrl_c_monitor() -> JoinHandle<()> {
         tokio::spawn(async move {
             signal::ctrl_c().await.expect("failed to listen for event");
@@ -1,10 +1,18 @@
+mod bus;
 mod interrupt;
 mod product;
 mod reload;
+mod resources;
+mod status;
+mod timing;
 
+pub use bus::{BuildEvent, Bus, ServerEvent, Subscriber, Topic};
 pub use interrupt::Interrupt;
 pub use product::{Outcome, Product, ProductSet, ServerRestart};
 pub use reload::{ReloadSignal, ReloadType};
+pub use resources::{ResourceCounts, Resources, Tracked};
+pub use status::{BuildResult, BuildStatus, Status};
+pub use timing::{CycleRecorder, CycleTimes, Phase, Timings, TimingsHistory, ToolRun};
 
 #[macro_export]
 macro_rules! location {
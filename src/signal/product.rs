@@ -1,11 +1,8 @@
 use derive_more::Display;
 use itertools::Itertools;
 use std::{collections::HashSet, fmt};
-use tokio::sync::broadcast;
 
-lazy_static::lazy_static! {
-  static ref SERVER_RESTART_CHANNEL: broadcast::Sender::<()> = broadcast::channel::<()>(1).0;
-}
+use super::{Bus, ServerEvent, Subscriber};
 
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub enum Outcome<T> {
@@ -81,14 +78,12 @@ impl fmt::Display for ProductSet {
 pub struct ServerRestart {}
 
 impl ServerRestart {
-    pub fn subscribe() -> broadcast::Receiver<()> {
-        SERVER_RESTART_CHANNEL.subscribe()
+    pub fn subscribe() -> Subscriber<ServerEvent> {
+        Bus::server().subscribe()
     }
 
     pub fn send() {
         log::trace!("Server restart sent");
-        if let Err(e) = SERVER_RESTART_CHANNEL.send(()) {
-            log::error!("Error could not send product changes due to {e}")
-        }
+        Bus::server().publish(ServerEvent::Restart)
     }
 }
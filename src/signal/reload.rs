@@ -1,8 +1,11 @@
+use std::sync::Mutex;
+
 use leptos_hot_reload::diff::Patches;
-use tokio::sync::broadcast;
+
+use super::{Bus, Subscriber};
 
 lazy_static::lazy_static! {
-  static ref RELOAD_CHANNEL: broadcast::Sender::<ReloadType> = broadcast::channel::<ReloadType>(1).0;
+  static ref LAST_BUILD_ERROR: Mutex<Option<ReloadType>> = Mutex::new(None);
 }
 
 #[derive(Debug, Clone)]
@@ -10,34 +13,67 @@ pub enum ReloadType {
     Full,
     Style,
     ViewPatches(String),
+    /// the output of a failed build, for the error overlay
+    BuildError(String),
+    /// the last stderr lines of the crashed server, for the error overlay
+    ServerCrash(String),
+    ClearBuildError,
 }
 
 pub struct ReloadSignal {}
 
 impl ReloadSignal {
     pub fn send_full() {
-        if let Err(e) = RELOAD_CHANNEL.send(ReloadType::Full) {
-            log::error!(r#"Error could not send reload "Full" due to: {e}"#);
-        }
+        Bus::reload().publish(ReloadType::Full)
     }
     pub fn send_style() {
-        if let Err(e) = RELOAD_CHANNEL.send(ReloadType::Style) {
-            log::error!(r#"Error could not send reload "Style" due to: {e}"#);
-        }
+        Bus::reload().publish(ReloadType::Style)
     }
 
     pub fn send_view_patches(view_patches: &Patches) {
         match serde_json::to_string(view_patches) {
-            Ok(data) => {
-                if let Err(e) = RELOAD_CHANNEL.send(ReloadType::ViewPatches(data)) {
-                    log::error!(r#"Error could not send reload "View Patches" due to: {e}"#);
-                }
-            }
+            Ok(data) => Bus::reload().publish(ReloadType::ViewPatches(data)),
             Err(e) => log::error!(r#"Error could not send reload "View Patches" due to: {e}"#),
         }
     }
 
-    pub fn subscribe() -> broadcast::Receiver<ReloadType> {
-        RELOAD_CHANNEL.subscribe()
+    pub fn send_build_error(output: &str) {
+        Self::send_error(ReloadType::BuildError(output.to_string()));
+    }
+
+    pub fn send_server_crash(output: &str) {
+        Self::send_error(ReloadType::ServerCrash(output.to_string()));
+    }
+
+    fn send_error(error: ReloadType) {
+        *LAST_BUILD_ERROR.lock().unwrap() = Some(error.clone());
+        Bus::reload().publish(error);
+    }
+
+    /// Clears the error overlay, if a build error or server crash was sent.
+    pub fn send_clear_build_error() {
+        if LAST_BUILD_ERROR.lock().unwrap().take().is_none() {
+            return;
+        }
+        Bus::reload().publish(ReloadType::ClearBuildError);
+    }
+
+    /// Clears the error overlay of a server crash, when the server was restarted.
+    pub fn send_clear_server_crash() {
+        let mut last = LAST_BUILD_ERROR.lock().unwrap();
+        if !matches!(*last, Some(ReloadType::ServerCrash(_))) {
+            return;
+        }
+        *last = None;
+        Bus::reload().publish(ReloadType::ClearBuildError);
+    }
+
+    /// The last failed build or server crash, for newly connected browsers
+    pub fn last_build_error() -> Option<ReloadType> {
+        LAST_BUILD_ERROR.lock().unwrap().clone()
+    }
+
+    pub fn subscribe() -> Subscriber<ReloadType> {
+        Bus::reload().subscribe()
     }
 }
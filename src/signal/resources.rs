@@ -0,0 +1,83 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+lazy_static::lazy_static! {
+  static ref CHILDREN: Mutex<Vec<(u32, &'static str)>> = Mutex::new(Vec::new());
+  static ref LEAKS: Mutex<Vec<(u32, &'static str)>> = Mutex::new(Vec::new());
+}
+
+static WATCHERS: AtomicUsize = AtomicUsize::new(0);
+static RELOAD_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// The child processes, file watchers and reload connections open in watch mode, for the
+/// watchdog.
+pub struct Resources {}
+
+/// Removes its resource from [Resources] when dropped.
+pub enum Tracked {
+    Child(u32),
+    Watcher,
+    ReloadConnection,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        match self {
+            Self::Child(pid) => CHILDREN.lock().unwrap().retain(|(p, _)| p != pid),
+            Self::Watcher => _ = WATCHERS.fetch_sub(1, Ordering::Relaxed),
+            Self::ReloadConnection => _ = RELOAD_CONNECTIONS.fetch_sub(1, Ordering::Relaxed),
+        }
+    }
+}
+
+/// The resources open at a point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceCounts {
+    /// the names of the running processes, e.g. `Cargo` or `Server`
+    pub children: Vec<&'static str>,
+    pub watchers: usize,
+    pub reload_connections: usize,
+}
+
+impl Resources {
+    pub fn child(name: &'static str, pid: u32) -> Tracked {
+        CHILDREN.lock().unwrap().push((pid, name));
+        Tracked::Child(pid)
+    }
+
+    /// The handle of the child was dropped while it still ran, as when the build it was
+    /// part of is cancelled, and killed the child: the watchdog reports it. Only the pid is
+    /// kept, for the log, as it may be reused once the child is reaped.
+    pub fn leaked(name: &'static str, pid: u32) {
+        LEAKS.lock().unwrap().push((pid, name));
+    }
+
+    pub fn take_leaks() -> Vec<(u32, &'static str)> {
+        std::mem::take(&mut LEAKS.lock().unwrap())
+    }
+
+    pub fn watcher() -> Tracked {
+        WATCHERS.fetch_add(1, Ordering::Relaxed);
+        Tracked::Watcher
+    }
+
+    pub fn reload_connection() -> Tracked {
+        RELOAD_CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+        Tracked::ReloadConnection
+    }
+
+    pub fn counts() -> ResourceCounts {
+        ResourceCounts {
+            children: CHILDREN
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(_, name)| *name)
+                .collect(),
+            watchers: WATCHERS.load(Ordering::Relaxed),
+            reload_connections: RELOAD_CONNECTIONS.load(Ordering::Relaxed),
+        }
+    }
+}
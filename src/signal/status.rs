@@ -0,0 +1,88 @@
+use std::{sync::Mutex, time::SystemTime};
+
+use serde::Serialize;
+
+use super::{BuildEvent, Bus, CycleTimes};
+
+lazy_static::lazy_static! {
+  static ref STATUS: Mutex<BuildStatus> = Mutex::new(BuildStatus::default());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BuildResult {
+    Success,
+    Failed,
+    Interrupted,
+}
+
+/// The state of the build, for the daemon control socket and the status endpoint.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BuildStatus {
+    pub building: bool,
+    /// the phases of the current build that finished, in the order they did
+    pub phases_done: Vec<&'static str>,
+    /// the external tools running, e.g. `Cargo` twice for the lib and server builds
+    pub tools_running: Vec<&'static str>,
+    /// the number of finished builds
+    pub builds: u64,
+    pub last_result: Option<BuildResult>,
+    /// unix timestamp (seconds) of when the last build finished
+    pub last_finished: Option<u64>,
+    /// the phase timings of the last successful build
+    pub last_cycle: Option<String>,
+    /// the pid of the running server, for `cargo leptos ctl profile`
+    pub server_pid: Option<u32>,
+}
+
+/// Tracks the build state across the build cycles, and publishes its changes on [Bus::build].
+pub struct Status {}
+
+impl Status {
+    pub fn get() -> BuildStatus {
+        STATUS.lock().unwrap().clone()
+    }
+
+    pub fn start_build() {
+        let mut status = STATUS.lock().unwrap();
+        status.building = true;
+        status.phases_done.clear();
+        Bus::build().publish(BuildEvent::Started);
+    }
+
+    pub fn finish_phase(phase: &'static str) {
+        STATUS.lock().unwrap().phases_done.push(phase);
+        Bus::build().publish(BuildEvent::PhaseFinished(phase));
+    }
+
+    pub fn start_tool(tool: &'static str) {
+        STATUS.lock().unwrap().tools_running.push(tool);
+    }
+
+    pub fn finish_tool(tool: &'static str) {
+        let mut status = STATUS.lock().unwrap();
+        if let Some(i) = status.tools_running.iter().position(|t| *t == tool) {
+            status.tools_running.remove(i);
+        }
+    }
+
+    pub fn set_server_pid(pid: Option<u32>) {
+        STATUS.lock().unwrap().server_pid = pid;
+    }
+
+    pub fn finish_build(result: BuildResult, cycle: Option<&CycleTimes>) {
+        let mut status = STATUS.lock().unwrap();
+        status.building = false;
+        status.builds += 1;
+        status.last_result = Some(result);
+        status.last_finished = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs());
+        if let Some(cycle) = cycle {
+            status.last_cycle = Some(cycle.to_string());
+        }
+        drop(status);
+        Bus::build().publish(BuildEvent::Finished(result));
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use itertools::Itertools;
+
+use super::{BuildEvent, Bus, Status, Subscriber};
+
+const HISTORY_LEN: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    CargoServer,
+    CargoLib,
+    WasmBindgen,
+    WasmOpt,
+    Style,
+    Assets,
+}
+
+impl Phase {
+    const ALL: [Phase; 6] = [
+        Phase::CargoServer,
+        Phase::CargoLib,
+        Phase::WasmBindgen,
+        Phase::WasmOpt,
+        Phase::Style,
+        Phase::Assets,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::CargoServer => "server",
+            Self::CargoLib => "lib",
+            Self::WasmBindgen => "bindgen",
+            Self::WasmOpt => "wasm-opt",
+            Self::Style => "style",
+            Self::Assets => "assets",
+        }
+    }
+}
+
+/// A run of an external tool, see [crate::ext::tool::Tool].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolRun {
+    pub tool: &'static str,
+    /// the exit code, none if interrupted or killed by a signal
+    pub code: Option<i32>,
+    pub interrupted: bool,
+    pub duration: Duration,
+}
+
+impl fmt::Display for ToolRun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            _ if self.interrupted => write!(f, "interrupted after {}", secs(self.duration)),
+            Some(code) => write!(f, "exited with code {code} in {}", secs(self.duration)),
+            None => write!(f, "killed after {}", secs(self.duration)),
+        }
+    }
+}
+
+/// The time spent in each phase during one build cycle.
+#[derive(Debug, Clone, Default)]
+pub struct CycleTimes {
+    phases: Vec<(Phase, Duration)>,
+    tools: Vec<ToolRun>,
+    total: Duration,
+}
+
+impl CycleTimes {
+    fn get(&self, phase: Phase) -> Option<Duration> {
+        self.phases
+            .iter()
+            .filter(|(p, _)| *p == phase)
+            .map(|(_, d)| *d)
+            .reduce(|a, b| a + b)
+    }
+
+    /// The time spent in each phase that ran, in pipeline order.
+    pub fn phases(&self) -> Vec<(&'static str, Duration)> {
+        Phase::ALL
+            .iter()
+            .filter_map(|p| self.get(*p).map(|d| (p.label(), d)))
+            .collect()
+    }
+
+    /// The external tools run during the cycle, in the order they finished.
+    pub fn tools(&self) -> &[ToolRun] {
+        &self.tools
+    }
+
+    /// The wall time of the cycle (phases run concurrently, so they add up to more).
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+}
+
+impl fmt::Display for CycleTimes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let phases = Phase::ALL
+            .iter()
+            .filter_map(|p| self.get(*p).map(|d| format!("{} {}", p.label(), secs(d))))
+            .join(", ");
+        if phases.is_empty() {
+            write!(f, "total {}", secs(self.total))
+        } else {
+            write!(f, "total {} ({phases})", secs(self.total))
+        }
+    }
+}
+
+fn secs(d: Duration) -> String {
+    format!("{:.2}s", d.as_secs_f64())
+}
+
+/// Publishes the phase timings of the build pipeline on [Bus::build].
+pub struct Timings {}
+
+impl Timings {
+    /// Starts a timer for the given phase, to be passed to [Timings::record].
+    pub fn start() -> Instant {
+        Instant::now()
+    }
+
+    pub fn record(phase: Phase, started: Instant) {
+        let elapsed = started.elapsed();
+        log::trace!("Timing {} took {}", phase.label(), secs(elapsed));
+        Bus::build().publish(BuildEvent::PhaseTimed(phase, elapsed));
+        Status::finish_phase(phase.label());
+    }
+
+    pub fn record_tool(run: ToolRun) {
+        Bus::build().publish(BuildEvent::ToolFinished(run));
+    }
+
+    /// Starts collecting the timings of a cycle, call before starting its build.
+    pub fn start_cycle() -> CycleRecorder {
+        CycleRecorder {
+            events: Bus::build().subscribe(),
+        }
+    }
+}
+
+/// Collects the timings published during a build cycle.
+pub struct CycleRecorder {
+    events: Subscriber<BuildEvent>,
+}
+
+impl CycleRecorder {
+    /// Closes the cycle with the timings published since it started.
+    pub fn finish(mut self, started: Instant) -> CycleTimes {
+        let mut cycle = CycleTimes::default();
+        while let Some(event) = self.events.try_recv() {
+            match event {
+                BuildEvent::PhaseTimed(phase, elapsed) => cycle.phases.push((phase, elapsed)),
+                BuildEvent::ToolFinished(run) => cycle.tools.push(run),
+                _ => {}
+            }
+        }
+        cycle.total = started.elapsed();
+        cycle
+    }
+}
+
+/// The timings of the last cycles of a watch, for `--stats`.
+#[derive(Debug, Default)]
+pub struct TimingsHistory {
+    cycles: VecDeque<CycleTimes>,
+}
+
+impl TimingsHistory {
+    pub fn push(&mut self, cycle: CycleTimes) {
+        if self.cycles.len() == HISTORY_LEN {
+            self.cycles.pop_front();
+        }
+        self.cycles.push_back(cycle);
+    }
+
+    /// The average timings of the last cycles (phases that didn't run are not counted).
+    pub fn averages(&self) -> CycleTimes {
+        let mut avg = CycleTimes::default();
+        if self.cycles.is_empty() {
+            return avg;
+        }
+        for phase in Phase::ALL {
+            let runs = self
+                .cycles
+                .iter()
+                .filter_map(|c| c.get(phase))
+                .collect_vec();
+            if !runs.is_empty() {
+                avg.phases
+                    .push((phase, runs.iter().sum::<Duration>() / runs.len() as u32));
+            }
+        }
+        avg.total =
+            self.cycles.iter().map(|c| c.total).sum::<Duration>() / self.cycles.len() as u32;
+        avg
+    }
+
+    /// The number of cycles the averages are of.
+    pub fn count(&self) -> usize {
+        self.cycles.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cycle_collects_the_published_timings() {
+        let recorder = Timings::start_cycle();
+        let started = Timings::start();
+        Timings::record(Phase::Style, started);
+        Timings::record_tool(ToolRun {
+            tool: "Sass",
+            code: Some(0),
+            interrupted: false,
+            duration: Duration::from_millis(5),
+        });
+        // the builds of the other tests may publish theirs meanwhile
+        let cycle = recorder.finish(started);
+        assert!(cycle.phases().iter().any(|(phase, _)| *phase == "style"));
+        assert!(cycle.tools().iter().any(|run| run.tool == "Sass"));
+
+        let mut history = TimingsHistory::default();
+        for _ in 0..HISTORY_LEN + 2 {
+            history.push(cycle.clone());
+        }
+        assert_eq!(history.count(), HISTORY_LEN);
+        assert!(!history.averages().phases().is_empty());
+    }
+}
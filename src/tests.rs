@@ -1,18 +1,29 @@
+mod fixture;
+
+use std::time::Duration;
+
 use camino::Utf8PathBuf;
 
 use crate::{
+    command::{BuildCommand, PrerenderCommand},
     config::{Cli, Commands, Opts},
-    ext::PathBufExt,
+    ext::{sync::wait_for_socket, PathBufExt},
     run,
+    signal::{BuildEvent, BuildResult, Bus, Phase, ReloadType, Subscriber},
 };
+use fixture::{running, shutdown, within, Fixture, BUILD_TIMEOUT};
 
 #[tokio::test]
 async fn workspace_build() {
-    let command = Commands::Build(Opts::default());
+    let _running = running().await;
+    let command = Commands::Build(BuildCommand::default());
 
     let cli = Cli {
         manifest_path: Some(Utf8PathBuf::from("examples/workspace/Cargo.toml")),
         log: Vec::new(),
+        color: Default::default(),
+        error_format: Default::default(),
+        log_format: Default::default(),
         command,
     };
 
@@ -24,16 +35,182 @@ async fn workspace_build() {
     insta::assert_display_snapshot!(site_dir.ls_ascii(0).unwrap_or_default());
 }
 
+/// Builds the fixture and checks the files of its site.
+async fn fixture_build(name: &str) -> Fixture {
+    let _running = running().await;
+    let fixture = Fixture::new(name).await;
+
+    let cli = fixture.cli(Commands::Build(BuildCommand::default()));
+    within(BUILD_TIMEOUT, "The build", run(cli)).await.unwrap();
+
+    let site = fixture.site_dir();
+    for file in ["pkg/fixture.wasm", "pkg/fixture.js", "pkg/fixture.css"] {
+        assert!(site.join(file).exists(), "{name} has no {file} in its site");
+    }
+    fixture
+}
+
+#[tokio::test]
+async fn actix_build() {
+    fixture_build("actix").await;
+}
+
+#[tokio::test]
+async fn axum_build() {
+    fixture_build("axum").await;
+}
+
+#[tokio::test]
+async fn csr_build() {
+    let fixture = fixture_build("csr").await;
+    let index = std::fs::read_to_string(fixture.site_dir().join("index.html")).unwrap();
+    assert!(index.contains(r#"<link rel="modulepreload" href="/pkg/fixture.js">"#));
+}
+
+/// Prerenders the root route of the fixture, as the only one configured.
+#[tokio::test]
+async fn axum_prerender() {
+    let _running = running().await;
+    let fixture = Fixture::new("axum").await;
+
+    let cli = fixture.cli(Commands::Prerender(PrerenderCommand {
+        routes: None,
+        sitemap: false,
+        crawl: false,
+        opts: Opts::default(),
+    }));
+    within(BUILD_TIMEOUT, "The prerender", run(cli))
+        .await
+        .unwrap();
+
+    let index = std::fs::read_to_string(fixture.site_dir().join("index.html")).unwrap();
+    assert!(index.contains(">fixture</h1>"), "{index}");
+}
+
+/// Watches the fixture, then saves a style and a view change and checks that the browser is
+/// told to reload each.
+#[tokio::test]
+async fn axum_watch_reload() {
+    let _running = running().await;
+    let fixture = Fixture::new("axum").await;
+    let mut builds = Bus::build().subscribe();
+    let mut reloads = Bus::reload().subscribe();
+
+    let watch = tokio::spawn(run(fixture.cli(Commands::Watch(Opts::default()))));
+    let build = within(BUILD_TIMEOUT, "The first build", async {
+        loop {
+            if let Some(BuildEvent::Finished(result)) = builds.recv().await {
+                return result;
+            }
+        }
+    })
+    .await;
+    assert_eq!(build, BuildResult::Success);
+
+    fixture.edit("style/main.css", "red", "blue").await;
+    within(Duration::from_secs(60), "The style reload", async {
+        while !matches!(reloads.recv().await, Some(ReloadType::Style)) {}
+    })
+    .await;
+    let css = std::fs::read_to_string(fixture.site_dir().join("pkg/fixture.css")).unwrap();
+    assert!(css.contains("blue"));
+
+    fixture
+        .edit("src/lib.rs", r#""fixture""#, r#""edited""#)
+        .await;
+    within(BUILD_TIMEOUT, "The rebuild reload", async {
+        while !matches!(reloads.recv().await, Some(ReloadType::Full)) {}
+    })
+    .await;
+
+    shutdown(watch).await;
+}
+
+/// Watches the fixture, then changes the site-addr in its Cargo.toml and checks that the
+/// watch starts again and builds with it.
+#[tokio::test]
+async fn axum_watch_config_restart() {
+    let _running = running().await;
+    let fixture = Fixture::new("axum").await;
+    let mut builds = Bus::build().subscribe();
+
+    let watch = tokio::spawn(run(fixture.cli(Commands::Watch(Opts::default()))));
+    let build = within(BUILD_TIMEOUT, "The first build", next_build(&mut builds)).await;
+    assert_eq!(build, BuildResult::Success);
+
+    fixture
+        .edit("Cargo.toml", "127.0.0.1:3120", "127.0.0.1:3130")
+        .await;
+    let build = within(BUILD_TIMEOUT, "The restart build", next_build(&mut builds)).await;
+    assert_eq!(build, BuildResult::Success);
+    assert!(wait_for_socket("Test", "127.0.0.1:3130".parse().unwrap()).await);
+
+    shutdown(watch).await;
+}
+
+/// Watches the fixture with --reuse-artifacts and rebuilds it after a view change, then checks
+/// that a build reuses the front and server of the watch.
+#[tokio::test]
+async fn axum_watch_then_build_reuses() {
+    let _running = running().await;
+    let fixture = Fixture::new("axum").await;
+    let opts = Opts {
+        reuse_artifacts: true,
+        ..Opts::default()
+    };
+    let mut builds = Bus::build().subscribe();
+    let mut reloads = Bus::reload().subscribe();
+
+    let watch = tokio::spawn(run(fixture.cli(Commands::Watch(opts.clone()))));
+    let build = within(BUILD_TIMEOUT, "The first build", next_build(&mut builds)).await;
+    assert_eq!(build, BuildResult::Success);
+
+    fixture
+        .edit("src/lib.rs", r#""fixture""#, r#""edited""#)
+        .await;
+    within(BUILD_TIMEOUT, "The rebuild reload", async {
+        while !matches!(reloads.recv().await, Some(ReloadType::Full)) {}
+    })
+    .await;
+    shutdown(watch).await;
+
+    let mut builds = Bus::build().subscribe();
+    let cli = fixture.cli(Commands::Build(BuildCommand {
+        strict: false,
+        opts,
+    }));
+    within(BUILD_TIMEOUT, "The build", run(cli)).await.unwrap();
+    while let Some(event) = builds.try_recv() {
+        if let BuildEvent::PhaseTimed(phase, _) = event {
+            assert!(
+                ![Phase::CargoLib, Phase::CargoServer].contains(&phase),
+                "The build ran {phase:?} instead of reusing the watch artifacts"
+            );
+        }
+    }
+}
+
+async fn next_build(builds: &mut Subscriber<BuildEvent>) -> BuildResult {
+    loop {
+        if let Some(BuildEvent::Finished(result)) = builds.recv().await {
+            return result;
+        }
+    }
+}
+
 // TODO: `cargo-leptos` sets the cwd which is a global env
 // and that prevents builds to run in parallel in the same process
 //
 // #[tokio::test]
 // async fn project_build() {
-//     let command = Commands::Build(Opts::default());
+//     let command = Commands::Build(BuildCommand::default());
 
 //     let cli = Cli {
 //         manifest_path: Some(Utf8PathBuf::from("examples/project/Cargo.toml")),
 //         log: Vec::new(),
+//         color: Default::default(),
+//         error_format: Default::default(),
+//         log_format: Default::default(),
 //         command,
 //     };
 
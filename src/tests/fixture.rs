@@ -0,0 +1,134 @@
+use std::{env, future::Future, time::Duration};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use temp_dir::TempDir;
+use tokio::{
+    sync::{Mutex, MutexGuard},
+    task::JoinHandle,
+    time::timeout,
+};
+
+use crate::{
+    config::{Cli, Commands},
+    ext::{
+        anyhow::Result,
+        exe::{get_cache_dir, ENV_VAR_LEPTOS_CACHE_DIR},
+        fs,
+    },
+    signal::Interrupt,
+};
+
+lazy_static::lazy_static! {
+  /// `run` changes the current dir of the process, so the projects are built one at a time.
+  static ref RUNNING: Mutex<()> = Mutex::new(());
+}
+
+/// The time a fixture has for a cargo build of its lib and bin, from a cold target dir.
+pub const BUILD_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Holds the current dir of the process until dropped, when it is restored.
+pub struct Running {
+    cwd: Utf8PathBuf,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        _ = env::set_current_dir(&self.cwd);
+        env::remove_var("CARGO_TARGET_DIR");
+    }
+}
+
+/// Waits for the other fixture runs to finish.
+pub async fn running() -> Running {
+    let guard = RUNNING.lock().await;
+    Running {
+        cwd: Utf8PathBuf::from_path_buf(env::current_dir().unwrap()).unwrap(),
+        _guard: guard,
+    }
+}
+
+/// A copy of a project of `examples/fixtures` in a temp dir, so that the runs start from a
+/// clean site and the edits of the watch tests don't touch the repo. The cargo target dir is
+/// kept in `target/fixtures` for the next runs, so it must be created while `running`.
+pub struct Fixture {
+    _dir: TempDir,
+    pub root: Utf8PathBuf,
+}
+
+impl Fixture {
+    pub async fn new(name: &str) -> Self {
+        seed_tool_cache().await;
+        let dir = TempDir::new().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap().join(name);
+        let crate_dir = Utf8PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let fixture = crate_dir.join("examples/fixtures").join(name);
+        fs::copy_dir_all(fixture, &root).await.unwrap();
+        env::set_var(
+            "CARGO_TARGET_DIR",
+            crate_dir.join("target/fixtures").join(name),
+        );
+        Self { _dir: dir, root }
+    }
+
+    pub fn cli(&self, command: Commands) -> Cli {
+        Cli {
+            manifest_path: Some(self.root.join("Cargo.toml")),
+            log: Vec::new(),
+            color: Default::default(),
+            error_format: Default::default(),
+            log_format: Default::default(),
+            command,
+        }
+    }
+
+    pub fn site_dir(&self) -> Utf8PathBuf {
+        self.root.join("target/site")
+    }
+
+    /// Replaces the text in a file of the fixture, like an editor saving it.
+    pub async fn edit(&self, file: &str, from: &str, to: &str) {
+        let file = self.root.join(file);
+        let content = fs::read_to_string(&file).await.unwrap();
+        assert!(content.contains(from), "{file} has no {from:?}");
+        fs::write(&file, content.replace(from, to)).await.unwrap();
+    }
+}
+
+/// The fixtures share a tool cache under the target dir, which CI keeps between runs. It is
+/// seeded from the cache of the user (or `LEPTOS_FIXTURE_TOOLS`) on first use, so that the
+/// runs don't download the tools.
+async fn seed_tool_cache() {
+    let cache = Utf8PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/leptos-fixture-cache");
+    if !cache.exists() {
+        let seed = match env::var("LEPTOS_FIXTURE_TOOLS") {
+            Ok(seed) => Some(Utf8PathBuf::from(seed)),
+            Err(_) => get_cache_dir()
+                .ok()
+                .and_then(|dir| Utf8PathBuf::from_path_buf(dir).ok()),
+        };
+        match seed {
+            Some(seed) if seed.exists() => fs::copy_dir_all(&seed, &cache).await.unwrap(),
+            _ => fs::create_dir_all(&cache).await.unwrap(),
+        }
+    }
+    env::set_var(ENV_VAR_LEPTOS_CACHE_DIR, cache.as_str());
+}
+
+/// Runs the future, failing the test when it takes longer than the timeout.
+pub async fn within<T>(duration: Duration, what: &str, fut: impl Future<Output = T>) -> T {
+    timeout(duration, fut)
+        .await
+        .unwrap_or_else(|_| panic!("{what} took more than {duration:?}"))
+}
+
+/// Stops the watch and waits for it to return, then lets the next runs start, as the shutdown
+/// is global to the process.
+pub async fn shutdown(watch: JoinHandle<Result<()>>) {
+    Interrupt::request_shutdown().await;
+    within(Duration::from_secs(30), "The watch shutdown", watch)
+        .await
+        .unwrap()
+        .unwrap();
+    Interrupt::reset_shutdown().await;
+}